@@ -1,9 +1,13 @@
+use alloc::vec;
+use alloc::vec::Vec;
 use core::ops::{Add, Mul, Sub};
 
 use p3_field::{Algebra, ExtensionField, Field, PrimeCharacteristicRing};
 use p3_matrix::Matrix;
 use p3_matrix::dense::RowMajorMatrix;
 
+use crate::{AuxPhase, Interaction};
+
 /// An AIR (algebraic intermediate representation).
 pub trait BaseAir<F>: Sync {
     /// The number of columns (a.k.a. registers) in this AIR.
@@ -12,6 +16,73 @@ pub trait BaseAir<F>: Sync {
     fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
         None
     }
+
+    /// The offsets, relative to the current row, of the main trace rows this AIR reads.
+    ///
+    /// Row `i` of the `main` matrix exposed to `eval` is the row at `main_row_offsets()[i]`. The
+    /// offsets must be strictly increasing and start with `0, 1`, so the default window of the
+    /// current and next rows can be extended with rows further ahead, e.g. `[0, 1, 2, 7]`. A
+    /// constraint reading the row at offset `k` should be guarded by
+    /// [`AirBuilder::is_transition_window`] with a size of `k + 1`.
+    fn main_row_offsets(&self) -> Vec<usize> {
+        vec![0, 1]
+    }
+
+    /// The periodic columns of this AIR, each given by its values over a single period.
+    ///
+    /// The value of a periodic column on row `i` is `column[i % column.len()]`. The length of each
+    /// column must be a power of two no larger than the trace height. Unlike main trace columns,
+    /// periodic columns are never committed to, as the verifier can evaluate them itself. They
+    /// are read in `eval` through [`PeriodicAirBuilder::periodic_values`].
+    fn periodic_columns(&self) -> Vec<Vec<F>> {
+        vec![]
+    }
+
+    /// The tuples this AIR sends in a lookup argument, on any bus.
+    fn sends(&self) -> Vec<Interaction<F>>
+    where
+        F: Field,
+    {
+        vec![]
+    }
+
+    /// The tuples this AIR receives in a lookup argument, on any bus.
+    fn receives(&self) -> Vec<Interaction<F>>
+    where
+        F: Field,
+    {
+        vec![]
+    }
+
+    /// The auxiliary trace phases of this AIR, in the order they are committed to.
+    ///
+    /// The extension field columns of each phase are generated by
+    /// [`generate_aux_trace`](Self::generate_aux_trace) once the phase's challenges have been
+    /// sampled, and are read in `eval` through [`AuxBuilder::aux`].
+    fn aux_phases(&self) -> Vec<AuxPhase> {
+        vec![]
+    }
+
+    /// Generate the columns of auxiliary phase `phase` from the preprocessed and main traces, the
+    /// traces of all earlier phases and the challenges of this phase.
+    ///
+    /// # Panics
+    /// The default implementation panics, as an AIR without auxiliary phases is never asked to
+    /// generate one.
+    fn generate_aux_trace<EF>(
+        &self,
+        phase: usize,
+        _preprocessed: Option<&RowMajorMatrix<F>>,
+        _main: &RowMajorMatrix<F>,
+        _previous_phases: &[RowMajorMatrix<EF>],
+        _challenges: &[EF],
+    ) -> RowMajorMatrix<EF>
+    where
+        F: Field,
+        EF: ExtensionField<F>,
+    {
+        panic!("AIR does not declare auxiliary phase {phase}")
+    }
 }
 
 ///  An AIR with 0 or more public values.
@@ -58,10 +129,7 @@ pub trait AirBuilder: Sized {
 
     /// Returns a sub-builder whose constraints are enforced only when `condition` is nonzero.
     fn when<I: Into<Self::Expr>>(&mut self, condition: I) -> FilteredAirBuilder<'_, Self> {
-        FilteredAirBuilder {
-            inner: self,
-            condition: condition.into(),
-        }
+        FilteredAirBuilder::new(self, condition.into())
     }
 
     /// Returns a sub-builder whose constraints are enforced only when `x != y`.
@@ -99,6 +167,14 @@ pub trait AirBuilder: Sized {
     /// into a single assert_zeros call will improve performance.
     fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I);
 
+    /// Assert that `x` is zero wherever `condition` is nonzero.
+    ///
+    /// A [`FilteredAirBuilder`] asserts its constraints through this method, so a builder can
+    /// override it to tell filtered constraints apart from unconditional ones.
+    fn assert_zero_when<I: Into<Self::Expr>>(&mut self, condition: Self::Expr, x: I) {
+        self.assert_zero(condition * x.into());
+    }
+
     /// Assert that every element of a given array is 0.
     ///
     /// This should be preferred over calling `assert_zero` multiple times.
@@ -139,6 +215,15 @@ pub trait AirBuilderWithPublicValues: AirBuilder {
     fn public_values(&self) -> &[Self::PublicVar];
 }
 
+/// An `AirBuilder` with access to the periodic columns of an AIR, see
+/// [`BaseAir::periodic_columns`].
+pub trait PeriodicAirBuilder: AirBuilder {
+    type PeriodicVar: Into<Self::Expr> + Copy;
+
+    /// The value of every periodic column on the current row.
+    fn periodic_values(&self) -> &[Self::PeriodicVar];
+}
+
 pub trait PairBuilder: AirBuilder {
     fn preprocessed(&self) -> Self::M;
 }
@@ -154,6 +239,15 @@ pub trait ExtensionBuilder: AirBuilder {
     where
         I: Into<Self::ExprEF>;
 
+    /// Assert that `x` is zero wherever `condition` is nonzero, see
+    /// [`AirBuilder::assert_zero_when`].
+    fn assert_zero_ext_when<I>(&mut self, condition: Self::Expr, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        self.assert_zero_ext(x.into() * condition);
+    }
+
     fn assert_eq_ext<I1, I2>(&mut self, x: I1, y: I2)
     where
         I1: Into<Self::ExprEF>,
@@ -180,13 +274,31 @@ pub trait PermutationAirBuilder: ExtensionBuilder {
     fn permutation_randomness(&self) -> &[Self::RandomVar];
 }
 
+/// An `AirBuilder` with access to the auxiliary trace phases of an AIR, see
+/// [`BaseAir::aux_phases`].
+pub trait AuxBuilder: ExtensionBuilder {
+    type MAux: Matrix<Self::VarEF>;
+
+    type AuxChallenge: Into<Self::ExprEF> + Copy;
+
+    /// The columns of auxiliary phase `phase`, in the current and next rows.
+    fn aux(&self, phase: usize) -> Self::MAux;
+
+    /// The challenges sampled before auxiliary phase `phase` was generated.
+    fn aux_challenges(&self, phase: usize) -> &[Self::AuxChallenge];
+}
+
 #[derive(Debug)]
 pub struct FilteredAirBuilder<'a, AB: AirBuilder> {
     pub inner: &'a mut AB,
     condition: AB::Expr,
 }
 
-impl<AB: AirBuilder> FilteredAirBuilder<'_, AB> {
+impl<'a, AB: AirBuilder> FilteredAirBuilder<'a, AB> {
+    pub const fn new(inner: &'a mut AB, condition: AB::Expr) -> Self {
+        Self { inner, condition }
+    }
+
     pub fn condition(&self) -> AB::Expr {
         self.condition.clone()
     }
@@ -215,7 +327,15 @@ impl<AB: AirBuilder> AirBuilder for FilteredAirBuilder<'_, AB> {
     }
 
     fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
-        self.inner.assert_zero(self.condition() * x.into());
+        self.inner.assert_zero_when(self.condition(), x);
+    }
+}
+
+impl<AB: PeriodicAirBuilder> PeriodicAirBuilder for FilteredAirBuilder<'_, AB> {
+    type PeriodicVar = AB::PeriodicVar;
+
+    fn periodic_values(&self) -> &[Self::PeriodicVar] {
+        self.inner.periodic_values()
     }
 }
 
@@ -228,7 +348,7 @@ impl<AB: ExtensionBuilder> ExtensionBuilder for FilteredAirBuilder<'_, AB> {
     where
         I: Into<Self::ExprEF>,
     {
-        self.inner.assert_zero_ext(x.into() * self.condition());
+        self.inner.assert_zero_ext_when(self.condition(), x);
     }
 }
 
@@ -245,3 +365,17 @@ impl<AB: PermutationAirBuilder> PermutationAirBuilder for FilteredAirBuilder<'_,
         self.inner.permutation_randomness()
     }
 }
+
+impl<AB: AuxBuilder> AuxBuilder for FilteredAirBuilder<'_, AB> {
+    type MAux = AB::MAux;
+
+    type AuxChallenge = AB::AuxChallenge;
+
+    fn aux(&self, phase: usize) -> Self::MAux {
+        self.inner.aux(phase)
+    }
+
+    fn aux_challenges(&self, phase: usize) -> &[Self::AuxChallenge] {
+        self.inner.aux_challenges(phase)
+    }
+}
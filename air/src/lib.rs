@@ -5,8 +5,12 @@
 extern crate alloc;
 
 mod air;
+mod lookup;
+mod rap;
 pub mod utils;
 mod virtual_column;
 
 pub use air::*;
+pub use lookup::*;
+pub use rap::*;
 pub use virtual_column::*;
@@ -0,0 +1,39 @@
+use alloc::vec::Vec;
+
+use p3_field::Field;
+use serde::{Deserialize, Serialize};
+
+use crate::VirtualPairCol;
+
+/// A tuple of values which an AIR sends or receives on a bus, on every row, with some
+/// multiplicity.
+///
+/// Both the values and the multiplicity are affine combinations of the preprocessed and main
+/// columns of the current row. A lookup argument proves that, for every bus and summed over all
+/// rows of all AIRs taking part in it, every tuple is sent exactly as many times as it is
+/// received. Buses let one AIR delegate work to another, e.g. a CPU AIR may send the inputs and
+/// outputs of a hash on a bus on which a hash AIR receives them. They also keep unrelated lookups
+/// within a single AIR apart.
+///
+/// All tuples on a bus should have the same length.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Interaction<F: Field> {
+    pub bus: usize,
+    pub values: Vec<VirtualPairCol<F>>,
+    pub multiplicity: VirtualPairCol<F>,
+}
+
+impl<F: Field> Interaction<F> {
+    pub const fn new(
+        bus: usize,
+        values: Vec<VirtualPairCol<F>>,
+        multiplicity: VirtualPairCol<F>,
+    ) -> Self {
+        Self {
+            bus,
+            values,
+            multiplicity,
+        }
+    }
+}
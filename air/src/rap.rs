@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// The shape of an auxiliary trace phase of an AIR, see [`BaseAir::aux_phases`].
+///
+/// An AIR with auxiliary phases is a randomized AIR: the columns of each phase are generated after
+/// the verifier has sampled the phase's challenges, which bind the main trace and every earlier
+/// phase. This is what permutation checks, multiset hashes and grand products need.
+///
+/// [`BaseAir::aux_phases`]: crate::BaseAir::aux_phases
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuxPhase {
+    /// The number of extension field challenges sampled before the phase is generated.
+    pub num_challenges: usize,
+    /// The number of extension field columns in the phase.
+    pub width: usize,
+}
+
+impl AuxPhase {
+    pub const fn new(num_challenges: usize, width: usize) -> Self {
+        Self {
+            num_challenges,
+            width,
+        }
+    }
+}
@@ -3,16 +3,18 @@ use alloc::vec::Vec;
 use core::ops::Mul;
 
 use p3_field::{Field, PrimeCharacteristicRing};
+use serde::{Deserialize, Serialize};
 
 /// An affine function over columns in a PAIR.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct VirtualPairCol<F: Field> {
     column_weights: Vec<(PairCol, F)>,
     constant: F,
 }
 
 /// A column in a PAIR, i.e. either a preprocessed column or a main trace column.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum PairCol {
     Preprocessed(usize),
     Main(usize),
@@ -57,6 +59,11 @@ impl<F: Field> VirtualPairCol<F> {
 
     pub const ONE: Self = Self::constant(F::ONE);
 
+    /// The columns this virtual column combines, with their weights.
+    pub fn column_weights(&self) -> &[(PairCol, F)] {
+        &self.column_weights
+    }
+
     #[must_use]
     pub const fn constant(x: F) -> Self {
         Self {
@@ -10,6 +10,7 @@ use p3_matrix::dense::RowMajorMatrix;
 use p3_util::{log2_ceil_usize, log2_strict_usize};
 use tracing::instrument;
 
+use crate::CircleEvaluations;
 use crate::point::Point;
 
 /// A twin-coset of the circle group on F. It has a power-of-two size and an arbitrary shift.
@@ -212,6 +213,70 @@ impl<F: ComplexExtendable> PolynomialSpace for CircleDomain<F> {
             inv_vanishing: sels.iter().map(|s| s.inv_vanishing).collect(),
         }
     }
+
+    /// In standard position, the `i`'th point of a domain of size `n` is `g_{2n}^{2i + 1}`, so
+    /// doubling it `log(n/p)` times gives the `i % p`'th point of the standard position domain of
+    /// size `p`. A periodic column is thus the interpolant of `values` over that domain, composed
+    /// with the doubling map.
+    fn periodic_column_at_point<Ext: ExtensionField<F>>(&self, values: &[F], point: Ext) -> Ext {
+        assert!(
+            self.is_standard(),
+            "periodic columns are only supported on standard position twin cosets"
+        );
+        assert!(log2_strict_usize(values.len()) <= self.log_n);
+        if values.len() == 1 {
+            return Ext::from(values[0]);
+        }
+        // Evaluating on a point of a domain needs at least 4 points, and a column with a shorter
+        // period is also periodic with period 4.
+        let log_period = log2_strict_usize(values.len()).max(2);
+        let values = values
+            .iter()
+            .copied()
+            .cycle()
+            .take(1 << log_period)
+            .collect();
+        let mut point = Point::from_projective_line(point);
+        for _ in log_period..self.log_n {
+            point = point.double();
+        }
+        CircleEvaluations::from_natural_order(
+            Self::standard(log_period),
+            RowMajorMatrix::new_col(values),
+        )
+        .evaluate_at_point(point)[0]
+    }
+
+    /// Doubling the `i`'th point of a standard position coset `log(n/p)` times gives the
+    /// `i % m`'th point of the standard position domain of size `m = |coset| p / n`, so the
+    /// interpolant only needs to be evaluated over that domain, and then repeats cyclically.
+    fn periodic_column_on_coset(&self, values: &[F], coset: Self) -> Vec<F> {
+        assert!(
+            self.is_standard() && coset.is_standard(),
+            "periodic columns are only supported on standard position twin cosets"
+        );
+        assert!(log2_strict_usize(values.len()) <= self.log_n);
+        assert!(coset.log_n >= self.log_n);
+        if values.len() == 1 {
+            return vec![values[0]; coset.size()];
+        }
+        let log_period = log2_strict_usize(values.len()).max(2);
+        let values = values
+            .iter()
+            .copied()
+            .cycle()
+            .take(1 << log_period)
+            .collect();
+        let distinct = CircleEvaluations::from_natural_order(
+            Self::standard(log_period),
+            RowMajorMatrix::new_col(values),
+        )
+        .extrapolate(Self::standard(coset.log_n - (self.log_n - log_period)))
+        .to_natural_order()
+        .to_row_major_matrix()
+        .values;
+        distinct.into_iter().cycle().take(coset.size()).collect()
+    }
 }
 
 // 0 1 2 .. len-1 len len len-1 .. 1 0 0 1 ..
@@ -232,7 +297,6 @@ mod tests {
     use rand::rngs::SmallRng;
 
     use super::*;
-    use crate::CircleEvaluations;
 
     fn assert_is_twin_coset<F: ComplexExtendable>(d: CircleDomain<F>) {
         let pts = d.points().collect_vec();
@@ -395,6 +459,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn periodic_columns() {
+        type F = Mersenne31;
+        let log_n = 6;
+        let n = 1 << log_n;
+
+        let d = CircleDomain::<F>::standard(log_n);
+        let coset = d.create_disjoint_domain(4 * n);
+        let mut rng = SmallRng::seed_from_u64(1);
+        for period in [1, 2, 8, n] {
+            let values = RowMajorMatrix::<F>::rand(&mut rng, period, 1).values;
+
+            // A periodic column agrees with the interpolant of the repeated values.
+            let column = values.iter().copied().cycle().take(n).collect_vec();
+            let evals = CircleEvaluations::from_natural_order(d, RowMajorMatrix::new_col(column));
+            let on_coset = d.periodic_column_on_coset(&values, coset);
+            for (p, value) in coset.points().zip(on_coset) {
+                assert_eq!(evals.evaluate_at_point(p)[0], value);
+            }
+        }
+    }
+
     #[test]
     fn test_circle_domain() {
         do_test_circle_domain(4, 8);
@@ -1,3 +1,4 @@
+use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt::Debug;
 use core::marker::PhantomData;
@@ -5,7 +6,7 @@ use core::marker::PhantomData;
 use itertools::Itertools;
 use p3_commit::Mmcs;
 use p3_field::extension::ComplexExtendable;
-use p3_field::{ExtensionField, batch_multiplicative_inverse};
+use p3_field::{ExtensionField, Field, batch_multiplicative_inverse};
 use p3_fri::FriGenericConfig;
 use p3_matrix::Matrix;
 use p3_util::{log2_strict_usize, reverse_bits_len};
@@ -127,9 +128,77 @@ pub(crate) fn fold_x_row<F: ComplexExtendable, EF: ExtensionField<F>>(
     (sum + beta * diff).halve()
 }
 
+/// The x-coordinate at which circle FRI evaluates the folded codeword at `index` of a layer of
+/// height `2^log_height`.
+///
+/// Each row folded by [`fold_x_row`] holds evaluations at `x` and `-x`, so consecutive indices
+/// come in pairs of opposite x-coordinates.
+pub(crate) fn folded_domain_x<F: ComplexExtendable>(index: usize, log_height: usize) -> F {
+    let x = CircleDomain::<F>::standard(log_height + 1)
+        .nth_x_twiddle(reverse_bits_len(index >> 1, log_height - 1));
+    if index & 1 == 0 { x } else { -x }
+}
+
+/// Interpolate the evaluations of a layer of circle FRI, which must be those of a polynomial in
+/// the x-coordinate with fewer than `2^log_final_poly_len` coefficients, into its coefficients.
+///
+/// In debug builds, panics if the evaluations are not those of such a polynomial.
+pub(crate) fn interpolate_final_poly<F: ComplexExtendable, EF: ExtensionField<F>>(
+    evals: &[EF],
+    log_final_poly_len: usize,
+) -> Vec<EF> {
+    let log_height = log2_strict_usize(evals.len());
+    let final_poly_len = 1 << log_final_poly_len;
+    assert!(final_poly_len <= evals.len());
+    let final_poly = if final_poly_len == 1 {
+        vec![evals[0]]
+    } else {
+        let xs = (0..final_poly_len)
+            .map(|i| folded_domain_x::<F>(i, log_height))
+            .collect_vec();
+
+        // Newton's divided differences, followed by expanding the Newton form into coefficients.
+        let mut newton = evals[..final_poly_len].to_vec();
+        for j in 1..final_poly_len {
+            let denominators = (j..final_poly_len).map(|i| xs[i] - xs[i - j]).collect_vec();
+            let inverses = batch_multiplicative_inverse(&denominators);
+            for i in (j..final_poly_len).rev() {
+                newton[i] = (newton[i] - newton[i - 1]) * inverses[i - j];
+            }
+        }
+        let mut coeffs = vec![EF::ZERO; final_poly_len];
+        for (&x, &c) in xs.iter().zip(&newton).rev() {
+            // coeffs <- coeffs * (X - x) + c
+            for k in (1..final_poly_len).rev() {
+                coeffs[k] = coeffs[k - 1] - coeffs[k] * x;
+            }
+            coeffs[0] = c - coeffs[0] * x;
+        }
+        coeffs
+    };
+
+    debug_assert!(
+        evals.iter().enumerate().all(|(i, &eval)| eval_final_poly(
+            &final_poly,
+            folded_domain_x::<F>(i, log_height)
+        ) == eval),
+        "the folded codeword is not of the expected degree"
+    );
+    final_poly
+}
+
+/// Evaluate the final polynomial of circle FRI, given by its coefficients, at `x`.
+pub(crate) fn eval_final_poly<F: Field, EF: ExtensionField<F>>(final_poly: &[EF], x: F) -> EF {
+    final_poly
+        .iter()
+        .rev()
+        .fold(EF::ZERO, |acc, &coeff| acc * x + coeff)
+}
+
 #[cfg(test)]
 mod tests {
     use itertools::iproduct;
+    use p3_field::PrimeCharacteristicRing;
     use p3_field::extension::BinomialExtensionField;
     use p3_matrix::dense::RowMajorMatrix;
     use p3_mersenne_31::Mersenne31;
@@ -189,4 +258,56 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn fold_x_matches_folded_domain() {
+        // Folding the evaluations of P(x) = P_e(2x^2 - 1) + x P_o(2x^2 - 1) gives those of
+        // P_e + beta P_o, one layer down.
+        let mut rng = SmallRng::seed_from_u64(1);
+        let log_height = 6;
+        let p_e: Vec<EF> = (0..4).map(|_| rng.random()).collect();
+        let p_o: Vec<EF> = (0..4).map(|_| rng.random()).collect();
+        let evals = (0..1 << log_height)
+            .map(|i| {
+                let x = folded_domain_x::<F>(i, log_height);
+                let pi_x = x.square().double() - F::ONE;
+                eval_final_poly(&p_e, pi_x) + eval_final_poly(&p_o, pi_x) * x
+            })
+            .collect_vec();
+
+        let beta: EF = rng.random();
+        let folded = fold_x::<F, EF>(beta, RowMajorMatrix::new(evals, 2));
+        for (i, &eval) in folded.iter().enumerate() {
+            let x = folded_domain_x::<F>(i, log_height - 1);
+            assert_eq!(
+                eval,
+                eval_final_poly(&p_e, x) + beta * eval_final_poly(&p_o, x)
+            );
+        }
+    }
+
+    #[test]
+    fn interpolate_final_poly_roundtrip() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        for (log_final_poly_len, log_blowup) in iproduct!(0..5, 1..3) {
+            let final_poly: Vec<EF> = (0..1 << log_final_poly_len).map(|_| rng.random()).collect();
+            let log_height = log_final_poly_len + log_blowup;
+            let evals = (0..1 << log_height)
+                .map(|i| eval_final_poly(&final_poly, folded_domain_x::<F>(i, log_height)))
+                .collect_vec();
+            assert_eq!(
+                interpolate_final_poly::<F, EF>(&evals, log_final_poly_len),
+                final_poly
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "the folded codeword is not of the expected degree")]
+    fn interpolate_final_poly_high_degree() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let evals: Vec<EF> = (0..8).map(|_| rng.random()).collect();
+        interpolate_final_poly::<F, EF>(&evals, 1);
+    }
 }
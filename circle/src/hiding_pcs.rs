@@ -0,0 +1,363 @@
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use itertools::{Itertools, izip};
+use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
+use p3_commit::{FriBasedPcs, FriParameters, Mmcs, OpenedValues, Pcs, PolynomialSpace};
+use p3_field::extension::ComplexExtendable;
+use p3_field::{ExtensionField, Field};
+use p3_fri::verifier::FriError;
+use p3_fri::{FriConfig, add_random_cols, get_zp_cis};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::horizontally_truncated::HorizontallyTruncated;
+use p3_util::zip_eq::zip_eq;
+use rand::Rng;
+use rand::distr::{Distribution, StandardUniform};
+use tracing::info_span;
+
+use crate::{CircleDomain, CircleEvaluations, CirclePcs};
+
+/// A hiding circle PCS. Both MMCSs must also be hiding; this is not enforced at compile time so
+/// it's the user's responsibility to configure.
+///
+/// Each committed trace is randomized over the standard twin-coset of twice its size, by adding a
+/// random multiple of the vanishing polynomial of its own domain. This leaves the trace unchanged
+/// on its domain while hiding its evaluations everywhere else.
+#[derive(Debug)]
+pub struct HidingCirclePcs<Val: Field, InputMmcs, FriMmcs, R> {
+    inner: CirclePcs<Val, InputMmcs, FriMmcs>,
+    num_random_codewords: usize,
+    rng: RefCell<R>,
+}
+
+impl<Val: Field, InputMmcs, FriMmcs, R> HidingCirclePcs<Val, InputMmcs, FriMmcs, R> {
+    pub fn new(
+        mmcs: InputMmcs,
+        fri_config: FriConfig<FriMmcs>,
+        num_random_codewords: usize,
+        rng: R,
+    ) -> Self {
+        Self {
+            inner: CirclePcs::new(mmcs, fri_config),
+            num_random_codewords,
+            rng: rng.into(),
+        }
+    }
+}
+
+impl<Val: Field, InputMmcs, FriMmcs, R> FriBasedPcs
+    for HidingCirclePcs<Val, InputMmcs, FriMmcs, R>
+{
+    fn fri_parameters(&self) -> FriParameters {
+        self.inner.fri_parameters()
+    }
+}
+
+impl<Val, InputMmcs, FriMmcs, Challenge, Challenger, R> Pcs<Challenge, Challenger>
+    for HidingCirclePcs<Val, InputMmcs, FriMmcs, R>
+where
+    Val: ComplexExtendable,
+    StandardUniform: Distribution<Val>,
+    Challenge: ExtensionField<Val>,
+    InputMmcs: Mmcs<Val>,
+    FriMmcs: Mmcs<Challenge>,
+    Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<FriMmcs::Commitment>,
+    R: Rng + Send + Sync,
+{
+    type Domain = CircleDomain<Val>;
+    type Commitment =
+        <CirclePcs<Val, InputMmcs, FriMmcs> as Pcs<Challenge, Challenger>>::Commitment;
+    type ProverData =
+        <CirclePcs<Val, InputMmcs, FriMmcs> as Pcs<Challenge, Challenger>>::ProverData;
+    type EvaluationsOnDomain<'a> = HorizontallyTruncated<
+        Val,
+        <CirclePcs<Val, InputMmcs, FriMmcs> as Pcs<Challenge, Challenger>>::EvaluationsOnDomain<'a>,
+    >;
+    /// The first item contains the openings of the random polynomials added by this wrapper.
+    /// The second item is the usual circle PCS proof.
+    type Proof = (
+        OpenedValues<Challenge>,
+        <CirclePcs<Val, InputMmcs, FriMmcs> as Pcs<Challenge, Challenger>>::Proof,
+    );
+    type Error = <CirclePcs<Val, InputMmcs, FriMmcs> as Pcs<Challenge, Challenger>>::Error;
+
+    const ZK: bool = true;
+
+    fn natural_domain_for_degree(&self, degree: usize) -> Self::Domain {
+        <CirclePcs<Val, InputMmcs, FriMmcs> as Pcs<Challenge, Challenger>>::natural_domain_for_degree(
+            &self.inner, degree)
+    }
+
+    /// Commit to matrices given by their evaluations over a standard domain `H`, randomized over
+    /// `domain`, the standard domain of twice the size of `H`.
+    ///
+    /// Each column `p` is replaced by `p + v_H * t` for a random `t` of the same degree, and
+    /// `num_random_codewords` random columns are appended.
+    fn commit(
+        &self,
+        evaluations: impl IntoIterator<Item = (Self::Domain, RowMajorMatrix<Val>)>,
+    ) -> (Self::Commitment, Self::ProverData) {
+        let randomized_evaluations: Vec<(Self::Domain, RowMajorMatrix<Val>)> =
+            info_span!("randomize polys").in_scope(|| {
+                evaluations
+                    .into_iter()
+                    .map(|(domain, mat)| {
+                        assert_eq!(domain.size(), 2 * mat.height());
+                        let trace_domain = CircleDomain::standard(domain.log_n - 1);
+
+                        let mut rng = self.rng.borrow_mut();
+                        let mask = RowMajorMatrix::rand(&mut *rng, mat.height(), mat.width());
+                        let masked_evaluations =
+                            mask_evaluations(trace_domain, mat, trace_domain, mask, domain);
+                        let random_evaluation = add_random_cols(
+                            masked_evaluations,
+                            self.num_random_codewords,
+                            &mut *rng,
+                        );
+
+                        (domain, random_evaluation)
+                    })
+                    .collect()
+            });
+
+        Pcs::<Challenge, Challenger>::commit(&self.inner, randomized_evaluations)
+    }
+
+    /// Commit to the quotient polynomial. We first decompose the quotient polynomial into
+    /// `num_chunks` many smaller polynomials `q_i`, each given by its evaluations over a twin-coset
+    /// `H_i`. These are then randomized as explained in Section 4.2 of
+    /// https://eprint.iacr.org/2024/1037.pdf, and committed over the standard domains of twice
+    /// the size of the `H_i`.
+    ///
+    /// # Panics
+    /// This function panics if `num_chunks` is either `0` or `1`. The first case makes no logical
+    /// sense and in the second case, the resulting commitment would not be hiding.
+    fn commit_quotient(
+        &self,
+        quotient_domain: Self::Domain,
+        quotient_evaluations: RowMajorMatrix<Val>,
+        num_chunks: usize,
+    ) -> (Self::Commitment, Self::ProverData) {
+        assert!(num_chunks > 1);
+
+        let evaluations = quotient_domain.split_evals(num_chunks, quotient_evaluations);
+        let domains = quotient_domain.split_domains(num_chunks);
+
+        let cis = get_zp_cis(&domains);
+        let last_chunk = num_chunks - 1;
+        let last_chunk_ci_inv = cis[last_chunk].inverse();
+
+        let mut rng = self.rng.borrow_mut();
+        let randomized_evaluations: Vec<RowMajorMatrix<Val>> = evaluations
+            .into_iter()
+            .map(|mat| add_random_cols(mat, self.num_random_codewords, &mut *rng))
+            .collect();
+
+        // Let q'_i(X) = q_i(X) + v_H_i(X) * t_i(X) where t_i(X) is random, for 1 <= i < d, and
+        // q'_d(X) = q_d(X) - v_H_d(X) / c_d * \sum c_i t_i(X), where the c_i are the Lagrange
+        // normalization constants. The masks are all given by their evaluations over the same
+        // domain, so that we can take linear combinations of them pointwise.
+        let h = randomized_evaluations[0].height();
+        let w = randomized_evaluations[0].width();
+        let mask_domain = CircleDomain::standard(domains[0].log_n);
+        let mut masks: Vec<RowMajorMatrix<Val>> = (0..last_chunk)
+            .map(|_| RowMajorMatrix::rand(&mut *rng, h, w))
+            .collect();
+        let mut last_mask = RowMajorMatrix::new(Val::zero_vec(h * w), w);
+        for (&ci, mask) in cis.iter().zip(&masks) {
+            let mul_coeff = ci * last_chunk_ci_inv;
+            for (last, &t) in last_mask.values.iter_mut().zip(&mask.values) {
+                *last -= mul_coeff * t;
+            }
+        }
+        masks.push(last_mask);
+
+        let ldes = izip!(domains, randomized_evaluations, masks)
+            .map(|(domain, evals, mask)| {
+                let ext_domain = CircleDomain::standard(domain.log_n + 1);
+                (
+                    ext_domain,
+                    mask_evaluations(domain, evals, mask_domain, mask, ext_domain),
+                )
+            })
+            .collect_vec();
+
+        Pcs::<Challenge, Challenger>::commit(&self.inner, ldes)
+    }
+
+    fn get_evaluations_on_domain<'a>(
+        &self,
+        prover_data: &'a Self::ProverData,
+        idx: usize,
+        domain: Self::Domain,
+    ) -> Self::EvaluationsOnDomain<'a> {
+        let inner_evals = <CirclePcs<Val, InputMmcs, FriMmcs> as Pcs<
+            Challenge,
+            Challenger,
+        >>::get_evaluations_on_domain(
+            &self.inner, prover_data, idx, domain
+        );
+        let inner_width = inner_evals.width();
+        // Truncate off the columns representing random codewords we added in `commit` above.
+        // The unwrap is safe as inner_width - self.num_random_codewords <= inner_width.
+        HorizontallyTruncated::new(inner_evals, inner_width - self.num_random_codewords).unwrap()
+    }
+
+    fn open(
+        &self,
+        // For each round,
+        rounds: Vec<(
+            &Self::ProverData,
+            // for each matrix,
+            Vec<
+                // points to open
+                Vec<Challenge>,
+            >,
+        )>,
+        challenger: &mut Challenger,
+    ) -> (OpenedValues<Challenge>, Self::Proof) {
+        let (mut inner_opened_values, inner_proof) = self.inner.open(rounds, challenger);
+
+        // inner_opened_values includes opened values for the random codewords. Those should be
+        // hidden from our caller, so we split them off and store them in the proof.
+        let opened_values_rand = inner_opened_values
+            .iter_mut()
+            .map(|opened_values_for_round| {
+                opened_values_for_round
+                    .iter_mut()
+                    .map(|opened_values_for_mat| {
+                        opened_values_for_mat
+                            .iter_mut()
+                            .map(|opened_values_for_point| {
+                                let split =
+                                    opened_values_for_point.len() - self.num_random_codewords;
+                                opened_values_for_point.drain(split..).collect()
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        (inner_opened_values, (opened_values_rand, inner_proof))
+    }
+
+    fn verify(
+        &self,
+        // For each round:
+        mut rounds: Vec<(
+            Self::Commitment,
+            // for each matrix:
+            Vec<(
+                // its domain,
+                Self::Domain,
+                // for each point:
+                Vec<(
+                    // the point,
+                    Challenge,
+                    // values at the point
+                    Vec<Challenge>,
+                )>,
+            )>,
+        )>,
+        proof: &Self::Proof,
+        challenger: &mut Challenger,
+    ) -> Result<(), Self::Error> {
+        let (opened_values_for_rand_cws, inner_proof) = proof;
+        // Merge the opened values of the random codewords back into `rounds`, undoing the split
+        // done in `open`, so that the inner PCS can check them.
+        for (round, rand_round) in zip_eq(
+            rounds.iter_mut(),
+            opened_values_for_rand_cws,
+            FriError::InvalidProofShape,
+        )? {
+            for (mat, rand_mat) in
+                zip_eq(round.1.iter_mut(), rand_round, FriError::InvalidProofShape)?
+            {
+                for (point, rand_point) in
+                    zip_eq(mat.1.iter_mut(), rand_mat, FriError::InvalidProofShape)?
+                {
+                    point.1.extend(rand_point);
+                }
+            }
+        }
+        self.inner.verify(rounds, inner_proof, challenger)
+    }
+
+    fn get_opt_randomization_poly_commitment(
+        &self,
+        ext_trace_domain: Self::Domain,
+    ) -> Option<(Self::Commitment, Self::ProverData)> {
+        let random_vals = RowMajorMatrix::rand(
+            &mut *self.rng.borrow_mut(),
+            ext_trace_domain.size(),
+            self.num_random_codewords + Challenge::DIMENSION,
+        );
+        let extended_domain = <Self as Pcs<Challenge, Challenger>>::natural_domain_for_degree(
+            self,
+            ext_trace_domain.size(),
+        );
+        let r_commit_and_data =
+            Pcs::<Challenge, Challenger>::commit(&self.inner, [(extended_domain, random_vals)]);
+        Some(r_commit_and_data)
+    }
+}
+
+/// Evaluate `p + v_H * t` over `target`, in natural order, where each column `p` is given by its
+/// evaluations over `domain = H` and the matching column of the mask `t` by its evaluations over
+/// `mask_domain`.
+fn mask_evaluations<F: ComplexExtendable>(
+    domain: CircleDomain<F>,
+    evals: RowMajorMatrix<F>,
+    mask_domain: CircleDomain<F>,
+    mask: RowMajorMatrix<F>,
+    target: CircleDomain<F>,
+) -> RowMajorMatrix<F> {
+    let mut evals = CircleEvaluations::from_natural_order(domain, evals)
+        .extrapolate(target)
+        .to_natural_order()
+        .to_row_major_matrix();
+    let mask = CircleEvaluations::from_natural_order(mask_domain, mask)
+        .extrapolate(target)
+        .to_natural_order()
+        .to_row_major_matrix();
+    izip!(evals.rows_mut(), mask.row_slices(), target.points()).for_each(|(row, mask_row, pt)| {
+        let vanishing = domain.vanishing_poly(pt);
+        row.iter_mut()
+            .zip(mask_row)
+            .for_each(|(x, &t)| *x += vanishing * t);
+    });
+    evals
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_mersenne_31::Mersenne31;
+    use rand::SeedableRng;
+    use rand::rngs::SmallRng;
+
+    use super::*;
+
+    type F = Mersenne31;
+
+    #[test]
+    fn masked_evaluations_agree_on_domain() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let log_n = 4;
+        let domain = CircleDomain::<F>::standard(log_n);
+        let target = CircleDomain::standard(log_n + 1);
+        let evals = RowMajorMatrix::<F>::rand(&mut rng, 1 << log_n, 3);
+        let mask = RowMajorMatrix::<F>::rand(&mut rng, 1 << log_n, 3);
+
+        let masked = CircleEvaluations::from_natural_order(
+            target,
+            mask_evaluations(domain, evals.clone(), domain, mask, target),
+        );
+        // The mask doubles the degree, but leaves the evaluations over the domain unchanged.
+        assert_eq!(masked.dim(), 1 << (log_n + 1));
+        for (pt, row) in domain.points().zip(evals.rows()) {
+            assert_eq!(masked.evaluate_at_point(pt), row.collect_vec());
+        }
+    }
+}
@@ -1,11 +1,14 @@
-use alloc::collections::BTreeMap;
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::vec;
 use alloc::vec::Vec;
 use core::marker::PhantomData;
 
 use itertools::{Itertools, izip};
 use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
-use p3_commit::{BatchOpening, BatchOpeningRef, Mmcs, OpenedValues, Pcs, PolynomialSpace};
+use p3_commit::{
+    FriBasedPcs, FriParameters, Mmcs, MultiBatchOpening, MultiBatchOpeningRef, OpenedValues, Pcs,
+    PolynomialSpace,
+};
 use p3_field::extension::ComplexExtendable;
 use p3_field::{ExtensionField, Field};
 use p3_fri::FriConfig;
@@ -52,9 +55,10 @@ pub struct CircleInputProof<
     InputMmcs: Mmcs<Val>,
     FriMmcs: Mmcs<Challenge>,
 > {
-    input_openings: Vec<BatchOpening<Val, InputMmcs>>,
-    first_layer_siblings: Vec<Challenge>,
-    first_layer_proof: FriMmcs::Proof,
+    input_openings: Vec<MultiBatchOpening<Val, InputMmcs>>,
+    /// For each query, the sibling of the queried value in each first layer matrix.
+    first_layer_siblings: Vec<Vec<Challenge>>,
+    first_layer_proof: Vec<FriMmcs::Proof>,
 }
 
 #[derive(Debug)]
@@ -86,6 +90,12 @@ pub struct CirclePcsProof<
     >,
 }
 
+impl<Val: Field, InputMmcs, FriMmcs> FriBasedPcs for CirclePcs<Val, InputMmcs, FriMmcs> {
+    fn fri_parameters(&self) -> FriParameters {
+        self.fri_config.parameters()
+    }
+}
+
 impl<Val, InputMmcs, FriMmcs, Challenge, Challenger> Pcs<Challenge, Challenger>
     for CirclePcs<Val, InputMmcs, FriMmcs>
 where
@@ -298,32 +308,40 @@ where
         let g: CircleFriConfig<Val, Challenge, InputMmcs, FriMmcs> =
             CircleFriGenericConfig(PhantomData);
 
-        let fri_proof = prove(&g, &self.fri_config, fri_input, challenger, |index| {
+        let fri_proof = prove(&g, &self.fri_config, fri_input, challenger, |indices| {
             // CircleFriFolder asks for an extra query index bit, so we use that here to index
             // the first layer fold.
 
-            // Open the input (big opening, lots of columns) at the full index...
+            // Open the input (big opening, lots of columns) at the full indices...
             let input_openings = rounds
                 .iter()
                 .map(|(data, _)| {
                     let log_max_batch_height = log2_strict_usize(self.mmcs.get_max_height(data));
-                    let reduced_index = index >> (log_max_height - log_max_batch_height);
-                    self.mmcs.open_batch(reduced_index, data)
+                    let reduced_indices = indices
+                        .iter()
+                        .map(|index| index >> (log_max_height - log_max_batch_height))
+                        .collect_vec();
+                    self.mmcs.open_multi_batch(&reduced_indices, data)
                 })
                 .collect();
 
-            // We committed to first_layer in pairs, so open the reduced index and include the sibling
-            // as part of the input proof.
+            // We committed to first_layer in pairs, so open the reduced indices and include the
+            // siblings as part of the input proof.
+            let first_layer_indices = indices.iter().map(|index| index >> 1).collect_vec();
             let (first_layer_values, first_layer_proof) = self
                 .fri_config
                 .mmcs
-                .open_batch(index >> 1, &first_layer_data)
+                .open_multi_batch(&first_layer_indices, &first_layer_data)
                 .unpack();
-            let first_layer_siblings = izip!(&first_layer_values, &log_heights)
-                .map(|(v, log_height)| {
-                    let reduced_index = index >> (log_max_height - log_height);
-                    let sibling_index = (reduced_index & 1) ^ 1;
-                    v[sibling_index]
+            let first_layer_siblings = izip!(indices, &first_layer_values)
+                .map(|(index, query_values)| {
+                    izip!(query_values, &log_heights)
+                        .map(|(v, log_height)| {
+                            let reduced_index = index >> (log_max_height - log_height);
+                            let sibling_index = (reduced_index & 1) ^ 1;
+                            v[sibling_index]
+                        })
+                        .collect()
                 })
                 .collect();
             CircleInputProof {
@@ -381,8 +399,10 @@ where
         let bivariate_beta: Challenge = challenger.sample_algebra_element();
 
         // +1 to account for first layer
-        let log_global_max_height =
-            proof.fri_proof.commit_phase_commits.len() + self.fri_config.log_blowup + 1;
+        let log_global_max_height = proof.fri_proof.log_folding_arities().iter().sum::<usize>()
+            + self.fri_config.log_blowup
+            + self.fri_config.log_final_poly_len
+            + 1;
 
         let g: CircleFriConfig<Val, Challenge, InputMmcs, FriMmcs> =
             CircleFriGenericConfig(PhantomData);
@@ -392,9 +412,9 @@ where
             &self.fri_config,
             &proof.fri_proof,
             challenger,
-            |index, input_proof| {
-                // log_height -> (alpha_offset, ro)
-                let mut reduced_openings = BTreeMap::new();
+            |indices, input_proof| {
+                // For each query, log_height -> (alpha_offset, ro)
+                let mut reduced_openings = vec![BTreeMap::new(); indices.len()];
 
                 let CircleInputProof {
                     input_openings,
@@ -415,111 +435,144 @@ where
                         .map(|&height| Dimensions { width: 0, height })
                         .collect_vec();
 
-                    let (dims, idx) = if let Some(log_batch_max_height) =
+                    let (dims, idxs) = if let Some(log_batch_max_height) =
                         batch_heights.iter().max().map(|x| log2_strict_usize(*x))
                     {
                         (
                             &batch_dims[..],
-                            index >> (log_global_max_height - log_batch_max_height),
+                            indices
+                                .iter()
+                                .map(|index| {
+                                    index >> (log_global_max_height - log_batch_max_height)
+                                })
+                                .collect_vec(),
                         )
                     } else {
                         // Empty batch?
-                        (&[][..], 0)
+                        (&[][..], vec![0; indices.len()])
                     };
 
                     self.mmcs
-                        .verify_batch(batch_commit, dims, idx, batch_opening.into())
+                        .verify_multi_batch(batch_commit, dims, &idxs, batch_opening.into())
                         .map_err(InputError::InputMmcsError)?;
 
-                    for (ps_at_x, (mat_domain, mat_points_and_values)) in zip_eq(
-                        &batch_opening.opened_values,
-                        mats,
+                    for ((&index, query_opening), query_reduced_openings) in zip_eq(
+                        zip_eq(
+                            indices,
+                            &batch_opening.opened_values,
+                            InputError::InputShapeError,
+                        )?,
+                        &mut reduced_openings,
                         InputError::InputShapeError,
                     )? {
-                        let log_height = mat_domain.log_n + self.fri_config.log_blowup;
-                        let bits_reduced = log_global_max_height - log_height;
-                        let orig_idx = cfft_permute_index(index >> bits_reduced, log_height);
+                        for (ps_at_x, (mat_domain, mat_points_and_values)) in
+                            zip_eq(query_opening, mats, InputError::InputShapeError)?
+                        {
+                            let log_height = mat_domain.log_n + self.fri_config.log_blowup;
+                            let bits_reduced = log_global_max_height - log_height;
+                            let orig_idx = cfft_permute_index(index >> bits_reduced, log_height);
 
-                        let committed_domain = CircleDomain::standard(log_height);
-                        let x = committed_domain.nth_point(orig_idx);
+                            let committed_domain = CircleDomain::standard(log_height);
+                            let x = committed_domain.nth_point(orig_idx);
 
-                        let (alpha_offset, ro) = reduced_openings
-                            .entry(log_height)
-                            .or_insert((Challenge::ONE, Challenge::ZERO));
-                        let alpha_pow_width_2 = alpha.exp_u64(ps_at_x.len() as u64).square();
+                            let (alpha_offset, ro) = query_reduced_openings
+                                .entry(log_height)
+                                .or_insert((Challenge::ONE, Challenge::ZERO));
+                            let alpha_pow_width_2 = alpha.exp_u64(ps_at_x.len() as u64).square();
 
-                        for (zeta_uni, ps_at_zeta) in mat_points_and_values {
-                            let zeta = Point::from_projective_line(*zeta_uni);
+                            for (zeta_uni, ps_at_zeta) in mat_points_and_values {
+                                let zeta = Point::from_projective_line(*zeta_uni);
 
-                            *ro += *alpha_offset
-                                * deep_quotient_reduce_row(alpha, x, zeta, ps_at_x, ps_at_zeta);
+                                *ro += *alpha_offset
+                                    * deep_quotient_reduce_row(alpha, x, zeta, ps_at_x, ps_at_zeta);
 
-                            *alpha_offset *= alpha_pow_width_2;
+                                *alpha_offset *= alpha_pow_width_2;
+                            }
                         }
                     }
                 }
 
                 // Verify bivariate fold and lambda correction
 
-                let (mut fri_input, fl_dims, fl_leaves): (Vec<_>, Vec<_>, Vec<_>) = zip_eq(
-                    zip_eq(
-                        reduced_openings,
-                        first_layer_siblings,
-                        InputError::InputShapeError,
-                    )?,
-                    &proof.lambdas,
+                let mut fri_inputs = Vec::with_capacity(indices.len());
+                let mut fl_leaves = Vec::with_capacity(indices.len());
+                for ((&index, query_reduced_openings), query_fl_siblings) in zip_eq(
+                    zip_eq(indices, reduced_openings, InputError::InputShapeError)?,
+                    first_layer_siblings,
                     InputError::InputShapeError,
-                )?
-                .map(|(((log_height, (_, ro)), &fl_sib), &lambda)| {
-                    assert!(log_height > 0);
+                )? {
+                    let (mut fri_input, query_fl_leaves): (Vec<_>, Vec<_>) = zip_eq(
+                        zip_eq(
+                            query_reduced_openings,
+                            query_fl_siblings,
+                            InputError::InputShapeError,
+                        )?,
+                        &proof.lambdas,
+                        InputError::InputShapeError,
+                    )?
+                    .map(|(((log_height, (_, ro)), &fl_sib), &lambda)| {
+                        assert!(log_height > 0);
 
-                    let orig_size = log_height - self.fri_config.log_blowup;
-                    let bits_reduced = log_global_max_height - log_height;
-                    let orig_idx = cfft_permute_index(index >> bits_reduced, log_height);
+                        let orig_size = log_height - self.fri_config.log_blowup;
+                        let bits_reduced = log_global_max_height - log_height;
+                        let orig_idx = cfft_permute_index(index >> bits_reduced, log_height);
 
-                    let lde_domain = CircleDomain::standard(log_height);
-                    let p: Point<Val> = lde_domain.nth_point(orig_idx);
+                        let lde_domain = CircleDomain::standard(log_height);
+                        let p: Point<Val> = lde_domain.nth_point(orig_idx);
 
-                    let lambda_corrected = ro - lambda * p.v_n(orig_size);
+                        let lambda_corrected = ro - lambda * p.v_n(orig_size);
 
-                    let mut fl_values = vec![lambda_corrected; 2];
-                    fl_values[((index >> bits_reduced) & 1) ^ 1] = fl_sib;
+                        let mut fl_values = vec![lambda_corrected; 2];
+                        fl_values[((index >> bits_reduced) & 1) ^ 1] = fl_sib;
 
-                    let fri_input = (
-                        // - 1 here is because we have already folded a layer.
-                        log_height - 1,
-                        fold_y_row(
-                            index >> (bits_reduced + 1),
-                            // - 1 here is log_arity.
+                        let fri_input = (
+                            // - 1 here is because we have already folded a layer.
                             log_height - 1,
-                            bivariate_beta,
-                            fl_values.iter().copied(),
-                        ),
-                    );
+                            fold_y_row(
+                                index >> (bits_reduced + 1),
+                                // - 1 here is log_arity.
+                                log_height - 1,
+                                bivariate_beta,
+                                fl_values.iter().copied(),
+                            ),
+                        );
 
-                    let fl_dims = Dimensions {
-                        width: 0,
-                        height: 1 << (log_height - 1),
-                    };
+                        (fri_input, fl_values)
+                    })
+                    .unzip();
 
-                    (fri_input, fl_dims, fl_values)
-                })
-                .multiunzip();
+                    // sort descending
+                    fri_input.reverse();
+
+                    fri_inputs.push(fri_input);
+                    fl_leaves.push(query_fl_leaves);
+                }
 
-                // sort descending
-                fri_input.reverse();
+                // The first layer holds one matrix for each input height, in ascending order.
+                let fl_dims = rounds
+                    .iter()
+                    .flat_map(|(_, mats)| mats)
+                    .map(|(domain, _)| domain.log_n + self.fri_config.log_blowup)
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .map(|log_height| Dimensions {
+                        width: 0,
+                        height: 1 << (log_height - 1),
+                    })
+                    .collect_vec();
+                let fl_indices = indices.iter().map(|index| index >> 1).collect_vec();
 
                 self.fri_config
                     .mmcs
-                    .verify_batch(
+                    .verify_multi_batch(
                         &proof.first_layer_commitment,
                         &fl_dims,
-                        index >> 1,
-                        BatchOpeningRef::new(&fl_leaves, first_layer_proof),
+                        &fl_indices,
+                        MultiBatchOpeningRef::new(&fl_leaves, first_layer_proof),
                     )
                     .map_err(InputError::FirstLayerMmcsError)?;
 
-                Ok(fri_input)
+                Ok(fri_inputs)
             },
         )
     }
@@ -540,10 +593,11 @@ mod tests {
 
     use super::*;
 
-    #[test]
-    fn circle_pcs() {
-        // Very simple pcs test. More rigorous tests in p3_fri/tests/pcs.
-
+    fn do_test_circle_pcs(
+        log_final_poly_len: usize,
+        log_folding_arities: Vec<usize>,
+        num_rounds: usize,
+    ) {
         let mut rng = SmallRng::seed_from_u64(0);
 
         type Val = Mersenne31;
@@ -565,7 +619,11 @@ mod tests {
 
         type Challenger = SerializingChallenger32<Val, HashChallenger<u8, ByteHash, 32>>;
 
-        let fri_config = create_test_fri_config(challenge_mmcs, 0);
+        let fri_config = FriConfig {
+            log_folding_arity: log_folding_arities.last().copied().unwrap_or(1),
+            log_folding_arities,
+            ..create_test_fri_config(challenge_mmcs, log_final_poly_len)
+        };
 
         type Pcs = CirclePcs<Val, ValMmcs, ChallengeMmcs>;
         let pcs = Pcs {
@@ -574,29 +632,56 @@ mod tests {
             _phantom: PhantomData,
         };
 
-        let log_n = 10;
-
-        let d = <Pcs as p3_commit::Pcs<Challenge, Challenger>>::natural_domain_for_degree(
-            &pcs,
-            1 << log_n,
-        );
-
-        let evals = RowMajorMatrix::rand(&mut rng, 1 << log_n, 1);
+        // Matrices of different heights, which are rolled into FRI at different rounds.
+        let domains_and_evals = [10, 7].map(|log_n| {
+            let d = <Pcs as p3_commit::Pcs<Challenge, Challenger>>::natural_domain_for_degree(
+                &pcs,
+                1 << log_n,
+            );
+            (d, RowMajorMatrix::rand(&mut rng, 1 << log_n, 1))
+        });
+        let domains = domains_and_evals.clone().map(|(d, _)| d);
 
         let (comm, data) =
-            <Pcs as p3_commit::Pcs<Challenge, Challenger>>::commit(&pcs, [(d, evals)]);
+            <Pcs as p3_commit::Pcs<Challenge, Challenger>>::commit(&pcs, domains_and_evals);
 
         let zeta: Challenge = rng.random();
 
         let mut chal = Challenger::from_hasher(vec![], byte_hash);
-        let (values, proof) = pcs.open(vec![(&data, vec![vec![zeta]])], &mut chal);
+        let (values, proof) = pcs.open(vec![(&data, vec![vec![zeta]; 2])], &mut chal);
+        assert_eq!(proof.fri_proof.commit_phase_commits.len(), num_rounds);
 
         let mut chal = Challenger::from_hasher(vec![], byte_hash);
-        pcs.verify(
-            vec![(comm, vec![(d, vec![(zeta, values[0][0][0].clone())])])],
-            &proof,
-            &mut chal,
-        )
-        .expect("verify err");
+        let claims = domains
+            .into_iter()
+            .zip(&values[0])
+            .map(|(d, mat_values)| (d, vec![(zeta, mat_values[0].clone())]))
+            .collect();
+        pcs.verify(vec![(comm, claims)], &proof, &mut chal)
+            .expect("verify err");
+    }
+
+    #[test]
+    fn circle_pcs() {
+        // Very simple pcs test. More rigorous tests in p3_fri/tests/pcs.
+        do_test_circle_pcs(0, vec![], 9);
+    }
+
+    #[test]
+    fn circle_pcs_higher_arity() {
+        // Folding from a domain of size 2^11, with an input rolled in at 2^8, down to 2^2. Rounds
+        // are shortened so as not to fold past either.
+        do_test_circle_pcs(0, vec![2], 5);
+        do_test_circle_pcs(0, vec![4], 3);
+        // The last entry of the schedule is used for every later round.
+        do_test_circle_pcs(0, vec![3, 1, 2], 5);
+    }
+
+    #[test]
+    fn circle_pcs_final_poly() {
+        // Stopping at a final polynomial of 2^3 coefficients, over a domain of size 2^5.
+        do_test_circle_pcs(3, vec![], 6);
+        do_test_circle_pcs(3, vec![2], 4);
+        do_test_circle_pcs(1, vec![], 8);
     }
 }
@@ -2,6 +2,7 @@ use alloc::vec::Vec;
 
 use p3_commit::Mmcs;
 use p3_field::Field;
+use p3_util::log2_ceil_usize;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -11,32 +12,40 @@ use serde::{Deserialize, Serialize};
 ))]
 pub struct CircleFriProof<F: Field, M: Mmcs<F>, Witness, InputProof> {
     pub commit_phase_commits: Vec<M::Commitment>,
-    pub query_proofs: Vec<CircleQueryProof<F, M, InputProof>>,
-    // This could become Vec<FC::Challenge> if this library was generalized to support non-constant
-    // final polynomials.
-    pub final_poly: F,
+    /// The openings of the inputs at all queried locations.
+    pub input_proof: InputProof,
+    /// For each commit phase commitment, this contains openings of a commit phase codeword at all
+    /// queried locations, along with a single opening proof for them.
+    pub commit_phase_openings: Vec<CircleCommitPhaseProofStep<F, M>>,
+    /// The coefficients of the final polynomial, in the x-coordinate of the final domain.
+    pub final_poly: Vec<F>,
     pub pow_witness: Witness,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-#[serde(bound(
-    serialize = "InputProof: Serialize",
-    deserialize = "InputProof: Deserialize<'de>",
-))]
-pub struct CircleQueryProof<F: Field, M: Mmcs<F>, InputProof> {
-    pub input_proof: InputProof,
-    /// For each commit phase commitment, this contains openings of a commit phase codeword at the
-    /// queried location, along with an opening proof.
-    pub commit_phase_openings: Vec<CircleCommitPhaseProofStep<F, M>>,
+impl<F: Field, M: Mmcs<F>, Witness, InputProof> CircleFriProof<F, M, Witness, InputProof> {
+    /// The log of the folding arity of each commit phase round, as claimed by the openings of the
+    /// first query, or folding in half if there are no queries.
+    ///
+    /// The verifier checks every query against these arities and its own configuration.
+    pub fn log_folding_arities(&self) -> Vec<usize> {
+        self.commit_phase_openings
+            .iter()
+            .map(|step| {
+                step.sibling_values
+                    .first()
+                    .map_or(1, |siblings| log2_ceil_usize(siblings.len() + 1))
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(bound = "")]
 pub struct CircleCommitPhaseProofStep<F: Field, M: Mmcs<F>> {
-    /// The opening of the commit phase codeword at the sibling location.
-    // This may change to Vec<FC::Challenge> if the library is generalized to support other FRI
-    // folding arities besides 2, meaning that there can be multiple siblings.
-    pub sibling_value: F,
+    /// For each query, the openings of the commit phase codeword at the sibling locations, i.e.
+    /// the queried row of the committed matrix, whose width is the folding arity, without the
+    /// queried location.
+    pub sibling_values: Vec<Vec<F>>,
 
-    pub opening_proof: M::Proof,
+    pub opening_proof: Vec<M::Proof>,
 }
@@ -130,6 +130,21 @@ pub trait PolynomialSpace: Copy {
     ///
     /// Note that these may not be normalized.
     fn selectors_on_coset(&self, coset: Self) -> LagrangeSelectors<Vec<Self::Val>>;
+
+    /// Evaluate a periodic column at the given point.
+    ///
+    /// A periodic column is the polynomial of degree less than `self.size()` which takes the value
+    /// `values[i % values.len()]` at the `i`'th point of the space. `values.len()` must be a power
+    /// of two no larger than `self.size()`.
+    fn periodic_column_at_point<Ext: ExtensionField<Self::Val>>(
+        &self,
+        values: &[Self::Val],
+        point: Ext,
+    ) -> Ext;
+
+    /// Evaluate a periodic column, as in [`periodic_column_at_point`](Self::periodic_column_at_point),
+    /// at all points of the given disjoint `PolynomialSpace`.
+    fn periodic_column_on_coset(&self, values: &[Self::Val], coset: Self) -> Vec<Self::Val>;
 }
 
 impl<Val: TwoAdicField> PolynomialSpace for TwoAdicMultiplicativeCoset<Val> {
@@ -281,4 +296,68 @@ impl<Val: TwoAdicField> PolynomialSpace for TwoAdicMultiplicativeCoset<Val> {
                 .collect(),
         }
     }
+
+    /// A periodic column with period `p` on `gH` is `Q((g^{-1}X)^{|H|/p})`, where `Q` is the
+    /// polynomial interpolating `values` over the subgroup of order `p`, since `(g^{-1}gh^i)^{|H|/p}`
+    /// is the `i % p`'th element of that subgroup.
+    fn periodic_column_at_point<Ext: ExtensionField<Val>>(
+        &self,
+        values: &[Val],
+        point: Ext,
+    ) -> Ext {
+        let log_period = log2_strict_usize(values.len());
+        assert!(log_period <= self.log_size());
+        let y = (point * self.shift_inverse()).exp_power_of_2(self.log_size() - log_period);
+        interpolate_subgroup_at_point(values, y)
+    }
+
+    /// The points of the coset `sK` are mapped to `(g^{-1}s)^{|H|/p} K^{|H|/p}`, so the column only
+    /// needs to be interpolated at `|K| p / |H|` distinct points, which then repeat cyclically.
+    fn periodic_column_on_coset(&self, values: &[Val], coset: Self) -> Vec<Val> {
+        let log_period = log2_strict_usize(values.len());
+        assert!(log_period <= self.log_size());
+        assert!(coset.log_size() >= self.log_size());
+        let log_stride = self.log_size() - log_period;
+        let shift = (coset.shift() * self.shift_inverse()).exp_power_of_2(log_stride);
+        let generator = coset.subgroup_generator().exp_power_of_2(log_stride);
+        let distinct = cyclic_subgroup_coset_known_order(
+            generator,
+            shift,
+            1 << (coset.log_size() - log_stride),
+        )
+        .map(|y| interpolate_subgroup_at_point(values, y))
+        .collect_vec();
+        distinct.into_iter().cycle().take(coset.size()).collect()
+    }
+}
+
+/// Evaluate at `point` the polynomial taking the value `values[i]` at `w^i`, where `w` is the
+/// generator of the two-adic subgroup of order `values.len()`, using the barycentric formula
+///
+/// `Q(X) = (X^p - 1)/p \sum_i values[i] w^i / (X - w^i)`.
+///
+/// The formula divides by `X - w^i`, so if `point` lies in the subgroup, the matching entry of
+/// `values` is returned directly.
+fn interpolate_subgroup_at_point<F: TwoAdicField, Ext: ExtensionField<F>>(
+    values: &[F],
+    point: Ext,
+) -> Ext {
+    let log_period = log2_strict_usize(values.len());
+    let subgroup = F::two_adic_generator(log_period)
+        .powers()
+        .take(values.len())
+        .collect_vec();
+    if let Some(i) = subgroup.iter().position(|&w| point == Ext::from(w)) {
+        return values[i].into();
+    }
+    let denominators = subgroup.iter().map(|&w| point - w).collect_vec();
+    let inverses = batch_multiplicative_inverse(&denominators);
+    let sum = values
+        .iter()
+        .zip(&subgroup)
+        .zip(inverses)
+        .map(|((&v, &w), inv)| inv * (v * w))
+        .sum::<Ext>();
+    let z = point.exp_power_of_2(log_period) - Ext::ONE;
+    z * sum * F::from_usize(values.len()).inverse()
 }
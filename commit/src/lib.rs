@@ -7,7 +7,9 @@ extern crate alloc;
 mod adapters;
 mod domain;
 mod mmcs;
+mod multilinear_pcs;
 mod pcs;
+mod soundness;
 
 #[cfg(any(test, feature = "test-utils"))]
 pub mod testing;
@@ -15,4 +17,6 @@ pub mod testing;
 pub use adapters::*;
 pub use domain::*;
 pub use mmcs::*;
+pub use multilinear_pcs::*;
 pub use pcs::*;
+pub use soundness::*;
@@ -20,7 +20,7 @@ pub trait Mmcs<T: Send + Sync + Clone>: Clone {
     type ProverData<M>;
     type Commitment: Clone + Serialize + DeserializeOwned;
     type Proof: Clone + Serialize + DeserializeOwned;
-    type Error: Debug;
+    type Error: Debug + From<WrongNumberOfOpenings>;
 
     /// Commits to a batch of matrices at once and returns both the commitment and associated prover data.
     ///
@@ -88,6 +88,31 @@ pub trait Mmcs<T: Send + Sync + Clone>: Clone {
         prover_data: &Self::ProverData<M>,
     ) -> BatchOpening<T, Self>;
 
+    /// Opens the rows at several indices from each matrix in the batch.
+    ///
+    /// Each index is interpreted as in [`open_batch`]. By default, every index is opened separately
+    /// with its own proof. An MMCS may override this so that authentication data shared between
+    /// the indices, such as common Merkle tree nodes, only appears in the proof of one of them.
+    ///
+    /// # Parameters
+    /// - `indices`: The global row indices (relative to max height). Duplicates are allowed.
+    /// - `prover_data`: Prover data returned from [`commit`] or related methods.
+    ///
+    /// # Returns
+    /// A [`MultiBatchOpening`] containing, for each index in order, the opened rows of every
+    /// matrix and a proof.
+    fn open_multi_batch<M: Matrix<T>>(
+        &self,
+        indices: &[usize],
+        prover_data: &Self::ProverData<M>,
+    ) -> MultiBatchOpening<T, Self> {
+        let (opened_values, opening_proofs) = indices
+            .iter()
+            .map(|&index| self.open_batch(index, prover_data).unpack())
+            .unzip();
+        MultiBatchOpening::new(opened_values, opening_proofs)
+    }
+
     /// Returns references to all matrices originally committed to in the batch.
     ///
     /// This allows access to the underlying data for inspection or additional logic.
@@ -149,8 +174,50 @@ pub trait Mmcs<T: Send + Sync + Clone>: Clone {
         index: usize,
         batch_opening: BatchOpeningRef<T, Self>,
     ) -> Result<(), Self::Error>;
+
+    /// Verifies an opening of several row indices at once against the original commitment.
+    ///
+    /// This is the verifier-side analogue of [`open_multi_batch`]. By default, the opening of every
+    /// index is verified separately with [`verify_batch`].
+    ///
+    /// # Parameters
+    /// - `commit`: The original commitment.
+    /// - `dimensions`: Dimensions of the committed matrices, in order.
+    /// - `indices`: The global row indices that were opened, in the order they were opened.
+    /// - `batch_opening`: A reference to the values, one batch of rows per index, and the proofs.
+    ///
+    /// # Returns
+    /// `Ok(())` if the opening is valid; otherwise returns a verification error.
+    fn verify_multi_batch(
+        &self,
+        commit: &Self::Commitment,
+        dimensions: &[Dimensions],
+        indices: &[usize],
+        batch_opening: MultiBatchOpeningRef<T, Self>,
+    ) -> Result<(), Self::Error> {
+        let (opened_values, opening_proofs) = batch_opening.unpack();
+        if opened_values.len() != indices.len() || opening_proofs.len() != indices.len() {
+            return Err(WrongNumberOfOpenings.into());
+        }
+        for ((&index, opened_values), opening_proof) in
+            indices.iter().zip(opened_values).zip(opening_proofs)
+        {
+            self.verify_batch(
+                commit,
+                dimensions,
+                index,
+                BatchOpeningRef::new(opened_values, opening_proof),
+            )?;
+        }
+        Ok(())
+    }
 }
 
+/// The error returned when an opening of several indices does not hold one batch of rows and one
+/// proof per index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrongNumberOfOpenings;
+
 /// A Batched opening proof.
 ///
 /// Contains a collection of opened values at a Merkle proof for those openings.
@@ -226,3 +293,75 @@ impl<'a, T: Send + Sync + Clone, InputMmcs: Mmcs<T>> From<&'a BatchOpening<T, In
         Self::new(&batch_opening.opened_values, &batch_opening.opening_proof)
     }
 }
+
+/// A batched opening proof of several row indices at once.
+///
+/// Contains the opened values and a proof for each index. An MMCS which overrides
+/// [`Mmcs::open_multi_batch`] may leave authentication data shared between indices out of all but
+/// one of their proofs, so a single proof need not verify on its own.
+///
+/// Primarily used by the prover.
+#[derive(Serialize, Deserialize, Clone)]
+// Enable Serialize/Deserialize whenever T supports it.
+#[serde(bound(serialize = "T: Serialize"))]
+#[serde(bound(deserialize = "T: DeserializeOwned"))]
+pub struct MultiBatchOpening<T: Send + Sync + Clone, InputMmcs: Mmcs<T>> {
+    /// For each opened index, the opened row values from each matrix in the batch.
+    pub opened_values: Vec<Vec<Vec<T>>>,
+    /// The proofs showing the values are valid openings, one per index.
+    pub opening_proof: Vec<InputMmcs::Proof>,
+}
+
+impl<T: Send + Sync + Clone, InputMmcs: Mmcs<T>> MultiBatchOpening<T, InputMmcs> {
+    /// Creates a new multi-index batch opening proof.
+    #[inline]
+    pub fn new(opened_values: Vec<Vec<Vec<T>>>, opening_proof: Vec<InputMmcs::Proof>) -> Self {
+        Self {
+            opened_values,
+            opening_proof,
+        }
+    }
+
+    /// Unpacks the multi-index batch opening proof into its components.
+    #[inline]
+    pub fn unpack(self) -> (Vec<Vec<Vec<T>>>, Vec<InputMmcs::Proof>) {
+        (self.opened_values, self.opening_proof)
+    }
+}
+
+/// A reference to a batched opening proof of several row indices at once.
+///
+/// Primarily used by the verifier.
+#[derive(Copy, Clone)]
+pub struct MultiBatchOpeningRef<'a, T: Send + Sync + Clone, InputMmcs: Mmcs<T>> {
+    /// Reference to the opened row values, for each opened index.
+    pub opened_values: &'a [Vec<Vec<T>>],
+    /// Reference to the proofs used for verification, one per index.
+    pub opening_proof: &'a [InputMmcs::Proof],
+}
+
+impl<'a, T: Send + Sync + Clone, InputMmcs: Mmcs<T>> MultiBatchOpeningRef<'a, T, InputMmcs> {
+    /// Creates a new reference to a multi-index batch opening proof.
+    #[inline]
+    pub fn new(opened_values: &'a [Vec<Vec<T>>], opening_proof: &'a [InputMmcs::Proof]) -> Self {
+        Self {
+            opened_values,
+            opening_proof,
+        }
+    }
+
+    /// Unpacks the multi-index batch opening proof into its components.
+    #[inline]
+    pub fn unpack(&self) -> (&'a [Vec<Vec<T>>], &'a [InputMmcs::Proof]) {
+        (self.opened_values, self.opening_proof)
+    }
+}
+
+impl<'a, T: Send + Sync + Clone, InputMmcs: Mmcs<T>> From<&'a MultiBatchOpening<T, InputMmcs>>
+    for MultiBatchOpeningRef<'a, T, InputMmcs>
+{
+    #[inline]
+    fn from(batch_opening: &'a MultiBatchOpening<T, InputMmcs>) -> Self {
+        Self::new(&batch_opening.opened_values, &batch_opening.opening_proof)
+    }
+}
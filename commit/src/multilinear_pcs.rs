@@ -0,0 +1,58 @@
+//! Traits for multilinear polynomial commitment schemes.
+
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+use p3_field::{ExtensionField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// A polynomial commitment scheme for multilinear polynomials, defined by their evaluations over
+/// the Boolean hypercube.
+///
+/// A matrix of height `2^n` holds a batch of polynomials in `n` variables, one in each column. Row
+/// `i` holds their evaluations at the point of `{0, 1}^n` whose `j`-th coordinate is bit `j` of `i`.
+pub trait MultilinearPcs<Challenge, Challenger>
+where
+    Challenge: ExtensionField<Self::Val>,
+{
+    /// The field the committed evaluations live in.
+    type Val: Field;
+
+    /// The commitment that's sent to the verifier.
+    type Commitment: Clone + Serialize + DeserializeOwned;
+
+    /// Data that the prover stores for committed polynomials, to help the prover with opening.
+    type ProverData;
+
+    /// The opening argument.
+    type Proof: Clone + Serialize + DeserializeOwned;
+
+    /// The type of a proof verification error.
+    type Error: Debug;
+
+    /// Commit to the columns of `evals`, whose height must be a power of two.
+    fn commit(&self, evals: RowMajorMatrix<Self::Val>) -> (Self::Commitment, Self::ProverData);
+
+    /// Open every committed polynomial at `point`, which must have one coordinate for each
+    /// variable.
+    ///
+    /// Returns the evaluation of each column at `point` along with a proof of their correctness.
+    fn open(
+        &self,
+        prover_data: &Self::ProverData,
+        point: &[Challenge],
+        challenger: &mut Challenger,
+    ) -> (Vec<Challenge>, Self::Proof);
+
+    /// Verify that the committed polynomials evaluate to `values` at `point`.
+    fn verify(
+        &self,
+        commitment: &Self::Commitment,
+        point: &[Challenge],
+        values: &[Challenge],
+        proof: &Self::Proof,
+        challenger: &mut Challenger,
+    ) -> Result<(), Self::Error>;
+}
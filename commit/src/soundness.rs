@@ -0,0 +1,22 @@
+//! The parameters of the low-degree test underlying a PCS, from which its soundness is bounded.
+
+/// The parameters of a FRI low-degree test which its soundness error depends on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FriParameters {
+    /// The log of the inverse of the rate of the code, i.e. of the blowup factor.
+    pub log_blowup: usize,
+    /// The log of the number of coefficients of the polynomial FRI stops folding at.
+    pub log_final_poly_len: usize,
+    /// The log of the largest folding arity of any commit phase round.
+    pub log_folding_arity: usize,
+    /// The number of queries of the query phase.
+    pub num_queries: usize,
+    /// The number of bits of grinding required before the query phase.
+    pub proof_of_work_bits: usize,
+}
+
+/// A PCS whose openings are checked by a FRI low-degree test.
+pub trait FriBasedPcs {
+    /// The parameters of the FRI low-degree test.
+    fn fri_parameters(&self) -> FriParameters;
+}
@@ -9,7 +9,7 @@ use p3_poseidon2::GenericPoseidon2LinearLayers;
 use p3_poseidon2_air::{Poseidon2Air, VectorizedPoseidon2Air};
 use p3_uni_stark::{
     DebugConstraintBuilder, ProverConstraintFolder, StarkGenericConfig, SymbolicAirBuilder,
-    SymbolicExpression, VerifierConstraintFolder,
+    SymbolicExpression, Val, VerifierConstraintFolder,
 };
 use rand::distr::StandardUniform;
 use rand::prelude::Distribution;
@@ -49,7 +49,7 @@ pub enum ProofObjective<
 /// the output of some number of hashes using a given hash function.
 pub trait ExampleHashAir<F: Field, SC: StarkGenericConfig>:
     BaseAir<F>
-    + for<'a> Air<DebugConstraintBuilder<'a, F>>
+    + for<'a> Air<DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>
     + Air<SymbolicAirBuilder<F>>
     + for<'a> Air<ProverConstraintFolder<'a, SC>>
     + for<'a> Air<VerifierConstraintFolder<'a, SC>>
@@ -0,0 +1,497 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter;
+use core::marker::PhantomData;
+
+use itertools::Itertools;
+use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
+use p3_commit::{Mmcs, MultiBatchOpening, MultiBatchOpeningRef, MultilinearPcs};
+use p3_dft::TwoAdicSubgroupDft;
+use p3_field::{ExtensionField, Field, PackedFieldExtension, TwoAdicField, dot_product};
+use p3_matrix::bitrev::BitReversibleMatrix;
+use p3_matrix::dense::{RowMajorMatrix, RowMajorMatrixView};
+use p3_matrix::{Dimensions, Matrix};
+use p3_maybe_rayon::prelude::*;
+use p3_util::log2_strict_usize;
+use p3_util::zip_eq::zip_eq;
+use serde::{Deserialize, Serialize};
+use tracing::{info_span, instrument};
+
+use crate::{CommitPhaseProofStep, FriConfig, FriGenericConfig, TwoAdicFriGenericConfig};
+
+/// The Basefold multilinear PCS, see https://eprint.iacr.org/2023/1705.
+///
+/// The coefficients of a multilinear polynomial `f` in the monomial basis are read as the
+/// coefficients of a univariate polynomial, whose Reed-Solomon codeword is committed. Folding this
+/// codeword in half with a challenge `r`, as in FRI, gives the codeword of `f` with its first
+/// variable fixed to `r`. An opening runs a sumcheck for `f(z) = sum_b eq(z, b) f(b)` and uses each
+/// of its challenges to fold the codeword, so the final constant must be the evaluation of `f` at
+/// the sumcheck's random point.
+///
+/// Every opening folds all the way down to a constant, so `log_final_poly_len` must be zero and
+/// all folds are in half.
+#[derive(Debug)]
+pub struct BasefoldPcs<Val, Dft, InputMmcs, FriMmcs> {
+    pub(crate) dft: Dft,
+    pub(crate) mmcs: InputMmcs,
+    pub(crate) fri: FriConfig<FriMmcs>,
+    _phantom: PhantomData<Val>,
+}
+
+impl<Val, Dft, InputMmcs, FriMmcs> BasefoldPcs<Val, Dft, InputMmcs, FriMmcs> {
+    pub fn new(dft: Dft, mmcs: InputMmcs, fri: FriConfig<FriMmcs>) -> Self {
+        assert_eq!(
+            fri.log_final_poly_len, 0,
+            "Basefold folds down to a constant"
+        );
+        assert!(
+            fri.log_folding_arity == 1
+                && fri
+                    .log_folding_arities
+                    .iter()
+                    .all(|&log_arity| log_arity == 1),
+            "Basefold folds in half"
+        );
+        Self {
+            dft,
+            mmcs,
+            fri,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+pub struct BasefoldProverData<Val: Send + Sync + Clone, InputMmcs: Mmcs<Val>> {
+    /// The committed evaluations over the hypercube.
+    evals: RowMajorMatrix<Val>,
+    /// The committed codewords, in bit-reversed order.
+    codewords: InputMmcs::ProverData<RowMajorMatrix<Val>>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound(
+    serialize = "Witness: Serialize, InputProof: Serialize",
+    deserialize = "Witness: Deserialize<'de>, InputProof: Deserialize<'de>"
+))]
+pub struct BasefoldProof<F: Field, M: Mmcs<F>, Witness, InputProof> {
+    /// The evaluations at 0, 1 and 2 of the round polynomial of each round of the sumcheck.
+    pub sumcheck_polys: Vec<[F; 3]>,
+    /// The commitments to the folded codewords, except for the last one, which is constant.
+    pub commit_phase_commits: Vec<M::Commitment>,
+    /// The constant value of the last folded codeword.
+    pub final_value: F,
+    pub pow_witness: Witness,
+    /// The openings of the committed codewords at each queried location and its sibling.
+    pub input_proof: InputProof,
+    /// The openings of each folded codeword at all queried locations.
+    pub commit_phase_openings: Vec<CommitPhaseProofStep<F, M>>,
+}
+
+#[derive(Debug)]
+pub enum BasefoldError<CommitMmcsErr, InputError> {
+    InvalidProofShape,
+    CommitPhaseMmcsError(CommitMmcsErr),
+    InputError(InputError),
+    /// A round polynomial of the sumcheck, or its final claim, is inconsistent.
+    SumcheckMismatch,
+    FinalValueMismatch,
+    InvalidPowWitness,
+}
+
+impl<Val, Dft, InputMmcs, FriMmcs, Challenge, Challenger> MultilinearPcs<Challenge, Challenger>
+    for BasefoldPcs<Val, Dft, InputMmcs, FriMmcs>
+where
+    Val: TwoAdicField,
+    Dft: TwoAdicSubgroupDft<Val>,
+    InputMmcs: Mmcs<Val>,
+    FriMmcs: Mmcs<Challenge>,
+    Challenge: TwoAdicField + ExtensionField<Val>,
+    Challenger:
+        FieldChallenger<Val> + CanObserve<FriMmcs::Commitment> + GrindingChallenger<Witness = Val>,
+{
+    type Val = Val;
+    type Commitment = InputMmcs::Commitment;
+    type ProverData = BasefoldProverData<Val, InputMmcs>;
+    type Proof = BasefoldProof<Challenge, FriMmcs, Val, MultiBatchOpening<Val, InputMmcs>>;
+    type Error = BasefoldError<FriMmcs::Error, InputMmcs::Error>;
+
+    fn commit(&self, evals: RowMajorMatrix<Val>) -> (Self::Commitment, Self::ProverData) {
+        let log_height = log2_strict_usize(evals.height());
+        let mut coeffs = evals.clone();
+        evals_to_coeffs(&mut coeffs);
+        coeffs.values.resize(
+            coeffs.width() << (log_height + self.fri.log_blowup),
+            Val::ZERO,
+        );
+        // The codewords are evaluations over a subgroup rather than a coset, as folding relies on
+        // the codeword being that of the coefficients themselves.
+        let codewords = self
+            .dft
+            .dft_batch(coeffs)
+            .bit_reverse_rows()
+            .to_row_major_matrix();
+        let (commitment, codewords) = self.mmcs.commit_matrix(codewords);
+        (commitment, BasefoldProverData { evals, codewords })
+    }
+
+    #[instrument(name = "Basefold open", skip_all)]
+    fn open(
+        &self,
+        prover_data: &Self::ProverData,
+        point: &[Challenge],
+        challenger: &mut Challenger,
+    ) -> (Vec<Challenge>, Self::Proof) {
+        let evals = &prover_data.evals;
+        let num_vars = point.len();
+        assert_eq!(evals.height(), 1 << num_vars);
+        let log_height = num_vars + self.fri.log_blowup;
+
+        let mut eq = eq_evals(point);
+        let values = evals.columnwise_dot_product(&eq);
+        for &value in &values {
+            challenger.observe_algebra_element(value);
+        }
+
+        // Batch combination challenge
+        let alpha: Challenge = challenger.sample_algebra_element();
+        let packed_alpha_powers =
+            Challenge::ExtensionPacking::packed_ext_powers_capped(alpha, evals.width())
+                .collect_vec();
+        let mut reduced_evals = evals
+            .rowwise_packed_dot_product::<Challenge>(&packed_alpha_powers)
+            .collect::<Vec<_>>();
+        let codewords = self.mmcs.get_matrices(&prover_data.codewords)[0];
+        let mut folded = info_span!("reduce codewords").in_scope(|| {
+            codewords
+                .rowwise_packed_dot_product::<Challenge>(&packed_alpha_powers)
+                .collect::<Vec<_>>()
+        });
+
+        let g: TwoAdicFriGenericConfig<(), ()> = TwoAdicFriGenericConfig(PhantomData);
+        let mut sumcheck_polys = vec![];
+        let mut commits = vec![];
+        let mut data = vec![];
+
+        for round in 0..num_vars {
+            let _guard = info_span!("Basefold round", round).entered();
+
+            // The round polynomial is `h(t) = sum_b eq(t, b) f(t, b)` over the remaining
+            // variables `b`, which has degree 2 in `t`.
+            let sumcheck_poly = reduced_evals
+                .par_chunks_exact(2)
+                .zip(eq.par_chunks_exact(2))
+                .par_fold_reduce(
+                    || [Challenge::ZERO; 3],
+                    |acc, (f, eq)| {
+                        let f_2 = f[1].double() - f[0];
+                        let eq_2 = eq[1].double() - eq[0];
+                        [
+                            acc[0] + f[0] * eq[0],
+                            acc[1] + f[1] * eq[1],
+                            acc[2] + f_2 * eq_2,
+                        ]
+                    },
+                    |a, b| [a[0] + b[0], a[1] + b[1], a[2] + b[2]],
+                );
+            for &y in &sumcheck_poly {
+                challenger.observe_algebra_element(y);
+            }
+            let r: Challenge = challenger.sample_algebra_element();
+
+            reduced_evals = fix_first_variable(&reduced_evals, r);
+            eq = fix_first_variable(&eq, r);
+            folded = g.fold_matrix(r, RowMajorMatrixView::new(&folded, 2));
+
+            if round + 1 < num_vars {
+                let (commit, prover_data) = self
+                    .fri
+                    .mmcs
+                    .commit_matrix(RowMajorMatrix::new(folded.clone(), 2));
+                challenger.observe(commit.clone());
+                commits.push(commit);
+                data.push(prover_data);
+            }
+            sumcheck_polys.push(sumcheck_poly);
+        }
+
+        let final_value = reduced_evals[0];
+        debug_assert!(
+            folded.iter().all(|&x| x == final_value),
+            "The last folded codeword should be the evaluation at the sumcheck point"
+        );
+        challenger.observe_algebra_element(final_value);
+
+        let pow_witness = challenger.grind(self.fri.proof_of_work_bits);
+
+        let (input_proof, commit_phase_openings) = info_span!("query phase").in_scope(|| {
+            let indices = iter::repeat_with(|| challenger.sample_bits(log_height))
+                .take(self.fri.num_queries)
+                .collect_vec();
+
+            let input_proof = self
+                .mmcs
+                .open_multi_batch(&input_rows(&indices, num_vars), &prover_data.codewords);
+
+            let commit_phase_openings = data
+                .iter()
+                .enumerate()
+                .map(|(round, data)| {
+                    // The codeword committed after folding in round `round` is folded again in the
+                    // next round, so the queried location is in row `index >> (round + 2)`.
+                    let rows = indices
+                        .iter()
+                        .map(|&index| index >> (round + 2))
+                        .collect_vec();
+                    let (opened_rows, opening_proof) =
+                        self.fri.mmcs.open_multi_batch(&rows, data).unpack();
+                    let sibling_values = opened_rows
+                        .into_iter()
+                        .zip(&indices)
+                        .map(|(mut opened_row, &index)| {
+                            let mut row = opened_row.pop().unwrap();
+                            row.remove((index >> (round + 1)) & 1);
+                            row
+                        })
+                        .collect();
+                    CommitPhaseProofStep {
+                        sibling_values,
+                        opening_proof,
+                    }
+                })
+                .collect();
+
+            (input_proof, commit_phase_openings)
+        });
+
+        let proof = BasefoldProof {
+            sumcheck_polys,
+            commit_phase_commits: commits,
+            final_value,
+            pow_witness,
+            input_proof,
+            commit_phase_openings,
+        };
+        (values, proof)
+    }
+
+    fn verify(
+        &self,
+        commitment: &Self::Commitment,
+        point: &[Challenge],
+        values: &[Challenge],
+        proof: &Self::Proof,
+        challenger: &mut Challenger,
+    ) -> Result<(), Self::Error> {
+        let num_vars = point.len();
+        let log_height = num_vars + self.fri.log_blowup;
+        if proof.sumcheck_polys.len() != num_vars
+            || proof.commit_phase_commits.len() != num_vars.saturating_sub(1)
+            || proof.commit_phase_openings.len() != proof.commit_phase_commits.len()
+        {
+            return Err(BasefoldError::InvalidProofShape);
+        }
+
+        for &value in values {
+            challenger.observe_algebra_element(value);
+        }
+
+        // Batch combination challenge
+        let alpha: Challenge = challenger.sample_algebra_element();
+        let alpha_powers = alpha.powers().take(values.len()).collect_vec();
+        let mut claim: Challenge =
+            dot_product(alpha_powers.iter().copied(), values.iter().copied());
+
+        let mut rs = vec![];
+        for (round, sumcheck_poly) in proof.sumcheck_polys.iter().enumerate() {
+            if sumcheck_poly[0] + sumcheck_poly[1] != claim {
+                return Err(BasefoldError::SumcheckMismatch);
+            }
+            for &y in sumcheck_poly {
+                challenger.observe_algebra_element(y);
+            }
+            let r: Challenge = challenger.sample_algebra_element();
+            claim = interpolate_quadratic(sumcheck_poly, r);
+            rs.push(r);
+
+            if let Some(commit) = proof.commit_phase_commits.get(round) {
+                challenger.observe(commit.clone());
+            }
+        }
+
+        challenger.observe_algebra_element(proof.final_value);
+        if claim != proof.final_value * eval_eq(point, &rs) {
+            return Err(BasefoldError::SumcheckMismatch);
+        }
+
+        if !challenger.check_witness(self.fri.proof_of_work_bits, proof.pow_witness) {
+            return Err(BasefoldError::InvalidPowWitness);
+        }
+
+        let indices = iter::repeat_with(|| challenger.sample_bits(log_height))
+            .take(self.fri.num_queries)
+            .collect_vec();
+
+        let rows = input_rows(&indices, num_vars);
+        self.mmcs
+            .verify_multi_batch(
+                commitment,
+                &[Dimensions {
+                    width: values.len(),
+                    height: 1 << log_height,
+                }],
+                &rows,
+                (&proof.input_proof).into(),
+            )
+            .map_err(BasefoldError::InputError)?;
+        let reduced_rows = proof
+            .input_proof
+            .opened_values
+            .iter()
+            .map(|opened_rows| match opened_rows.as_slice() {
+                [row] if row.len() == values.len() => Ok(dot_product::<Challenge, _, _>(
+                    alpha_powers.iter().copied(),
+                    row.iter().copied(),
+                )),
+                _ => Err(BasefoldError::InvalidProofShape),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if num_vars == 0 {
+            return match reduced_rows.iter().all(|&x| x == proof.final_value) {
+                true => Ok(()),
+                false => Err(BasefoldError::FinalValueMismatch),
+            };
+        }
+
+        // Each query opened the pair of locations which the first round folds together.
+        let g: TwoAdicFriGenericConfig<(), ()> = TwoAdicFriGenericConfig(PhantomData);
+        let mut folded = indices
+            .iter()
+            .zip(reduced_rows.chunks_exact(2))
+            .map(|(&index, pair)| {
+                g.fold_row(index >> 1, log_height - 1, rs[0], pair.iter().copied())
+            })
+            .collect_vec();
+
+        for (round, (commit, step)) in proof
+            .commit_phase_commits
+            .iter()
+            .zip(&proof.commit_phase_openings)
+            .enumerate()
+        {
+            // This codeword was committed after folding in round `round`, and is folded again
+            // in the next round.
+            let log_folded_height = log_height - round - 2;
+            let rows = indices
+                .iter()
+                .map(|&index| index >> (round + 2))
+                .collect_vec();
+
+            let opened_values = zip_eq(
+                &indices,
+                &step.sibling_values,
+                BasefoldError::InvalidProofShape,
+            )?
+            .zip(&folded)
+            .map(|((&index, siblings), &value)| match siblings.as_slice() {
+                &[sibling] => {
+                    let mut row = vec![sibling; 2];
+                    row[(index >> (round + 1)) & 1] = value;
+                    Ok(vec![row])
+                }
+                _ => Err(BasefoldError::InvalidProofShape),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+            self.fri
+                .mmcs
+                .verify_multi_batch(
+                    commit,
+                    &[Dimensions {
+                        width: 2,
+                        height: 1 << log_folded_height,
+                    }],
+                    &rows,
+                    MultiBatchOpeningRef::new(&opened_values, &step.opening_proof),
+                )
+                .map_err(BasefoldError::CommitPhaseMmcsError)?;
+
+            folded = rows
+                .iter()
+                .zip(opened_values)
+                .map(|(&row, mut opened)| {
+                    g.fold_row(
+                        row,
+                        log_folded_height,
+                        rs[round + 1],
+                        opened.pop().unwrap().into_iter(),
+                    )
+                })
+                .collect();
+        }
+
+        match folded.iter().all(|&x| x == proof.final_value) {
+            true => Ok(()),
+            false => Err(BasefoldError::FinalValueMismatch),
+        }
+    }
+}
+
+/// The rows of the committed codewords opened for each query, namely the queried location and
+/// its sibling in the first fold, if there is one.
+fn input_rows(indices: &[usize], num_vars: usize) -> Vec<usize> {
+    match num_vars {
+        0 => indices.to_vec(),
+        _ => indices
+            .iter()
+            .flat_map(|&index| [index & !1, index | 1])
+            .collect(),
+    }
+}
+
+/// Convert the columns of `mat` from evaluations over the hypercube to coefficients in the
+/// multilinear monomial basis, in place.
+fn evals_to_coeffs<F: Field>(mat: &mut RowMajorMatrix<F>) {
+    let width = mat.width();
+    for var in 0..log2_strict_usize(mat.height()) {
+        let half = width << var;
+        mat.values.par_chunks_mut(2 * half).for_each(|chunk| {
+            let (lo, hi) = chunk.split_at_mut(half);
+            hi.iter_mut().zip(lo.iter()).for_each(|(h, &l)| *h -= l);
+        });
+    }
+}
+
+/// The evaluations of `eq(point, b)` over the hypercube, in the order of its rows.
+fn eq_evals<F: Field>(point: &[F]) -> Vec<F> {
+    let mut evals = vec![F::ONE];
+    for &z in point {
+        let (lo, hi): (Vec<_>, Vec<_>) = evals.iter().map(|&e| (e - e * z, e * z)).unzip();
+        evals = lo;
+        evals.extend(hi);
+    }
+    evals
+}
+
+/// `eq(x, y) = prod_i (x_i y_i + (1 - x_i) (1 - y_i))`.
+fn eval_eq<F: Field>(x: &[F], y: &[F]) -> F {
+    x.iter()
+        .zip(y)
+        .map(|(&x_i, &y_i)| x_i * y_i + (F::ONE - x_i) * (F::ONE - y_i))
+        .product()
+}
+
+/// Fix the first variable of a multilinear polynomial given by its evaluations over the hypercube.
+fn fix_first_variable<F: Field>(evals: &[F], r: F) -> Vec<F> {
+    evals
+        .par_chunks_exact(2)
+        .map(|pair| pair[0] + r * (pair[1] - pair[0]))
+        .collect()
+}
+
+/// Evaluate at `r` the quadratic taking the given values at 0, 1 and 2.
+fn interpolate_quadratic<F: Field>(&[y_0, y_1, y_2]: &[F; 3], r: F) -> F {
+    // Lagrange interpolation over {0, 1, 2}.
+    let (r_1, r_2) = (r - F::ONE, r - F::TWO);
+    (y_0 * r_1 * r_2 + y_2 * r * r_1).halve() - y_1 * r * r_2
+}
@@ -1,20 +1,39 @@
 use alloc::vec::Vec;
 use core::fmt::Debug;
 
+use p3_commit::FriParameters;
 use p3_field::{ExtensionField, Field};
 use p3_matrix::Matrix;
+use p3_matrix::dense::{RowMajorMatrix, RowMajorMatrixView};
 
 #[derive(Debug)]
 pub struct FriConfig<M> {
     pub log_blowup: usize,
-    // TODO: This parameter and FRI early stopping are not yet implemented in `CirclePcs`.
     pub log_final_poly_len: usize,
+    /// The log of the folding arity of commit phase rounds past the end of `log_folding_arities`.
+    pub log_folding_arity: usize,
+    /// The log of the folding arity of each of the first rounds of the commit phase.
+    pub log_folding_arities: Vec<usize>,
     pub num_queries: usize,
     pub proof_of_work_bits: usize,
     pub mmcs: M,
 }
 
 impl<M> FriConfig<M> {
+    /// A FRI instance with the given parameters, e.g. as selected by `p3-uni-stark`'s
+    /// `select_fri_parameters`, which folds by `2^log_folding_arity` in every round.
+    pub const fn from_parameters(parameters: FriParameters, mmcs: M) -> Self {
+        Self {
+            log_blowup: parameters.log_blowup,
+            log_final_poly_len: parameters.log_final_poly_len,
+            log_folding_arity: parameters.log_folding_arity,
+            log_folding_arities: Vec::new(),
+            num_queries: parameters.num_queries,
+            proof_of_work_bits: parameters.proof_of_work_bits,
+            mmcs,
+        }
+    }
+
     pub const fn blowup(&self) -> usize {
         1 << self.log_blowup
     }
@@ -27,10 +46,49 @@ impl<M> FriConfig<M> {
     /// [ethSTARK](https://eprint.iacr.org/2021/582) conjecture.
     ///
     /// Certain users may instead want to look at proven soundness, a more complex calculation which
-    /// isn't currently supported by this crate.
+    /// `p3-uni-stark` performs from [`FriConfig::parameters`].
     pub const fn conjectured_soundness_bits(&self) -> usize {
         self.log_blowup * self.num_queries + self.proof_of_work_bits
     }
+
+    /// The log of the folding arity of commit phase round `round`, which folds evaluations over a
+    /// domain of size `2^log_height` towards a domain of size `2^log_target_height`, either that
+    /// of the final polynomial or the next input to roll in.
+    ///
+    /// The scheduled arity is shortened so as not to fold past the target.
+    pub fn log_folding_arity(
+        &self,
+        round: usize,
+        log_height: usize,
+        log_target_height: usize,
+    ) -> usize {
+        let log_arity = self
+            .log_folding_arities
+            .get(round)
+            .copied()
+            .unwrap_or(self.log_folding_arity);
+        assert!(log_arity > 0, "the folding arity must be at least 2");
+        log_arity.min(log_height - log_target_height)
+    }
+
+    /// The parameters of this FRI instance which its soundness depends on.
+    pub const fn parameters(&self) -> FriParameters {
+        let mut log_folding_arity = self.log_folding_arity;
+        let mut round = 0;
+        while round < self.log_folding_arities.len() {
+            if self.log_folding_arities.as_slice()[round] > log_folding_arity {
+                log_folding_arity = self.log_folding_arities.as_slice()[round];
+            }
+            round += 1;
+        }
+        FriParameters {
+            log_blowup: self.log_blowup,
+            log_final_poly_len: self.log_final_poly_len,
+            log_folding_arity,
+            num_queries: self.num_queries,
+            proof_of_work_bits: self.proof_of_work_bits,
+        }
+    }
 }
 
 /// Whereas `FriConfig` encompasses parameters the end user can set, `FriGenericConfig` is
@@ -44,8 +102,8 @@ pub trait FriGenericConfig<F: Field, EF: ExtensionField<F>> {
     fn extra_query_index_bits(&self) -> usize;
 
     /// Fold a row, returning a single column.
-    /// Right now the input row will always be 2 columns wide,
-    /// but we may support higher folding arity in the future.
+    /// The input row is always 2 columns wide; higher folding arities are handled by
+    /// `fold_row_with_arity`, which folds pairs repeatedly.
     fn fold_row(
         &self,
         index: usize,
@@ -56,6 +114,49 @@ pub trait FriGenericConfig<F: Field, EF: ExtensionField<F>> {
 
     /// Same as applying fold_row to every row, possibly faster.
     fn fold_matrix<M: Matrix<EF>>(&self, beta: EF, m: M) -> Vec<EF>;
+
+    /// Fold a row of `2^log_arity` evaluations into the evaluation at `index` of a domain of size
+    /// `2^log_folded_height`, by folding pairs `log_arity` times with `beta`, `beta^2`, `beta^4`
+    /// and so on.
+    fn fold_row_with_arity(
+        &self,
+        index: usize,
+        log_folded_height: usize,
+        log_arity: usize,
+        beta: EF,
+        mut evals: Vec<EF>,
+    ) -> EF {
+        debug_assert_eq!(evals.len(), 1 << log_arity);
+        let mut beta_pow = beta;
+        for layer in (0..log_arity).rev() {
+            evals = evals
+                .chunks_exact(2)
+                .enumerate()
+                .map(|(i, pair)| {
+                    self.fold_row(
+                        (index << layer) | i,
+                        log_folded_height + layer,
+                        beta_pow,
+                        pair.iter().copied(),
+                    )
+                })
+                .collect();
+            beta_pow = beta_pow.square();
+        }
+        evals[0]
+    }
+
+    /// Same as applying `fold_row_with_arity` to every row of `evals`, viewed as a matrix of
+    /// width `2^log_arity`.
+    fn fold_matrix_with_arity(&self, beta: EF, log_arity: usize, evals: &[EF]) -> Vec<EF> {
+        let mut folded = self.fold_matrix(beta, RowMajorMatrixView::new(evals, 2));
+        let mut beta_pow = beta;
+        for _ in 1..log_arity {
+            beta_pow = beta_pow.square();
+            folded = self.fold_matrix(beta_pow, RowMajorMatrix::new(folded, 2));
+        }
+        folded
+    }
 }
 
 /// Creates a minimal `FriConfig` for testing purposes.
@@ -67,6 +168,8 @@ pub const fn create_test_fri_config<Mmcs>(
     FriConfig {
         log_blowup: 2,
         log_final_poly_len,
+        log_folding_arity: 1,
+        log_folding_arities: Vec::new(),
         num_queries: 2,
         proof_of_work_bits: 1,
         mmcs,
@@ -79,6 +182,8 @@ pub const fn create_test_fri_config_zk<Mmcs>(mmcs: Mmcs) -> FriConfig<Mmcs> {
     FriConfig {
         log_blowup: 2,
         log_final_poly_len: 0,
+        log_folding_arity: 1,
+        log_folding_arities: Vec::new(),
         num_queries: 2,
         proof_of_work_bits: 1,
         mmcs,
@@ -91,6 +196,8 @@ pub const fn create_benchmark_fri_config<Mmcs>(mmcs: Mmcs) -> FriConfig<Mmcs> {
     FriConfig {
         log_blowup: 1,
         log_final_poly_len: 0,
+        log_folding_arity: 1,
+        log_folding_arities: Vec::new(),
         num_queries: 100,
         proof_of_work_bits: 16,
         mmcs,
@@ -102,6 +209,8 @@ pub fn create_benchmark_fri_config_zk<Mmcs>(mmcs: Mmcs) -> FriConfig<Mmcs> {
     FriConfig {
         log_blowup: 2,
         log_final_poly_len: 0,
+        log_folding_arity: 1,
+        log_folding_arities: Vec::new(),
         num_queries: 100,
         proof_of_work_bits: 16,
         mmcs,
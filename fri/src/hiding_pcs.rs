@@ -4,7 +4,9 @@ use core::fmt::Debug;
 
 use itertools::Itertools;
 use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
-use p3_commit::{BatchOpening, Mmcs, OpenedValues, Pcs, PolynomialSpace};
+use p3_commit::{
+    FriBasedPcs, FriParameters, Mmcs, MultiBatchOpening, OpenedValues, Pcs, PolynomialSpace,
+};
 use p3_dft::TwoAdicSubgroupDft;
 use p3_field::coset::TwoAdicMultiplicativeCoset;
 use p3_field::{ExtensionField, Field, TwoAdicField, batch_multiplicative_inverse};
@@ -47,6 +49,14 @@ impl<Val, Dft, InputMmcs, FriMmcs, R> HidingFriPcs<Val, Dft, InputMmcs, FriMmcs,
     }
 }
 
+impl<Val, Dft, InputMmcs, FriMmcs, R> FriBasedPcs
+    for HidingFriPcs<Val, Dft, InputMmcs, FriMmcs, R>
+{
+    fn fri_parameters(&self) -> FriParameters {
+        self.inner.fri_parameters()
+    }
+}
+
 impl<Val, Dft, InputMmcs, FriMmcs, Challenge, Challenger, R> Pcs<Challenge, Challenger>
     for HidingFriPcs<Val, Dft, InputMmcs, FriMmcs, R>
 where
@@ -71,7 +81,7 @@ where
     /// The second item is the usual FRI proof.
     type Proof = (
         OpenedValues<Challenge>,
-        FriProof<Challenge, FriMmcs, Val, Vec<BatchOpening<Val, InputMmcs>>>,
+        FriProof<Challenge, FriMmcs, Val, Vec<MultiBatchOpening<Val, InputMmcs>>>,
     );
     type Error = FriError<FriMmcs::Error, InputMmcs::Error>;
 
@@ -339,8 +349,9 @@ where
     }
 }
 
+/// Append `num_random_codewords` columns of uniformly random values to `mat`.
 #[instrument(level = "debug", skip_all)]
-fn add_random_cols<Val, R>(
+pub fn add_random_cols<Val, R>(
     mat: RowMajorMatrix<Val>,
     num_random_codewords: usize,
     mut rng: R,
@@ -370,7 +381,7 @@ where
 
 /// Compute the normalizing constants for the Langrange selectors of the provided domains.
 /// See Section 4.2 of https://eprint.iacr.org/2024/1037.pdf for more details.
-fn get_zp_cis<D: PolynomialSpace>(qc_domains: &[D]) -> Vec<p3_commit::Val<D>> {
+pub fn get_zp_cis<D: PolynomialSpace>(qc_domains: &[D]) -> Vec<p3_commit::Val<D>> {
     batch_multiplicative_inverse(
         &qc_domains
             .iter()
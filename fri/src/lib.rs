@@ -4,14 +4,18 @@
 
 extern crate alloc;
 
+mod basefold;
 mod config;
 mod hiding_pcs;
+mod low_degree;
 mod proof;
 pub mod prover;
 mod two_adic_pcs;
 pub mod verifier;
 
+pub use basefold::*;
 pub use config::*;
 pub use hiding_pcs::*;
+pub use low_degree::*;
 pub use proof::*;
 pub use two_adic_pcs::*;
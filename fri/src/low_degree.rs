@@ -0,0 +1,169 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use itertools::Itertools;
+use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
+use p3_commit::{Mmcs, MultiBatchOpening};
+use p3_field::{ExtensionField, PackedFieldExtension, TwoAdicField, dot_product};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::{Dimensions, Matrix};
+use p3_maybe_rayon::prelude::*;
+use p3_util::{log2_strict_usize, reverse_bits_len, reverse_slice_index_bits};
+use tracing::instrument;
+
+use crate::verifier::FriError;
+use crate::{FriConfig, FriProof, TwoAdicFriGenericConfig, prover, verifier};
+
+/// A proof that each column of a committed matrix is close to a Reed-Solomon codeword.
+pub type LowDegreeProof<Val, Challenge, InputMmcs, FriMmcs> =
+    FriProof<Challenge, FriMmcs, Val, MultiBatchOpening<Val, InputMmcs>>;
+
+/// Prove that each column of the single matrix committed in `prover_data` is close to the
+/// evaluations of a polynomial of degree less than `degree_bound`, which may be at most
+/// `height >> log_blowup`.
+///
+/// The matrix must hold its evaluations over the two-adic coset with the given `shift`, in
+/// bit-reversed order, as given by e.g. `dft.coset_lde_batch(.., shift).bit_reverse_rows()`. The
+/// columns are combined with powers of a random challenge, and FRI is run on the combination. As
+/// with `Pcs`, the commitment should have been observed by the challenger.
+///
+/// FRI itself tests the degree bound `height >> log_blowup`. A smaller `degree_bound` is tested by
+/// also combining each column times `x^(height >> log_blowup - degree_bound)`, which is below the
+/// FRI bound exactly when the column is below `degree_bound`.
+#[instrument(name = "FRI low-degree prover", skip_all)]
+pub fn prove_low_degree<Val, Challenge, InputMmcs, FriMmcs, Challenger>(
+    config: &FriConfig<FriMmcs>,
+    input_mmcs: &InputMmcs,
+    prover_data: &InputMmcs::ProverData<RowMajorMatrix<Val>>,
+    shift: Val,
+    degree_bound: usize,
+    challenger: &mut Challenger,
+) -> LowDegreeProof<Val, Challenge, InputMmcs, FriMmcs>
+where
+    Val: TwoAdicField,
+    Challenge: TwoAdicField + ExtensionField<Val>,
+    InputMmcs: Mmcs<Val>,
+    FriMmcs: Mmcs<Challenge>,
+    Challenger:
+        FieldChallenger<Val> + CanObserve<FriMmcs::Commitment> + GrindingChallenger<Witness = Val>,
+{
+    let matrices = input_mmcs.get_matrices(prover_data);
+    assert_eq!(matrices.len(), 1, "Expected a single committed matrix");
+    let evals = matrices[0];
+    assert!(
+        log2_strict_usize(evals.height()) > config.log_blowup + config.log_final_poly_len,
+        "The matrix is too short to fold"
+    );
+    let log_height = log2_strict_usize(evals.height());
+    let degree_shift = degree_shift(config, log_height, degree_bound);
+
+    // Batch combination challenge
+    let alpha: Challenge = challenger.sample_algebra_element();
+    let packed_alpha_powers =
+        Challenge::ExtensionPacking::packed_ext_powers_capped(alpha, evals.width()).collect_vec();
+    let mut reduced: Vec<Challenge> = evals
+        .rowwise_packed_dot_product::<Challenge>(&packed_alpha_powers)
+        .collect();
+    if degree_shift > 0 {
+        // The powers `x^degree_shift` of the points of the coset, in bit-reversed order.
+        let mut x_pows = Val::two_adic_generator(log_height)
+            .exp_u64(degree_shift as u64)
+            .shifted_powers(shift.exp_u64(degree_shift as u64))
+            .take(evals.height())
+            .collect_vec();
+        reverse_slice_index_bits(&mut x_pows);
+        let correction: Challenge = alpha.exp_u64(evals.width() as u64);
+        reduced
+            .par_iter_mut()
+            .zip(x_pows)
+            .for_each(|(r, x_pow)| *r += *r * correction * x_pow);
+    }
+
+    let g: TwoAdicFriGenericConfig<MultiBatchOpening<Val, InputMmcs>, InputMmcs::Error> =
+        TwoAdicFriGenericConfig(PhantomData);
+    prover::prove(&g, config, vec![reduced], challenger, |indices| {
+        input_mmcs.open_multi_batch(indices, prover_data)
+    })
+}
+
+/// Verify a proof that each column of the matrix of the given dimensions committed to in
+/// `commitment` is close to the evaluations over the coset with the given `shift` of a polynomial
+/// of degree less than `degree_bound`, where the height must be a power of two.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_low_degree<Val, Challenge, InputMmcs, FriMmcs, Challenger>(
+    config: &FriConfig<FriMmcs>,
+    input_mmcs: &InputMmcs,
+    commitment: &InputMmcs::Commitment,
+    dimensions: Dimensions,
+    shift: Val,
+    degree_bound: usize,
+    proof: &LowDegreeProof<Val, Challenge, InputMmcs, FriMmcs>,
+    challenger: &mut Challenger,
+) -> Result<(), FriError<FriMmcs::Error, InputMmcs::Error>>
+where
+    Val: TwoAdicField,
+    Challenge: TwoAdicField + ExtensionField<Val>,
+    InputMmcs: Mmcs<Val>,
+    FriMmcs: Mmcs<Challenge>,
+    Challenger:
+        FieldChallenger<Val> + CanObserve<FriMmcs::Commitment> + GrindingChallenger<Witness = Val>,
+{
+    // The FRI verifier infers the height from the proof, so check that it is the committed one.
+    if !dimensions.height.is_power_of_two() {
+        return Err(FriError::InvalidProofShape);
+    }
+    let log_height = log2_strict_usize(dimensions.height);
+    let log_proof_height = proof.log_folding_arities().iter().sum::<usize>()
+        + config.log_blowup
+        + config.log_final_poly_len;
+    if log_proof_height != log_height {
+        return Err(FriError::InvalidProofShape);
+    }
+    let degree_shift = degree_shift(config, log_height, degree_bound);
+
+    // Batch combination challenge
+    let alpha: Challenge = challenger.sample_algebra_element();
+    let alpha_powers = alpha.powers().take(dimensions.width).collect_vec();
+    let correction: Challenge = alpha.exp_u64(dimensions.width as u64);
+    let generator = Val::two_adic_generator(log_height);
+
+    let g: TwoAdicFriGenericConfig<MultiBatchOpening<Val, InputMmcs>, InputMmcs::Error> =
+        TwoAdicFriGenericConfig(PhantomData);
+    verifier::verify(&g, config, proof, challenger, |indices, input_proof| {
+        input_mmcs
+            .verify_multi_batch(commitment, &[dimensions], indices, input_proof.into())
+            .map_err(FriError::InputError)?;
+        input_proof
+            .opened_values
+            .iter()
+            .zip(indices)
+            .map(|(opened_rows, &index)| match opened_rows.as_slice() {
+                [row] if row.len() == dimensions.width => {
+                    let mut reduced = dot_product::<Challenge, _, _>(
+                        alpha_powers.iter().copied(),
+                        row.iter().copied(),
+                    );
+                    if degree_shift > 0 {
+                        let x =
+                            shift * generator.exp_u64(reverse_bits_len(index, log_height) as u64);
+                        reduced += reduced * correction * x.exp_u64(degree_shift as u64);
+                    }
+                    Ok(vec![(log_height, reduced)])
+                }
+                _ => Err(FriError::InvalidProofShape),
+            })
+            .collect()
+    })
+}
+
+/// The degree by which columns are shifted up so that FRI, which tests the degree bound
+/// `height >> log_blowup`, tests `degree_bound` instead.
+fn degree_shift<M>(config: &FriConfig<M>, log_height: usize, degree_bound: usize) -> usize {
+    let fri_degree_bound = 1 << (log_height - config.log_blowup);
+    assert!(
+        degree_bound <= fri_degree_bound,
+        "The degree bound must be at most height >> log_blowup"
+    );
+    fri_degree_bound - degree_bound
+}
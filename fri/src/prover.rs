@@ -11,7 +11,7 @@ use p3_matrix::dense::RowMajorMatrix;
 use p3_util::{log2_strict_usize, reverse_slice_index_bits};
 use tracing::{debug_span, info_span, instrument};
 
-use crate::{CommitPhaseProofStep, FriConfig, FriGenericConfig, FriProof, QueryProof};
+use crate::{CommitPhaseProofStep, FriConfig, FriGenericConfig, FriProof};
 
 #[instrument(name = "FRI prover", skip_all)]
 pub fn prove<G, Val, Challenge, M, Challenger>(
@@ -19,7 +19,7 @@ pub fn prove<G, Val, Challenge, M, Challenger>(
     config: &FriConfig<M>,
     inputs: Vec<Vec<Challenge>>,
     challenger: &mut Challenger,
-    open_input: impl Fn(usize) -> G::InputProof,
+    open_input: impl Fn(&[usize]) -> G::InputProof,
 ) -> FriProof<Challenge, M, Challenger::Witness, G::InputProof>
 where
     Val: Field,
@@ -47,23 +47,30 @@ where
 
     let pow_witness = challenger.grind(config.proof_of_work_bits);
 
-    let query_proofs = info_span!("query phase").in_scope(|| {
-        iter::repeat_with(|| challenger.sample_bits(log_max_height + g.extra_query_index_bits()))
-            .take(config.num_queries)
-            .map(|index| QueryProof {
-                input_proof: open_input(index),
-                commit_phase_openings: answer_query(
-                    config,
-                    &commit_phase_result.data,
-                    index >> g.extra_query_index_bits(),
-                ),
-            })
-            .collect()
+    let (input_proof, commit_phase_openings) = info_span!("query phase").in_scope(|| {
+        let indices = iter::repeat_with(|| {
+            challenger.sample_bits(log_max_height + g.extra_query_index_bits())
+        })
+        .take(config.num_queries)
+        .collect_vec();
+        let input_proof = open_input(&indices);
+        let domain_indices = indices
+            .iter()
+            .map(|index| index >> g.extra_query_index_bits())
+            .collect();
+        let commit_phase_openings = answer_queries(
+            config,
+            &commit_phase_result.data,
+            &commit_phase_result.log_arities,
+            domain_indices,
+        );
+        (input_proof, commit_phase_openings)
     });
 
     FriProof {
         commit_phase_commits: commit_phase_result.commits,
-        query_proofs,
+        input_proof,
+        commit_phase_openings,
         final_poly: commit_phase_result.final_poly,
         pow_witness,
     }
@@ -72,6 +79,7 @@ where
 struct CommitPhaseResult<F: Field, M: Mmcs<F>> {
     commits: Vec<M::Commitment>,
     data: Vec<M::ProverData<RowMajorMatrix<F>>>,
+    log_arities: Vec<usize>,
     final_poly: Vec<F>,
 }
 
@@ -93,19 +101,34 @@ where
     let mut folded = inputs_iter.next().unwrap();
     let mut commits = vec![];
     let mut data = vec![];
+    let mut log_arities = vec![];
+    let log_final_height = config.log_blowup + config.log_final_poly_len;
 
     while folded.len() > config.blowup() * config.final_poly_len() {
-        let leaves = RowMajorMatrix::new(folded, 2);
+        // Fold no further than the height of the next input to roll in, as the verifier only
+        // knows its evaluation at the queried location.
+        let log_height = log2_strict_usize(folded.len());
+        let log_target_height = inputs_iter
+            .peek()
+            .map(|v| log2_strict_usize(v.len()))
+            .filter(|&log_input_height| log_input_height < log_height)
+            .map_or(log_final_height, |log_input_height| {
+                log_input_height.max(log_final_height)
+            });
+        let log_arity = config.log_folding_arity(commits.len(), log_height, log_target_height);
+
+        let leaves = RowMajorMatrix::new(folded, 1 << log_arity);
         let (commit, prover_data) = config.mmcs.commit_matrix(leaves);
         challenger.observe(commit.clone());
 
         let beta: Challenge = challenger.sample_algebra_element();
         // We passed ownership of `current` to the MMCS, so get a reference to it
         let leaves = config.mmcs.get_matrices(&prover_data).pop().unwrap();
-        folded = g.fold_matrix(beta, leaves.as_view());
+        folded = g.fold_matrix_with_arity(beta, log_arity, &leaves.values);
 
         commits.push(commit);
         data.push(prover_data);
+        log_arities.push(log_arity);
 
         if let Some(v) = inputs_iter.next_if(|v| v.len() == folded.len()) {
             izip!(&mut folded, v).for_each(|(c, x)| *c += x);
@@ -122,7 +145,8 @@ where
     reverse_slice_index_bits(&mut folded);
     // TODO: For better performance, we could run the IDFT on only the first half
     //       (or less, depending on `log_blowup`) of `final_poly`.
-    let final_poly = debug_span!("idft final poly").in_scope(|| Radix2Dit::default().idft(folded));
+    let mut final_poly =
+        debug_span!("idft final poly").in_scope(|| Radix2Dit::default().idft(folded));
 
     // The evaluation domain is "blown-up" relative to the polynomial degree of `final_poly`,
     // so all coefficients after the first final_poly_len should be zero.
@@ -133,6 +157,7 @@ where
             .all(|x| x.is_zero()),
         "All coefficients beyond final_poly_len must be zero"
     );
+    final_poly.truncate(config.final_poly_len());
 
     // Observe all coefficients of the final polynomial.
     for &x in &final_poly {
@@ -142,14 +167,16 @@ where
     CommitPhaseResult {
         commits,
         data,
+        log_arities,
         final_poly,
     }
 }
 
-fn answer_query<F, M>(
+fn answer_queries<F, M>(
     config: &FriConfig<M>,
     commit_phase_commits: &[M::ProverData<RowMajorMatrix<F>>],
-    index: usize,
+    log_arities: &[usize],
+    mut indices: Vec<usize>,
 ) -> Vec<CommitPhaseProofStep<F, M>>
 where
     F: Field,
@@ -157,21 +184,35 @@ where
 {
     commit_phase_commits
         .iter()
-        .enumerate()
-        .map(|(i, commit)| {
-            let index_i = index >> i;
-            let index_i_sibling = index_i ^ 1;
-            let index_pair = index_i >> 1;
-
-            let (mut opened_rows, opening_proof) =
-                config.mmcs.open_batch(index_pair, commit).unpack();
-            assert_eq!(opened_rows.len(), 1);
-            let opened_row = &opened_rows.pop().unwrap();
-            assert_eq!(opened_row.len(), 2, "Committed data should be in pairs");
-            let sibling_value = opened_row[index_i_sibling % 2];
+        .zip(log_arities)
+        .map(|(commit, &log_arity)| {
+            let indices_in_row = indices
+                .iter_mut()
+                .map(|index| {
+                    let index_in_row = *index % (1 << log_arity);
+                    *index >>= log_arity;
+                    index_in_row
+                })
+                .collect_vec();
+
+            let (opened_rows, opening_proof) =
+                config.mmcs.open_multi_batch(&indices, commit).unpack();
+            let sibling_values = izip!(opened_rows, indices_in_row)
+                .map(|(mut opened_rows, index_in_row)| {
+                    assert_eq!(opened_rows.len(), 1);
+                    let mut sibling_values = opened_rows.pop().unwrap();
+                    assert_eq!(
+                        sibling_values.len(),
+                        1 << log_arity,
+                        "Committed rows should have the folding arity"
+                    );
+                    sibling_values.remove(index_in_row);
+                    sibling_values
+                })
+                .collect();
 
             CommitPhaseProofStep {
-                sibling_value,
+                sibling_values,
                 opening_proof,
             }
         })
@@ -6,7 +6,7 @@ use core::marker::PhantomData;
 
 use itertools::{Itertools, izip};
 use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
-use p3_commit::{BatchOpening, Mmcs, OpenedValues, Pcs};
+use p3_commit::{FriBasedPcs, FriParameters, Mmcs, MultiBatchOpening, OpenedValues, Pcs};
 use p3_dft::TwoAdicSubgroupDft;
 use p3_field::coset::TwoAdicMultiplicativeCoset;
 use p3_field::{
@@ -50,7 +50,7 @@ pub struct TwoAdicFriGenericConfig<InputProof, InputError>(
 );
 
 pub type TwoAdicFriGenericConfigForMmcs<F, M> =
-    TwoAdicFriGenericConfig<Vec<BatchOpening<F, M>>, <M as Mmcs<F>>::Error>;
+    TwoAdicFriGenericConfig<Vec<MultiBatchOpening<F, M>>, <M as Mmcs<F>>::Error>;
 
 impl<F: TwoAdicField, InputProof, InputError: Debug, EF: ExtensionField<F>> FriGenericConfig<F, EF>
     for TwoAdicFriGenericConfig<InputProof, InputError>
@@ -125,6 +125,12 @@ impl<F: TwoAdicField, InputProof, InputError: Debug, EF: ExtensionField<F>> FriG
     }
 }
 
+impl<Val, Dft, InputMmcs, FriMmcs> FriBasedPcs for TwoAdicFriPcs<Val, Dft, InputMmcs, FriMmcs> {
+    fn fri_parameters(&self) -> FriParameters {
+        self.fri.parameters()
+    }
+}
+
 impl<Val, Dft, InputMmcs, FriMmcs, Challenge, Challenger> Pcs<Challenge, Challenger>
     for TwoAdicFriPcs<Val, Dft, InputMmcs, FriMmcs>
 where
@@ -140,7 +146,7 @@ where
     type Commitment = InputMmcs::Commitment;
     type ProverData = InputMmcs::ProverData<RowMajorMatrix<Val>>;
     type EvaluationsOnDomain<'a> = BitReversedMatrixView<RowMajorMatrixView<'a, Val>>;
-    type Proof = FriProof<Challenge, FriMmcs, Val, Vec<BatchOpening<Val, InputMmcs>>>;
+    type Proof = FriProof<Challenge, FriMmcs, Val, Vec<MultiBatchOpening<Val, InputMmcs>>>;
     type Error = FriError<FriMmcs::Error, InputMmcs::Error>;
     const ZK: bool = false;
 
@@ -408,14 +414,17 @@ where
         let g: TwoAdicFriGenericConfigForMmcs<Val, InputMmcs> =
             TwoAdicFriGenericConfig(PhantomData);
 
-        let fri_proof = prover::prove(&g, &self.fri, fri_input, challenger, |index| {
+        let fri_proof = prover::prove(&g, &self.fri, fri_input, challenger, |indices| {
             rounds
                 .iter()
                 .map(|(data, _)| {
                     let log_max_height = log2_strict_usize(self.mmcs.get_max_height(data));
                     let bits_reduced = log_global_max_height - log_max_height;
-                    let reduced_index = index >> bits_reduced;
-                    self.mmcs.open_batch(reduced_index, data)
+                    let reduced_indices = indices
+                        .iter()
+                        .map(|index| index >> bits_reduced)
+                        .collect_vec();
+                    self.mmcs.open_multi_batch(&reduced_indices, data)
                 })
                 .collect()
         });
@@ -458,17 +467,19 @@ where
         // Batch combination challenge
         let alpha: Challenge = challenger.sample_algebra_element();
 
-        let log_global_max_height =
-            proof.commit_phase_commits.len() + self.fri.log_blowup + self.fri.log_final_poly_len;
+        let log_global_max_height = proof.log_folding_arities().iter().sum::<usize>()
+            + self.fri.log_blowup
+            + self.fri.log_final_poly_len;
 
         let g: TwoAdicFriGenericConfigForMmcs<Val, InputMmcs> =
             TwoAdicFriGenericConfig(PhantomData);
 
-        verifier::verify(&g, &self.fri, proof, challenger, |index, input_proof| {
+        verifier::verify(&g, &self.fri, proof, challenger, |indices, input_proof| {
             // TODO: separate this out into functions
 
-            // log_height -> (alpha_pow, reduced_opening)
-            let mut reduced_openings = BTreeMap::<usize, (Challenge, Challenge)>::new();
+            // For each query, log_height -> (alpha_pow, reduced_opening)
+            let mut reduced_openings =
+                vec![BTreeMap::<usize, (Challenge, Challenge)>::new(); indices.len()];
 
             for (batch_opening, (batch_commit, mats)) in
                 zip_eq(input_proof, &rounds, FriError::InvalidProofShape)?
@@ -486,65 +497,83 @@ where
                 if let Some(batch_max_height) = batch_heights.iter().max() {
                     let log_batch_max_height = log2_strict_usize(*batch_max_height);
                     let bits_reduced = log_global_max_height - log_batch_max_height;
-                    let reduced_index = index >> bits_reduced;
+                    let reduced_indices = indices
+                        .iter()
+                        .map(|index| index >> bits_reduced)
+                        .collect_vec();
 
-                    self.mmcs.verify_batch(
+                    self.mmcs.verify_multi_batch(
                         batch_commit,
                         &batch_dims,
-                        reduced_index,
+                        &reduced_indices,
                         batch_opening.into(),
                     )
                 } else {
                     // Empty batch?
                     self.mmcs
-                        .verify_batch(batch_commit, &[], 0, batch_opening.into())
+                        .verify_multi_batch(batch_commit, &[], indices, batch_opening.into())
                 }
                 .map_err(FriError::InputError)?;
 
-                for (mat_opening, (mat_domain, mat_points_and_values)) in zip_eq(
-                    &batch_opening.opened_values,
-                    mats,
+                for ((&index, query_opening), query_reduced_openings) in zip_eq(
+                    zip_eq(
+                        indices,
+                        &batch_opening.opened_values,
+                        FriError::InvalidProofShape,
+                    )?,
+                    &mut reduced_openings,
                     FriError::InvalidProofShape,
                 )? {
-                    let log_height = log2_strict_usize(mat_domain.size()) + self.fri.log_blowup;
-
-                    let bits_reduced = log_global_max_height - log_height;
-                    let rev_reduced_index = reverse_bits_len(index >> bits_reduced, log_height);
-
-                    // todo: this can be nicer with domain methods?
-
-                    let x = Val::GENERATOR
-                        * Val::two_adic_generator(log_height).exp_u64(rev_reduced_index as u64);
-
-                    let (alpha_pow, ro) = reduced_openings
-                        .entry(log_height)
-                        .or_insert((Challenge::ONE, Challenge::ZERO));
-
-                    for (z, ps_at_z) in mat_points_and_values {
-                        for (&p_at_x, &p_at_z) in
-                            zip_eq(mat_opening, ps_at_z, FriError::InvalidProofShape)?
-                        {
-                            let quotient = (-p_at_z + p_at_x) / (-*z + x);
-                            *ro += *alpha_pow * quotient;
-                            *alpha_pow *= alpha;
+                    for (mat_opening, (mat_domain, mat_points_and_values)) in
+                        zip_eq(query_opening, mats, FriError::InvalidProofShape)?
+                    {
+                        let log_height = log2_strict_usize(mat_domain.size()) + self.fri.log_blowup;
+
+                        let bits_reduced = log_global_max_height - log_height;
+                        let rev_reduced_index = reverse_bits_len(index >> bits_reduced, log_height);
+
+                        // todo: this can be nicer with domain methods?
+
+                        let x = Val::GENERATOR
+                            * Val::two_adic_generator(log_height).exp_u64(rev_reduced_index as u64);
+
+                        let (alpha_pow, ro) = query_reduced_openings
+                            .entry(log_height)
+                            .or_insert((Challenge::ONE, Challenge::ZERO));
+
+                        for (z, ps_at_z) in mat_points_and_values {
+                            for (&p_at_x, &p_at_z) in
+                                zip_eq(mat_opening, ps_at_z, FriError::InvalidProofShape)?
+                            {
+                                let quotient = (-p_at_z + p_at_x) / (-*z + x);
+                                *ro += *alpha_pow * quotient;
+                                *alpha_pow *= alpha;
+                            }
                         }
                     }
                 }
             }
 
-            // `reduced_openings` would have a log_height = log_blowup entry only if there was a
-            // trace matrix of height 1. In this case the reduced opening can be skipped as it will
-            // not be checked against any commit phase commit.
-            if let Some((_alpha_pow, ro)) = reduced_openings.remove(&self.fri.log_blowup) {
-                assert!(ro.is_zero());
-            }
-
-            // Return reduced openings descending by log_height.
-            Ok(reduced_openings
+            reduced_openings
                 .into_iter()
-                .rev()
-                .map(|(log_height, (_alpha_pow, ro))| (log_height, ro))
-                .collect())
+                .map(|mut query_reduced_openings| {
+                    // `reduced_openings` would have a log_height = log_blowup entry only if there
+                    // was a trace matrix of height 1. In this case the reduced opening can be
+                    // skipped as it will not be checked against any commit phase commit.
+                    if let Some((_alpha_pow, ro)) =
+                        query_reduced_openings.remove(&self.fri.log_blowup)
+                    {
+                        assert!(ro.is_zero());
+                    }
+
+                    // Return reduced openings descending by log_height.
+                    Ok(query_reduced_openings
+                        .into_iter()
+                        .rev()
+                        .map(|(log_height, (_alpha_pow, ro))| (log_height, ro))
+                        .collect())
+                })
+                .collect()
         })?;
 
         Ok(())
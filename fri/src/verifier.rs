@@ -1,9 +1,9 @@
 use alloc::vec;
 use alloc::vec::Vec;
 
-use itertools::Itertools;
+use itertools::{Itertools, izip};
 use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
-use p3_commit::{BatchOpeningRef, Mmcs};
+use p3_commit::{Mmcs, MultiBatchOpeningRef};
 use p3_field::{ExtensionField, Field, TwoAdicField};
 use p3_matrix::Dimensions;
 use p3_util::reverse_bits_len;
@@ -26,9 +26,9 @@ pub fn verify<G, Val, Challenge, M, Challenger>(
     proof: &FriProof<Challenge, M, Challenger::Witness, G::InputProof>,
     challenger: &mut Challenger,
     open_input: impl Fn(
-        usize,
+        &[usize],
         &G::InputProof,
-    ) -> Result<Vec<(usize, Challenge)>, FriError<M::Error, G::InputError>>,
+    ) -> Result<Vec<Vec<(usize, Challenge)>>, FriError<M::Error, G::InputError>>,
 ) -> Result<(), FriError<M::Error, G::InputError>>
 where
     Val: Field,
@@ -37,6 +37,11 @@ where
     Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<M::Commitment>,
     G: FriGenericConfig<Val, Challenge>,
 {
+    // Extra coefficients would raise the degree bound of the final polynomial.
+    if proof.final_poly.len() != config.final_poly_len() {
+        return Err(FriError::InvalidProofShape);
+    }
+
     let betas: Vec<Challenge> = proof
         .commit_phase_commits
         .iter()
@@ -52,55 +57,61 @@ where
         .iter()
         .for_each(|x| challenger.observe_algebra_element(*x));
 
-    if proof.query_proofs.len() != config.num_queries {
-        return Err(FriError::InvalidProofShape);
-    }
-
     // Check PoW.
     if !challenger.check_witness(config.proof_of_work_bits, proof.pow_witness) {
         return Err(FriError::InvalidPowWitness);
     }
 
-    // The log of the maximum domain size.
-    let log_max_height =
-        proof.commit_phase_commits.len() + config.log_blowup + config.log_final_poly_len;
-
     // The log of the final domain size.
     let log_final_height = config.log_blowup + config.log_final_poly_len;
 
-    for qp in &proof.query_proofs {
-        let index = challenger.sample_bits(log_max_height + g.extra_query_index_bits());
-        let ro = open_input(index, &qp.input_proof)?;
-
-        debug_assert!(
-            ro.iter().tuple_windows().all(|((l, _), (r, _))| l > r),
-            "reduced openings sorted by height descending"
-        );
-
-        let mut domain_index = index >> g.extra_query_index_bits();
-
-        // Starting at the evaluation at `index` of the initial domain,
-        // perform fri folds until the domain size reaches the final domain size.
-        // Check after each fold that the pair of sibling evaluations at the current
-        // node match the commitment.
-        let folded_eval = verify_query(
-            g,
-            config,
-            &mut domain_index,
+    // The log of the maximum domain size.
+    let log_arities = proof.log_folding_arities();
+    let log_max_height = log_arities.iter().sum::<usize>() + log_final_height;
+
+    let indices = (0..config.num_queries)
+        .map(|_| challenger.sample_bits(log_max_height + g.extra_query_index_bits()))
+        .collect_vec();
+    let reduced_openings = open_input(&indices, &proof.input_proof)?;
+    if reduced_openings.len() != config.num_queries {
+        return Err(FriError::InvalidProofShape);
+    }
+
+    debug_assert!(
+        reduced_openings
+            .iter()
+            .all(|ro| ro.iter().tuple_windows().all(|((l, _), (r, _))| l > r)),
+        "reduced openings sorted by height descending"
+    );
+
+    let mut domain_indices = indices
+        .iter()
+        .map(|index| index >> g.extra_query_index_bits())
+        .collect_vec();
+
+    // Starting at the evaluations at `indices` of the initial domain,
+    // perform fri folds until the domain size reaches the final domain size.
+    // Check after each fold that the rows of sibling evaluations at the current
+    // nodes match the commitment.
+    let folded_evals = verify_queries(
+        g,
+        config,
+        &mut domain_indices,
+        zip_eq(
             zip_eq(
-                zip_eq(
-                    &betas,
-                    &proof.commit_phase_commits,
-                    FriError::InvalidProofShape,
-                )?,
-                &qp.commit_phase_openings,
+                zip_eq(&betas, &log_arities, FriError::InvalidProofShape)?,
+                &proof.commit_phase_commits,
                 FriError::InvalidProofShape,
             )?,
-            ro,
-            log_max_height,
-            log_final_height,
-        )?;
+            &proof.commit_phase_openings,
+            FriError::InvalidProofShape,
+        )?,
+        reduced_openings,
+        log_max_height,
+        log_final_height,
+    )?;
 
+    for (domain_index, folded_eval) in domain_indices.into_iter().zip(folded_evals) {
         let mut eval = Challenge::ZERO;
 
         // We open the final polynomial at index `domain_index`, which corresponds to evaluating
@@ -126,84 +137,120 @@ where
 
 type CommitStep<'a, F, M> = (
     (
-        &'a F, // The challenge point beta used for the next fold of FRI evaluations.
+        (
+            &'a F,     // The challenge point beta used for the next fold of FRI evaluations.
+            &'a usize, // The log of the arity of the next fold.
+        ),
         &'a <M as Mmcs<F>>::Commitment, // A commitment to the FRI evaluations on the current domain.
     ),
-    &'a CommitPhaseProofStep<F, M>, // The sibling and opening proof for the current FRI node.
+    &'a CommitPhaseProofStep<F, M>, // The siblings and opening proof for the current FRI nodes.
 );
 
-/// Verifies a single query chain in the FRI proof.
+/// Verifies the query chains in the FRI proof, one round at a time.
 ///
-/// Given an initial `index` corresponding to a point in the initial domain
-/// and a series of `reduced_openings` corresponding to evaluations of
+/// Given initial `indices` corresponding to points in the initial domain
+/// and, for each query, a series of `reduced_openings` corresponding to evaluations of
 /// polynomials to be added in at specific domain sizes, perform the standard
-/// sequence of FRI folds, checking at each step that the pair of sibling evaluations
-/// match the commitment.
-fn verify_query<'a, G, F, EF, M>(
+/// sequence of FRI folds, checking at each step that the rows of sibling evaluations
+/// match the commitment and that their arity is the one the configuration schedules.
+fn verify_queries<'a, G, F, EF, M>(
     g: &G,
     config: &FriConfig<M>,
-    index: &mut usize,
+    indices: &mut [usize],
     steps: impl ExactSizeIterator<Item = CommitStep<'a, EF, M>>,
-    reduced_openings: Vec<(usize, EF)>,
+    reduced_openings: Vec<Vec<(usize, EF)>>,
     log_max_height: usize,
     log_final_height: usize,
-) -> Result<EF, FriError<M::Error, G::InputError>>
+) -> Result<Vec<EF>, FriError<M::Error, G::InputError>>
 where
     F: Field,
     EF: ExtensionField<F>,
     M: Mmcs<EF> + 'a,
     G: FriGenericConfig<F, EF>,
 {
-    let mut folded_eval = EF::ZERO;
-    let mut ro_iter = reduced_openings.into_iter().peekable();
+    let mut folded_evals = vec![EF::ZERO; indices.len()];
+    let mut ro_iters = reduced_openings
+        .into_iter()
+        .map(|ro| ro.into_iter().peekable())
+        .collect_vec();
 
     // We start with evaluations over a domain of size (1 << log_max_height). We fold
     // using FRI until the domain size reaches (1 << log_final_height).
-    for (log_folded_height, ((&beta, comm), opening)) in zip_eq(
-        (log_final_height..log_max_height).rev(),
-        steps,
-        FriError::InvalidProofShape,
-    )? {
-        // If there are new polynomials to roll in at this height, do so.
-        if let Some((_, ro)) = ro_iter.next_if(|(lh, _)| *lh == log_folded_height + 1) {
-            folded_eval += ro;
+    let mut log_height = log_max_height;
+    for (round, (((&beta, &log_arity), comm), opening)) in steps.enumerate() {
+        if opening.sibling_values.len() != indices.len() {
+            return Err(FriError::InvalidProofShape);
         }
 
-        // Get the index of the other sibling of the current fri node.
-        let index_sibling = *index ^ 1;
+        let mut rows = Vec::with_capacity(indices.len());
+        for (&index, folded_eval, ro_iter, sibling_values) in izip!(
+            indices.iter(),
+            &mut folded_evals,
+            &mut ro_iters,
+            &opening.sibling_values
+        ) {
+            // If there are new polynomials to roll in at this height, do so.
+            if let Some((_, ro)) = ro_iter.next_if(|(lh, _)| *lh == log_height) {
+                *folded_eval += ro;
+            }
+
+            // The prover folds no further than the next polynomial to roll in.
+            let log_target_height = ro_iter
+                .peek()
+                .map_or(log_final_height, |&(lh, _)| lh.max(log_final_height));
+            if log_target_height >= log_height
+                || log_arity != config.log_folding_arity(round, log_height, log_target_height)
+                || sibling_values.len() + 1 != 1 << log_arity
+            {
+                return Err(FriError::InvalidProofShape);
+            }
 
-        let mut evals = vec![folded_eval; 2];
-        evals[index_sibling % 2] = opening.sibling_value;
+            // Insert our own evaluation among those of its siblings.
+            let mut evals = sibling_values.clone();
+            evals.insert(index % (1 << log_arity), *folded_eval);
+            rows.push(vec![evals]);
+        }
+        let log_folded_height = log_height - log_arity;
 
         let dims = &[Dimensions {
-            width: 2,
+            width: 1 << log_arity,
             height: 1 << log_folded_height,
         }];
 
-        // Replace index with the index of the parent fri node.
-        *index >>= 1;
+        // Replace each index with the index of the parent fri node.
+        indices.iter_mut().for_each(|index| *index >>= log_arity);
 
         // Verify the commitment to the evaluations of the sibling nodes.
         config
             .mmcs
-            .verify_batch(
+            .verify_multi_batch(
                 comm,
                 dims,
-                *index,
-                BatchOpeningRef::new(&[evals.clone()], &opening.opening_proof), // It's possible to remove the clone here but unnecessary as evals is tiny.
+                indices,
+                MultiBatchOpeningRef::new(&rows, &opening.opening_proof),
             )
             .map_err(FriError::CommitPhaseMmcsError)?;
 
-        // Fold the pair of evaluations of sibling nodes into the evaluation of the parent fri node.
-        folded_eval = g.fold_row(*index, log_folded_height, beta, evals.into_iter());
+        // Fold each row of evaluations of sibling nodes into the evaluation of the parent fri node.
+        for (folded_eval, &index, mut row) in izip!(&mut folded_evals, indices.iter(), rows) {
+            *folded_eval = g.fold_row_with_arity(
+                index,
+                log_folded_height,
+                log_arity,
+                beta,
+                row.pop().unwrap(),
+            );
+        }
+        log_height = log_folded_height;
     }
 
-    // If ro_iter is not empty, we failed to fold in some polynomial evaluations.
-    if ro_iter.next().is_some() {
+    // If any ro_iter is not empty, we failed to fold in some polynomial evaluations.
+    if log_height != log_final_height || ro_iters.iter_mut().any(|ro_iter| ro_iter.next().is_some())
+    {
         return Err(FriError::InvalidProofShape);
     }
 
-    // If we reached this point, we have verified that, starting at the initial index,
-    // the chain of folds has produced folded_eval.
-    Ok(folded_eval)
+    // If we reached this point, we have verified that, starting at the initial indices,
+    // the chains of folds have produced folded_evals.
+    Ok(folded_evals)
 }
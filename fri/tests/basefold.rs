@@ -0,0 +1,166 @@
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::{CanObserve, DuplexChallenger, FieldChallenger};
+use p3_commit::{ExtensionMmcs, MultilinearPcs};
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{Field, PrimeCharacteristicRing};
+use p3_fri::{BasefoldError, BasefoldPcs, FriConfig};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+type Val = BabyBear;
+type Challenge = BinomialExtensionField<Val, 4>;
+
+type Perm = Poseidon2BabyBear<16>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs =
+    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Dft = Radix2DitParallel<Val>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+type MyPcs = BasefoldPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+
+fn get_pcs(log_blowup: usize) -> (MyPcs, Challenger) {
+    let perm = Perm::new_from_rng_128(&mut SmallRng::seed_from_u64(0));
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+
+    let fri_config = FriConfig {
+        log_blowup,
+        log_final_poly_len: 0,
+        log_folding_arity: 1,
+        log_folding_arities: vec![],
+        num_queries: 10,
+        proof_of_work_bits: 8,
+        mmcs: challenge_mmcs,
+    };
+
+    let pcs = MyPcs::new(Dft::default(), val_mmcs, fri_config);
+    (pcs, Challenger::new(perm))
+}
+
+/// Evaluate each column of `evals` at `point` by multilinear interpolation.
+fn eval_columns(evals: &RowMajorMatrix<Val>, point: &[Challenge]) -> Vec<Challenge> {
+    (0..evals.width())
+        .map(|col| {
+            let mut column: Vec<Challenge> = evals
+                .rows()
+                .map(|mut row| row.nth(col).unwrap().into())
+                .collect();
+            for &z in point {
+                column = column
+                    .chunks_exact(2)
+                    .map(|pair| pair[0] + z * (pair[1] - pair[0]))
+                    .collect();
+            }
+            column[0]
+        })
+        .collect()
+}
+
+fn do_test_basefold(log_blowup: usize, num_vars: usize, width: usize) {
+    let (pcs, challenger) = get_pcs(log_blowup);
+    let mut rng = SmallRng::seed_from_u64(num_vars as u64);
+    let evals = RowMajorMatrix::<Val>::rand(&mut rng, 1 << num_vars, width);
+
+    let (commitment, data) =
+        <MyPcs as MultilinearPcs<Challenge, Challenger>>::commit(&pcs, evals.clone());
+
+    let mut p_challenger = challenger.clone();
+    p_challenger.observe(commitment.clone());
+    let point: Vec<Challenge> = (0..num_vars)
+        .map(|_| p_challenger.sample_algebra_element())
+        .collect();
+    let (values, proof) = pcs.open(&data, &point, &mut p_challenger);
+    assert_eq!(values, eval_columns(&evals, &point));
+
+    let mut v_challenger = challenger;
+    v_challenger.observe(commitment.clone());
+    let verifier_point: Vec<Challenge> = (0..num_vars)
+        .map(|_| v_challenger.sample_algebra_element())
+        .collect();
+    assert_eq!(verifier_point, point);
+    pcs.verify(&commitment, &point, &values, &proof, &mut v_challenger)
+        .unwrap();
+}
+
+#[test]
+fn basefold_single_column() {
+    for num_vars in 0..8 {
+        do_test_basefold(1, num_vars, 1);
+    }
+}
+
+#[test]
+fn basefold_many_columns() {
+    for num_vars in [1, 5, 9] {
+        do_test_basefold(1, num_vars, 7);
+        do_test_basefold(2, num_vars, 7);
+    }
+}
+
+#[test]
+fn basefold_wrong_value_fails() {
+    let (pcs, challenger) = get_pcs(1);
+    let mut rng = SmallRng::seed_from_u64(1);
+    let evals = RowMajorMatrix::<Val>::rand(&mut rng, 1 << 6, 3);
+    let (commitment, data) = <MyPcs as MultilinearPcs<Challenge, Challenger>>::commit(&pcs, evals);
+    let point: Vec<Challenge> = (0..6).map(|_| rng.random()).collect();
+
+    let (mut values, proof) = pcs.open(&data, &point, &mut challenger.clone());
+    pcs.verify(
+        &commitment,
+        &point,
+        &values,
+        &proof,
+        &mut challenger.clone(),
+    )
+    .unwrap();
+
+    values[1] += Challenge::ONE;
+    assert!(matches!(
+        pcs.verify(
+            &commitment,
+            &point,
+            &values,
+            &proof,
+            &mut challenger.clone()
+        ),
+        Err(BasefoldError::SumcheckMismatch)
+    ));
+
+    values[1] -= Challenge::ONE;
+    let mut tampered = proof.clone();
+    tampered.final_value += Challenge::ONE;
+    assert!(
+        pcs.verify(
+            &commitment,
+            &point,
+            &values,
+            &tampered,
+            &mut challenger.clone()
+        )
+        .is_err()
+    );
+
+    let mut tampered = proof;
+    tampered.commit_phase_openings[0].sibling_values[0][0] += Challenge::ONE;
+    assert!(
+        pcs.verify(
+            &commitment,
+            &point,
+            &values,
+            &tampered,
+            &mut challenger.clone()
+        )
+        .is_err()
+    );
+}
@@ -3,11 +3,12 @@ use core::marker::PhantomData;
 
 use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
 use p3_challenger::{CanSampleBits, DuplexChallenger, FieldChallenger};
-use p3_commit::ExtensionMmcs;
+use p3_commit::{ExtensionMmcs, Mmcs};
 use p3_dft::{Radix2Dit, TwoAdicSubgroupDft};
 use p3_field::extension::BinomialExtensionField;
 use p3_field::{Field, PrimeCharacteristicRing};
-use p3_fri::{FriConfig, TwoAdicFriGenericConfig, prover, verifier};
+use p3_fri::verifier::FriError;
+use p3_fri::{FriConfig, FriProof, TwoAdicFriGenericConfig, prover, verifier};
 use p3_matrix::Matrix;
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::util::reverse_matrix_index_bits;
@@ -29,7 +30,11 @@ type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
 type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
 type MyFriConfig = FriConfig<ChallengeMmcs>;
 
-fn get_ldt_for_testing<R: Rng>(rng: &mut R, log_final_poly_len: usize) -> (Perm, MyFriConfig) {
+fn get_ldt_for_testing<R: Rng>(
+    rng: &mut R,
+    log_final_poly_len: usize,
+    log_folding_arities: Vec<usize>,
+) -> (Perm, MyFriConfig) {
     let perm = Perm::new_from_rng_128(rng);
     let hash = MyHash::new(perm.clone());
     let compress = MyCompress::new(perm.clone());
@@ -37,6 +42,8 @@ fn get_ldt_for_testing<R: Rng>(rng: &mut R, log_final_poly_len: usize) -> (Perm,
     let fri_config = FriConfig {
         log_blowup: 1,
         log_final_poly_len,
+        log_folding_arity: log_folding_arities.last().copied().unwrap_or(1),
+        log_folding_arities,
         num_queries: 10,
         proof_of_work_bits: 8,
         mmcs,
@@ -44,8 +51,48 @@ fn get_ldt_for_testing<R: Rng>(rng: &mut R, log_final_poly_len: usize) -> (Perm,
     (perm, fri_config)
 }
 
-fn do_test_fri_ldt<R: Rng>(rng: &mut R, log_final_poly_len: usize) {
-    let (perm, fc) = get_ldt_for_testing(rng, log_final_poly_len);
+fn do_test_fri_ldt<R: Rng>(
+    rng: &mut R,
+    log_final_poly_len: usize,
+    log_folding_arities: Vec<usize>,
+) {
+    prove_and_verify(
+        rng,
+        log_final_poly_len,
+        log_folding_arities.clone(),
+        log_folding_arities,
+    )
+    .unwrap();
+}
+
+type MyFriProof = FriProof<Challenge, ChallengeMmcs, Val, Vec<Vec<(usize, Challenge)>>>;
+type MyFriError = FriError<<ChallengeMmcs as Mmcs<Challenge>>::Error, ()>;
+
+/// Prove with the given folding arities and verify with possibly different ones.
+fn prove_and_verify<R: Rng>(
+    rng: &mut R,
+    log_final_poly_len: usize,
+    prover_log_folding_arities: Vec<usize>,
+    verifier_log_folding_arities: Vec<usize>,
+) -> Result<(), MyFriError> {
+    prove_tamper_and_verify(
+        rng,
+        log_final_poly_len,
+        prover_log_folding_arities,
+        verifier_log_folding_arities,
+        |_| {},
+    )
+}
+
+/// As `prove_and_verify`, but modify the proof with `tamper` before verifying it.
+fn prove_tamper_and_verify<R: Rng>(
+    rng: &mut R,
+    log_final_poly_len: usize,
+    prover_log_folding_arities: Vec<usize>,
+    verifier_log_folding_arities: Vec<usize>,
+    tamper: impl FnOnce(&mut MyFriProof),
+) -> Result<(), MyFriError> {
+    let (perm, fc) = get_ldt_for_testing(rng, log_final_poly_len, prover_log_folding_arities);
     let dft = Radix2Dit::default();
 
     let shift = Val::GENERATOR;
@@ -59,7 +106,7 @@ fn do_test_fri_ldt<R: Rng>(rng: &mut R, log_final_poly_len: usize) {
         })
         .collect();
 
-    let (proof, p_sample) = {
+    let (mut proof, p_sample) = {
         // Prover world
         let mut chal = Challenger::new(perm.clone());
         let alpha: Challenge = chal.sample_algebra_element();
@@ -94,41 +141,52 @@ fn do_test_fri_ldt<R: Rng>(rng: &mut R, log_final_poly_len: usize) {
         let log_max_height = log2_strict_usize(input[0].len());
 
         let proof = prover::prove(
-            &TwoAdicFriGenericConfig::<Vec<(usize, Challenge)>, ()>(PhantomData),
+            &TwoAdicFriGenericConfig::<Vec<Vec<(usize, Challenge)>>, ()>(PhantomData),
             &fc,
             input.clone(),
             &mut chal,
-            |idx| {
+            |indices| {
                 // As our "input opening proof", just pass through the literal reduced openings.
-                let mut ro = vec![];
-                for v in &input {
-                    let log_height = log2_strict_usize(v.len());
-                    ro.push((log_height, v[idx >> (log_max_height - log_height)]));
-                }
-                ro.sort_by_key(|(lh, _)| Reverse(*lh));
-                ro
+                indices
+                    .iter()
+                    .map(|idx| {
+                        let mut ro = vec![];
+                        for v in &input {
+                            let log_height = log2_strict_usize(v.len());
+                            ro.push((log_height, v[idx >> (log_max_height - log_height)]));
+                        }
+                        ro.sort_by_key(|(lh, _)| Reverse(*lh));
+                        ro
+                    })
+                    .collect()
             },
         );
 
         (proof, chal.sample_bits(8))
     };
 
+    let fc = FriConfig {
+        log_folding_arity: verifier_log_folding_arities.last().copied().unwrap_or(1),
+        log_folding_arities: verifier_log_folding_arities,
+        ..fc
+    };
+    tamper(&mut proof);
     let mut v_challenger = Challenger::new(perm);
     let _alpha: Challenge = v_challenger.sample_algebra_element();
     verifier::verify(
-        &TwoAdicFriGenericConfig::<Vec<(usize, Challenge)>, ()>(PhantomData),
+        &TwoAdicFriGenericConfig::<Vec<Vec<(usize, Challenge)>>, ()>(PhantomData),
         &fc,
         &proof,
         &mut v_challenger,
-        |_index, proof| Ok(proof.clone()),
-    )
-    .unwrap();
+        |_indices, proof| Ok(proof.clone()),
+    )?;
 
     assert_eq!(
         p_sample,
         v_challenger.sample_bits(8),
         "prover and verifier transcript have same state after FRI"
     );
+    Ok(())
 }
 
 #[test]
@@ -136,10 +194,39 @@ fn test_fri_ldt() {
     // FRI is kind of flaky depending on indexing luck
     for i in 0..4 {
         let mut rng = SmallRng::seed_from_u64(i as u64);
-        do_test_fri_ldt(&mut rng, i + 1);
+        do_test_fri_ldt(&mut rng, i + 1, vec![]);
+    }
+}
+
+#[test]
+fn test_fri_ldt_higher_arity() {
+    for (i, log_folding_arities) in [vec![2], vec![3], vec![4], vec![3, 1, 2]]
+        .into_iter()
+        .enumerate()
+    {
+        let mut rng = SmallRng::seed_from_u64(i as u64);
+        do_test_fri_ldt(&mut rng, i % 3, log_folding_arities);
     }
 }
 
+#[test]
+fn test_fri_ldt_arity_mismatch() {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let result = prove_and_verify(&mut rng, 1, vec![2], vec![1]);
+    assert!(matches!(result, Err(FriError::InvalidProofShape)));
+}
+
+#[test]
+fn test_fri_ldt_long_final_poly() {
+    // Extra coefficients would let the final polynomial have a higher degree than the config
+    // allows, so the proof must be rejected even if they are zero.
+    let mut rng = SmallRng::seed_from_u64(0);
+    let result = prove_tamper_and_verify(&mut rng, 1, vec![], vec![], |proof| {
+        proof.final_poly.push(Challenge::ZERO);
+    });
+    assert!(matches!(result, Err(FriError::InvalidProofShape)));
+}
+
 // This test is expected to panic because the polynomial degree is less than the final_poly_degree in the config.
 #[test]
 #[should_panic]
@@ -147,6 +234,6 @@ fn test_fri_ldt_should_panic() {
     // FRI is kind of flaky depending on indexing luck
     for i in 0..4 {
         let mut rng = SmallRng::seed_from_u64(i);
-        do_test_fri_ldt(&mut rng, 5);
+        do_test_fri_ldt(&mut rng, 5, vec![]);
     }
 }
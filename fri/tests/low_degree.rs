@@ -0,0 +1,279 @@
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::{CanObserve, DuplexChallenger};
+use p3_commit::{ExtensionMmcs, Mmcs};
+use p3_dft::{Radix2DitParallel, TwoAdicSubgroupDft};
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{Field, PrimeCharacteristicRing};
+use p3_fri::verifier::FriError;
+use p3_fri::{FriConfig, prove_low_degree, verify_low_degree};
+use p3_matrix::Matrix;
+use p3_matrix::bitrev::BitReversibleMatrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+type Val = BabyBear;
+type Challenge = BinomialExtensionField<Val, 4>;
+
+type Perm = Poseidon2BabyBear<16>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs =
+    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Dft = Radix2DitParallel<Val>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+type MyFriError =
+    FriError<<ChallengeMmcs as Mmcs<Challenge>>::Error, <ValMmcs as Mmcs<Val>>::Error>;
+
+fn setup(log_final_poly_len: usize) -> (ValMmcs, FriConfig<ChallengeMmcs>, Challenger) {
+    let perm = Perm::new_from_rng_128(&mut SmallRng::seed_from_u64(0));
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let fri_config = FriConfig {
+        log_blowup: 1,
+        log_final_poly_len,
+        log_folding_arity: 1,
+        log_folding_arities: vec![],
+        num_queries: 10,
+        proof_of_work_bits: 8,
+        mmcs: ChallengeMmcs::new(val_mmcs.clone()),
+    };
+    (val_mmcs, fri_config, Challenger::new(perm))
+}
+
+/// The bit-reversed evaluations over a coset of random polynomials of degree less than
+/// `2^log_degree`, blown up by `2^log_blowup`.
+fn random_codewords(log_degree: usize, log_blowup: usize, width: usize) -> RowMajorMatrix<Val> {
+    let mut rng = SmallRng::seed_from_u64(log_degree as u64);
+    let coeffs = RowMajorMatrix::<Val>::rand(&mut rng, 1 << log_degree, width);
+    Dft::default()
+        .coset_lde_batch(coeffs, log_blowup, Val::GENERATOR)
+        .bit_reverse_rows()
+        .to_row_major_matrix()
+}
+
+/// The bit-reversed evaluations over a coset of size `2^log_height` of random polynomials of degree
+/// less than `degree`, which need not be a power of two.
+fn random_codewords_of_degree(
+    degree: usize,
+    log_height: usize,
+    width: usize,
+) -> RowMajorMatrix<Val> {
+    let mut rng = SmallRng::seed_from_u64(degree as u64);
+    let mut coeffs = RowMajorMatrix::<Val>::rand(&mut rng, degree, width);
+    coeffs.pad_to_height(1 << log_height, Val::ZERO);
+    Dft::default()
+        .coset_dft_batch(coeffs, Val::GENERATOR)
+        .bit_reverse_rows()
+        .to_row_major_matrix()
+}
+
+/// Prove and verify that the columns of `evals` have degree less than `degree_bound`, or the
+/// largest bound FRI supports if it is `None`.
+fn prove_and_verify(
+    evals: RowMajorMatrix<Val>,
+    degree_bound: Option<usize>,
+    log_final_poly_len: usize,
+) -> Result<(), MyFriError> {
+    let (val_mmcs, fri_config, challenger) = setup(log_final_poly_len);
+    let dimensions = evals.dimensions();
+    let degree_bound = degree_bound.unwrap_or(dimensions.height >> fri_config.log_blowup);
+    let (commitment, prover_data) = val_mmcs.commit_matrix(evals);
+
+    let mut p_challenger = challenger.clone();
+    p_challenger.observe(commitment.clone());
+    let proof = prove_low_degree::<_, Challenge, _, _, _>(
+        &fri_config,
+        &val_mmcs,
+        &prover_data,
+        Val::GENERATOR,
+        degree_bound,
+        &mut p_challenger,
+    );
+
+    let mut v_challenger = challenger;
+    v_challenger.observe(commitment.clone());
+    verify_low_degree(
+        &fri_config,
+        &val_mmcs,
+        &commitment,
+        dimensions,
+        Val::GENERATOR,
+        degree_bound,
+        &proof,
+        &mut v_challenger,
+    )
+}
+
+#[test]
+fn test_low_degree() {
+    for log_degree in [1, 4, 8] {
+        for width in [1, 5] {
+            prove_and_verify(random_codewords(log_degree, 1, width), None, 0)
+                .expect("verification failed");
+        }
+    }
+}
+
+#[test]
+fn test_low_degree_final_poly() {
+    prove_and_verify(random_codewords(8, 1, 3), None, 3).expect("verification failed");
+}
+
+/// Random evaluations, which are far from any polynomial of degree less than `height >> 1`.
+fn random_evaluations() -> RowMajorMatrix<Val> {
+    RowMajorMatrix::rand(&mut SmallRng::seed_from_u64(1), 1 << 7, 2)
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "All coefficients beyond final_poly_len must be zero")]
+fn test_high_degree() {
+    let _ = prove_and_verify(random_evaluations(), None, 2);
+}
+
+#[cfg(not(debug_assertions))]
+#[test]
+fn test_high_degree() {
+    assert!(prove_and_verify(random_evaluations(), None, 2).is_err());
+    // Codewords of twice the degree bound.
+    assert!(prove_and_verify(random_codewords(7, 0, 2), None, 2).is_err());
+}
+
+#[test]
+fn test_low_degree_explicit_bound() {
+    // FRI itself tests a degree bound of `128` here.
+    for degree in [1, 100, 128] {
+        prove_and_verify(random_codewords_of_degree(degree, 8, 3), Some(degree), 0)
+            .expect("verification failed");
+    }
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "All coefficients beyond final_poly_len must be zero")]
+fn test_low_degree_explicit_bound_exceeded() {
+    let _ = prove_and_verify(random_codewords_of_degree(100, 8, 3), Some(99), 0);
+}
+
+#[cfg(not(debug_assertions))]
+#[test]
+fn test_low_degree_explicit_bound_exceeded() {
+    // Within the bound FRI tests by itself, but not within the explicit one.
+    let result = prove_and_verify(random_codewords_of_degree(100, 8, 3), Some(99), 0);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_tampered_final_poly() {
+    let (val_mmcs, fri_config, challenger) = setup(2);
+    let evals = random_codewords(6, 1, 2);
+    let dimensions = evals.dimensions();
+    let (commitment, prover_data) = val_mmcs.commit_matrix(evals);
+
+    let mut proof = prove_low_degree::<_, Challenge, _, _, _>(
+        &fri_config,
+        &val_mmcs,
+        &prover_data,
+        Val::GENERATOR,
+        dimensions.height / 2,
+        &mut challenger.clone(),
+    );
+    proof.final_poly[0] += Challenge::ONE;
+
+    let result = verify_low_degree(
+        &fri_config,
+        &val_mmcs,
+        &commitment,
+        dimensions,
+        Val::GENERATOR,
+        dimensions.height / 2,
+        &proof,
+        &mut challenger.clone(),
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_wrong_commitment() {
+    let (val_mmcs, fri_config, challenger) = setup(0);
+    let evals = random_codewords(6, 1, 2);
+    let dimensions = evals.dimensions();
+    let (_, prover_data) = val_mmcs.commit_matrix(evals);
+    let (other_commitment, _) = val_mmcs.commit_matrix(random_codewords(5, 2, 2));
+
+    let proof = prove_low_degree::<_, Challenge, _, _, _>(
+        &fri_config,
+        &val_mmcs,
+        &prover_data,
+        Val::GENERATOR,
+        dimensions.height / 2,
+        &mut challenger.clone(),
+    );
+
+    let result = verify_low_degree(
+        &fri_config,
+        &val_mmcs,
+        &other_commitment,
+        dimensions,
+        Val::GENERATOR,
+        dimensions.height / 2,
+        &proof,
+        &mut challenger.clone(),
+    );
+    assert!(matches!(result, Err(FriError::InputError(_))));
+}
+
+#[test]
+fn test_wrong_dimensions() {
+    let (val_mmcs, fri_config, challenger) = setup(0);
+    let evals = random_codewords(6, 1, 2);
+    let dimensions = evals.dimensions();
+    let (commitment, prover_data) = val_mmcs.commit_matrix(evals);
+
+    let proof = prove_low_degree::<_, Challenge, _, _, _>(
+        &fri_config,
+        &val_mmcs,
+        &prover_data,
+        Val::GENERATOR,
+        dimensions.height / 2,
+        &mut challenger.clone(),
+    );
+
+    let taller = p3_matrix::Dimensions {
+        height: dimensions.height * 2,
+        ..dimensions
+    };
+    let result = verify_low_degree(
+        &fri_config,
+        &val_mmcs,
+        &commitment,
+        taller,
+        Val::GENERATOR,
+        dimensions.height / 2,
+        &proof,
+        &mut challenger.clone(),
+    );
+    assert!(matches!(result, Err(FriError::InvalidProofShape)));
+
+    let not_power_of_two = p3_matrix::Dimensions {
+        height: dimensions.height - 1,
+        ..dimensions
+    };
+    let result = verify_low_degree(
+        &fri_config,
+        &val_mmcs,
+        &commitment,
+        not_power_of_two,
+        Val::GENERATOR,
+        dimensions.height / 2,
+        &proof,
+        &mut challenger.clone(),
+    );
+    assert!(matches!(result, Err(FriError::InvalidProofShape)));
+}
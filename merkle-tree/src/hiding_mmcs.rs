@@ -2,12 +2,12 @@ use alloc::vec::Vec;
 use core::cell::RefCell;
 
 use itertools::Itertools;
-use p3_commit::{BatchOpening, BatchOpeningRef, Mmcs};
+use p3_commit::{BatchOpening, BatchOpeningRef, Mmcs, MultiBatchOpening, MultiBatchOpeningRef};
 use p3_field::PackedValue;
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::stack::HorizontalPair;
 use p3_matrix::{Dimensions, Matrix};
-use p3_symmetric::{CryptographicHasher, Hash, PseudoCompressionFunction};
+use p3_symmetric::{CryptographicHasher, MerkleCap, PseudoCompressionFunction};
 use p3_util::zip_eq::zip_eq;
 use rand::Rng;
 use rand::distr::{Distribution, StandardUniform};
@@ -46,7 +46,12 @@ impl<P, PW, H, C, R, const DIGEST_ELEMS: usize, const SALT_ELEMS: usize>
     MerkleTreeHidingMmcs<P, PW, H, C, R, DIGEST_ELEMS, SALT_ELEMS>
 {
     pub const fn new(hash: H, compress: C, rng: R) -> Self {
-        let inner = MerkleTreeMmcs::new(hash, compress);
+        Self::new_with_cap_height(hash, compress, 0, rng)
+    }
+
+    /// Create an MMCS which commits to a cap of height `cap_height`, see `MerkleTreeMmcs`.
+    pub const fn new_with_cap_height(hash: H, compress: C, cap_height: usize, rng: R) -> Self {
+        let inner = MerkleTreeMmcs::new_with_cap_height(hash, compress, cap_height);
         Self {
             inner,
             rng: RefCell::new(rng),
@@ -73,7 +78,7 @@ where
 {
     type ProverData<M> =
         MerkleTree<P::Value, PW::Value, HorizontalPair<M, RowMajorMatrix<P::Value>>, DIGEST_ELEMS>;
-    type Commitment = Hash<P::Value, PW::Value, DIGEST_ELEMS>;
+    type Commitment = MerkleCap<P::Value, PW::Value, DIGEST_ELEMS>;
     /// The first item is salts; the second is the usual Merkle proof (sibling digests).
     type Proof = (Vec<Vec<P::Value>>, Vec<[PW::Value; DIGEST_ELEMS]>);
     type Error = MerkleTreeError;
@@ -109,6 +114,27 @@ where
         BatchOpening::new(openings, (salts, siblings))
     }
 
+    fn open_multi_batch<M: Matrix<P::Value>>(
+        &self,
+        indices: &[usize],
+        prover_data: &Self::ProverData<M>,
+    ) -> MultiBatchOpening<P::Value, Self> {
+        let (salted_openings, inner_proofs) =
+            self.inner.open_multi_batch(indices, prover_data).unpack();
+        let (openings, salts): (Vec<Vec<_>>, Vec<Vec<_>>) = salted_openings
+            .into_iter()
+            .map(|rows| {
+                rows.into_iter()
+                    .map(|row| {
+                        let (a, b) = row.split_at(row.len() - SALT_ELEMS);
+                        (a.to_vec(), b.to_vec())
+                    })
+                    .unzip()
+            })
+            .unzip();
+        MultiBatchOpening::new(openings, salts.into_iter().zip(inner_proofs).collect())
+    }
+
     fn get_matrices<'a, M: Matrix<P::Value>>(
         &self,
         prover_data: &'a Self::ProverData<M>,
@@ -136,6 +162,36 @@ where
             BatchOpeningRef::new(&opened_salted_values, siblings),
         )
     }
+
+    fn verify_multi_batch(
+        &self,
+        commit: &Self::Commitment,
+        dimensions: &[Dimensions],
+        indices: &[usize],
+        batch_opening: MultiBatchOpeningRef<P::Value, Self>,
+    ) -> Result<(), Self::Error> {
+        let (opened_values, proofs) = batch_opening.unpack();
+        let (salts, inner_proofs): (Vec<_>, Vec<_>) = proofs
+            .iter()
+            .map(|(salts, siblings)| (salts, siblings.clone()))
+            .unzip();
+
+        let opened_salted_values = zip_eq(opened_values, salts, MerkleTreeError::WrongBatchSize)?
+            .map(|(rows, row_salts)| {
+                zip_eq(rows, row_salts, MerkleTreeError::WrongBatchSize).map(|rows| {
+                    rows.map(|(opened, salt)| opened.iter().chain(salt.iter()).copied().collect())
+                        .collect()
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.inner.verify_multi_batch(
+            commit,
+            dimensions,
+            indices,
+            MultiBatchOpeningRef::new(&opened_salted_values, &inner_proofs),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -204,4 +260,24 @@ mod tests {
         let batch_proof = mmcs.open_batch(17, &prover_data);
         mmcs.verify_batch(&commit, &dims, 17, (&batch_proof).into())
     }
+
+    #[test]
+    fn multi_batch() -> Result<(), MerkleTreeError> {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mats = vec![
+            RowMajorMatrix::<F>::rand(&mut rng, 32, 3),
+            RowMajorMatrix::<F>::rand(&mut rng, 8, 2),
+        ];
+        let perm = Perm::new_from_rng_128(&mut rng);
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm);
+        let mmcs = MyMmcs::new(hash, compress, rng);
+
+        let dims = mats.iter().map(|m| m.dimensions()).collect_vec();
+
+        let (commit, prover_data) = mmcs.commit(mats);
+        let indices = [17, 2, 16, 17];
+        let batch_proof = mmcs.open_multi_batch(&indices, &prover_data);
+        mmcs.verify_multi_batch(&commit, &dims, &indices, (&batch_proof).into())
+    }
 }
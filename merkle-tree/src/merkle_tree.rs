@@ -8,7 +8,7 @@ use itertools::Itertools;
 use p3_field::PackedValue;
 use p3_matrix::Matrix;
 use p3_maybe_rayon::prelude::*;
-use p3_symmetric::{CryptographicHasher, Hash, PseudoCompressionFunction};
+use p3_symmetric::{CryptographicHasher, Hash, MerkleCap, PseudoCompressionFunction};
 use serde::{Deserialize, Serialize};
 use tracing::instrument;
 
@@ -23,7 +23,8 @@ use tracing::instrument;
 /// Leaf matrices may have arbitrary heights as long as any two heights
 /// that round **up** to the same power-of-two are equal.
 ///
-/// Use [`root`] to fetch the final digest once the tree is built.
+/// Use [`root`] to fetch the final digest once the tree is built, or [`cap`] to fetch the
+/// digests a few layers below it.
 ///
 /// This generally shouldn't be used directly. If you're using a Merkle tree as an MMCS,
 /// see `MerkleTreeMmcs`.
@@ -157,6 +158,27 @@ impl<F: Clone + Send + Sync, W: Clone, M: Matrix<F>, const DIGEST_ELEMS: usize>
     {
         self.digest_layers.last().unwrap()[0].into()
     }
+
+    /// Return the `2^cap_height` digests at height `cap_height` below the root.
+    ///
+    /// Digests to the right of the tallest matrices are padded with the default digest.
+    ///
+    /// # Panics
+    /// If `cap_height` exceeds the height of the tree.
+    #[must_use]
+    pub fn cap(&self, cap_height: usize) -> MerkleCap<F, W, DIGEST_ELEMS>
+    where
+        W: Copy + Default,
+    {
+        let log_height = self.digest_layers.len() - 1;
+        assert!(
+            cap_height <= log_height,
+            "cap height exceeds the height of the tree"
+        );
+        let mut digests = self.digest_layers[log_height - cap_height].clone();
+        digests.resize(1 << cap_height, [W::default(); DIGEST_ELEMS]);
+        digests.into()
+    }
 }
 
 /// Hash every row of the tallest matrices and build the first digest layer.
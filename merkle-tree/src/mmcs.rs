@@ -19,21 +19,25 @@
 //! get to the correct level. A proof for the values of say `M[5]` and `N[1]` consists of the siblings `H(M[4]), c23, c10`.
 //!
 
+use alloc::vec;
 use alloc::vec::Vec;
 use core::cmp::Reverse;
 use core::marker::PhantomData;
 
 use itertools::Itertools;
-use p3_commit::{BatchOpening, BatchOpeningRef, Mmcs};
+use p3_commit::{
+    BatchOpening, BatchOpeningRef, Mmcs, MultiBatchOpening, MultiBatchOpeningRef,
+    WrongNumberOfOpenings,
+};
 use p3_field::PackedValue;
 use p3_matrix::{Dimensions, Matrix};
-use p3_symmetric::{CryptographicHasher, Hash, PseudoCompressionFunction};
+use p3_symmetric::{CryptographicHasher, MerkleCap, PseudoCompressionFunction};
 use p3_util::{log2_ceil_usize, log2_strict_usize};
 use serde::{Deserialize, Serialize};
 
 use crate::MerkleTree;
 use crate::MerkleTreeError::{
-    EmptyBatch, IncompatibleHeights, RootMismatch, WrongBatchSize, WrongHeight,
+    EmptyBatch, IncompatibleHeights, RootMismatch, WrongBatchSize, WrongCapHeight, WrongHeight,
 };
 
 /// A vector commitment scheme backed by a `MerkleTree`.
@@ -43,10 +47,15 @@ use crate::MerkleTreeError::{
 /// - `PW`: an element of a digest
 /// - `H`: the leaf hasher
 /// - `C`: the digest compression function
+///
+/// The commitment is a [`MerkleCap`] of `2^cap_height` digests rather than the single root, which
+/// shortens every opening proof by `cap_height` siblings. The cap is lowered as needed so that
+/// every committed matrix has at least as many (padded) rows as the cap has digests.
 #[derive(Copy, Clone, Debug)]
 pub struct MerkleTreeMmcs<P, PW, H, C, const DIGEST_ELEMS: usize> {
     hash: H,
     compress: C,
+    cap_height: usize,
     _phantom: PhantomData<(P, PW)>,
 }
 
@@ -61,16 +70,45 @@ pub enum MerkleTreeError {
     IncompatibleHeights,
     RootMismatch,
     EmptyBatch,
+    WrongCapHeight {
+        cap_height: usize,
+        cap_len: usize,
+    },
+}
+
+impl From<WrongNumberOfOpenings> for MerkleTreeError {
+    fn from(_: WrongNumberOfOpenings) -> Self {
+        WrongBatchSize
+    }
 }
 
 impl<P, PW, H, C, const DIGEST_ELEMS: usize> MerkleTreeMmcs<P, PW, H, C, DIGEST_ELEMS> {
+    /// Create an MMCS which commits to the root of each tree.
     pub const fn new(hash: H, compress: C) -> Self {
+        Self::new_with_cap_height(hash, compress, 0)
+    }
+
+    /// Create an MMCS which commits to the `2^cap_height` digests at height `cap_height` below
+    /// the root of each tree.
+    pub const fn new_with_cap_height(hash: H, compress: C, cap_height: usize) -> Self {
         Self {
             hash,
             compress,
+            cap_height,
             _phantom: PhantomData,
         }
     }
+
+    /// The height of the cap committed to for matrices of the given heights.
+    ///
+    /// Shorter matrices are injected higher up in the tree, so the cap must not be below the layer
+    /// where the shortest matrix is injected, i.e. `cap_height` is at most the log of its height.
+    fn effective_cap_height(&self, heights: impl IntoIterator<Item = usize>) -> usize {
+        heights
+            .into_iter()
+            .map(log2_ceil_usize)
+            .fold(self.cap_height, usize::min)
+    }
 }
 
 impl<P, PW, H, C, const DIGEST_ELEMS: usize> Mmcs<P::Value>
@@ -88,7 +126,7 @@ where
     [PW::Value; DIGEST_ELEMS]: Serialize + for<'de> Deserialize<'de>,
 {
     type ProverData<M> = MerkleTree<P::Value, PW::Value, M, DIGEST_ELEMS>;
-    type Commitment = Hash<P::Value, PW::Value, DIGEST_ELEMS>;
+    type Commitment = MerkleCap<P::Value, PW::Value, DIGEST_ELEMS>;
     type Proof = Vec<[PW::Value; DIGEST_ELEMS]>;
     type Error = MerkleTreeError;
 
@@ -96,9 +134,10 @@ where
         &self,
         inputs: Vec<M>,
     ) -> (Self::Commitment, Self::ProverData<M>) {
+        let cap_height = self.effective_cap_height(inputs.iter().map(|m| m.height()));
         let tree = MerkleTree::new::<P, PW, H, C>(&self.hash, &self.compress, inputs);
-        let root = tree.root();
-        (root, tree)
+        let cap = tree.cap(cap_height);
+        (cap, tree)
     }
 
     /// Opens a batch of rows from committed matrices.
@@ -107,7 +146,7 @@ where
     /// the `j`th row of the ith matrix `M[i]`, with
     ///     `j == index >> (log2_ceil(max_height) - log2_ceil(M[i].height))`
     /// and `proof` is the vector of sibling Merkle tree nodes allowing the verifier to
-    /// reconstruct the committed root.
+    /// reconstruct the committed digest of the cap.
     fn open_batch<M: Matrix<P::Value>>(
         &self,
         index: usize,
@@ -117,25 +156,66 @@ where
         let log_max_height = log2_ceil_usize(max_height);
 
         // Get the matrix rows encountered along the path from the root to the given leaf index.
-        let openings = prover_data
-            .leaves
-            .iter()
-            .map(|matrix| {
-                let log2_height = log2_ceil_usize(matrix.height());
-                let bits_reduced = log_max_height - log2_height;
-                let reduced_index = index >> bits_reduced;
-                matrix.row(reduced_index).unwrap().into_iter().collect()
-            })
-            .collect_vec();
+        let openings = open_rows(&prover_data.leaves, log_max_height, index);
 
-        // Get all the siblings nodes corresponding to the path from the root to the given leaf index.
-        let proof = (0..log_max_height)
+        // Get all the siblings nodes corresponding to the path from the cap to the given leaf index.
+        let cap_height = self.effective_cap_height(prover_data.leaves.iter().map(|m| m.height()));
+        let proof = (0..log_max_height - cap_height)
             .map(|i| prover_data.digest_layers[i][(index >> i) ^ 1])
             .collect();
 
         BatchOpening::new(openings, proof)
     }
 
+    /// Opens a batch of rows at each of several indices from committed matrices.
+    ///
+    /// The openings for each index are as in `open_batch`. Rather than a full path, the proof of
+    /// each index lists the siblings on its path which are neither on the path of another index
+    /// nor in the proof of an earlier index, from the leaves up.
+    fn open_multi_batch<M: Matrix<P::Value>>(
+        &self,
+        indices: &[usize],
+        prover_data: &MerkleTree<P::Value, PW::Value, M, DIGEST_ELEMS>,
+    ) -> MultiBatchOpening<P::Value, Self> {
+        let max_height = self.get_max_height(prover_data);
+        let log_max_height = log2_ceil_usize(max_height);
+
+        let openings = indices
+            .iter()
+            .map(|&index| open_rows(&prover_data.leaves, log_max_height, index))
+            .collect();
+
+        // Each node is kept along with the first index whose path goes through it, which owns the
+        // siblings needed above it.
+        let cap_height = self.effective_cap_height(prover_data.leaves.iter().map(|m| m.height()));
+        let mut nodes = indices
+            .iter()
+            .copied()
+            .zip(0..)
+            .sorted_unstable()
+            .dedup_by(|(node, _), (other, _)| node == other)
+            .collect_vec();
+        let mut proofs = vec![vec![]; indices.len()];
+        for layer in &prover_data.digest_layers[..log_max_height - cap_height] {
+            let mut parents = Vec::with_capacity(nodes.len());
+            let mut nodes_iter = nodes.into_iter().peekable();
+            while let Some((node, owner)) = nodes_iter.next() {
+                // As the nodes are sorted, a sibling on another path must come straight after.
+                let owner = match nodes_iter.next_if(|&(next, _)| next == node ^ 1) {
+                    Some((_, sibling_owner)) => owner.min(sibling_owner),
+                    None => {
+                        proofs[owner].push(layer[node ^ 1]);
+                        owner
+                    }
+                };
+                parents.push((node >> 1, owner));
+            }
+            nodes = parents;
+        }
+
+        MultiBatchOpening::new(openings, proofs)
+    }
+
     fn get_matrices<'a, M: Matrix<P::Value>>(
         &self,
         prover_data: &'a Self::ProverData<M>,
@@ -145,14 +225,15 @@ where
 
     /// Verifies an opened batch of rows with respect to a given commitment.
     ///
-    /// - `commit`: The merkle root of the tree.
+    /// - `commit`: The merkle cap of the tree.
     /// - `dimensions`: A vector of the dimensions of the matrices committed to.
     /// - `index`: The index of a leaf in the tree.
     /// - `opened_values`: A vector of matrix rows. Assume that the tallest matrix committed
     ///   to has height `2^n >= M_tall.height() > 2^{n - 1}` and the `j`th matrix has height
     ///   `2^m >= Mj.height() > 2^{m - 1}`. Then `j`'th value of opened values must be the row `Mj[index >> (m - n)]`.
     /// - `proof`: A vector of sibling nodes. The `i`th element should be the node at level `i`
-    ///   with index `(index << i) ^ 1`.
+    ///   with index `(index << i) ^ 1`. The path stops at the cap, so there are `n` siblings less
+    ///   the height of the cap.
     ///
     /// Returns nothing if the verification is successful, otherwise returns an error.
     fn verify_batch(
@@ -193,16 +274,25 @@ where
             return Err(IncompatibleHeights);
         }
 
+        // The cap must hold one digest for each node at its height.
+        let cap_height = self.effective_cap_height(dimensions.iter().map(|dims| dims.height));
+        if commit.len() != 1 << cap_height {
+            return Err(WrongCapHeight {
+                cap_height,
+                cap_len: commit.len(),
+            });
+        }
+
         // Get the initial height padded to a power of two. As heights_tallest_first is sorted,
         // the initial height will be the maximum height.
         // Returns an error if either:
-        //              1. proof.len() != log_max_height
+        //              1. proof.len() != log_max_height - cap_height
         //              2. heights_tallest_first is empty.
         let mut curr_height_padded = match heights_tallest_first.peek() {
             Some((_, dims)) => {
                 let max_height = dims.height.next_power_of_two();
                 let log_max_height = log2_strict_usize(max_height);
-                if opening_proof.len() != log_max_height {
+                if opening_proof.len() != log_max_height - cap_height {
                     return Err(WrongHeight {
                         log_max_height,
                         num_siblings: opening_proof.len(),
@@ -252,8 +342,172 @@ where
             }
         }
 
-        // The computed root should equal the committed one.
-        if commit == &root {
+        // The computed node should equal the committed one at its position in the cap.
+        if commit.digests().get(index) == Some(&root) {
+            Ok(())
+        } else {
+            Err(RootMismatch)
+        }
+    }
+
+    /// Verifies batches of rows opened at several indices with respect to a given commitment.
+    ///
+    /// - `commit`: The merkle cap of the tree.
+    /// - `dimensions`: A vector of the dimensions of the matrices committed to.
+    /// - `indices`: The indices of leaves in the tree, in any order and possibly repeated.
+    /// - `opened_values`: For each index, a vector of matrix rows as in `verify_batch`.
+    /// - `proof`: For each index, the siblings on its path which are neither on the path of another
+    ///   index nor in the proof of an earlier index, as produced by `open_multi_batch`.
+    ///
+    /// Openings at repeated indices, and rows of shorter matrices shared by several indices,
+    /// must agree. Returns nothing if the verification is successful, otherwise returns an error.
+    fn verify_multi_batch(
+        &self,
+        commit: &Self::Commitment,
+        dimensions: &[Dimensions],
+        indices: &[usize],
+        batch_proof: MultiBatchOpeningRef<P::Value, Self>,
+    ) -> Result<(), Self::Error> {
+        let (opened_values, opening_proof) = batch_proof.unpack();
+        // Check that the openings have the correct shape.
+        if indices.len() != opened_values.len()
+            || indices.len() != opening_proof.len()
+            || opened_values
+                .iter()
+                .any(|openings| openings.len() != dimensions.len())
+        {
+            return Err(WrongBatchSize);
+        }
+
+        let mut heights_tallest_first = dimensions
+            .iter()
+            .enumerate()
+            .sorted_by_key(|(_, dims)| Reverse(dims.height))
+            .peekable();
+
+        // Matrix heights that round up to the same power of two must be equal
+        if !heights_tallest_first
+            .clone()
+            .map(|(_, dims)| dims.height)
+            .tuple_windows()
+            .all(|(curr, next)| {
+                curr == next || curr.next_power_of_two() != next.next_power_of_two()
+            })
+        {
+            return Err(IncompatibleHeights);
+        }
+
+        // The cap must hold one digest for each node at its height.
+        let cap_height = self.effective_cap_height(dimensions.iter().map(|dims| dims.height));
+        if commit.len() != 1 << cap_height {
+            return Err(WrongCapHeight {
+                cap_height,
+                cap_len: commit.len(),
+            });
+        }
+
+        let mut curr_height_padded = match heights_tallest_first.peek() {
+            Some((_, dims)) => dims.height.next_power_of_two(),
+            None => return Err(EmptyBatch),
+        };
+        let log_max_height = log2_strict_usize(curr_height_padded);
+        let wrong_height = || WrongHeight {
+            log_max_height,
+            num_siblings: opening_proof.iter().map(Vec::len).sum(),
+        };
+
+        // Hash the openings of the tallest matrices at each index. Each node is kept along with
+        // the openings below it, sorted by index, and repeated indices must hash alike.
+        let tallest = heights_tallest_first
+            .peeking_take_while(|(_, dims)| dims.height.next_power_of_two() == curr_height_padded)
+            .map(|(i, _)| i)
+            .collect_vec();
+        let mut nodes: Vec<(usize, [PW::Value; DIGEST_ELEMS], Vec<usize>)> = vec![];
+        for k in (0..indices.len()).sorted_by_key(|&k| indices[k]) {
+            let digest = self
+                .hash
+                .hash_iter_slices(tallest.iter().map(|&i| opened_values[k][i].as_slice()));
+            match nodes.last_mut() {
+                Some((node, node_digest, openings)) if *node == indices[k] => {
+                    if *node_digest != digest {
+                        return Err(RootMismatch);
+                    }
+                    openings.push(k);
+                }
+                _ => nodes.push((indices[k], digest, vec![k])),
+            }
+        }
+
+        let mut siblings = opening_proof.iter().map(|proof| proof.iter()).collect_vec();
+        for _ in cap_height..log_max_height {
+            curr_height_padded >>= 1;
+
+            // The matrices whose rows are injected at the next height, if any.
+            let injected = heights_tallest_first
+                .peek()
+                .map(|(_, dims)| dims.height)
+                .filter(|h| h.next_power_of_two() == curr_height_padded)
+                .map(|next_height| {
+                    heights_tallest_first
+                        .peeking_take_while(|(_, dims)| dims.height == next_height)
+                        .map(|(i, _)| i)
+                        .collect_vec()
+                })
+                .unwrap_or_default();
+
+            let mut parents = Vec::with_capacity(nodes.len());
+            let mut nodes_iter = nodes.into_iter().peekable();
+            while let Some((node, digest, mut openings)) = nodes_iter.next() {
+                // Take the sibling from the next path if it is there, or otherwise from the proof.
+                let (left, right) = match nodes_iter.next_if(|(next, _, _)| *next == node ^ 1) {
+                    Some((_, sibling, sibling_openings)) => {
+                        openings.extend(sibling_openings);
+                        (digest, sibling)
+                    }
+                    None => {
+                        // The sibling is in the proof of the first index below this node.
+                        let owner = *openings.iter().min().unwrap();
+                        let &sibling = siblings[owner].next().ok_or_else(wrong_height)?;
+                        if node & 1 == 0 {
+                            (digest, sibling)
+                        } else {
+                            (sibling, digest)
+                        }
+                    }
+                };
+                let mut parent = self.compress.compress([left, right]);
+
+                if !injected.is_empty() {
+                    // Every opening below this node claims the same injected rows.
+                    let mut injected_digests = openings.iter().map(|&k| {
+                        self.hash.hash_iter_slices(
+                            injected.iter().map(|&i| opened_values[k][i].as_slice()),
+                        )
+                    });
+                    let injected_digest = injected_digests.next().unwrap();
+                    if injected_digests.any(|other| other != injected_digest) {
+                        return Err(RootMismatch);
+                    }
+                    parent = self.compress.compress([parent, injected_digest]);
+                }
+
+                parents.push((node >> 1, parent, openings));
+            }
+            nodes = parents;
+        }
+
+        if siblings
+            .iter_mut()
+            .any(|siblings| siblings.next().is_some())
+        {
+            return Err(wrong_height());
+        }
+
+        // The computed nodes should equal the committed ones at their positions in the cap.
+        if nodes
+            .iter()
+            .all(|(node, digest, _)| commit.digests().get(*node) == Some(digest))
+        {
             Ok(())
         } else {
             Err(RootMismatch)
@@ -261,13 +515,32 @@ where
     }
 }
 
+/// The rows of each matrix on the path from the root to the leaf at `index`, where the tallest
+/// matrices have height `2^log_max_height` once padded.
+fn open_rows<F: Clone + Send + Sync, M: Matrix<F>>(
+    leaves: &[M],
+    log_max_height: usize,
+    index: usize,
+) -> Vec<Vec<F>> {
+    leaves
+        .iter()
+        .map(|matrix| {
+            let log2_height = log2_ceil_usize(matrix.height());
+            let bits_reduced = log_max_height - log2_height;
+            let reduced_index = index >> bits_reduced;
+            matrix.row(reduced_index).unwrap().into_iter().collect()
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use alloc::vec;
+    use alloc::vec::Vec;
 
     use itertools::Itertools;
     use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
-    use p3_commit::Mmcs;
+    use p3_commit::{BatchOpening, BatchOpeningRef, Mmcs};
     use p3_field::{Field, PrimeCharacteristicRing};
     use p3_matrix::dense::RowMajorMatrix;
     use p3_matrix::{Dimensions, Matrix};
@@ -278,6 +551,7 @@ mod tests {
     use rand::rngs::SmallRng;
 
     use super::MerkleTreeMmcs;
+    use crate::MerkleTreeError;
 
     type F = BabyBear;
 
@@ -287,6 +561,53 @@ mod tests {
     type MyMmcs =
         MerkleTreeMmcs<<F as Field>::Packing, <F as Field>::Packing, MyHash, MyCompress, 8>;
 
+    /// An MMCS which relies on the default multi-index openings, one path per index.
+    #[derive(Clone)]
+    struct PerIndexMmcs(MyMmcs);
+
+    impl Mmcs<F> for PerIndexMmcs {
+        type ProverData<M> = <MyMmcs as Mmcs<F>>::ProverData<M>;
+        type Commitment = <MyMmcs as Mmcs<F>>::Commitment;
+        type Proof = <MyMmcs as Mmcs<F>>::Proof;
+        type Error = MerkleTreeError;
+
+        fn commit<M: Matrix<F>>(&self, inputs: Vec<M>) -> (Self::Commitment, Self::ProverData<M>) {
+            self.0.commit(inputs)
+        }
+
+        fn open_batch<M: Matrix<F>>(
+            &self,
+            index: usize,
+            prover_data: &Self::ProverData<M>,
+        ) -> BatchOpening<F, Self> {
+            let (opened_values, proof) = self.0.open_batch(index, prover_data).unpack();
+            BatchOpening::new(opened_values, proof)
+        }
+
+        fn get_matrices<'a, M: Matrix<F>>(
+            &self,
+            prover_data: &'a Self::ProverData<M>,
+        ) -> Vec<&'a M> {
+            self.0.get_matrices(prover_data)
+        }
+
+        fn verify_batch(
+            &self,
+            commit: &Self::Commitment,
+            dimensions: &[Dimensions],
+            index: usize,
+            batch_opening: BatchOpeningRef<F, Self>,
+        ) -> Result<(), Self::Error> {
+            let (opened_values, proof) = batch_opening.unpack();
+            self.0.verify_batch(
+                commit,
+                dimensions,
+                index,
+                BatchOpeningRef::new(opened_values, proof),
+            )
+        }
+    }
+
     #[test]
     fn commit_single_1x8() {
         let mut rng = SmallRng::seed_from_u64(1);
@@ -318,7 +639,7 @@ mod tests {
                 compress.compress([hash.hash_item(v[6]), hash.hash_item(v[7])]),
             ]),
         ]);
-        assert_eq!(commit, expected_result);
+        assert_eq!(commit.digests(), [expected_result]);
     }
 
     #[test]
@@ -333,7 +654,7 @@ mod tests {
         let (commit, _) = mmcs.commit(vec![mat.clone()]);
 
         let expected_result = hash.hash_iter(mat.vertically_packed_row(0));
-        assert_eq!(commit, expected_result);
+        assert_eq!(commit.digests(), [expected_result]);
     }
 
     #[test]
@@ -356,7 +677,7 @@ mod tests {
             hash.hash_slice(&[F::ZERO, F::ONE]),
             hash.hash_slice(&[F::TWO, F::ONE]),
         ]);
-        assert_eq!(commit, expected_result);
+        assert_eq!(commit.digests(), [expected_result]);
     }
 
     #[test]
@@ -384,7 +705,7 @@ mod tests {
             ]),
             compress.compress([hash.hash_slice(&[F::TWO, F::TWO]), default_digest]),
         ]);
-        assert_eq!(commit, expected_result);
+        assert_eq!(commit.digests(), [expected_result]);
     }
 
     #[test]
@@ -473,7 +794,7 @@ mod tests {
             ]),
         ]);
 
-        assert_eq!(commit, expected_result);
+        assert_eq!(commit.digests(), [expected_result]);
 
         let (opened_values, _) = mmcs.open_batch(2, &prover_data).unpack();
         assert_eq!(
@@ -623,4 +944,245 @@ mod tests {
         mmcs.verify_batch(&commit, &dims, 17, (&batch_opening).into())
             .expect("expected verification to succeed");
     }
+
+    #[test]
+    fn commit_cap() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let perm = Perm::new_from_rng_128(&mut rng);
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm);
+        let mmcs = MyMmcs::new(hash.clone(), compress.clone());
+        let capped_mmcs = MyMmcs::new_with_cap_height(hash, compress.clone(), 3);
+
+        // A mat with 1000 rows and a mat with 70 rows, both taller than the cap.
+        let mats = vec![
+            RowMajorMatrix::<F>::rand(&mut rng, 1000, 8),
+            RowMajorMatrix::<F>::rand(&mut rng, 70, 3),
+        ];
+        let dims = mats.iter().map(|m| m.dimensions()).collect_vec();
+
+        let (root, _) = mmcs.commit(mats.clone());
+        let (cap, prover_data) = capped_mmcs.commit(mats);
+        assert_eq!(cap.len(), 8);
+
+        // Compressing the cap gives back the root.
+        let mut layer = cap.digests().to_vec();
+        while layer.len() > 1 {
+            layer = layer
+                .chunks_exact(2)
+                .map(|pair| compress.compress([pair[0], pair[1]]))
+                .collect();
+        }
+        assert_eq!(root.digests(), layer);
+
+        for index in [0, 6, 300, 559] {
+            let batch_opening = capped_mmcs.open_batch(index, &prover_data);
+            assert_eq!(batch_opening.opening_proof.len(), 10 - 3);
+            capped_mmcs
+                .verify_batch(&cap, &dims, index, (&batch_opening).into())
+                .expect("expected verification to succeed");
+        }
+    }
+
+    #[test]
+    fn cap_lowered_for_short_matrices() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let perm = Perm::new_from_rng_128(&mut rng);
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm);
+        let mmcs = MyMmcs::new_with_cap_height(hash, compress, 5);
+
+        // The mat with 4 rows is injected two levels below the root, so the cap can't be higher.
+        let mats = vec![
+            RowMajorMatrix::<F>::rand(&mut rng, 64, 2),
+            RowMajorMatrix::<F>::rand(&mut rng, 4, 2),
+        ];
+        let dims = mats.iter().map(|m| m.dimensions()).collect_vec();
+
+        let (cap, prover_data) = mmcs.commit(mats);
+        assert_eq!(cap.len(), 4);
+        let batch_opening = mmcs.open_batch(37, &prover_data);
+        assert_eq!(batch_opening.opening_proof.len(), 6 - 2);
+        mmcs.verify_batch(&cap, &dims, 37, (&batch_opening).into())
+            .expect("expected verification to succeed");
+    }
+
+    #[test]
+    fn verify_wrong_cap_height_fails() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let perm = Perm::new_from_rng_128(&mut rng);
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm);
+        let mmcs = MyMmcs::new_with_cap_height(hash.clone(), compress.clone(), 2);
+        let other_mmcs = MyMmcs::new_with_cap_height(hash, compress, 1);
+
+        let mat = RowMajorMatrix::<F>::rand(&mut rng, 32, 2);
+        let dims = vec![mat.dimensions()];
+
+        let (cap, prover_data) = mmcs.commit(vec![mat]);
+        let batch_opening = mmcs.open_batch(9, &prover_data);
+        assert!(matches!(
+            other_mmcs.verify_batch(&cap, &dims, 9, (&batch_opening).into()),
+            Err(MerkleTreeError::WrongCapHeight {
+                cap_height: 1,
+                cap_len: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn multi_batch_dedups_shared_nodes() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let perm = Perm::new_from_rng_128(&mut rng);
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm);
+        let mmcs = MyMmcs::new_with_cap_height(hash, compress, 1);
+
+        let mats = vec![
+            RowMajorMatrix::<F>::rand(&mut rng, 64, 3),
+            RowMajorMatrix::<F>::rand(&mut rng, 16, 2),
+            RowMajorMatrix::<F>::rand(&mut rng, 6, 1),
+        ];
+        let dims = mats.iter().map(|m| m.dimensions()).collect_vec();
+        let (cap, prover_data) = mmcs.commit(mats);
+
+        // Unsorted, with a repeated index and a pair of siblings.
+        let indices = [41, 3, 40, 47, 3, 17];
+        let multi_opening = mmcs.open_multi_batch(&indices, &prover_data);
+        for (&index, opened_values) in indices.iter().zip(&multi_opening.opened_values) {
+            assert_eq!(
+                opened_values,
+                &mmcs.open_batch(index, &prover_data).opened_values
+            );
+        }
+
+        // The paths of 40 and 41 meet straight away, and all paths meet below the cap.
+        let single_proofs_len = indices.len() * (6 - 1);
+        let multi_proof_len: usize = multi_opening.opening_proof.iter().map(Vec::len).sum();
+        assert!(multi_proof_len < single_proofs_len - 5);
+        // The repeated index and the sibling of the earlier 41 have nothing left to prove.
+        assert!(multi_opening.opening_proof[2].is_empty());
+        assert!(multi_opening.opening_proof[4].is_empty());
+
+        mmcs.verify_multi_batch(&cap, &dims, &indices, (&multi_opening).into())
+            .expect("expected verification to succeed");
+    }
+
+    #[test]
+    fn verify_tampered_multi_batch_fails() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let perm = Perm::new_from_rng_128(&mut rng);
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm);
+        let mmcs = MyMmcs::new(hash, compress);
+
+        let mats = vec![
+            RowMajorMatrix::<F>::rand(&mut rng, 32, 2),
+            RowMajorMatrix::<F>::rand(&mut rng, 8, 2),
+        ];
+        let dims = mats.iter().map(|m| m.dimensions()).collect_vec();
+        let (commit, prover_data) = mmcs.commit(mats);
+
+        let indices = [5, 9, 5, 12];
+        let multi_opening = mmcs.open_multi_batch(&indices, &prover_data);
+
+        // Disagreeing openings at a repeated index.
+        let mut tampered = multi_opening.clone();
+        tampered.opened_values[2][0][1] += F::ONE;
+        assert!(
+            mmcs.verify_multi_batch(&commit, &dims, &indices, (&tampered).into())
+                .is_err()
+        );
+
+        // Disagreeing openings of a row of the shorter matrix, shared by indices 9 and 12.
+        let mut tampered = multi_opening.clone();
+        tampered.opened_values[3][1][0] += F::ONE;
+        assert!(
+            mmcs.verify_multi_batch(&commit, &dims, &indices, (&tampered).into())
+                .is_err()
+        );
+
+        // A missing sibling.
+        let mut tampered = multi_opening.clone();
+        tampered.opening_proof[0].pop();
+        assert!(matches!(
+            mmcs.verify_multi_batch(&commit, &dims, &indices, (&tampered).into()),
+            Err(MerkleTreeError::WrongHeight { .. })
+        ));
+
+        // A superfluous sibling.
+        let mut tampered = multi_opening.clone();
+        tampered.opening_proof[0].push([F::ZERO; 8]);
+        assert!(matches!(
+            mmcs.verify_multi_batch(&commit, &dims, &indices, (&tampered).into()),
+            Err(MerkleTreeError::WrongHeight { .. })
+        ));
+
+        // A sibling moved to the proof of another index.
+        let mut tampered = multi_opening.clone();
+        let sibling = tampered.opening_proof[0].pop().unwrap();
+        tampered.opening_proof[3].push(sibling);
+        assert!(
+            mmcs.verify_multi_batch(&commit, &dims, &indices, (&tampered).into())
+                .is_err()
+        );
+
+        // A missing proof.
+        let mut tampered = multi_opening.clone();
+        tampered.opening_proof.pop();
+        assert!(matches!(
+            mmcs.verify_multi_batch(&commit, &dims, &indices, (&tampered).into()),
+            Err(MerkleTreeError::WrongBatchSize)
+        ));
+
+        // Openings claimed at other indices.
+        assert!(
+            mmcs.verify_multi_batch(&commit, &dims, &[5, 9, 5, 13], (&multi_opening).into())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn default_multi_batch_opens_every_index() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let perm = Perm::new_from_rng_128(&mut rng);
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm);
+        let mmcs = PerIndexMmcs(MyMmcs::new(hash, compress));
+
+        let mats = vec![
+            RowMajorMatrix::<F>::rand(&mut rng, 32, 2),
+            RowMajorMatrix::<F>::rand(&mut rng, 8, 2),
+        ];
+        let dims = mats.iter().map(|m| m.dimensions()).collect_vec();
+        let (commit, prover_data) = mmcs.commit(mats);
+
+        let indices = [5, 9, 5, 12];
+        let multi_opening = mmcs.open_multi_batch(&indices, &prover_data);
+        for ((&index, opened_values), proof) in indices
+            .iter()
+            .zip(&multi_opening.opened_values)
+            .zip(&multi_opening.opening_proof)
+        {
+            let opening = mmcs.open_batch(index, &prover_data);
+            assert_eq!(opened_values, &opening.opened_values);
+            assert_eq!(proof, &opening.opening_proof);
+        }
+        mmcs.verify_multi_batch(&commit, &dims, &indices, (&multi_opening).into())
+            .expect("expected verification to succeed");
+
+        let mut tampered = multi_opening.clone();
+        tampered.opened_values[3][1][0] += F::ONE;
+        assert!(matches!(
+            mmcs.verify_multi_batch(&commit, &dims, &indices, (&tampered).into()),
+            Err(MerkleTreeError::RootMismatch)
+        ));
+
+        let mut tampered = multi_opening;
+        tampered.opening_proof.pop();
+        assert!(matches!(
+            mmcs.verify_multi_batch(&commit, &dims, &indices, (&tampered).into()),
+            Err(MerkleTreeError::WrongBatchSize)
+        ));
+    }
 }
@@ -0,0 +1,86 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(Debug)]
+pub struct StirConfig<M> {
+    pub log_blowup: usize,
+    /// The log of the folding factor `k`, which must be at least 1. Each round folds the
+    /// polynomial by `k` but only halves the evaluation domain, so the rate of the tested code
+    /// improves by a factor of `k / 2` from one round to the next.
+    pub log_folding_factor: usize,
+    /// Rounds stop once folding would bring the degree below `2^log_final_poly_len`, after which
+    /// the folded polynomial is sent in the clear.
+    pub log_final_poly_len: usize,
+    /// The number of bits of security the queries of each round should provide, together with
+    /// the proof of work, assuming each query to a code of rate `ρ` provides `log2(1/ρ)` bits.
+    pub security_bits: usize,
+    pub proof_of_work_bits: usize,
+    pub mmcs: M,
+}
+
+impl<M> StirConfig<M> {
+    pub const fn blowup(&self) -> usize {
+        1 << self.log_blowup
+    }
+
+    pub const fn final_poly_len(&self) -> usize {
+        1 << self.log_final_poly_len
+    }
+
+    /// The log of the inverse rate of the code tested in the given round.
+    pub const fn log_inv_rate(&self, round: usize) -> usize {
+        self.log_blowup + round * (self.log_folding_factor - 1)
+    }
+
+    /// The number of queries made in the given round, which decreases as the rate improves.
+    pub const fn num_queries(&self, round: usize) -> usize {
+        self.security_bits
+            .saturating_sub(self.proof_of_work_bits)
+            .div_ceil(self.log_inv_rate(round))
+    }
+
+    /// The log of the folding factor of each round when testing a polynomial of degree less than
+    /// `2^log_degree`. All rounds but the last commit to a folded codeword.
+    ///
+    /// A round only commits if the folded polynomial stays longer than the final polynomial and
+    /// than the number of points it is then queried at, as the verifier divides these out of it.
+    /// The last round folds by no more than the remaining degree.
+    pub fn log_folding_factors(&self, mut log_degree: usize) -> Vec<usize> {
+        let mut log_folding_factors = vec![];
+        while log_degree > self.log_final_poly_len + self.log_folding_factor
+            && self.num_queries(log_folding_factors.len()) + 1
+                < 1 << (log_degree - self.log_folding_factor)
+        {
+            log_folding_factors.push(self.log_folding_factor);
+            log_degree -= self.log_folding_factor;
+        }
+        log_folding_factors.push(self.log_folding_factor.min(log_degree));
+        log_folding_factors
+    }
+}
+
+/// Creates a minimal `StirConfig` for testing purposes.
+/// This configuration is designed to reduce computational cost during tests.
+pub const fn create_test_stir_config<Mmcs>(mmcs: Mmcs) -> StirConfig<Mmcs> {
+    StirConfig {
+        log_blowup: 1,
+        log_folding_factor: 2,
+        log_final_poly_len: 0,
+        security_bits: 8,
+        proof_of_work_bits: 1,
+        mmcs,
+    }
+}
+
+/// Creates a `StirConfig` suitable for benchmarking.
+/// This configuration represents typical settings used in production-like scenarios.
+pub const fn create_benchmark_stir_config<Mmcs>(mmcs: Mmcs) -> StirConfig<Mmcs> {
+    StirConfig {
+        log_blowup: 1,
+        log_folding_factor: 4,
+        log_final_poly_len: 0,
+        security_bits: 100,
+        proof_of_work_bits: 16,
+        mmcs,
+    }
+}
@@ -0,0 +1,18 @@
+//! An implementation of the STIR low-degree test (LDT), and a PCS built on it.
+//!
+//! See [STIR: Reed–Solomon Proximity Testing with Fewer Queries](https://eprint.iacr.org/2024/390).
+
+#![no_std]
+
+extern crate alloc;
+
+mod config;
+mod pcs;
+mod proof;
+pub mod prover;
+mod utils;
+pub mod verifier;
+
+pub use config::*;
+pub use pcs::*;
+pub use proof::*;
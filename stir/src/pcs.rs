@@ -0,0 +1,442 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use itertools::{Itertools, izip};
+use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
+use p3_commit::{Mmcs, MultiBatchOpening, OpenedValues, Pcs};
+use p3_dft::TwoAdicSubgroupDft;
+use p3_field::coset::TwoAdicMultiplicativeCoset;
+use p3_field::{
+    ExtensionField, PackedFieldExtension, TwoAdicField, batch_multiplicative_inverse,
+    cyclic_subgroup_coset_known_order, dot_product,
+};
+use p3_interpolation::interpolate_coset_with_precomputation;
+use p3_matrix::bitrev::{BitReversedMatrixView, BitReversibleMatrix};
+use p3_matrix::dense::{RowMajorMatrix, RowMajorMatrixView};
+use p3_matrix::{Dimensions, Matrix};
+use p3_maybe_rayon::prelude::*;
+use p3_util::linear_map::LinearMap;
+use p3_util::zip_eq::zip_eq;
+use p3_util::{log2_strict_usize, reverse_slice_index_bits};
+use tracing::{info_span, instrument};
+
+use crate::utils::domain_point;
+use crate::verifier::{self, StirError};
+use crate::{StirConfig, StirProof, prover};
+
+/// A polynomial commitment scheme over two-adic cosets, which proves openings with the STIR
+/// low-degree test rather than FRI.
+///
+/// Commitments are the same as those of `TwoAdicFriPcs`. To open them, all quotients
+/// `(p(X) - p(z)) / (X - z)` are combined with powers of a random `alpha` into a single codeword
+/// over the largest domain, with each shorter codeword lifted by evaluating it at the point of its
+/// own domain which the point of the largest domain maps to.
+#[derive(Debug)]
+pub struct StirPcs<Val, Dft, InputMmcs, StirMmcs> {
+    pub(crate) dft: Dft,
+    pub(crate) mmcs: InputMmcs,
+    pub(crate) stir: StirConfig<StirMmcs>,
+    _phantom: PhantomData<Val>,
+}
+
+impl<Val, Dft, InputMmcs, StirMmcs> StirPcs<Val, Dft, InputMmcs, StirMmcs> {
+    pub const fn new(dft: Dft, mmcs: InputMmcs, stir: StirConfig<StirMmcs>) -> Self {
+        Self {
+            dft,
+            mmcs,
+            stir,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<Val, Dft, InputMmcs, StirMmcs, Challenge, Challenger> Pcs<Challenge, Challenger>
+    for StirPcs<Val, Dft, InputMmcs, StirMmcs>
+where
+    Val: TwoAdicField,
+    Dft: TwoAdicSubgroupDft<Val>,
+    InputMmcs: Mmcs<Val>,
+    StirMmcs: Mmcs<Challenge>,
+    Challenge: TwoAdicField + ExtensionField<Val>,
+    Challenger:
+        FieldChallenger<Val> + CanObserve<StirMmcs::Commitment> + GrindingChallenger<Witness = Val>,
+{
+    type Domain = TwoAdicMultiplicativeCoset<Val>;
+    type Commitment = InputMmcs::Commitment;
+    type ProverData = InputMmcs::ProverData<RowMajorMatrix<Val>>;
+    type EvaluationsOnDomain<'a> = BitReversedMatrixView<RowMajorMatrixView<'a, Val>>;
+    type Proof = StirProof<Challenge, StirMmcs, Val, Vec<MultiBatchOpening<Val, InputMmcs>>>;
+    type Error = StirError<StirMmcs::Error, InputMmcs::Error>;
+    const ZK: bool = false;
+
+    fn natural_domain_for_degree(&self, degree: usize) -> Self::Domain {
+        // This panics if (and only if) `degree` is not a power of 2 or `degree`
+        // > `1 << Val::TWO_ADICITY`.
+        TwoAdicMultiplicativeCoset::new(Val::ONE, log2_strict_usize(degree)).unwrap()
+    }
+
+    fn commit(
+        &self,
+        evaluations: impl IntoIterator<Item = (Self::Domain, RowMajorMatrix<Val>)>,
+    ) -> (Self::Commitment, Self::ProverData) {
+        let ldes: Vec<_> = evaluations
+            .into_iter()
+            .map(|(domain, evals)| {
+                assert_eq!(domain.size(), evals.height());
+                let shift = Val::GENERATOR / domain.shift();
+                // Commit to the bit-reversed LDE.
+                self.dft
+                    .coset_lde_batch(evals, self.stir.log_blowup, shift)
+                    .bit_reverse_rows()
+                    .to_row_major_matrix()
+            })
+            .collect();
+
+        self.mmcs.commit(ldes)
+    }
+
+    fn get_evaluations_on_domain<'a>(
+        &self,
+        prover_data: &'a Self::ProverData,
+        idx: usize,
+        domain: Self::Domain,
+    ) -> Self::EvaluationsOnDomain<'a> {
+        assert_eq!(domain.shift(), Val::GENERATOR);
+        let lde = self.mmcs.get_matrices(prover_data)[idx];
+        assert!(lde.height() >= domain.size());
+        lde.split_rows(domain.size()).0.bit_reverse_rows()
+    }
+
+    fn open(
+        &self,
+        // For each round,
+        rounds: Vec<(
+            &Self::ProverData,
+            // for each matrix,
+            Vec<
+                // points to open
+                Vec<Challenge>,
+            >,
+        )>,
+        challenger: &mut Challenger,
+    ) -> (OpenedValues<Challenge>, Self::Proof) {
+        let mats_and_points = rounds
+            .iter()
+            .map(|(data, points)| {
+                let mats = self
+                    .mmcs
+                    .get_matrices(data)
+                    .into_iter()
+                    .map(|m| m.as_view())
+                    .collect_vec();
+                debug_assert_eq!(
+                    mats.len(),
+                    points.len(),
+                    "each matrix should have a corresponding set of evaluation points"
+                );
+                (mats, points)
+            })
+            .collect_vec();
+
+        let (global_max_height, global_max_width) = mats_and_points
+            .iter()
+            .flat_map(|(mats, _)| mats.iter().map(|m| (m.height(), m.width())))
+            .reduce(|(hmax, wmax), (h, w)| (hmax.max(h), wmax.max(w)))
+            .expect("No Matrices Supplied?");
+        let log_global_max_height = log2_strict_usize(global_max_height);
+
+        let mut coset = cyclic_subgroup_coset_known_order(
+            Val::two_adic_generator(log_global_max_height),
+            Val::GENERATOR,
+            global_max_height,
+        )
+        .collect_vec();
+        reverse_slice_index_bits(&mut coset);
+
+        let inv_denoms = compute_inverse_denominators(&mats_and_points, &coset);
+
+        // Evaluate coset representations and write openings to the challenger
+        let all_opened_values = mats_and_points
+            .iter()
+            .map(|(mats, points)| {
+                izip!(mats.iter(), points.iter())
+                    .map(|(mat, points_for_mat)| {
+                        let h = mat.height() >> self.stir.log_blowup;
+                        // `subgroup` and `mat` are both in bit-reversed order, so we can truncate.
+                        let (low_coset, _) = mat.split_rows(h);
+                        let coset_h = &coset[..h];
+
+                        points_for_mat
+                            .iter()
+                            .map(|&point| {
+                                let _guard =
+                                    info_span!("evaluate matrix", dims = %mat.dimensions())
+                                        .entered();
+
+                                let inv_denoms = &inv_denoms.get(&point).unwrap()[..h];
+                                let ys = interpolate_coset_with_precomputation(
+                                    &low_coset,
+                                    Val::GENERATOR,
+                                    point,
+                                    coset_h,
+                                    inv_denoms,
+                                );
+                                ys.iter()
+                                    .for_each(|&y| challenger.observe_algebra_element(y));
+                                ys
+                            })
+                            .collect_vec()
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        // Batch combination challenge
+        let alpha: Challenge = challenger.sample_algebra_element();
+
+        let packed_alpha_powers =
+            Challenge::ExtensionPacking::packed_ext_powers_capped(alpha, global_max_width)
+                .collect_vec();
+        let alpha_powers =
+            Challenge::ExtensionPacking::to_ext_iter(packed_alpha_powers.iter().copied())
+                .collect_vec();
+
+        // Unlike FRI, STIR tests a single codeword, so every quotient gets its own powers of
+        // alpha, even across heights. `reduced_openings[log_height]` holds the combination of the
+        // quotients of that height.
+        let mut alpha_pow_offset = Challenge::ONE;
+        let mut reduced_openings: [_; 32] = core::array::from_fn(|_| None);
+
+        for ((mats, points), openings_for_round) in
+            mats_and_points.iter().zip(all_opened_values.iter())
+        {
+            for (mat, points_for_mat, openings_for_mat) in
+                izip!(mats.iter(), points.iter(), openings_for_round.iter())
+            {
+                let _guard =
+                    info_span!("reduce matrix quotient", dims = %mat.dimensions()).entered();
+
+                let log_height = log2_strict_usize(mat.height());
+                let reduced_opening_for_log_height: &mut Vec<Challenge> = reduced_openings
+                    [log_height]
+                    .get_or_insert_with(|| vec![Challenge::ZERO; mat.height()]);
+
+                let mat_compressed = info_span!("compress mat").in_scope(|| {
+                    mat.rowwise_packed_dot_product::<Challenge>(&packed_alpha_powers)
+                        .collect::<Vec<_>>()
+                });
+
+                for (&point, openings) in points_for_mat.iter().zip(openings_for_mat) {
+                    let reduced_openings: Challenge =
+                        dot_product(alpha_powers.iter().copied(), openings.iter().copied());
+
+                    mat_compressed
+                        .par_iter()
+                        .zip(reduced_opening_for_log_height.par_iter_mut())
+                        .zip(inv_denoms.get(&point).unwrap().par_iter())
+                        .for_each(|((&reduced_row, ro), &inv_denom)| {
+                            *ro += alpha_pow_offset * (reduced_openings - reduced_row) * inv_denom
+                        });
+                    alpha_pow_offset *= alpha.exp_u64(mat.width() as u64);
+                }
+            }
+        }
+
+        // Lift every reduced codeword to the largest domain and sum them.
+        let stir_input = info_span!("lift reduced openings").in_scope(|| {
+            let mut stir_input = vec![Challenge::ZERO; global_max_height];
+            for (log_height, reduced) in reduced_openings.iter().enumerate() {
+                if let Some(reduced) = reduced {
+                    let bits_reduced = log_global_max_height - log_height;
+                    stir_input
+                        .par_iter_mut()
+                        .enumerate()
+                        .for_each(|(i, x)| *x += reduced[i >> bits_reduced]);
+                }
+            }
+            stir_input
+        });
+
+        let stir_proof = prover::prove(&self.stir, stir_input, challenger, |indices| {
+            rounds
+                .iter()
+                .map(|(data, _)| {
+                    let log_max_height = log2_strict_usize(self.mmcs.get_max_height(data));
+                    let bits_reduced = log_global_max_height - log_max_height;
+                    let reduced_indices = indices
+                        .iter()
+                        .map(|index| index >> bits_reduced)
+                        .collect_vec();
+                    self.mmcs.open_multi_batch(&reduced_indices, data)
+                })
+                .collect()
+        });
+
+        (all_opened_values, stir_proof)
+    }
+
+    fn verify(
+        &self,
+        // For each round:
+        rounds: Vec<(
+            Self::Commitment,
+            // for each matrix:
+            Vec<(
+                // its domain,
+                Self::Domain,
+                // for each point:
+                Vec<(
+                    // the point,
+                    Challenge,
+                    // values at the point
+                    Vec<Challenge>,
+                )>,
+            )>,
+        )>,
+        proof: &Self::Proof,
+        challenger: &mut Challenger,
+    ) -> Result<(), Self::Error> {
+        // Write evaluations to challenger
+        for (_, round) in &rounds {
+            for (_, mat) in round {
+                for (_, point) in mat {
+                    point
+                        .iter()
+                        .for_each(|&opening| challenger.observe_algebra_element(opening));
+                }
+            }
+        }
+
+        // Batch combination challenge
+        let alpha: Challenge = challenger.sample_algebra_element();
+
+        let log_global_max_height = rounds
+            .iter()
+            .flat_map(|(_, mats)| mats)
+            .map(|(domain, _)| log2_strict_usize(domain.size()) + self.stir.log_blowup)
+            .max()
+            .ok_or(StirError::InvalidProofShape)?;
+
+        verifier::verify(
+            &self.stir,
+            log_global_max_height,
+            proof,
+            challenger,
+            |indices, input_proof| {
+                let mut reduced_openings = vec![Challenge::ZERO; indices.len()];
+                // Each quotient is combined with its own powers of alpha, in the order the prover
+                // used, so every query tracks the same running power.
+                let mut alpha_pows = vec![Challenge::ONE; indices.len()];
+
+                for (batch_opening, (batch_commit, mats)) in
+                    zip_eq(input_proof, &rounds, StirError::InvalidProofShape)?
+                {
+                    let batch_heights = mats
+                        .iter()
+                        .map(|(domain, _)| domain.size() << self.stir.log_blowup)
+                        .collect_vec();
+                    let batch_dims = batch_heights
+                        .iter()
+                        .map(|&height| Dimensions { width: 0, height })
+                        .collect_vec();
+
+                    if let Some(batch_max_height) = batch_heights.iter().max() {
+                        let log_batch_max_height = log2_strict_usize(*batch_max_height);
+                        let bits_reduced = log_global_max_height - log_batch_max_height;
+                        let reduced_indices = indices
+                            .iter()
+                            .map(|index| index >> bits_reduced)
+                            .collect_vec();
+
+                        self.mmcs.verify_multi_batch(
+                            batch_commit,
+                            &batch_dims,
+                            &reduced_indices,
+                            batch_opening.into(),
+                        )
+                    } else {
+                        // Empty batch?
+                        self.mmcs.verify_multi_batch(
+                            batch_commit,
+                            &[],
+                            indices,
+                            batch_opening.into(),
+                        )
+                    }
+                    .map_err(StirError::InputError)?;
+
+                    for (((&index, query_opening), ro), alpha_pow) in zip_eq(
+                        zip_eq(
+                            zip_eq(
+                                indices,
+                                &batch_opening.opened_values,
+                                StirError::InvalidProofShape,
+                            )?,
+                            &mut reduced_openings,
+                            StirError::InvalidProofShape,
+                        )?,
+                        &mut alpha_pows,
+                        StirError::InvalidProofShape,
+                    )? {
+                        for (mat_opening, (mat_domain, mat_points_and_values)) in
+                            zip_eq(query_opening, mats, StirError::InvalidProofShape)?
+                        {
+                            let log_height =
+                                log2_strict_usize(mat_domain.size()) + self.stir.log_blowup;
+                            let bits_reduced = log_global_max_height - log_height;
+                            let x: Val = domain_point(log_height, index >> bits_reduced);
+
+                            for (z, ps_at_z) in mat_points_and_values {
+                                for (&p_at_x, &p_at_z) in
+                                    zip_eq(mat_opening, ps_at_z, StirError::InvalidProofShape)?
+                                {
+                                    let quotient = (-p_at_z + p_at_x) / (-*z + x);
+                                    *ro += *alpha_pow * quotient;
+                                    *alpha_pow *= alpha;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                Ok(reduced_openings)
+            },
+        )
+    }
+}
+
+#[instrument(skip_all)]
+fn compute_inverse_denominators<F: TwoAdicField, EF: ExtensionField<F>, M: Matrix<F>>(
+    mats_and_points: &[(Vec<M>, &Vec<Vec<EF>>)],
+    coset: &[F],
+) -> LinearMap<EF, Vec<EF>> {
+    let mut max_log_height_for_point: LinearMap<EF, usize> = LinearMap::new();
+    for (mats, points) in mats_and_points {
+        for (mat, points_for_mat) in izip!(mats, *points) {
+            let log_height = log2_strict_usize(mat.height());
+            for &z in points_for_mat {
+                if let Some(lh) = max_log_height_for_point.get_mut(&z) {
+                    *lh = core::cmp::max(*lh, log_height);
+                } else {
+                    max_log_height_for_point.insert(z, log_height);
+                }
+            }
+        }
+    }
+
+    max_log_height_for_point
+        .into_iter()
+        .map(|(z, log_height)| {
+            (
+                z,
+                batch_multiplicative_inverse(
+                    &coset[..(1 << log_height)]
+                        .iter()
+                        .map(|&x| z - x)
+                        .collect_vec(),
+                ),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,36 @@
+use alloc::vec::Vec;
+
+use p3_commit::{Mmcs, MultiBatchOpening};
+use p3_field::Field;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound(
+    serialize = "Witness: Serialize, InputProof: Serialize",
+    deserialize = "Witness: Deserialize<'de>, InputProof: Deserialize<'de>"
+))]
+pub struct StirProof<F: Field, M: Mmcs<F>, Witness, InputProof> {
+    /// One entry for each round that commits to a folded codeword.
+    pub round_proofs: Vec<StirRoundProof<F, M, Witness>>,
+    /// The coefficients of the polynomial obtained by folding in the last round.
+    pub final_poly: Vec<F>,
+    pub final_pow_witness: Witness,
+    /// The openings of the inputs at the locations queried in the first round.
+    pub input_proof: InputProof,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound(
+    serialize = "Witness: Serialize",
+    deserialize = "Witness: Deserialize<'de>"
+))]
+pub struct StirRoundProof<F: Field, M: Mmcs<F>, Witness> {
+    /// A commitment to the evaluations of the folded polynomial, with each row of the committed
+    /// matrix holding the points that the next round folds together.
+    pub commitment: M::Commitment,
+    /// The evaluation of the folded polynomial at an out-of-domain point.
+    pub ood_answer: F,
+    pub pow_witness: Witness,
+    /// The openings of the committed matrix at all rows queried in the next round.
+    pub query_openings: MultiBatchOpening<F, M>,
+}
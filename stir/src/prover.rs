@@ -0,0 +1,168 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter;
+
+use itertools::Itertools;
+use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
+use p3_commit::Mmcs;
+use p3_dft::{Radix2Dit, TwoAdicSubgroupDft};
+use p3_field::{ExtensionField, TwoAdicField};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_util::{log2_strict_usize, reverse_slice_index_bits};
+use tracing::{debug_span, info_span, instrument};
+
+use crate::utils::{degree_correct, divide_by_vanishing, eval_poly, fold_point, fold_poly};
+use crate::{StirConfig, StirProof, StirRoundProof};
+
+/// Prove that `input`, the evaluations of a function over the coset `gH` in bit-reversed order,
+/// where `g` is the generator of `Val`, is close to a polynomial of degree less than
+/// `|H| / blowup`.
+///
+/// `open_input` is called once with the indices of `input` queried in the first round.
+#[instrument(name = "STIR prover", skip_all)]
+pub fn prove<Val, Challenge, M, Challenger, InputProof>(
+    config: &StirConfig<M>,
+    input: Vec<Challenge>,
+    challenger: &mut Challenger,
+    open_input: impl Fn(&[usize]) -> InputProof,
+) -> StirProof<Challenge, M, Challenger::Witness, InputProof>
+where
+    Val: TwoAdicField,
+    Challenge: ExtensionField<Val> + TwoAdicField,
+    M: Mmcs<Challenge>,
+    Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<M::Commitment>,
+{
+    let mut log_height = log2_strict_usize(input.len());
+    assert!(log_height >= config.log_blowup);
+    let log_degree = log_height - config.log_blowup;
+    let log_folding_factors = config.log_folding_factors(log_degree);
+    let (&log_final_arity, log_round_arities) = log_folding_factors.split_last().unwrap();
+
+    let dft = Radix2Dit::default();
+    let shift = Challenge::from(Val::GENERATOR);
+
+    let mut coeffs = debug_span!("idft input").in_scope(|| {
+        let mut evals = input;
+        reverse_slice_index_bits(&mut evals);
+        let mut coeffs = dft.coset_idft(evals, shift);
+        debug_assert!(
+            coeffs[1 << log_degree..].iter().all(|c| c.is_zero()),
+            "The input is not a low degree codeword"
+        );
+        coeffs.truncate(1 << log_degree);
+        coeffs
+    });
+
+    let mut input_proof = None;
+    let mut prover_data = None;
+    // The openings of each committed codeword, which are made in the round after its commitment.
+    let mut query_openings = vec![];
+    // Open the rows of the current codeword which fold to the points at the given indices.
+    let mut open_rows =
+        |indices: &[usize],
+         log_arity: usize,
+         prover_data: Option<&M::ProverData<RowMajorMatrix<Challenge>>>| {
+            match prover_data {
+                None => {
+                    let leaf_indices = indices
+                        .iter()
+                        .flat_map(|&index| (index << log_arity)..((index + 1) << log_arity))
+                        .collect_vec();
+                    input_proof = Some(open_input(&leaf_indices));
+                }
+                Some(data) => query_openings.push(config.mmcs.open_multi_batch(indices, data)),
+            }
+        };
+
+    let mut commitments = vec![];
+    let mut ood_answers = vec![];
+    let mut pow_witnesses = vec![];
+
+    for (round, (&log_arity, &log_next_arity)) in
+        log_folding_factors.iter().tuple_windows().enumerate()
+    {
+        let _guard = info_span!("STIR round", round).entered();
+
+        let beta: Challenge = challenger.sample_algebra_element();
+        let folded = fold_poly(&coeffs, log_arity, beta);
+
+        // The folded codeword is evaluated over a domain half the size of the current one, so
+        // its rate improves by a factor of `2^log_arity / 2`.
+        let mut evals = folded.clone();
+        evals.resize(1 << (log_height - 1), Challenge::ZERO);
+        let mut evals = debug_span!("dft folded").in_scope(|| dft.coset_dft(evals, shift));
+        reverse_slice_index_bits(&mut evals);
+        let (commitment, data) = config
+            .mmcs
+            .commit_matrix(RowMajorMatrix::new(evals, 1 << log_next_arity));
+        challenger.observe(commitment.clone());
+
+        // Out-of-domain sample.
+        let ood_point: Challenge = challenger.sample_algebra_element();
+        let ood_answer = eval_poly(&folded, ood_point);
+        challenger.observe_algebra_element(ood_answer);
+
+        let pow_witness = challenger.grind(config.proof_of_work_bits);
+
+        let indices = iter::repeat_with(|| challenger.sample_bits(log_height - log_arity))
+            .take(config.num_queries(round))
+            .collect_vec();
+        open_rows(&indices, log_arity, prover_data.as_ref());
+
+        let comb: Challenge = challenger.sample_algebra_element();
+
+        // The verifier learns the folded polynomial at the out-of-domain point and at the points
+        // the queried rows fold to. Quotient these out, and correct the degree.
+        let points =
+            iter::once(ood_point)
+                .chain(
+                    indices.into_iter().sorted().dedup().map(|index| {
+                        Challenge::from(fold_point::<Val>(log_height, log_arity, index))
+                    }),
+                )
+                .collect_vec();
+        coeffs = degree_correct(&divide_by_vanishing(&folded, &points), comb, points.len());
+        debug_assert_eq!(coeffs.len(), folded.len());
+
+        commitments.push(commitment);
+        ood_answers.push(ood_answer);
+        pow_witnesses.push(pow_witness);
+        prover_data = Some(data);
+        log_height -= 1;
+    }
+
+    let beta: Challenge = challenger.sample_algebra_element();
+    let final_poly = fold_poly(&coeffs, log_final_arity, beta);
+    for &x in &final_poly {
+        challenger.observe_algebra_element(x);
+    }
+
+    let final_pow_witness = challenger.grind(config.proof_of_work_bits);
+
+    let indices = iter::repeat_with(|| challenger.sample_bits(log_height - log_final_arity))
+        .take(config.num_queries(log_round_arities.len()))
+        .collect_vec();
+    open_rows(&indices, log_final_arity, prover_data.as_ref());
+
+    let round_proofs = commitments
+        .into_iter()
+        .zip_eq(ood_answers)
+        .zip_eq(pow_witnesses)
+        .zip_eq(query_openings)
+        .map(
+            |(((commitment, ood_answer), pow_witness), query_openings)| StirRoundProof {
+                commitment,
+                ood_answer,
+                pow_witness,
+                query_openings,
+            },
+        )
+        .collect();
+
+    StirProof {
+        round_proofs,
+        final_poly,
+        final_pow_witness,
+        input_proof: input_proof.unwrap(),
+    }
+}
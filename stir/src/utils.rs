@@ -0,0 +1,109 @@
+use alloc::vec::Vec;
+
+use p3_field::{ExtensionField, Field, TwoAdicField};
+use p3_util::reverse_bits_len;
+
+/// Evaluate the polynomial with the given coefficients at `x`.
+pub(crate) fn eval_poly<F: Field>(coeffs: &[F], x: F) -> F {
+    coeffs.iter().rfold(F::ZERO, |acc, &c| acc * x + c)
+}
+
+/// The point of the folded domain that the row `index` of a codeword of log height `log_height`
+/// folds to, when folding by `2^log_arity`.
+///
+/// Codewords are evaluations over the coset `gH` in bit-reversed order, so each row of
+/// `2^log_arity` consecutive evaluations is a coset `x_0 K` of the subgroup `K` of that order, and
+/// the row folds to `x_0^|K|`.
+pub(crate) fn fold_point<F: TwoAdicField>(log_height: usize, log_arity: usize, index: usize) -> F {
+    let log_folded_height = log_height - log_arity;
+    F::GENERATOR.exp_power_of_2(log_arity)
+        * F::two_adic_generator(log_folded_height)
+            .exp_u64(reverse_bits_len(index, log_folded_height) as u64)
+}
+
+/// The point of the domain at which the row `index` of a codeword of log height `log_height` is
+/// evaluated.
+pub(crate) fn domain_point<F: TwoAdicField>(log_height: usize, index: usize) -> F {
+    F::GENERATOR
+        * F::two_adic_generator(log_height).exp_u64(reverse_bits_len(index, log_height) as u64)
+}
+
+/// Fold the polynomial `f(X) = sum_i X^i f_i(X^k)` by `k = 2^log_arity` into
+/// `sum_i beta^i f_i(X)`.
+pub(crate) fn fold_poly<F: Field>(coeffs: &[F], log_arity: usize, beta: F) -> Vec<F> {
+    coeffs
+        .chunks(1 << log_arity)
+        .map(|chunk| eval_poly(chunk, beta))
+        .collect()
+}
+
+/// Given the evaluations of `f` at a row `x_0 K` of a codeword, in bit-reversed order, compute
+/// the evaluation of the fold of `f` by `beta` at `x_0^|K|`.
+///
+/// On this row `f` agrees with the polynomial `P(X) = sum_i X^i f_i(x_0^|K|)`, so the fold is
+/// `P(beta)`, which we find by interpolating `u -> P(x_0 u)` over `K` at `beta / x_0`.
+pub(crate) fn fold_row<F: TwoAdicField, EF: ExtensionField<F>>(row: &[EF], x_0: F, beta: EF) -> EF {
+    let arity = row.len();
+    if arity == 1 {
+        return row[0];
+    }
+    let log_arity = arity.ilog2() as usize;
+    let u = beta * x_0.inverse();
+
+    // sum_i P(g^i) g^i / (u - g^i), where `g` generates `K`.
+    let mut sum = EF::ZERO;
+    for (i, g_i) in F::two_adic_generator(log_arity)
+        .powers()
+        .take(arity)
+        .enumerate()
+    {
+        let eval = row[reverse_bits_len(i, log_arity)];
+        let diff = u - g_i;
+        if diff.is_zero() {
+            return eval;
+        }
+        sum += eval * g_i * diff.inverse();
+    }
+    // The barycentric weight `(u^|K| - 1) / |K|`.
+    sum * (u.exp_power_of_2(log_arity) - EF::ONE) * F::from_usize(arity).inverse()
+}
+
+/// The quotient of `f` by the vanishing polynomial of `points`, discarding the remainder.
+///
+/// If the answers to the points are the evaluations of `f`, the remainder is their interpolant
+/// and this is `(f - Ans) / V`.
+pub(crate) fn divide_by_vanishing<F: Field>(coeffs: &[F], points: &[F]) -> Vec<F> {
+    let mut quotient = coeffs.to_vec();
+    for &point in points {
+        // Synthetic division by `X - point`.
+        let mut carry = F::ZERO;
+        for c in quotient.iter_mut().rev() {
+            let next = *c + carry * point;
+            *c = carry;
+            carry = next;
+        }
+        // The leading coefficient is left as zero, and the carry is the remainder.
+        quotient.pop();
+    }
+    quotient
+}
+
+/// Multiply `f` by `sum_{i=0}^{num_points} (comb X)^i`, which brings the degree of a quotient by
+/// the vanishing polynomial of `num_points` points back up to that of the dividend.
+pub(crate) fn degree_correct<F: Field>(coeffs: &[F], comb: F, num_points: usize) -> Vec<F> {
+    let comb_pow = comb.exp_u64(num_points as u64 + 1);
+    let mut result = Vec::with_capacity(coeffs.len() + num_points);
+    let mut acc = F::ZERO;
+    for i in 0..coeffs.len() + num_points {
+        // This is a sliding window sum of `comb^j c_{i - j}` over `0 <= j <= num_points`.
+        acc *= comb;
+        if let Some(&c) = coeffs.get(i) {
+            acc += c;
+        }
+        if let Some(&c) = i.checked_sub(num_points + 1).and_then(|j| coeffs.get(j)) {
+            acc -= comb_pow * c;
+        }
+        result.push(acc);
+    }
+    result
+}
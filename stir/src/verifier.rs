@@ -0,0 +1,281 @@
+use alloc::vec::Vec;
+use core::iter;
+
+use itertools::Itertools;
+use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
+use p3_commit::Mmcs;
+use p3_field::{ExtensionField, Field, TwoAdicField};
+use p3_matrix::Dimensions;
+
+use crate::utils::{domain_point, eval_poly, fold_point, fold_row};
+use crate::{StirConfig, StirProof, StirRoundProof};
+
+#[derive(Debug)]
+pub enum StirError<RoundMmcsErr, InputError> {
+    InvalidProofShape,
+    RoundMmcsError(RoundMmcsErr),
+    InputError(InputError),
+    FinalPolyMismatch,
+    InvalidPowWitness,
+}
+
+/// Verify that the function opened by `open_input`, over a coset of log height `log_height` in
+/// bit-reversed order, is close to a polynomial of degree less than `2^log_height / blowup`.
+///
+/// `open_input` is given the queried indices of the input and must return the input evaluated
+/// at each of them.
+pub fn verify<Val, Challenge, M, Challenger, InputProof, InputError>(
+    config: &StirConfig<M>,
+    mut log_height: usize,
+    proof: &StirProof<Challenge, M, Challenger::Witness, InputProof>,
+    challenger: &mut Challenger,
+    open_input: impl Fn(
+        &[usize],
+        &InputProof,
+    ) -> Result<Vec<Challenge>, StirError<M::Error, InputError>>,
+) -> Result<(), StirError<M::Error, InputError>>
+where
+    Val: TwoAdicField,
+    Challenge: ExtensionField<Val> + TwoAdicField,
+    M: Mmcs<Challenge>,
+    Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<M::Commitment>,
+{
+    let mut log_degree = log_height
+        .checked_sub(config.log_blowup)
+        .ok_or(StirError::InvalidProofShape)?;
+    let log_folding_factors = config.log_folding_factors(log_degree);
+    let (&log_final_arity, log_round_arities) = log_folding_factors.split_last().unwrap();
+    if proof.round_proofs.len() != log_round_arities.len() {
+        return Err(StirError::InvalidProofShape);
+    }
+
+    let mut prev_round = None;
+
+    for (round, (round_proof, &log_arity)) in
+        proof.round_proofs.iter().zip(log_round_arities).enumerate()
+    {
+        let beta: Challenge = challenger.sample_algebra_element();
+        challenger.observe(round_proof.commitment.clone());
+
+        let ood_point: Challenge = challenger.sample_algebra_element();
+        challenger.observe_algebra_element(round_proof.ood_answer);
+
+        if !challenger.check_witness(config.proof_of_work_bits, round_proof.pow_witness) {
+            return Err(StirError::InvalidPowWitness);
+        }
+
+        let indices = iter::repeat_with(|| challenger.sample_bits(log_height - log_arity))
+            .take(config.num_queries(round))
+            .collect_vec();
+        let folds = fold_queried_rows::<Val, _, _, _, InputError>(
+            config,
+            log_height,
+            log_arity,
+            &indices,
+            beta,
+            prev_round.as_ref(),
+            || open_input(&row_leaves(&indices, log_arity), &proof.input_proof),
+        )?;
+
+        let comb: Challenge = challenger.sample_algebra_element();
+
+        let (points, answers) = iter::once((ood_point, round_proof.ood_answer))
+            .chain(
+                indices
+                    .into_iter()
+                    .zip(folds)
+                    .sorted_by_key(|&(index, _)| index)
+                    .dedup_by(|(a, _), (b, _)| a == b)
+                    .map(|(index, fold)| {
+                        (
+                            Challenge::from(fold_point::<Val>(log_height, log_arity, index)),
+                            fold,
+                        )
+                    }),
+            )
+            .unzip();
+
+        prev_round = Some(CommittedRound {
+            proof: round_proof,
+            quotient: Quotient::new(points, answers, comb),
+        });
+        log_height -= 1;
+        log_degree -= log_arity;
+    }
+
+    let beta: Challenge = challenger.sample_algebra_element();
+
+    if proof.final_poly.len() != 1 << (log_degree - log_final_arity) {
+        return Err(StirError::InvalidProofShape);
+    }
+    for &x in &proof.final_poly {
+        challenger.observe_algebra_element(x);
+    }
+
+    if !challenger.check_witness(config.proof_of_work_bits, proof.final_pow_witness) {
+        return Err(StirError::InvalidPowWitness);
+    }
+
+    let indices = iter::repeat_with(|| challenger.sample_bits(log_height - log_final_arity))
+        .take(config.num_queries(log_round_arities.len()))
+        .collect_vec();
+    let folds = fold_queried_rows::<Val, _, _, _, InputError>(
+        config,
+        log_height,
+        log_final_arity,
+        &indices,
+        beta,
+        prev_round.as_ref(),
+        || open_input(&row_leaves(&indices, log_final_arity), &proof.input_proof),
+    )?;
+
+    for (index, fold) in indices.into_iter().zip(folds) {
+        let x: Challenge = fold_point::<Val>(log_height, log_final_arity, index).into();
+        if eval_poly(&proof.final_poly, x) != fold {
+            return Err(StirError::FinalPolyMismatch);
+        }
+    }
+
+    Ok(())
+}
+
+/// The indices of all leaves of the given rows, when each row holds `2^log_arity` leaves.
+fn row_leaves(indices: &[usize], log_arity: usize) -> Vec<usize> {
+    indices
+        .iter()
+        .flat_map(|&index| (index << log_arity)..((index + 1) << log_arity))
+        .collect()
+}
+
+/// Open the queried rows of the current codeword and fold each of them by `beta`.
+///
+/// In the first round the current codeword is the input. In later rounds it is the quotient of
+/// the codeword committed in the previous round, whose openings are in that round's proof.
+fn fold_queried_rows<Val, Challenge, M, Witness, InputError>(
+    config: &StirConfig<M>,
+    log_height: usize,
+    log_arity: usize,
+    indices: &[usize],
+    beta: Challenge,
+    prev_round: Option<&CommittedRound<'_, Challenge, M, Witness>>,
+    open_input: impl FnOnce() -> Result<Vec<Challenge>, StirError<M::Error, InputError>>,
+) -> Result<Vec<Challenge>, StirError<M::Error, InputError>>
+where
+    Val: TwoAdicField,
+    Challenge: ExtensionField<Val> + TwoAdicField,
+    M: Mmcs<Challenge>,
+{
+    let arity = 1 << log_arity;
+    let rows = match prev_round {
+        None => {
+            let leaves = open_input()?;
+            if leaves.len() != indices.len() * arity {
+                return Err(StirError::InvalidProofShape);
+            }
+            leaves.chunks(arity).map(<[_]>::to_vec).collect_vec()
+        }
+        Some(CommittedRound {
+            proof: round_proof,
+            quotient,
+        }) => {
+            let dims = Dimensions {
+                width: arity,
+                height: 1 << (log_height - log_arity),
+            };
+            config
+                .mmcs
+                .verify_multi_batch(
+                    &round_proof.commitment,
+                    &[dims],
+                    indices,
+                    (&round_proof.query_openings).into(),
+                )
+                .map_err(StirError::RoundMmcsError)?;
+
+            indices
+                .iter()
+                .zip(&round_proof.query_openings.opened_values)
+                .map(|(&index, opened_rows)| {
+                    let row = match opened_rows.as_slice() {
+                        [row] if row.len() == arity => row,
+                        _ => return Err(StirError::InvalidProofShape),
+                    };
+                    Ok(row
+                        .iter()
+                        .enumerate()
+                        .map(|(j, &eval)| {
+                            let x = domain_point::<Val>(log_height, (index << log_arity) + j);
+                            quotient.eval(x.into(), eval)
+                        })
+                        .collect_vec())
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+
+    Ok(indices
+        .iter()
+        .zip(rows)
+        .map(|(&index, row)| {
+            let x_0 = domain_point::<Val>(log_height, index << log_arity);
+            fold_row(&row, x_0, beta)
+        })
+        .collect())
+}
+
+/// A round which committed to a codeword, along with the quotient which turns that codeword into
+/// the one tested in the next round.
+struct CommittedRound<'a, F: Field, M: Mmcs<F>, Witness> {
+    proof: &'a StirRoundProof<F, M, Witness>,
+    quotient: Quotient<F>,
+}
+
+/// The map from a committed codeword `g` to the codeword
+/// `(g - Ans) / V * sum_{i=0}^{|S|} (comb X)^i`, where `V` vanishes on the set `S` of points and
+/// `Ans` interpolates the answers over them.
+struct Quotient<F> {
+    points: Vec<F>,
+    /// The barycentric weights `ans_s / prod_{s' != s} (s - s')`, so that
+    /// `Ans(x) / V(x) = sum_s weight_s / (x - s)`.
+    weights: Vec<F>,
+    comb: F,
+}
+
+impl<F: Field> Quotient<F> {
+    fn new(points: Vec<F>, answers: Vec<F>, comb: F) -> Self {
+        let weights = points
+            .iter()
+            .zip(answers)
+            .map(|(&s, answer)| {
+                let denom: F = points.iter().filter(|&&t| t != s).map(|&t| s - t).product();
+                answer * denom.inverse()
+            })
+            .collect();
+        Self {
+            points,
+            weights,
+            comb,
+        }
+    }
+
+    fn eval(&self, x: F, g_x: F) -> F {
+        let vanishing: F = self.points.iter().map(|&s| x - s).product();
+        let ans_over_vanishing: F = self
+            .points
+            .iter()
+            .zip(&self.weights)
+            .map(|(&s, &weight)| weight * (x - s).inverse())
+            .sum();
+        let quotient = g_x * vanishing.inverse() - ans_over_vanishing;
+
+        // sum_{i=0}^{|S|} (comb x)^i, as a geometric series.
+        let num_terms = self.points.len() + 1;
+        let ratio = self.comb * x;
+        let degree_correction = if ratio == F::ONE {
+            F::from_usize(num_terms)
+        } else {
+            (ratio.exp_u64(num_terms as u64) - F::ONE) * (ratio - F::ONE).inverse()
+        };
+        quotient * degree_correction
+    }
+}
@@ -0,0 +1,38 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use p3_field::Field;
+
+/// The evaluations of `eq(point, b)` over the Boolean hypercube, where entry `i` corresponds to the
+/// point whose `j`-th coordinate is bit `j` of `i`.
+pub fn eq_evals<F: Field>(point: &[F]) -> Vec<F> {
+    let mut evals = vec![F::ONE];
+    for &z in point {
+        let (lo, hi): (Vec<_>, Vec<_>) = evals.iter().map(|&e| (e - e * z, e * z)).unzip();
+        evals = lo;
+        evals.extend(hi);
+    }
+    evals
+}
+
+/// `eq(x, y) = prod_i (x_i y_i + (1 - x_i) (1 - y_i))`, which for points of the hypercube is one
+/// if they are equal and zero otherwise.
+pub fn eval_eq<F: Field>(x: &[F], y: &[F]) -> F {
+    debug_assert_eq!(x.len(), y.len());
+    x.iter()
+        .zip(y)
+        .map(|(&x_i, &y_i)| x_i * y_i + (F::ONE - x_i) * (F::ONE - y_i))
+        .product()
+}
+
+/// `eq(b, point)` for the point `b` of the hypercube corresponding to `index`.
+pub fn eval_eq_at_index<F: Field>(index: usize, point: &[F]) -> F {
+    point
+        .iter()
+        .enumerate()
+        .map(|(j, &z)| match (index >> j) & 1 {
+            0 => F::ONE - z,
+            _ => z,
+        })
+        .product()
+}
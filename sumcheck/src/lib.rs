@@ -0,0 +1,20 @@
+//! The sumcheck protocol, and protocols built on it: a zerocheck which proves that AIR constraints
+//! vanish over the Boolean hypercube, and LogUp-GKR which proves a sum of fractions for lookups.
+
+#![no_std]
+
+extern crate alloc;
+
+mod eq;
+mod logup_gkr;
+mod polynomial;
+mod proof;
+pub mod prover;
+pub mod verifier;
+mod zerocheck;
+
+pub use eq::*;
+pub use logup_gkr::*;
+pub use polynomial::*;
+pub use proof::*;
+pub use zerocheck::*;
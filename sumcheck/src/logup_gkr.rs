@@ -0,0 +1,396 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use p3_air::Interaction;
+use p3_challenger::FieldChallenger;
+use p3_field::{Algebra, ExtensionField, Field};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_maybe_rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{info_span, instrument};
+
+use crate::verifier::SumcheckError;
+use crate::{SumcheckPolynomial, SumcheckProof, eq_evals, eval_eq, prover, verifier};
+
+/// A proof of the fractional sum `sum_b p(b) / q(b)` over the Boolean hypercube, through a layered
+/// circuit whose every gate adds two fractions.
+///
+/// Layer `k` of the circuit has `2^k` fractions, with the root at layer zero and the input
+/// fractions at the bottom. Fraction `i` of a layer is the sum of fractions `i` and `i + 2^k` of
+/// the layer below.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LogUpGkrProof<F> {
+    /// The numerator of the sum, as computed by the circuit.
+    pub numerator: F,
+    /// The denominator of the sum, as computed by the circuit.
+    pub denominator: F,
+    /// The proof reducing a claim about each layer to one about the layer below, from the root
+    /// down.
+    pub layers: Vec<GkrLayerProof<F>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GkrLayerProof<F> {
+    pub sumcheck: SumcheckProof<F>,
+    /// The evaluations of the numerators of the layer below at the sumcheck point, with the last
+    /// variable set to zero and one.
+    pub numerators: [F; 2],
+    /// The same for the denominators.
+    pub denominators: [F; 2],
+}
+
+/// The claim a LogUp-GKR proof reduces to, namely that the multilinear extensions of the input
+/// numerators and denominators evaluate to `numerator` and `denominator` at `point`.
+///
+/// It must be checked by the caller, e.g. against openings of the committed traces the fractions
+/// are derived from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogUpGkrClaim<F> {
+    pub point: Vec<F>,
+    pub numerator: F,
+    pub denominator: F,
+}
+
+#[derive(Debug)]
+pub enum LogUpGkrError {
+    InvalidProofShape,
+    /// The denominator of the sum is zero, so the sum is not defined.
+    ZeroDenominator,
+    SumcheckError(SumcheckError),
+    /// The claimed evaluations of a layer are inconsistent with its sumcheck.
+    LayerMismatch,
+}
+
+/// Prove the sum of `numerators[i] / denominators[i]`.
+///
+/// For a LogUp lookup argument, the numerators are typically the multiplicities of the looked up
+/// values, negated for the table, and the denominators `alpha - v` for a random `alpha` and each
+/// value `v`, so that the sum is zero, as given for the interactions of an AIR by
+/// [`interaction_fractions`]. Unlike the running sum columns of a univariate LogUp, no auxiliary
+/// column is committed.
+///
+/// Returns the proof and the claim about the inputs it reduces to.
+#[instrument(name = "LogUp-GKR prover", skip_all)]
+pub fn prove_logup_gkr<F, EF, Challenger>(
+    numerators: Vec<EF>,
+    denominators: Vec<EF>,
+    challenger: &mut Challenger,
+) -> (LogUpGkrProof<EF>, LogUpGkrClaim<EF>)
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    Challenger: FieldChallenger<F>,
+{
+    assert!(numerators.len().is_power_of_two());
+    assert_eq!(numerators.len(), denominators.len());
+
+    // Evaluate the circuit from the inputs up to the root.
+    let mut layers = vec![(numerators, denominators)];
+    info_span!("evaluate circuit").in_scope(|| {
+        while layers.last().unwrap().0.len() > 1 {
+            let (p, q) = layers.last().unwrap();
+            let half = p.len() / 2;
+            let (p_lo, p_hi) = p.split_at(half);
+            let (q_lo, q_hi) = q.split_at(half);
+            let parent = (
+                p_lo.par_iter()
+                    .zip(p_hi)
+                    .zip(q_lo.par_iter().zip(q_hi))
+                    .map(|((&p_lo, &p_hi), (&q_lo, &q_hi))| p_lo * q_hi + p_hi * q_lo)
+                    .collect(),
+                q_lo.par_iter()
+                    .zip(q_hi)
+                    .map(|(&lo, &hi)| lo * hi)
+                    .collect(),
+            );
+            layers.push(parent);
+        }
+    });
+
+    let (root_p, root_q) = layers.pop().unwrap();
+    let (numerator, denominator) = (root_p[0], root_q[0]);
+    challenger.observe_algebra_element(numerator);
+    challenger.observe_algebra_element(denominator);
+
+    let mut point = vec![];
+    let (mut claim_p, mut claim_q) = (numerator, denominator);
+    let mut layer_proofs = Vec::with_capacity(layers.len());
+    for (p, q) in layers.into_iter().rev() {
+        let _guard = info_span!("layer", num_vars = point.len()).entered();
+
+        let lambda: EF = challenger.sample_algebra_element();
+        let half = p.len() / 2;
+        let multilinears = vec![
+            eq_evals(&point),
+            p[..half].to_vec(),
+            p[half..].to_vec(),
+            q[..half].to_vec(),
+            q[half..].to_vec(),
+        ];
+        let (sumcheck, sumcheck_point, evals) =
+            prover::prove(&GkrLayerPolynomial { lambda }, multilinears, challenger);
+
+        let (numerators, denominators) = ([evals[1], evals[2]], [evals[3], evals[4]]);
+        let r = observe_layer(&numerators, &denominators, challenger);
+        point = sumcheck_point;
+        point.push(r);
+        claim_p = numerators[0] + r * (numerators[1] - numerators[0]);
+        claim_q = denominators[0] + r * (denominators[1] - denominators[0]);
+
+        layer_proofs.push(GkrLayerProof {
+            sumcheck,
+            numerators,
+            denominators,
+        });
+    }
+
+    let proof = LogUpGkrProof {
+        numerator,
+        denominator,
+        layers: layer_proofs,
+    };
+    let claim = LogUpGkrClaim {
+        point,
+        numerator: claim_p,
+        denominator: claim_q,
+    };
+    (proof, claim)
+}
+
+/// Verify a LogUp-GKR proof for `2^log_size` input fractions.
+///
+/// On success, the sum is `proof.numerator / proof.denominator`, which the caller must check
+/// against the expected sum, along with the returned claim about the inputs.
+pub fn verify_logup_gkr<F, EF, Challenger>(
+    log_size: usize,
+    proof: &LogUpGkrProof<EF>,
+    challenger: &mut Challenger,
+) -> Result<LogUpGkrClaim<EF>, LogUpGkrError>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    Challenger: FieldChallenger<F>,
+{
+    if proof.layers.len() != log_size {
+        return Err(LogUpGkrError::InvalidProofShape);
+    }
+    if proof.denominator.is_zero() {
+        return Err(LogUpGkrError::ZeroDenominator);
+    }
+    challenger.observe_algebra_element(proof.numerator);
+    challenger.observe_algebra_element(proof.denominator);
+
+    let mut point = vec![];
+    let (mut claim_p, mut claim_q) = (proof.numerator, proof.denominator);
+    for (num_vars, layer) in proof.layers.iter().enumerate() {
+        let lambda: EF = challenger.sample_algebra_element();
+        let poly = GkrLayerPolynomial { lambda };
+        let subclaim = verifier::verify(
+            poly.degree(),
+            num_vars,
+            lambda * claim_p + claim_q,
+            &layer.sumcheck,
+            challenger,
+        )
+        .map_err(LogUpGkrError::SumcheckError)?;
+
+        let [p_lo, p_hi] = layer.numerators;
+        let [q_lo, q_hi] = layer.denominators;
+        let expected = eval_eq(&point, &subclaim.point)
+            * GkrLayerPolynomial::combine(lambda, &[p_lo, p_hi, q_lo, q_hi]);
+        if expected != subclaim.value {
+            return Err(LogUpGkrError::LayerMismatch);
+        }
+
+        let r = observe_layer(&layer.numerators, &layer.denominators, challenger);
+        point = subclaim.point;
+        point.push(r);
+        claim_p = p_lo + r * (p_hi - p_lo);
+        claim_q = q_lo + r * (q_hi - q_lo);
+    }
+
+    Ok(LogUpGkrClaim {
+        point,
+        numerator: claim_p,
+        denominator: claim_q,
+    })
+}
+
+/// The fractions of a LogUp argument over the interactions of an AIR, as inputs to
+/// [`prove_logup_gkr`].
+///
+/// Fraction `i h + r`, for a trace of height `h`, is that of interaction `i` on row `r`: its
+/// multiplicity, negated for received tuples, over the fingerprint `alpha - (b + beta v_0 + ... +
+/// beta^k v_{k-1})` of its tuple on bus `b`, as in the univariate LogUp of `p3-uni-stark`. The
+/// interactions are padded to a power of two with fractions `0 / 1`. The claim a proof reduces
+/// to is then checked with [`eval_interaction_fractions`], from openings of the trace columns at
+/// the first `log2(h)` coordinates of its point.
+///
+/// # Panics
+/// Panics if there are no interactions or the height of the trace is not a power of two.
+#[instrument(skip_all)]
+pub fn interaction_fractions<F, EF>(
+    preprocessed: Option<&RowMajorMatrix<F>>,
+    main: &RowMajorMatrix<F>,
+    sends: &[Interaction<F>],
+    receives: &[Interaction<F>],
+    alpha: EF,
+    beta: EF,
+) -> (Vec<EF>, Vec<EF>)
+where
+    F: Field,
+    EF: ExtensionField<F>,
+{
+    let height = main.height();
+    assert!(height.is_power_of_two());
+    let interactions: Vec<_> = signed_interactions(sends, receives).collect();
+    assert!(!interactions.is_empty(), "the AIR has no interactions");
+
+    let size = height * interactions.len().next_power_of_two();
+    let mut numerators = EF::zero_vec(size);
+    let mut denominators = vec![EF::ONE; size];
+    for ((interaction, sign), (numerators, denominators)) in interactions.iter().zip(
+        numerators
+            .chunks_exact_mut(height)
+            .zip(denominators.chunks_exact_mut(height)),
+    ) {
+        numerators
+            .par_iter_mut()
+            .zip(denominators.par_iter_mut())
+            .enumerate()
+            .for_each(|(row, (numerator, denominator))| {
+                let main_row = main.row_slice(row).expect("row index is in bounds");
+                let preprocessed_row = preprocessed
+                    .and_then(|preprocessed| preprocessed.row_slice(row))
+                    .map(|row| row.to_vec())
+                    .unwrap_or_default();
+                let multiplicity: F = interaction
+                    .multiplicity
+                    .apply::<F, F>(&preprocessed_row, &main_row);
+                *numerator = EF::from(multiplicity * *sign);
+                *denominator = fingerprint(interaction, &preprocessed_row, &main_row, alpha, beta);
+            });
+    }
+    (numerators, denominators)
+}
+
+/// Evaluate the multilinear extensions of the numerators and denominators given by
+/// [`interaction_fractions`] at `point`, from the evaluations of the preprocessed and main columns
+/// at its first `point.len() - log2(num_interactions)` coordinates.
+///
+/// The verifier compares the result against the [`LogUpGkrClaim`] returned by
+/// [`verify_logup_gkr`], having checked the column evaluations against the trace commitment.
+pub fn eval_interaction_fractions<F, EF>(
+    sends: &[Interaction<F>],
+    receives: &[Interaction<F>],
+    preprocessed_evals: &[EF],
+    main_evals: &[EF],
+    alpha: EF,
+    beta: EF,
+    point: &[EF],
+) -> (EF, EF)
+where
+    F: Field,
+    EF: ExtensionField<F>,
+{
+    let interactions: Vec<_> = signed_interactions(sends, receives).collect();
+    let log_interactions = interactions.len().next_power_of_two().ilog2() as usize;
+    assert!(point.len() >= log_interactions);
+
+    // The fractions are affine in the columns, so their multilinear extensions are the same affine
+    // functions of the columns' extensions.
+    let weights = eq_evals(&point[point.len() - log_interactions..]);
+    let (mut numerator, mut denominator) = (EF::ZERO, EF::ZERO);
+    for (i, weight) in weights.into_iter().enumerate() {
+        match interactions.get(i) {
+            Some((interaction, sign)) => {
+                let multiplicity: EF = interaction
+                    .multiplicity
+                    .apply::<EF, EF>(preprocessed_evals, main_evals);
+                numerator += weight * multiplicity * *sign;
+                denominator +=
+                    weight * fingerprint(interaction, preprocessed_evals, main_evals, alpha, beta);
+            }
+            None => denominator += weight,
+        }
+    }
+    (numerator, denominator)
+}
+
+/// Pair every interaction with the sign of its multiplicity.
+fn signed_interactions<'a, F: Field>(
+    sends: &'a [Interaction<F>],
+    receives: &'a [Interaction<F>],
+) -> impl Iterator<Item = (&'a Interaction<F>, F)> {
+    sends
+        .iter()
+        .map(|interaction| (interaction, F::ONE))
+        .chain(receives.iter().map(|interaction| (interaction, F::NEG_ONE)))
+}
+
+/// `alpha - (b + beta v_0 + ... + beta^k v_{k-1})` for the tuple of `interaction` on bus `b`.
+fn fingerprint<F, EF, Var>(
+    interaction: &Interaction<F>,
+    preprocessed: &[Var],
+    main: &[Var],
+    alpha: EF,
+    beta: EF,
+) -> EF
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    Var: Into<EF> + Copy,
+{
+    let combined = interaction
+        .values
+        .iter()
+        .rev()
+        .fold(EF::ZERO, |acc, value| {
+            (acc + value.apply::<EF, Var>(preprocessed, main)) * beta
+        });
+    alpha - combined - EF::from_usize(interaction.bus)
+}
+
+/// Observe the claimed evaluations of a layer, and sample the last coordinate of the point the
+/// next layer is evaluated at.
+fn observe_layer<F, EF, Challenger>(
+    numerators: &[EF; 2],
+    denominators: &[EF; 2],
+    challenger: &mut Challenger,
+) -> EF
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    Challenger: FieldChallenger<F>,
+{
+    for &eval in numerators.iter().chain(denominators) {
+        challenger.observe_algebra_element(eval);
+    }
+    challenger.sample_algebra_element()
+}
+
+/// The polynomial `eq(r, x) (lambda p'(x) + q'(x))` summed to reduce a claim about a layer at `r`
+/// to one about the layer below, where `p'(x) = p(x, 0) q(x, 1) + p(x, 1) q(x, 0)` and
+/// `q'(x) = q(x, 0) q(x, 1)`.
+struct GkrLayerPolynomial<EF> {
+    lambda: EF,
+}
+
+impl<EF: Field> GkrLayerPolynomial<EF> {
+    /// `lambda p' + q'`, given `[p(x, 0), p(x, 1), q(x, 0), q(x, 1)]`.
+    fn combine<Var: Algebra<EF>>(lambda: EF, evals: &[Var]) -> Var {
+        let [p_lo, p_hi, q_lo, q_hi] = [0, 1, 2, 3].map(|i| evals[i].clone());
+        (p_lo * q_hi.clone() + p_hi * q_lo.clone()) * lambda + q_lo * q_hi
+    }
+}
+
+impl<EF: Field> SumcheckPolynomial<EF> for GkrLayerPolynomial<EF> {
+    fn degree(&self) -> usize {
+        3
+    }
+
+    fn eval<Var: Algebra<EF>>(&self, multilinear_evals: &[Var]) -> Var {
+        multilinear_evals[0].clone() * Self::combine(self.lambda, &multilinear_evals[1..])
+    }
+}
@@ -0,0 +1,14 @@
+use p3_field::{Algebra, Field};
+
+/// A polynomial in the values of some multilinear polynomials, whose sum over the Boolean
+/// hypercube is proven by the sumcheck protocol.
+pub trait SumcheckPolynomial<F: Field>: Sync {
+    /// A bound on the degree of the polynomial in any single variable of the hypercube, which
+    /// bounds the degree of each round polynomial.
+    fn degree(&self) -> usize;
+
+    /// Evaluate the polynomial given the values of each multilinear polynomial.
+    ///
+    /// This is generic so that the prover can evaluate it on packed values.
+    fn eval<Var: Algebra<F>>(&self, multilinear_evals: &[Var]) -> Var;
+}
@@ -0,0 +1,18 @@
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SumcheckProof<F> {
+    /// For each round, the evaluations of the round polynomial at `0, 1, ..., degree`.
+    pub round_polys: Vec<Vec<F>>,
+}
+
+/// The claim that a sumcheck reduces to, namely that the summed polynomial evaluates to `value`
+/// at `point`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SumcheckSubclaim<F> {
+    /// The random point, with one coordinate for each variable of the hypercube.
+    pub point: Vec<F>,
+    pub value: F,
+}
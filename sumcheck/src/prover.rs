@@ -0,0 +1,140 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::Itertools;
+use p3_challenger::FieldChallenger;
+use p3_field::{
+    Algebra, ExtensionField, Field, PackedFieldExtension, PackedValue, PrimeCharacteristicRing,
+};
+use p3_maybe_rayon::prelude::*;
+use tracing::{info_span, instrument};
+
+use crate::{SumcheckPolynomial, SumcheckProof};
+
+/// Prove the sum of `poly` over the Boolean hypercube, given the evaluations of the multilinear
+/// polynomials it is a polynomial in.
+///
+/// Entry `i` of a multilinear's evaluations corresponds to the point whose `j`-th coordinate is bit
+/// `j` of `i`. Variables are bound from the last to the first.
+///
+/// Returns the proof, the random point the sum is reduced to, and the evaluation of each
+/// multilinear at that point.
+#[instrument(name = "sumcheck prover", skip_all)]
+pub fn prove<F, EF, P, Challenger>(
+    poly: &P,
+    mut multilinears: Vec<Vec<EF>>,
+    challenger: &mut Challenger,
+) -> (SumcheckProof<EF>, Vec<EF>, Vec<EF>)
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    P: SumcheckPolynomial<EF>,
+    Challenger: FieldChallenger<F>,
+{
+    let height = multilinears.first().map_or(1, Vec::len);
+    assert!(height.is_power_of_two());
+    assert!(multilinears.iter().all(|evals| evals.len() == height));
+    let num_vars = height.ilog2() as usize;
+
+    let mut round_polys = Vec::with_capacity(num_vars);
+    let mut challenges = Vec::with_capacity(num_vars);
+
+    for round in 0..num_vars {
+        let _guard = info_span!("sumcheck round", round).entered();
+
+        let round_poly = compute_round_poly::<F, EF, P>(poly, &multilinears);
+        for &y in &round_poly {
+            challenger.observe_algebra_element(y);
+        }
+        let r: EF = challenger.sample_algebra_element();
+
+        multilinears = multilinears
+            .into_iter()
+            .map(|evals| {
+                let (lo, hi) = evals.split_at(evals.len() / 2);
+                lo.par_iter()
+                    .zip(hi)
+                    .map(|(&lo, &hi)| lo + r * (hi - lo))
+                    .collect()
+            })
+            .collect();
+
+        round_polys.push(round_poly);
+        challenges.push(r);
+    }
+
+    // The last variable was bound first.
+    challenges.reverse();
+    let multilinear_evals = multilinears.into_iter().map(|evals| evals[0]).collect();
+
+    (SumcheckProof { round_polys }, challenges, multilinear_evals)
+}
+
+/// Evaluate `t -> sum_b poly(multilinears(b, t))` at `t = 0, 1, ..., degree`, where `t` is the
+/// last remaining variable.
+fn compute_round_poly<F, EF, P>(poly: &P, multilinears: &[Vec<EF>]) -> Vec<EF>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    P: SumcheckPolynomial<EF>,
+{
+    let num_points = poly.degree() + 1;
+    let half = multilinears.first().map_or(1, Vec::len) / 2;
+    let width = F::Packing::WIDTH;
+
+    // Sum `poly` along the lines from `lo` to `hi`, stepping `t` by one.
+    fn sum_along_lines<EF: Field, Var: Algebra<EF> + Copy, P: SumcheckPolynomial<EF>>(
+        poly: &P,
+        num_points: usize,
+        lo: Vec<Var>,
+        hi: Vec<Var>,
+    ) -> Vec<Var> {
+        let diffs = hi.iter().zip(&lo).map(|(&hi, &lo)| hi - lo).collect_vec();
+        let mut values = lo;
+        (0..num_points)
+            .map(|t| {
+                if t > 0 {
+                    values.iter_mut().zip(&diffs).for_each(|(v, &d)| *v += d);
+                }
+                poly.eval(&values)
+            })
+            .collect()
+    }
+
+    fn add<Var: PrimeCharacteristicRing>(a: Vec<Var>, b: Vec<Var>) -> Vec<Var> {
+        a.into_iter().zip(b).map(|(a, b)| a + b).collect()
+    }
+
+    if half >= width {
+        let sums = (0..half / width)
+            .into_par_iter()
+            .map(|chunk| {
+                let pack = |offset: usize| {
+                    multilinears
+                        .iter()
+                        .map(|evals| {
+                            EF::ExtensionPacking::from_ext_slice(
+                                &evals[offset + chunk * width..offset + (chunk + 1) * width],
+                            )
+                        })
+                        .collect_vec()
+                };
+                sum_along_lines(poly, num_points, pack(0), pack(half))
+            })
+            .par_fold_reduce(|| vec![EF::ExtensionPacking::ZERO; num_points], add, add);
+        sums.into_iter()
+            .map(|sum| EF::ExtensionPacking::to_ext_iter([sum]).sum())
+            .collect()
+    } else {
+        (0..half)
+            .map(|i| {
+                let lo = multilinears.iter().map(|evals| evals[i]).collect_vec();
+                let hi = multilinears
+                    .iter()
+                    .map(|evals| evals[half + i])
+                    .collect_vec();
+                sum_along_lines(poly, num_points, lo, hi)
+            })
+            .fold(vec![EF::ZERO; num_points], add)
+    }
+}
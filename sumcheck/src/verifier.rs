@@ -0,0 +1,80 @@
+use alloc::vec::Vec;
+
+use p3_challenger::FieldChallenger;
+use p3_field::{ExtensionField, Field};
+
+use crate::{SumcheckProof, SumcheckSubclaim};
+
+#[derive(Debug)]
+pub enum SumcheckError {
+    InvalidProofShape,
+    /// A round polynomial does not sum to the claim of its round.
+    RoundSumMismatch,
+    /// The polynomial does not evaluate to the final claim at the random point.
+    FinalEvalMismatch,
+}
+
+/// Verify a sumcheck proof that a polynomial of the given degree sums to `claim` over the Boolean
+/// hypercube with `num_vars` variables.
+///
+/// On success, the claim is reduced to an evaluation of the polynomial at a random point, which
+/// the caller must check.
+pub fn verify<F, EF, Challenger>(
+    degree: usize,
+    num_vars: usize,
+    mut claim: EF,
+    proof: &SumcheckProof<EF>,
+    challenger: &mut Challenger,
+) -> Result<SumcheckSubclaim<EF>, SumcheckError>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    Challenger: FieldChallenger<F>,
+{
+    if proof.round_polys.len() != num_vars {
+        return Err(SumcheckError::InvalidProofShape);
+    }
+
+    let mut point = Vec::with_capacity(num_vars);
+    for round_poly in &proof.round_polys {
+        if round_poly.len() != degree + 1 {
+            return Err(SumcheckError::InvalidProofShape);
+        }
+        // A round polynomial of degree zero is constant, so it takes the same value at one.
+        let at_one = round_poly.get(1).copied().unwrap_or(round_poly[0]);
+        if round_poly[0] + at_one != claim {
+            return Err(SumcheckError::RoundSumMismatch);
+        }
+        for &y in round_poly {
+            challenger.observe_algebra_element(y);
+        }
+        let r: EF = challenger.sample_algebra_element();
+        claim = interpolate_at(round_poly, r);
+        point.push(r);
+    }
+
+    // The last variable was bound first.
+    point.reverse();
+    Ok(SumcheckSubclaim {
+        point,
+        value: claim,
+    })
+}
+
+/// Evaluate at `x` the polynomial taking the given values at `0, 1, ..., evals.len() - 1`.
+fn interpolate_at<F: Field>(evals: &[F], x: F) -> F {
+    let nodes = (0..evals.len()).map(F::from_usize);
+    evals
+        .iter()
+        .zip(nodes.clone())
+        .map(|(&y, x_i)| {
+            let (num, denom) = nodes
+                .clone()
+                .filter(|&x_j| x_j != x_i)
+                .fold((F::ONE, F::ONE), |(num, denom), x_j| {
+                    (num * (x - x_j), denom * (x_i - x_j))
+                });
+            y * num * denom.inverse()
+        })
+        .sum()
+}
@@ -0,0 +1,895 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter;
+
+use itertools::Itertools;
+use p3_air::Air;
+use p3_challenger::FieldChallenger;
+use p3_field::{
+    Algebra, ExtensionField, Field, PackedFieldExtension, PackedValue, PrimeCharacteristicRing,
+};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_maybe_rayon::prelude::*;
+use p3_uni_stark::{Entry, SymbolicAirBuilder, SymbolicExpression, get_symbolic_constraints};
+use serde::{Deserialize, Serialize};
+use tracing::{info_span, instrument};
+
+use crate::verifier::SumcheckError;
+use crate::{
+    SumcheckPolynomial, SumcheckProof, eq_evals, eval_eq, eval_eq_at_index, prover, verifier,
+};
+
+/// A set of AIR constraints, to be proven to vanish on every row of a trace of height `2^n`, seen
+/// as the Boolean hypercube with `n` variables where row `i` is the point whose `j`-th coordinate
+/// is bit `j` of `i`.
+///
+/// As with univariate STARKs, the row after the last row is the first. Only the main trace,
+/// preprocessed trace and public values may be referenced.
+#[derive(Debug)]
+pub struct ZerocheckConstraints<F: Field> {
+    constraints: Vec<SymbolicExpression<F>>,
+    main_row_offsets: Vec<usize>,
+    width: usize,
+    preprocessed_width: usize,
+    num_public_values: usize,
+    /// The sizes of all transition windows the constraints are gated by.
+    window_sizes: Vec<usize>,
+}
+
+#[derive(Debug)]
+pub enum ZerocheckConstraintError {
+    /// The constraint references a variable other than a main or preprocessed column or a public
+    /// value, e.g. a permutation column or a challenge.
+    UnsupportedEntry { constraint: usize },
+    /// The constraint references a column, row or public value which does not exist.
+    VariableOutOfBounds { constraint: usize },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ZerocheckProof<F> {
+    pub sumcheck: SumcheckProof<F>,
+    /// For each row offset of the main trace, the evaluation at the zerocheck point of each column
+    /// shifted up by that offset.
+    pub main_evals: Vec<Vec<F>>,
+    /// The same for the preprocessed trace, on the current and next rows, if there is one.
+    pub preprocessed_evals: Vec<Vec<F>>,
+    /// The sumcheck reducing the evaluations of shifted columns above to evaluations of the
+    /// columns themselves at a single point.
+    pub shift_sumcheck: SumcheckProof<F>,
+    /// The evaluation of each main column at the point of `shift_sumcheck`.
+    pub main_openings: Vec<F>,
+    /// The same for the preprocessed columns.
+    pub preprocessed_openings: Vec<F>,
+}
+
+#[derive(Debug)]
+pub enum ZerocheckError {
+    InvalidProofShape,
+    SumcheckError(SumcheckError),
+    /// The claimed column openings are inconsistent with the shift sumcheck.
+    ShiftMismatch,
+}
+
+impl<F: Field> ZerocheckConstraints<F> {
+    pub fn new(
+        constraints: Vec<SymbolicExpression<F>>,
+        main_row_offsets: Vec<usize>,
+        width: usize,
+        preprocessed_width: usize,
+        num_public_values: usize,
+    ) -> Result<Self, ZerocheckConstraintError> {
+        let mut this = Self {
+            constraints: vec![],
+            main_row_offsets,
+            width,
+            preprocessed_width,
+            num_public_values,
+            window_sizes: vec![],
+        };
+        for (i, constraint) in constraints.iter().enumerate() {
+            this.check(constraint, i)?;
+        }
+        this.constraints = constraints;
+        Ok(this)
+    }
+
+    pub fn from_air<A>(air: &A, num_public_values: usize) -> Result<Self, ZerocheckConstraintError>
+    where
+        A: Air<SymbolicAirBuilder<F>>,
+    {
+        let preprocessed_width = air.preprocessed_trace().map_or(0, |trace| trace.width());
+        Self::new(
+            get_symbolic_constraints(air, preprocessed_width, num_public_values),
+            air.main_row_offsets(),
+            air.width(),
+            preprocessed_width,
+            num_public_values,
+        )
+    }
+
+    /// Check that constraint `i` only references supported variables which exist, and collect
+    /// the sizes of the transition windows it is gated by.
+    fn check(
+        &mut self,
+        expr: &SymbolicExpression<F>,
+        i: usize,
+    ) -> Result<(), ZerocheckConstraintError> {
+        match expr {
+            SymbolicExpression::Variable(v) => {
+                let in_bounds = match v.entry {
+                    Entry::Main { offset } => {
+                        offset < self.main_row_offsets.len() && v.index < self.width
+                    }
+                    Entry::Preprocessed { offset } => {
+                        offset < self.num_preprocessed_offsets()
+                            && v.index < self.preprocessed_width
+                    }
+                    Entry::Public => v.index < self.num_public_values,
+                    _ => {
+                        return Err(ZerocheckConstraintError::UnsupportedEntry { constraint: i });
+                    }
+                };
+                if !in_bounds {
+                    return Err(ZerocheckConstraintError::VariableOutOfBounds { constraint: i });
+                }
+            }
+            SymbolicExpression::IsTransitionWindow(size) => {
+                if !self.window_sizes.contains(size) {
+                    self.window_sizes.push(*size);
+                }
+            }
+            SymbolicExpression::Add { x, y, .. }
+            | SymbolicExpression::Sub { x, y, .. }
+            | SymbolicExpression::Mul { x, y, .. } => {
+                self.check(x, i)?;
+                self.check(y, i)?;
+            }
+            SymbolicExpression::Neg { x, .. } => self.check(x, i)?,
+            SymbolicExpression::IsFirstRow
+            | SymbolicExpression::IsLastRow
+            | SymbolicExpression::IsTransition
+            | SymbolicExpression::Constant(_) => {}
+        }
+        Ok(())
+    }
+
+    /// The number of rows of the preprocessed trace which the constraints may reference.
+    fn num_preprocessed_offsets(&self) -> usize {
+        if self.preprocessed_width == 0 { 0 } else { 2 }
+    }
+
+    /// The index of the first multilinear holding the main trace, which comes after the `eq`
+    /// polynomial and the selectors.
+    fn main_start(&self) -> usize {
+        4 + self.window_sizes.len()
+    }
+
+    fn preprocessed_start(&self) -> usize {
+        self.main_start() + self.main_row_offsets.len() * self.width
+    }
+
+    /// The selectors for a trace of the given height, in the order of their multilinears.
+    fn selectors<T: Field>(&self, height: usize) -> Vec<Selector<T>> {
+        let last = height - 1;
+        [
+            Selector::new(T::ZERO, [(0, T::ONE)]),
+            Selector::new(T::ZERO, [(last, T::ONE)]),
+            Selector::new(T::ONE, [(last, T::NEG_ONE)]),
+        ]
+        .into_iter()
+        .chain(self.window_sizes.iter().map(|&size| {
+            // One on all rows but the last `size - 1`.
+            let rows = height.saturating_sub(size.saturating_sub(1))..height;
+            Selector::new(T::ONE, rows.map(|row| (row, T::NEG_ONE)))
+        }))
+        .collect()
+    }
+
+    /// The row offset of each group of shifted columns, the main trace at each of its offsets then
+    /// the preprocessed trace, paired with the given data for the trace of the group.
+    fn shift_groups<T: Copy>(
+        &self,
+        main: T,
+        preprocessed: Option<T>,
+    ) -> impl Iterator<Item = (usize, T)> {
+        let main = self.main_row_offsets.iter().map(move |&k| (k, main));
+        let preprocessed = preprocessed
+            .into_iter()
+            .flat_map(|p| (0..self.num_preprocessed_offsets()).map(move |k| (k, p)));
+        main.chain(preprocessed)
+    }
+}
+
+/// Prove that `constraints` vanish on every row of the given traces.
+///
+/// This samples a random point `tau` and combines the constraints into `C` with powers of a random
+/// `alpha`, then runs a sumcheck for `sum_b eq(tau, b) C(b) = 0`. This leaves a claim about the
+/// traces shifted by each row offset at a point `z`, which a second sumcheck reduces to the
+/// evaluations of the unshifted columns at the returned point, as in
+/// [`main_openings`](ZerocheckProof::main_openings). The caller must check those, e.g. with a
+/// multilinear PCS.
+///
+/// The public values are observed by the challenger here, but the traces are not: the caller must
+/// commit to them and observe the commitments before calling this, so that the random challenges
+/// are bound to the traces.
+#[instrument(name = "zerocheck prover", skip_all)]
+pub fn prove_zerocheck<F, EF, Challenger>(
+    constraints: &ZerocheckConstraints<F>,
+    main: &RowMajorMatrix<F>,
+    preprocessed: Option<&RowMajorMatrix<F>>,
+    public_values: &[F],
+    challenger: &mut Challenger,
+) -> (ZerocheckProof<EF>, Vec<EF>)
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    Challenger: FieldChallenger<F>,
+{
+    let height = main.height();
+    assert!(height.is_power_of_two());
+    assert_eq!(main.width(), constraints.width);
+    assert_eq!(
+        preprocessed.map_or(0, |p| p.width()),
+        constraints.preprocessed_width
+    );
+    assert_eq!(public_values.len(), constraints.num_public_values);
+    let num_vars = height.ilog2() as usize;
+
+    challenger.observe_slice(public_values);
+    let tau: Vec<EF> = (0..num_vars)
+        .map(|_| challenger.sample_algebra_element())
+        .collect();
+    let alpha: EF = challenger.sample_algebra_element();
+
+    let columns = constraints
+        .main_row_offsets
+        .iter()
+        .flat_map(|&offset| {
+            (0..constraints.width).map(move |col| ShiftedColumn {
+                matrix: main,
+                offset,
+                col,
+            })
+        })
+        .chain(preprocessed.into_iter().flat_map(|preprocessed| {
+            (0..2).flat_map(move |offset| {
+                (0..constraints.preprocessed_width).map(move |col| ShiftedColumn {
+                    matrix: preprocessed,
+                    offset,
+                    col,
+                })
+            })
+        }))
+        .collect_vec();
+
+    let poly = ZerocheckPolynomial::new(constraints, public_values, alpha);
+    let (sumcheck, point, evals) = prove_rounds(
+        &poly,
+        eq_evals(&tau),
+        constraints.selectors(height),
+        &columns,
+        challenger,
+    );
+
+    let main_evals = evals[..constraints.main_row_offsets.len() * constraints.width]
+        .chunks(constraints.width.max(1))
+        .map(<[_]>::to_vec)
+        .take(constraints.main_row_offsets.len())
+        .collect_vec();
+    let preprocessed_evals = evals[main_evals.len() * constraints.width..]
+        .chunks(constraints.preprocessed_width.max(1))
+        .map(<[_]>::to_vec)
+        .collect_vec();
+    for &eval in main_evals.iter().chain(&preprocessed_evals).flatten() {
+        challenger.observe_algebra_element(eval);
+    }
+
+    // Reduce the evaluations of the shifted columns to evaluations of the columns at one point.
+    // Since `f((b + k) mod N)` summed against `eq(z, b)` is `f(c)` summed against
+    // `eq(z, (c - k) mod N)`, this is a sumcheck of `sum_k S_k(c) G_k(c)`, where `S_k` is the
+    // shifted `eq` polynomial and `G_k` combines the columns read at offset `k` with powers of a
+    // random `gamma`.
+    let gamma: EF = challenger.sample_algebra_element();
+    let (shift_sumcheck, shift_point, _) = {
+        let _span = info_span!("shift reduction").entered();
+        let eq_z = eq_evals(&point);
+        let mut gamma_powers = gamma.powers();
+        let mut multilinears = vec![];
+        for (offset, matrix) in constraints.shift_groups(main, preprocessed) {
+            let shift = height - offset % height;
+            multilinears.push(
+                (0..height)
+                    .map(|row| eq_z[(row + shift) % height])
+                    .collect(),
+            );
+            let coeffs = gamma_powers.by_ref().take(matrix.width()).collect_vec();
+            multilinears.push(
+                matrix
+                    .par_row_slices()
+                    .map(|row| {
+                        row.iter()
+                            .zip(&coeffs)
+                            .map(|(&value, &coeff)| coeff * value)
+                            .sum()
+                    })
+                    .collect(),
+            );
+        }
+        prover::prove(&ShiftPolynomial, multilinears, challenger)
+    };
+
+    let eq_r = eq_evals(&shift_point);
+    let main_openings = main.columnwise_dot_product(&eq_r);
+    let preprocessed_openings = preprocessed.map_or_else(Vec::new, |preprocessed| {
+        preprocessed.columnwise_dot_product(&eq_r)
+    });
+    for &opening in main_openings.iter().chain(&preprocessed_openings) {
+        challenger.observe_algebra_element(opening);
+    }
+
+    let proof = ZerocheckProof {
+        sumcheck,
+        main_evals,
+        preprocessed_evals,
+        shift_sumcheck,
+        main_openings,
+        preprocessed_openings,
+    };
+    (proof, shift_point)
+}
+
+/// Run the sumcheck rounds of a zerocheck, returning the proof, the random point and the
+/// evaluation of each column at it.
+///
+/// The first round reads the columns in the base field, and only their halves folded with the
+/// first challenge are stored, in the extension field. The selectors are never stored densely.
+fn prove_rounds<F, EF, Challenger>(
+    poly: &ZerocheckPolynomial<F, EF>,
+    mut eq: Vec<EF>,
+    selectors: Vec<Selector<F>>,
+    columns: &[ShiftedColumn<'_, F>],
+    challenger: &mut Challenger,
+) -> (SumcheckProof<EF>, Vec<EF>, Vec<EF>)
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    Challenger: FieldChallenger<F>,
+{
+    let height = eq.len();
+    let num_vars = height.ilog2() as usize;
+    let width = F::Packing::WIDTH;
+    let num_values = 1 + selectors.len() + columns.len();
+
+    let mut round_polys = Vec::with_capacity(num_vars);
+    let mut challenges = Vec::with_capacity(num_vars);
+    let mut observe_and_sample = |round_poly: Vec<EF>| {
+        for &y in &round_poly {
+            challenger.observe_algebra_element(y);
+        }
+        let r: EF = challenger.sample_algebra_element();
+        round_polys.push(round_poly);
+        challenges.push(r);
+        r
+    };
+
+    if num_vars == 0 {
+        let evals = columns.iter().map(|col| EF::from(col.get(0))).collect();
+        return (SumcheckProof { round_polys }, challenges, evals);
+    }
+
+    let round_poly = {
+        let _span = info_span!("sumcheck round", round = 0).entered();
+        let value = |j: usize, row: usize| match j.checked_sub(1 + selectors.len()) {
+            Some(col) => columns[col].get(row),
+            None => selectors[j - 1].at(row),
+        };
+        if height / 2 >= width {
+            sum_over_lines(
+                poly,
+                &eq,
+                num_values,
+                width,
+                |j, start| F::Packing::from_fn(|k| value(j, start + k)),
+                EF::ExtensionPacking::from_ext_slice,
+                F::Packing::from,
+                |sum| EF::ExtensionPacking::to_ext_iter([sum]).sum(),
+            )
+        } else {
+            sum_over_lines(
+                poly,
+                &eq,
+                num_values,
+                1,
+                value,
+                |eq| eq[0],
+                |c| c,
+                |sum| sum,
+            )
+        }
+    };
+    let r = observe_and_sample(round_poly);
+    let half = height / 2;
+    eq = fold(&eq, r);
+    let mut selectors = selectors
+        .iter()
+        .map(|selector| selector.fold(half, r))
+        .collect_vec();
+    let mut columns: Vec<Vec<EF>> = columns
+        .iter()
+        .map(|col| {
+            (0..half)
+                .into_par_iter()
+                .map(|row| {
+                    let lo = col.get(row);
+                    r * (col.get(half + row) - lo) + lo
+                })
+                .collect()
+        })
+        .collect_vec();
+
+    for round in 1..num_vars {
+        let _span = info_span!("sumcheck round", round).entered();
+        let half = eq.len() / 2;
+        let round_poly = if half >= width {
+            sum_over_lines(
+                poly,
+                &eq,
+                num_values,
+                width,
+                |j, start| match j.checked_sub(1 + selectors.len()) {
+                    Some(col) => {
+                        EF::ExtensionPacking::from_ext_slice(&columns[col][start..start + width])
+                    }
+                    None => EF::ExtensionPacking::from_ext_slice(
+                        &(start..start + width)
+                            .map(|row| selectors[j - 1].at(row))
+                            .collect_vec(),
+                    ),
+                },
+                EF::ExtensionPacking::from_ext_slice,
+                |c| EF::ExtensionPacking::from(EF::from(c)),
+                |sum| EF::ExtensionPacking::to_ext_iter([sum]).sum(),
+            )
+        } else {
+            sum_over_lines(
+                poly,
+                &eq,
+                num_values,
+                1,
+                |j, row| match j.checked_sub(1 + selectors.len()) {
+                    Some(col) => columns[col][row],
+                    None => selectors[j - 1].at(row),
+                },
+                |eq| eq[0],
+                EF::from,
+                |sum| sum,
+            )
+        };
+        let r = observe_and_sample(round_poly);
+        eq = fold(&eq, r);
+        selectors = selectors
+            .iter()
+            .map(|selector| selector.fold(half, r))
+            .collect();
+        columns = columns.iter().map(|col| fold(col, r)).collect();
+    }
+
+    // The last variable was bound first.
+    challenges.reverse();
+    let evals = columns.into_iter().map(|col| col[0]).collect();
+    (SumcheckProof { round_polys }, challenges, evals)
+}
+
+/// Evaluate the round polynomial of a zerocheck at `t = 0, 1, ..., degree`, summing along the
+/// lines from each point of the lower half of the hypercube to the matching point of the upper
+/// half, `lanes` lines at a time.
+///
+/// `load(j, start)` gives value `j` of the lines from `start` to `start + lanes`, where index zero
+/// is unused as it stands for `eq`, which is passed separately as it may lie in a larger field.
+#[allow(clippy::too_many_arguments)]
+fn sum_over_lines<F, EF, V, PE>(
+    poly: &ZerocheckPolynomial<F, EF>,
+    eq: &[EF],
+    num_values: usize,
+    lanes: usize,
+    load: impl Fn(usize, usize) -> V + Sync,
+    pack_eq: impl Fn(&[EF]) -> PE + Sync,
+    constant: impl Fn(F) -> V + Sync,
+    unpack: impl Fn(PE) -> EF,
+) -> Vec<EF>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    V: PrimeCharacteristicRing + Copy + Send + Sync,
+    PE: Algebra<EF> + Algebra<V> + Copy + Send + Sync,
+{
+    let num_points = poly.degree() + 1;
+    let half = eq.len() / 2;
+
+    fn add<Var: PrimeCharacteristicRing>(a: Vec<Var>, b: Vec<Var>) -> Vec<Var> {
+        a.into_iter().zip(b).map(|(a, b)| a + b).collect()
+    }
+
+    let sums = (0..half / lanes)
+        .into_par_iter()
+        .map(|line| {
+            let start = line * lanes;
+            let mut eq_t = pack_eq(&eq[start..start + lanes]);
+            let eq_diff = pack_eq(&eq[half + start..half + start + lanes]) - eq_t;
+            let mut values = iter::once(V::ZERO)
+                .chain((1..num_values).map(|j| load(j, start)))
+                .collect_vec();
+            let diffs = iter::once(V::ZERO)
+                .chain((1..num_values).map(|j| load(j, half + start) - values[j]))
+                .collect_vec();
+            (0..num_points)
+                .map(|t| {
+                    if t > 0 {
+                        eq_t += eq_diff;
+                        values.iter_mut().zip(&diffs).for_each(|(v, &d)| *v += d);
+                    }
+                    eq_t * poly.combine::<V, PE>(&values, &constant)
+                })
+                .collect_vec()
+        })
+        .par_fold_reduce(|| vec![PE::ZERO; num_points], add, add);
+    sums.into_iter().map(unpack).collect()
+}
+
+/// Bind the last variable of a multilinear to `r`.
+fn fold<EF: Field>(evals: &[EF], r: EF) -> Vec<EF> {
+    let (lo, hi) = evals.split_at(evals.len() / 2);
+    lo.par_iter()
+        .zip(hi)
+        .map(|(&lo, &hi)| lo + r * (hi - lo))
+        .collect()
+}
+
+/// Verify a zerocheck proof for a trace of height `2^log_height`.
+///
+/// On success, returns the point at which the column openings in the proof must still be checked.
+/// As with [`prove_zerocheck`], the challenger must have observed the trace commitments first.
+pub fn verify_zerocheck<F, EF, Challenger>(
+    constraints: &ZerocheckConstraints<F>,
+    log_height: usize,
+    public_values: &[F],
+    proof: &ZerocheckProof<EF>,
+    challenger: &mut Challenger,
+) -> Result<Vec<EF>, ZerocheckError>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    Challenger: FieldChallenger<F>,
+{
+    let shape_ok = public_values.len() == constraints.num_public_values
+        && proof.main_evals.len() == constraints.main_row_offsets.len()
+        && proof
+            .main_evals
+            .iter()
+            .all(|evals| evals.len() == constraints.width)
+        && proof.preprocessed_evals.len() == constraints.num_preprocessed_offsets()
+        && proof
+            .preprocessed_evals
+            .iter()
+            .all(|evals| evals.len() == constraints.preprocessed_width)
+        && proof.main_openings.len() == constraints.width
+        && proof.preprocessed_openings.len() == constraints.preprocessed_width;
+    if !shape_ok {
+        return Err(ZerocheckError::InvalidProofShape);
+    }
+
+    challenger.observe_slice(public_values);
+    let tau: Vec<EF> = (0..log_height)
+        .map(|_| challenger.sample_algebra_element())
+        .collect();
+    let alpha: EF = challenger.sample_algebra_element();
+
+    let poly = ZerocheckPolynomial::new(constraints, public_values, alpha);
+    let subclaim = verifier::verify(
+        poly.degree(),
+        log_height,
+        EF::ZERO,
+        &proof.sumcheck,
+        challenger,
+    )
+    .map_err(ZerocheckError::SumcheckError)?;
+    let point = subclaim.point;
+
+    let evals = proof
+        .main_evals
+        .iter()
+        .chain(&proof.preprocessed_evals)
+        .flatten()
+        .copied()
+        .collect_vec();
+    for &eval in &evals {
+        challenger.observe_algebra_element(eval);
+    }
+
+    // The selectors are known to the verifier, so it evaluates them itself.
+    let multilinear_evals = iter::once(eval_eq(&tau, &point))
+        .chain(
+            constraints
+                .selectors::<F>(1 << log_height)
+                .iter()
+                .map(|selector| selector.eval(&point)),
+        )
+        .chain(evals.iter().copied())
+        .collect_vec();
+    if poly.eval(&multilinear_evals) != subclaim.value {
+        return Err(ZerocheckError::SumcheckError(
+            SumcheckError::FinalEvalMismatch,
+        ));
+    }
+
+    let gamma: EF = challenger.sample_algebra_element();
+    let claim = evals
+        .iter()
+        .zip(gamma.powers())
+        .map(|(&eval, gamma_pow)| eval * gamma_pow)
+        .sum();
+    let subclaim = verifier::verify(2, log_height, claim, &proof.shift_sumcheck, challenger)
+        .map_err(ZerocheckError::SumcheckError)?;
+    let shift_point = subclaim.point;
+
+    // Each group is a shift polynomial, which the verifier evaluates itself, times a combination of
+    // the openings.
+    let mut gamma_powers = gamma.powers();
+    let preprocessed_openings =
+        (constraints.preprocessed_width > 0).then_some(&proof.preprocessed_openings[..]);
+    let expected = constraints
+        .shift_groups(&proof.main_openings[..], preprocessed_openings)
+        .map(|(offset, openings)| {
+            let combined: EF = openings
+                .iter()
+                .zip(gamma_powers.by_ref())
+                .map(|(&opening, gamma_pow)| opening * gamma_pow)
+                .sum();
+            eval_shift(offset, &point, &shift_point) * combined
+        })
+        .sum::<EF>();
+    if expected != subclaim.value {
+        return Err(ZerocheckError::ShiftMismatch);
+    }
+
+    for &opening in proof
+        .main_openings
+        .iter()
+        .chain(&proof.preprocessed_openings)
+    {
+        challenger.observe_algebra_element(opening);
+    }
+
+    Ok(shift_point)
+}
+
+/// `sum_b eq(z, b) eq(r, (b + offset) mod 2^n)`, the multilinear extension in `c` of
+/// `eq(z, (c - offset) mod 2^n)` evaluated at `r`.
+///
+/// This adds `offset` to `b` bit by bit, tracking the sum for each carry.
+fn eval_shift<EF: Field>(offset: usize, z: &[EF], r: &[EF]) -> EF {
+    debug_assert_eq!(z.len(), r.len());
+    let eq_bit = |x: EF, bit: usize| if bit == 0 { EF::ONE - x } else { x };
+    let mut carries = [EF::ONE, EF::ZERO];
+    for (j, (&z_j, &r_j)) in z.iter().zip(r).enumerate() {
+        let offset_bit = (offset >> j) & 1;
+        let mut next = [EF::ZERO; 2];
+        for (carry, &weight) in carries.iter().enumerate() {
+            for b_bit in 0..2 {
+                let sum = b_bit + offset_bit + carry;
+                next[sum >> 1] += weight * eq_bit(z_j, b_bit) * eq_bit(r_j, sum & 1);
+            }
+        }
+        carries = next;
+    }
+    // The final carry wraps around.
+    carries[0] + carries[1]
+}
+
+/// A column of a trace, shifted up by `offset` rows, where the row after the last is the first.
+struct ShiftedColumn<'a, F> {
+    matrix: &'a RowMajorMatrix<F>,
+    offset: usize,
+    col: usize,
+}
+
+impl<F: Clone + Send + Sync> ShiftedColumn<'_, F> {
+    fn get(&self, row: usize) -> F {
+        let height = self.matrix.height();
+        self.matrix.values[((row + self.offset) % height) * self.matrix.width + self.col].clone()
+    }
+}
+
+/// The multilinear extension of a function on the hypercube which is `constant` except on a few
+/// points, which folding a variable keeps sparse.
+#[derive(Clone, Debug)]
+struct Selector<T> {
+    constant: T,
+    /// The points, as indices, where the function differs from `constant`, with the difference.
+    terms: Vec<(usize, T)>,
+}
+
+impl<T: Field> Selector<T> {
+    fn new(constant: T, terms: impl IntoIterator<Item = (usize, T)>) -> Self {
+        Self {
+            constant,
+            terms: terms.into_iter().collect(),
+        }
+    }
+
+    fn at(&self, index: usize) -> T {
+        self.terms
+            .iter()
+            .filter(|&&(i, _)| i == index)
+            .fold(self.constant, |acc, &(_, diff)| acc + diff)
+    }
+
+    /// Bind the last variable of a hypercube with `2 * half` points to `r`.
+    fn fold<EF: ExtensionField<T>>(&self, half: usize, r: EF) -> Selector<EF> {
+        let mut terms: Vec<(usize, EF)> = Vec::with_capacity(self.terms.len());
+        for &(index, diff) in &self.terms {
+            let (index, diff) = if index < half {
+                (index, (EF::ONE - r) * diff)
+            } else {
+                (index - half, r * diff)
+            };
+            match terms.iter_mut().find(|(i, _)| *i == index) {
+                Some((_, d)) => *d += diff,
+                None => terms.push((index, diff)),
+            }
+        }
+        Selector {
+            constant: EF::from(self.constant),
+            terms,
+        }
+    }
+
+    fn eval<EF: ExtensionField<T>>(&self, point: &[EF]) -> EF {
+        self.terms
+            .iter()
+            .map(|&(index, diff)| eval_eq_at_index::<EF>(index, point) * diff)
+            .sum::<EF>()
+            + self.constant
+    }
+}
+
+/// A constraint with every column and selector resolved to the index of its multilinear and every
+/// public value to a constant, so that it can be shared between threads.
+enum Expr<F> {
+    Multilinear(usize),
+    Constant(F),
+    Add(Box<Self>, Box<Self>),
+    Sub(Box<Self>, Box<Self>),
+    Neg(Box<Self>),
+    Mul(Box<Self>, Box<Self>),
+}
+
+impl<F: Field> Expr<F> {
+    /// Compile a constraint which [`ZerocheckConstraints::new`] has checked.
+    fn compile(
+        expr: &SymbolicExpression<F>,
+        constraints: &ZerocheckConstraints<F>,
+        public_values: &[F],
+    ) -> Self {
+        let compile = |expr| Box::new(Self::compile(expr, constraints, public_values));
+        match expr {
+            SymbolicExpression::Variable(v) => match v.entry {
+                Entry::Main { offset } => Self::Multilinear(
+                    constraints.main_start() + offset * constraints.width + v.index,
+                ),
+                Entry::Preprocessed { offset } => Self::Multilinear(
+                    constraints.preprocessed_start()
+                        + offset * constraints.preprocessed_width
+                        + v.index,
+                ),
+                Entry::Public => Self::Constant(public_values[v.index]),
+                _ => unreachable!("rejected by ZerocheckConstraints::new"),
+            },
+            SymbolicExpression::IsFirstRow => Self::Multilinear(1),
+            SymbolicExpression::IsLastRow => Self::Multilinear(2),
+            SymbolicExpression::IsTransition => Self::Multilinear(3),
+            SymbolicExpression::IsTransitionWindow(size) => {
+                let index = constraints
+                    .window_sizes
+                    .iter()
+                    .position(|s| s == size)
+                    .unwrap();
+                Self::Multilinear(4 + index)
+            }
+            SymbolicExpression::Constant(c) => Self::Constant(*c),
+            SymbolicExpression::Add { x, y, .. } => Self::Add(compile(x), compile(y)),
+            SymbolicExpression::Sub { x, y, .. } => Self::Sub(compile(x), compile(y)),
+            SymbolicExpression::Neg { x, .. } => Self::Neg(compile(x)),
+            SymbolicExpression::Mul { x, y, .. } => Self::Mul(compile(x), compile(y)),
+        }
+    }
+
+    /// The degree in any single variable of the hypercube, where every multilinear has degree one.
+    fn degree(&self) -> usize {
+        match self {
+            Self::Multilinear(_) => 1,
+            Self::Constant(_) => 0,
+            Self::Add(x, y) | Self::Sub(x, y) => x.degree().max(y.degree()),
+            Self::Neg(x) => x.degree(),
+            Self::Mul(x, y) => x.degree() + y.degree(),
+        }
+    }
+
+    /// Evaluate the constraint, mapping each constant into the ring of the values with `constant`.
+    fn eval<V: PrimeCharacteristicRing>(&self, values: &[V], constant: &impl Fn(F) -> V) -> V {
+        match self {
+            Self::Multilinear(i) => values[*i].clone(),
+            Self::Constant(c) => constant(*c),
+            Self::Add(x, y) => x.eval(values, constant) + y.eval(values, constant),
+            Self::Sub(x, y) => x.eval(values, constant) - y.eval(values, constant),
+            Self::Neg(x) => -x.eval(values, constant),
+            Self::Mul(x, y) => x.eval(values, constant) * y.eval(values, constant),
+        }
+    }
+}
+
+/// The polynomial `eq(tau, b) sum_i alpha^i C_i(b)` summed by a zerocheck.
+struct ZerocheckPolynomial<F, EF> {
+    constraints: Vec<Expr<F>>,
+    alpha_powers: Vec<EF>,
+}
+
+impl<F: Field, EF: ExtensionField<F>> ZerocheckPolynomial<F, EF> {
+    fn new(constraints: &ZerocheckConstraints<F>, public_values: &[F], alpha: EF) -> Self {
+        Self {
+            constraints: constraints
+                .constraints
+                .iter()
+                .map(|constraint| Expr::compile(constraint, constraints, public_values))
+                .collect(),
+            alpha_powers: alpha.powers().take(constraints.constraints.len()).collect(),
+        }
+    }
+
+    /// `sum_i alpha^i C_i(b)`, where the constraints are evaluated on values in a smaller ring than
+    /// the combination, e.g. the base field in the first round of the prover.
+    fn combine<V, Var>(&self, values: &[V], constant: &impl Fn(F) -> V) -> Var
+    where
+        V: PrimeCharacteristicRing,
+        Var: Algebra<EF> + Algebra<V>,
+    {
+        self.constraints.iter().zip(&self.alpha_powers).fold(
+            Var::ZERO,
+            |acc, (constraint, &alpha_pow)| {
+                acc + Var::from(constraint.eval(values, constant)) * alpha_pow
+            },
+        )
+    }
+}
+
+impl<F: Field, EF: ExtensionField<F>> SumcheckPolynomial<EF> for ZerocheckPolynomial<F, EF> {
+    fn degree(&self) -> usize {
+        // One more for the `eq` polynomial.
+        self.constraints.iter().map(Expr::degree).max().unwrap_or(0) + 1
+    }
+
+    fn eval<Var: Algebra<EF>>(&self, multilinear_evals: &[Var]) -> Var {
+        let combined: Var = self.combine(multilinear_evals, &|c| Var::from(EF::from(c)));
+        multilinear_evals[0].clone() * combined
+    }
+}
+
+/// `sum_k S_k(c) G_k(c)` over groups of shifted columns, where the multilinears alternate between
+/// the shifted `eq` polynomial `S_k` of a group and the combination `G_k` of its columns.
+struct ShiftPolynomial;
+
+impl<F: Field> SumcheckPolynomial<F> for ShiftPolynomial {
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn eval<Var: Algebra<F>>(&self, multilinear_evals: &[Var]) -> Var {
+        multilinear_evals
+            .chunks_exact(2)
+            .fold(Var::ZERO, |acc, pair| {
+                acc + pair[0].clone() * pair[1].clone()
+            })
+    }
+}
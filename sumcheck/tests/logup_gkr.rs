@@ -0,0 +1,114 @@
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::{CanObserve, CanSample, DuplexChallenger};
+use p3_field::PrimeCharacteristicRing;
+use p3_field::extension::BinomialExtensionField;
+use p3_sumcheck::{LogUpGkrError, eq_evals, prove_logup_gkr, verify_logup_gkr};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+type Val = BabyBear;
+type Challenge = BinomialExtensionField<Val, 4>;
+type Perm = Poseidon2BabyBear<16>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+
+fn challenger() -> Challenger {
+    let mut rng = SmallRng::seed_from_u64(1);
+    Challenger::new(Perm::new_from_rng_128(&mut rng))
+}
+
+/// The fractions of a LogUp argument that `lookups` are all in `table`: `-m_j / (alpha - t_j)` for
+/// each table value `t_j` looked up `m_j` times, then `1 / (alpha - f_i)` for each lookup `f_i`.
+fn lookup_fractions(
+    table: &[Val],
+    lookups: &[Val],
+    challenger: &mut Challenger,
+) -> (Vec<Challenge>, Vec<Challenge>) {
+    challenger.observe_slice(table);
+    challenger.observe_slice(lookups);
+    let alpha: Challenge = challenger.sample();
+
+    let numerators = table
+        .iter()
+        .map(|t| -Challenge::from_usize(lookups.iter().filter(|&f| f == t).count()))
+        .chain(lookups.iter().map(|_| Challenge::ONE))
+        .collect();
+    let denominators = table
+        .iter()
+        .chain(lookups)
+        .map(|&v| alpha - Challenge::from(v))
+        .collect();
+    (numerators, denominators)
+}
+
+fn table_and_lookups(log_size: usize) -> (Vec<Val>, Vec<Val>) {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let table: Vec<Val> = (0..1 << log_size).map(|_| rng.random()).collect();
+    let lookups = (0..1 << log_size)
+        .map(|_| table[rng.random_range(0..table.len())])
+        .collect();
+    (table, lookups)
+}
+
+fn do_test_logup_gkr(table: &[Val], lookups: &[Val]) -> Result<Challenge, LogUpGkrError> {
+    let log_size = (table.len() + lookups.len()).ilog2() as usize;
+
+    let mut p_challenger = challenger();
+    let (numerators, denominators) = lookup_fractions(table, lookups, &mut p_challenger);
+    let (proof, claim) =
+        prove_logup_gkr(numerators.clone(), denominators.clone(), &mut p_challenger);
+
+    // The claim is about the multilinear extensions of the inputs.
+    let eq = eq_evals(&claim.point);
+    let mle = |evals: &[Challenge]| evals.iter().zip(&eq).map(|(&v, &e)| v * e).sum();
+    assert_eq!(claim.numerator, mle(&numerators));
+    assert_eq!(claim.denominator, mle(&denominators));
+
+    let mut v_challenger = challenger();
+    lookup_fractions(table, lookups, &mut v_challenger);
+    let verifier_claim = verify_logup_gkr(log_size, &proof, &mut v_challenger)?;
+    assert_eq!(verifier_claim, claim);
+    Ok(proof.numerator)
+}
+
+#[test]
+fn test_logup_gkr_lookup() {
+    for log_size in [0, 1, 5, 10] {
+        let (table, lookups) = table_and_lookups(log_size);
+        let numerator = do_test_logup_gkr(&table, &lookups).expect("verification failed");
+        assert_eq!(numerator, Challenge::ZERO);
+    }
+}
+
+#[test]
+fn test_logup_gkr_missing_value() {
+    let (table, mut lookups) = table_and_lookups(6);
+    lookups[3] += Val::ONE;
+    let numerator = do_test_logup_gkr(&table, &lookups).expect("verification failed");
+    assert_ne!(numerator, Challenge::ZERO);
+}
+
+#[test]
+fn test_logup_gkr_tampered_proof() {
+    let (table, lookups) = table_and_lookups(6);
+    let mut challenger = challenger();
+    let (numerators, denominators) = lookup_fractions(&table, &lookups, &mut challenger);
+    let (proof, _) = prove_logup_gkr(numerators, denominators, &mut challenger.clone());
+
+    let mut tampered = proof.clone();
+    tampered.layers[3].numerators[1] += Challenge::ONE;
+    let result = verify_logup_gkr(7, &tampered, &mut challenger.clone());
+    assert!(matches!(result, Err(LogUpGkrError::LayerMismatch)));
+
+    let mut tampered = proof.clone();
+    tampered.numerator += Challenge::ONE;
+    let result = verify_logup_gkr(7, &tampered, &mut challenger.clone());
+    assert!(matches!(result, Err(LogUpGkrError::LayerMismatch)));
+
+    let mut tampered = proof.clone();
+    tampered.layers[5].sumcheck.round_polys[2][0] += Challenge::ONE;
+    let result = verify_logup_gkr(7, &tampered, &mut challenger.clone());
+    assert!(matches!(result, Err(LogUpGkrError::SumcheckError(_))));
+
+    let result = verify_logup_gkr(6, &proof, &mut challenger.clone());
+    assert!(matches!(result, Err(LogUpGkrError::InvalidProofShape)));
+}
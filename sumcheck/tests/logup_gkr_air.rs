@@ -0,0 +1,227 @@
+use p3_air::{BaseAir, Interaction, VirtualPairCol};
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::{CanObserve, DuplexChallenger, FieldChallenger};
+use p3_commit::{ExtensionMmcs, MultilinearPcs};
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{Field, PrimeCharacteristicRing};
+use p3_fri::{BasefoldError, BasefoldPcs, FriConfig};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_sumcheck::{
+    LogUpGkrError, LogUpGkrProof, eval_interaction_fractions, interaction_fractions,
+    prove_logup_gkr, verify_logup_gkr,
+};
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+type Val = BabyBear;
+type Challenge = BinomialExtensionField<Val, 4>;
+
+type Perm = Poseidon2BabyBear<16>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs =
+    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Dft = Radix2DitParallel<Val>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+type MyPcs = BasefoldPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+
+/// Columns `[value_0, value_1, table, multiplicity]`, where both value columns are looked up in the
+/// table column, with the multiplicity of each table entry.
+struct TableLookupAir;
+
+impl<F: Field> BaseAir<F> for TableLookupAir {
+    fn width(&self) -> usize {
+        4
+    }
+
+    fn sends(&self) -> Vec<Interaction<F>> {
+        (0..2)
+            .map(|column| {
+                Interaction::new(
+                    0,
+                    vec![VirtualPairCol::single_main(column)],
+                    VirtualPairCol::ONE,
+                )
+            })
+            .collect()
+    }
+
+    fn receives(&self) -> Vec<Interaction<F>> {
+        vec![Interaction::new(
+            0,
+            vec![VirtualPairCol::single_main(2)],
+            VirtualPairCol::single_main(3),
+        )]
+    }
+}
+
+fn generate_trace(log_height: usize) -> RowMajorMatrix<Val> {
+    let mut rng = SmallRng::seed_from_u64(log_height as u64);
+    let height = 1 << log_height;
+    let table: Vec<Val> = (0..height).map(|_| rng.random()).collect();
+    let mut multiplicities = vec![0; height];
+    let mut values = Vec::with_capacity(4 * height);
+    for row in 0..height {
+        for _ in 0..2 {
+            let index = rng.random_range(0..height);
+            multiplicities[index] += 1;
+            values.push(table[index]);
+        }
+        values.extend([table[row], Val::ZERO]);
+    }
+    for (row, multiplicity) in multiplicities.into_iter().enumerate() {
+        values[4 * row + 3] = Val::from_usize(multiplicity);
+    }
+    RowMajorMatrix::new(values, 4)
+}
+
+fn setup() -> (MyPcs, Challenger) {
+    let perm = Perm::new_from_rng_128(&mut SmallRng::seed_from_u64(0));
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let fri_config = FriConfig {
+        log_blowup: 1,
+        log_final_poly_len: 0,
+        log_folding_arity: 1,
+        log_folding_arities: vec![],
+        num_queries: 10,
+        proof_of_work_bits: 8,
+        mmcs: ChallengeMmcs::new(val_mmcs.clone()),
+    };
+    (
+        MyPcs::new(Dft::default(), val_mmcs, fri_config),
+        Challenger::new(perm),
+    )
+}
+
+/// A lookup proof for the committed trace of a [`TableLookupAir`].
+struct LookupProof {
+    commitment: <MyPcs as MultilinearPcs<Challenge, Challenger>>::Commitment,
+    gkr: LogUpGkrProof<Challenge>,
+    main_evals: Vec<Challenge>,
+    opening: <MyPcs as MultilinearPcs<Challenge, Challenger>>::Proof,
+}
+
+#[derive(Debug)]
+enum LookupError {
+    Gkr(LogUpGkrError),
+    Pcs(<MyPcs as MultilinearPcs<Challenge, Challenger>>::Error),
+    /// The column evaluations are inconsistent with the claim of the GKR proof.
+    ClaimMismatch,
+    /// The fractions do not add up to zero.
+    NonZeroSum,
+}
+
+fn prove(pcs: &MyPcs, mut challenger: Challenger, trace: RowMajorMatrix<Val>) -> LookupProof {
+    let air = TableLookupAir;
+    let (commitment, data) =
+        <MyPcs as MultilinearPcs<Challenge, Challenger>>::commit(pcs, trace.clone());
+    challenger.observe(commitment.clone());
+    let alpha: Challenge = challenger.sample_algebra_element();
+    let beta: Challenge = challenger.sample_algebra_element();
+
+    let (numerators, denominators) =
+        interaction_fractions(None, &trace, &air.sends(), &air.receives(), alpha, beta);
+    let (gkr, claim) = prove_logup_gkr(numerators, denominators, &mut challenger);
+
+    // The first coordinates of the claim's point select the row, the others the interaction.
+    let log_height = trace.height().ilog2() as usize;
+    let (main_evals, opening) = pcs.open(&data, &claim.point[..log_height], &mut challenger);
+    LookupProof {
+        commitment,
+        gkr,
+        main_evals,
+        opening,
+    }
+}
+
+fn verify(
+    pcs: &MyPcs,
+    mut challenger: Challenger,
+    log_height: usize,
+    proof: &LookupProof,
+) -> Result<(), LookupError> {
+    let air = TableLookupAir;
+    challenger.observe(proof.commitment.clone());
+    let alpha: Challenge = challenger.sample_algebra_element();
+    let beta: Challenge = challenger.sample_algebra_element();
+
+    // Three interactions, padded to four.
+    let claim =
+        verify_logup_gkr(log_height + 2, &proof.gkr, &mut challenger).map_err(LookupError::Gkr)?;
+    if proof.gkr.numerator != Challenge::ZERO {
+        return Err(LookupError::NonZeroSum);
+    }
+
+    let row_point = &claim.point[..log_height];
+    pcs.verify(
+        &proof.commitment,
+        row_point,
+        &proof.main_evals,
+        &proof.opening,
+        &mut challenger,
+    )
+    .map_err(LookupError::Pcs)?;
+    let expected = eval_interaction_fractions::<Val, _>(
+        &air.sends(),
+        &air.receives(),
+        &[],
+        &proof.main_evals,
+        alpha,
+        beta,
+        &claim.point,
+    );
+    if expected != (claim.numerator, claim.denominator) {
+        return Err(LookupError::ClaimMismatch);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_logup_gkr_air() {
+    let (pcs, challenger) = setup();
+    for log_height in [1, 4, 7] {
+        let proof = prove(&pcs, challenger.clone(), generate_trace(log_height));
+        verify(&pcs, challenger.clone(), log_height, &proof).expect("verification failed");
+    }
+}
+
+#[test]
+fn test_logup_gkr_air_missing_value() {
+    let (pcs, challenger) = setup();
+    let mut trace = generate_trace(5);
+    trace.values[4] += Val::ONE;
+    let proof = prove(&pcs, challenger.clone(), trace);
+    assert!(matches!(
+        verify(&pcs, challenger, 5, &proof),
+        Err(LookupError::NonZeroSum)
+    ));
+}
+
+#[test]
+fn test_logup_gkr_air_wrong_evals() {
+    let (pcs, challenger) = setup();
+    let mut proof = prove(&pcs, challenger.clone(), generate_trace(5));
+    proof.main_evals[2] += Challenge::ONE;
+    assert!(matches!(
+        verify(&pcs, challenger, 5, &proof),
+        Err(LookupError::Pcs(BasefoldError::SumcheckMismatch))
+    ));
+}
+
+#[test]
+fn test_logup_gkr_air_tampered_layer() {
+    let (pcs, challenger) = setup();
+    let mut proof = prove(&pcs, challenger.clone(), generate_trace(5));
+    proof.gkr.layers[4].denominators[0] += Challenge::ONE;
+    assert!(matches!(
+        verify(&pcs, challenger, 5, &proof),
+        Err(LookupError::Gkr(LogUpGkrError::LayerMismatch))
+    ));
+}
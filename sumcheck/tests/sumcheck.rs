@@ -0,0 +1,404 @@
+use core::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir, PairBuilder};
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::DuplexChallenger;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{Algebra, Field, PrimeCharacteristicRing};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_sumcheck::verifier::SumcheckError;
+use p3_sumcheck::{
+    SumcheckPolynomial, ZerocheckConstraintError, ZerocheckConstraints, ZerocheckError, eq_evals,
+    prove_zerocheck, prover, verifier, verify_zerocheck,
+};
+use p3_uni_stark::{Entry, SymbolicAirBuilder, SymbolicVariable};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+type Val = BabyBear;
+type Challenge = BinomialExtensionField<Val, 4>;
+type Perm = Poseidon2BabyBear<16>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+
+fn challenger() -> Challenger {
+    let mut rng = SmallRng::seed_from_u64(1);
+    Challenger::new(Perm::new_from_rng_128(&mut rng))
+}
+
+/// The product of two multilinears, times a constant.
+struct Product;
+
+impl<F: Field> SumcheckPolynomial<F> for Product {
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn eval<Var: Algebra<F>>(&self, multilinear_evals: &[Var]) -> Var {
+        multilinear_evals[0].clone() * multilinear_evals[1].clone() * F::TWO
+    }
+}
+
+fn do_test_sumcheck(log_n: usize) {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let multilinears: Vec<Vec<Challenge>> = (0..2)
+        .map(|_| (0..1 << log_n).map(|_| rng.random()).collect())
+        .collect();
+    let claim = multilinears[0]
+        .iter()
+        .zip(&multilinears[1])
+        .map(|(&a, &b)| a * b * Challenge::TWO)
+        .sum();
+
+    let (proof, point, evals) =
+        prover::prove::<Val, _, _, _>(&Product, multilinears.clone(), &mut challenger());
+
+    // The claimed evaluations are those of the multilinear extensions at the point.
+    let eq = eq_evals(&point);
+    for (multilinear, &eval) in multilinears.iter().zip(&evals) {
+        let expected: Challenge = multilinear.iter().zip(&eq).map(|(&a, &e)| a * e).sum();
+        assert_eq!(eval, expected);
+    }
+
+    let subclaim = verifier::verify::<Val, _, _>(2, log_n, claim, &proof, &mut challenger())
+        .expect("verification failed");
+    assert_eq!(subclaim.point, point);
+    assert_eq!(
+        subclaim.value,
+        SumcheckPolynomial::<Challenge>::eval(&Product, &evals)
+    );
+
+    let result =
+        verifier::verify::<Val, _, _>(2, log_n, claim + Challenge::ONE, &proof, &mut challenger());
+    assert!(matches!(result, Err(SumcheckError::RoundSumMismatch)));
+}
+
+#[test]
+fn test_sumcheck_small() {
+    do_test_sumcheck(2);
+}
+
+#[test]
+fn test_sumcheck_packed() {
+    do_test_sumcheck(10);
+}
+
+/// Two columns `(a, b)` stepping to `(b, a + b)`, with the first and last rows fixed by public
+/// values.
+struct FibonacciAir;
+
+impl<F> BaseAir<F> for FibonacciAir {
+    fn width(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for FibonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let pis = builder.public_values();
+        let (a, b, x) = (pis[0], pis[1], pis[2]);
+
+        let local = main.row_slice(0).unwrap();
+        let next = main.row_slice(1).unwrap();
+        let local: &[AB::Var] = (*local).borrow();
+        let next: &[AB::Var] = (*next).borrow();
+
+        let mut when_first_row = builder.when_first_row();
+        when_first_row.assert_eq(local[0], a);
+        when_first_row.assert_eq(local[1], b);
+
+        let mut when_transition = builder.when_transition();
+        when_transition.assert_eq(local[1], next[0]);
+        when_transition.assert_eq(local[0] + local[1], next[1]);
+
+        builder.when_last_row().assert_eq(local[1], x);
+    }
+}
+
+fn fibonacci_trace(n: usize) -> RowMajorMatrix<Val> {
+    let mut values = vec![Val::ZERO, Val::ONE];
+    for i in 1..n {
+        let (a, b) = (values[2 * i - 2], values[2 * i - 1]);
+        values.extend([b, a + b]);
+    }
+    RowMajorMatrix::new(values, 2)
+}
+
+/// A column `a` with `a' = a + s'`, where `s` is a preprocessed column holding the row index.
+struct RunningSumAir {
+    log_height: usize,
+}
+
+impl<F: Field> BaseAir<F> for RunningSumAir {
+    fn width(&self) -> usize {
+        1
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        Some(RowMajorMatrix::new_col(
+            (0..1 << self.log_height).map(F::from_usize).collect(),
+        ))
+    }
+}
+
+impl<AB: PairBuilder> Air<AB> for RunningSumAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let preprocessed = builder.preprocessed();
+        let (local, next) = (main.row_slice(0).unwrap(), main.row_slice(1).unwrap());
+        let prep_next = preprocessed.row_slice(1).unwrap();
+
+        builder.when_first_row().assert_zero(local[0]);
+        builder
+            .when_transition()
+            .assert_eq(next[0], local[0] + prep_next[0]);
+    }
+}
+
+fn running_sum_trace(log_height: usize) -> RowMajorMatrix<Val> {
+    let mut acc = Val::ZERO;
+    let values = (0..1 << log_height)
+        .map(|i| {
+            acc += Val::from_usize(i);
+            acc
+        })
+        .collect();
+    RowMajorMatrix::new_col(values)
+}
+
+/// A column which increases by one every five rows, read at row offsets zero and five.
+struct StrideAir;
+
+impl<F> BaseAir<F> for StrideAir {
+    fn width(&self) -> usize {
+        1
+    }
+
+    fn main_row_offsets(&self) -> Vec<usize> {
+        vec![0, 5]
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for StrideAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, ahead) = (main.row_slice(0).unwrap(), main.row_slice(1).unwrap());
+        builder
+            .when_transition_window(6)
+            .assert_eq(ahead[0], local[0] + AB::Expr::ONE);
+    }
+}
+
+fn do_test_zerocheck<A>(
+    air: &A,
+    trace: &RowMajorMatrix<Val>,
+    public_values: &[Val],
+) -> Result<(), ZerocheckError>
+where
+    A: BaseAir<Val> + Air<SymbolicAirBuilder<Val>>,
+{
+    let constraints = ZerocheckConstraints::from_air(air, public_values.len()).unwrap();
+    let preprocessed = air.preprocessed_trace();
+    let log_height = trace.height().ilog2() as usize;
+
+    let (proof, point) = prove_zerocheck::<_, Challenge, _>(
+        &constraints,
+        trace,
+        preprocessed.as_ref(),
+        public_values,
+        &mut challenger(),
+    );
+
+    // The openings are those of the committed columns at the point.
+    let eq = eq_evals(&point);
+    assert_eq!(proof.main_openings, trace.columnwise_dot_product(&eq));
+    assert_eq!(
+        proof.preprocessed_openings,
+        preprocessed.map_or_else(Vec::new, |p| p.columnwise_dot_product(&eq))
+    );
+
+    let verifier_point = verify_zerocheck(
+        &constraints,
+        log_height,
+        public_values,
+        &proof,
+        &mut challenger(),
+    )?;
+    assert_eq!(verifier_point, point);
+    Ok(())
+}
+
+#[test]
+fn test_zerocheck_fibonacci() {
+    let trace = fibonacci_trace(1 << 6);
+    let x = trace.get(trace.height() - 1, 1).unwrap();
+    do_test_zerocheck(&FibonacciAir, &trace, &[Val::ZERO, Val::ONE, x])
+        .expect("verification failed");
+}
+
+#[test]
+fn test_zerocheck_small() {
+    for n in [1, 2] {
+        let trace = fibonacci_trace(n);
+        let x = trace.get(n - 1, 1).unwrap();
+        do_test_zerocheck(&FibonacciAir, &trace, &[Val::ZERO, Val::ONE, x])
+            .expect("verification failed");
+    }
+}
+
+#[test]
+fn test_zerocheck_preprocessed() {
+    for log_height in [3, 8] {
+        let air = RunningSumAir { log_height };
+        do_test_zerocheck(&air, &running_sum_trace(log_height), &[]).expect("verification failed");
+    }
+}
+
+#[test]
+fn test_zerocheck_row_offsets() {
+    let trace = RowMajorMatrix::new_col((0..1 << 7).map(|i| Val::from_usize(i / 5)).collect());
+    do_test_zerocheck(&StrideAir, &trace, &[]).expect("verification failed");
+
+    let mut trace = trace;
+    trace.values[40] += Val::ONE;
+    assert!(do_test_zerocheck(&StrideAir, &trace, &[]).is_err());
+}
+
+#[test]
+fn test_zerocheck_wrong_public_value() {
+    let trace = fibonacci_trace(1 << 6);
+    let x = trace.get(trace.height() - 1, 1).unwrap();
+    let result = do_test_zerocheck(&FibonacciAir, &trace, &[Val::ZERO, Val::ONE, x + Val::ONE]);
+    assert!(matches!(
+        result,
+        Err(ZerocheckError::SumcheckError(
+            SumcheckError::RoundSumMismatch | SumcheckError::FinalEvalMismatch
+        ))
+    ));
+}
+
+#[test]
+fn test_zerocheck_invalid_trace() {
+    let mut trace = fibonacci_trace(1 << 6);
+    let x = trace.get(trace.height() - 1, 1).unwrap();
+    trace.values[20] += Val::ONE;
+    let result = do_test_zerocheck(&FibonacciAir, &trace, &[Val::ZERO, Val::ONE, x]);
+    assert!(matches!(
+        result,
+        Err(ZerocheckError::SumcheckError(
+            SumcheckError::RoundSumMismatch | SumcheckError::FinalEvalMismatch
+        ))
+    ));
+}
+
+#[test]
+fn test_zerocheck_wrong_opening() {
+    let trace = fibonacci_trace(1 << 6);
+    let x = trace.get(trace.height() - 1, 1).unwrap();
+    let public_values = [Val::ZERO, Val::ONE, x];
+    let constraints = ZerocheckConstraints::from_air(&FibonacciAir, 3).unwrap();
+    let (mut proof, _) = prove_zerocheck::<_, Challenge, _>(
+        &constraints,
+        &trace,
+        None,
+        &public_values,
+        &mut challenger(),
+    );
+
+    proof.main_openings[1] += Challenge::ONE;
+    let result = verify_zerocheck(&constraints, 6, &public_values, &proof, &mut challenger());
+    assert!(matches!(result, Err(ZerocheckError::ShiftMismatch)));
+}
+
+#[test]
+fn test_zerocheck_wrong_num_public_values() {
+    let trace = fibonacci_trace(1 << 6);
+    let x = trace.get(trace.height() - 1, 1).unwrap();
+    let constraints = ZerocheckConstraints::from_air(&FibonacciAir, 3).unwrap();
+    let (proof, _) = prove_zerocheck::<_, Challenge, _>(
+        &constraints,
+        &trace,
+        None,
+        &[Val::ZERO, Val::ONE, x],
+        &mut challenger(),
+    );
+
+    let result = verify_zerocheck(
+        &constraints,
+        6,
+        &[Val::ZERO, Val::ONE],
+        &proof,
+        &mut challenger(),
+    );
+    assert!(matches!(result, Err(ZerocheckError::InvalidProofShape)));
+}
+
+#[test]
+fn test_zerocheck_unread_public_value() {
+    // No constraint reads the public value, but the proof is still bound to it.
+    let air = RunningSumAir { log_height: 4 };
+    let constraints = ZerocheckConstraints::from_air(&air, 1).unwrap();
+    let preprocessed = BaseAir::<Val>::preprocessed_trace(&air);
+    let (proof, _) = prove_zerocheck::<_, Challenge, _>(
+        &constraints,
+        &running_sum_trace(4),
+        preprocessed.as_ref(),
+        &[Val::ZERO],
+        &mut challenger(),
+    );
+
+    verify_zerocheck(&constraints, 4, &[Val::ZERO], &proof, &mut challenger())
+        .expect("verification failed");
+    let result = verify_zerocheck(&constraints, 4, &[Val::ONE], &proof, &mut challenger());
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_zerocheck_invalid_constraints() {
+    let public = SymbolicVariable::new(Entry::Public, 2);
+    let main = SymbolicVariable::new(Entry::Main { offset: 0 }, 0);
+    let result =
+        ZerocheckConstraints::<Val>::new(vec![main.into(), main - public], vec![0, 1], 1, 0, 2);
+    assert!(matches!(
+        result,
+        Err(ZerocheckConstraintError::VariableOutOfBounds { constraint: 1 })
+    ));
+
+    let challenge = SymbolicVariable::new(Entry::Challenge, 0);
+    let result = ZerocheckConstraints::<Val>::new(vec![challenge.into()], vec![0, 1], 1, 0, 0);
+    assert!(matches!(
+        result,
+        Err(ZerocheckConstraintError::UnsupportedEntry { constraint: 0 })
+    ));
+}
+
+/// A constant, of degree zero in every variable.
+struct Constant;
+
+impl<F: Field> SumcheckPolynomial<F> for Constant {
+    fn degree(&self) -> usize {
+        0
+    }
+
+    fn eval<Var: Algebra<F>>(&self, _multilinear_evals: &[Var]) -> Var {
+        Var::from(F::TWO)
+    }
+}
+
+#[test]
+fn test_sumcheck_degree_zero() {
+    let log_n = 4;
+    let multilinears = vec![vec![Challenge::ZERO; 1 << log_n]];
+    let (proof, point, _) =
+        prover::prove::<Val, _, _, _>(&Constant, multilinears, &mut challenger());
+    let claim = Challenge::from_usize(2 << log_n);
+
+    let subclaim = verifier::verify::<Val, _, _>(0, log_n, claim, &proof, &mut challenger())
+        .expect("verification failed");
+    assert_eq!(subclaim.point, point);
+    assert_eq!(subclaim.value, Challenge::TWO);
+
+    let result = verifier::verify::<Val, _, _>(0, log_n, claim.double(), &proof, &mut challenger());
+    assert!(matches!(result, Err(SumcheckError::RoundSumMismatch)));
+}
@@ -0,0 +1,69 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Hash;
+
+/// The top `cap_height` layers of a Merkle tree, represented by the `2^cap_height` digests at that
+/// height, with a phantom type parameter to ensure that the digests are associated with a
+/// particular field.
+///
+/// A cap of height 0 is just the root of the tree. Committing to a larger cap lets opening proofs
+/// stop `cap_height` levels early.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(serialize = "[W; DIGEST_ELEMS]: Serialize"))]
+#[serde(bound(deserialize = "[W; DIGEST_ELEMS]: Deserialize<'de>"))]
+pub struct MerkleCap<F, W, const DIGEST_ELEMS: usize> {
+    digests: Vec<[W; DIGEST_ELEMS]>,
+    _marker: PhantomData<F>,
+}
+
+impl<F, W, const DIGEST_ELEMS: usize> MerkleCap<F, W, DIGEST_ELEMS> {
+    /// The digests of the cap, from left to right.
+    pub fn digests(&self) -> &[[W; DIGEST_ELEMS]] {
+        &self.digests
+    }
+
+    /// The number of digests in the cap.
+    pub fn len(&self) -> usize {
+        self.digests.len()
+    }
+
+    /// Whether the cap holds no digests. This is never the case for a cap of a Merkle tree.
+    pub fn is_empty(&self) -> bool {
+        self.digests.is_empty()
+    }
+}
+
+impl<F, W, const DIGEST_ELEMS: usize> From<Vec<[W; DIGEST_ELEMS]>>
+    for MerkleCap<F, W, DIGEST_ELEMS>
+{
+    fn from(digests: Vec<[W; DIGEST_ELEMS]>) -> Self {
+        Self {
+            digests,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F, W, const DIGEST_ELEMS: usize> From<Hash<F, W, DIGEST_ELEMS>>
+    for MerkleCap<F, W, DIGEST_ELEMS>
+{
+    fn from(root: Hash<F, W, DIGEST_ELEMS>) -> Self {
+        vec![root.into()].into()
+    }
+}
+
+impl<F, W, const DIGEST_ELEMS: usize> IntoIterator for MerkleCap<F, W, DIGEST_ELEMS> {
+    type Item = Hash<F, W, DIGEST_ELEMS>;
+    type IntoIter = core::iter::Map<
+        vec::IntoIter<[W; DIGEST_ELEMS]>,
+        fn([W; DIGEST_ELEMS]) -> Hash<F, W, DIGEST_ELEMS>,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.digests.into_iter().map(Hash::from)
+    }
+}
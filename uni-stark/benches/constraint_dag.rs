@@ -0,0 +1,120 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use p3_air::{Air, BaseAir};
+use p3_baby_bear::{BabyBear, GenericPoseidon2LinearLayersBabyBear, Poseidon2BabyBear};
+use p3_blake3_air::Blake3Air;
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{BasedVectorSpace, Field, PackedValue, PrimeCharacteristicRing};
+use p3_fri::TwoAdicFriPcs;
+use p3_matrix::dense::RowMajorMatrixView;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_poseidon2_air::{Poseidon2Air, RoundConstants};
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{
+    ConstraintDag, PackedChallenge, PackedVal, ProverConstraintFolder, StarkConfig,
+    SymbolicAirBuilder, get_symbolic_constraints,
+};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+type Val = BabyBear;
+type Perm = Poseidon2BabyBear<16>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs =
+    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+type Pcs = TwoAdicFriPcs<Val, Radix2DitParallel<Val>, ValMmcs, ChallengeMmcs>;
+type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+
+type MyPoseidon2Air = Poseidon2Air<Val, GenericPoseidon2LinearLayersBabyBear, 16, 7, 1, 4, 13>;
+
+/// The number of packed quotient domain points folded per iteration.
+const NUM_POINTS: usize = 1 << 8;
+
+/// Compare folding the constraints of `air` at a batch of random points with the AIR itself, as
+/// the quotient computation used to, and with its constraint DAG.
+fn bench_air<A>(c: &mut Criterion, name: &str, air: &A)
+where
+    A: BaseAir<Val>
+        + Air<SymbolicAirBuilder<Val>>
+        + for<'a> Air<ProverConstraintFolder<'a, MyConfig>>,
+{
+    let constraints = get_symbolic_constraints(air, 0, 0);
+    let dag = ConstraintDag::new(&constraints);
+
+    let mut rng = SmallRng::seed_from_u64(0);
+    let width = air.width();
+    let rows = (0..NUM_POINTS)
+        .map(|_| {
+            (0..2 * width)
+                .map(|_| PackedVal::<MyConfig>::from_fn(|_| rng.random()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let alpha: Challenge = rng.random();
+    let alpha_powers = alpha.powers().take(constraints.len()).collect::<Vec<_>>();
+    let decomposed_alpha_powers = (0..<Challenge as BasedVectorSpace<Val>>::DIMENSION)
+        .map(|i| {
+            alpha_powers
+                .iter()
+                .map(|x| <Challenge as BasedVectorSpace<Val>>::as_basis_coefficients_slice(x)[i])
+                .collect()
+        })
+        .collect::<Vec<_>>();
+
+    let fold = |main: &[PackedVal<MyConfig>], use_dag: bool| {
+        let mut folder = ProverConstraintFolder::<MyConfig> {
+            main: RowMajorMatrixView::new(main, width),
+            preprocessed: RowMajorMatrixView::new(&[], 0),
+            permutation: RowMajorMatrixView::new(&[], 0),
+            permutation_randomness: &[],
+            cumulative_sum: Challenge::ZERO,
+            aux: vec![],
+            aux_challenges: &[],
+            public_values: &vec![],
+            periodic_values: vec![],
+            is_first_row: PackedVal::<MyConfig>::ONE,
+            is_last_row: PackedVal::<MyConfig>::ZERO,
+            is_transition: PackedVal::<MyConfig>::ONE,
+            is_transition_windows: vec![],
+            alpha_powers: &alpha_powers,
+            decomposed_alpha_powers: &decomposed_alpha_powers,
+            accumulator: PackedChallenge::<MyConfig>::ZERO,
+            constraint_index: 0,
+        };
+        if use_dag {
+            dag.eval(&mut folder);
+        } else {
+            air.eval(&mut folder);
+        }
+        folder.accumulator
+    };
+
+    let mut group = c.benchmark_group(format!("fold_constraints::<{name}>"));
+    group.sample_size(10);
+    group.bench_function("air", |b| {
+        b.iter(|| {
+            rows.iter()
+                .map(|main| fold(main, false))
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("dag", |b| {
+        b.iter(|| rows.iter().map(|main| fold(main, true)).collect::<Vec<_>>())
+    });
+    group.finish();
+}
+
+fn bench_constraint_dag(c: &mut Criterion) {
+    let constants = RoundConstants::from_rng(&mut SmallRng::seed_from_u64(1));
+    bench_air(c, "Poseidon2Air", &MyPoseidon2Air::new(constants));
+    bench_air(c, "Blake3Air", &Blake3Air {});
+}
+
+criterion_group!(benches, bench_constraint_dag);
+criterion_main!(benches);
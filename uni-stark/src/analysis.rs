@@ -0,0 +1,279 @@
+//! Static analysis of an AIR's constraints, to spot expensive constraints before proving.
+
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use p3_air::Air;
+use p3_field::Field;
+use p3_matrix::Matrix;
+use tracing::instrument;
+
+use crate::symbolic_builder::log_quotient_degree_for;
+use crate::{Entry, SymbolicAirBuilder, SymbolicExpression, get_symbolic_constraints};
+
+/// Statistics about a single constraint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConstraintStats {
+    /// The degree of the constraint, as a multiple of the trace length.
+    pub degree: usize,
+    /// The number of multiplications needed to evaluate the constraint, counting shared
+    /// subexpressions once.
+    pub num_multiplications: usize,
+    /// The number of additions, subtractions and negations needed to evaluate the constraint,
+    /// counting shared subexpressions once.
+    pub num_additions: usize,
+}
+
+/// A summary of the constraints of an AIR and of the cost of proving it, computed by
+/// [`analyze_air`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AirReport {
+    /// The statistics of every constraint, in the order they are asserted, followed by the
+    /// constraints of the lookup argument, if any.
+    pub constraints: Vec<ConstraintStats>,
+    /// The largest degree of any constraint.
+    pub max_constraint_degree: usize,
+    /// The indices of the constraints whose degree is `max_constraint_degree`.
+    pub max_degree_constraints: Vec<usize>,
+    /// The main columns which no constraint refers to.
+    pub unused_main_columns: Vec<usize>,
+    /// The preprocessed columns which no constraint refers to.
+    pub unused_preprocessed_columns: Vec<usize>,
+    /// The log of the number of chunks the quotient polynomial is split into, ignoring ZK.
+    pub log_quotient_degree: usize,
+    /// The number of multiplications needed to evaluate all constraints at a point, counting
+    /// subexpressions shared between constraints once.
+    pub num_multiplications: usize,
+    /// The number of additions, subtractions and negations needed to evaluate all constraints at
+    /// a point, counting subexpressions shared between constraints once.
+    pub num_additions: usize,
+    /// Whether the analysis assumed a zero-knowledge PCS, as `0` or `1`.
+    pub is_zk: usize,
+}
+
+impl AirReport {
+    /// The number of chunks the quotient polynomial is split into, ignoring ZK.
+    pub const fn quotient_degree(&self) -> usize {
+        1 << self.log_quotient_degree
+    }
+
+    /// The size of the domain the trace is committed over, for a trace of height `2^log_degree`
+    /// and a PCS with a blowup factor of `2^log_blowup`.
+    pub const fn lde_size(&self, log_degree: usize, log_blowup: usize) -> usize {
+        1 << (log_degree + self.is_zk + log_blowup)
+    }
+
+    /// The size of the domain the quotient polynomial is computed over, for a trace of height
+    /// `2^log_degree`.
+    pub const fn quotient_domain_size(&self, log_degree: usize) -> usize {
+        1 << (log_degree + self.is_zk + self.log_quotient_degree)
+    }
+
+    /// A rough estimate of the number of field operations the prover spends computing the
+    /// quotient polynomial of a trace of height `2^log_degree`.
+    ///
+    /// Every constraint is evaluated at every point of the quotient domain and then folded into
+    /// the accumulator with a multiplication by a power of `alpha` and an addition.
+    pub const fn quotient_cost(&self, log_degree: usize) -> usize {
+        let ops_per_point =
+            self.num_multiplications + self.num_additions + 2 * self.constraints.len();
+        self.quotient_domain_size(log_degree) * ops_per_point
+    }
+}
+
+/// Analyze the constraints of `air`, including those on its preprocessed trace, if any.
+///
+/// `is_zk` should be `1` if the AIR will be proven with a zero-knowledge PCS and `0` otherwise,
+/// as this raises the quotient degree.
+#[instrument(name = "analyze AIR", skip_all)]
+pub fn analyze_air<F, A>(air: &A, num_public_values: usize, is_zk: usize) -> AirReport
+where
+    F: Field,
+    A: Air<SymbolicAirBuilder<F>>,
+{
+    let preprocessed_width = air.preprocessed_trace().map_or(0, |t| t.width());
+    let symbolic_constraints = get_symbolic_constraints(air, preprocessed_width, num_public_values);
+
+    let constraints = symbolic_constraints
+        .iter()
+        .map(|constraint| {
+            let mut stats = ConstraintStats {
+                degree: constraint.degree_multiple(),
+                num_multiplications: 0,
+                num_additions: 0,
+            };
+            visit_nodes(constraint, &mut BTreeSet::new(), &mut |node| {
+                count_operation(
+                    node,
+                    &mut stats.num_multiplications,
+                    &mut stats.num_additions,
+                );
+            });
+            stats
+        })
+        .collect::<Vec<_>>();
+
+    let max_constraint_degree = constraints.iter().map(|c| c.degree).max().unwrap_or(0);
+    let max_degree_constraints = constraints
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.degree == max_constraint_degree)
+        .map(|(i, _)| i)
+        .collect();
+
+    // Walk all constraints together, so that shared subexpressions are only counted once.
+    let mut num_multiplications = 0;
+    let mut num_additions = 0;
+    let mut main_used = vec![false; air.width()];
+    let mut preprocessed_used = vec![false; preprocessed_width];
+    let mut seen = BTreeSet::new();
+    for constraint in &symbolic_constraints {
+        visit_nodes(constraint, &mut seen, &mut |node| {
+            count_operation(node, &mut num_multiplications, &mut num_additions);
+            if let SymbolicExpression::Variable(v) = node {
+                match v.entry {
+                    Entry::Main { .. } => main_used[v.index] = true,
+                    Entry::Preprocessed { .. } => preprocessed_used[v.index] = true,
+                    _ => {}
+                }
+            }
+        });
+    }
+    let unused = |used: Vec<bool>| {
+        used.into_iter()
+            .enumerate()
+            .filter(|(_, used)| !used)
+            .map(|(i, _)| i)
+            .collect()
+    };
+
+    AirReport {
+        constraints,
+        max_constraint_degree,
+        max_degree_constraints,
+        unused_main_columns: unused(main_used),
+        unused_preprocessed_columns: unused(preprocessed_used),
+        log_quotient_degree: log_quotient_degree_for(max_constraint_degree, is_zk),
+        num_multiplications,
+        num_additions,
+        is_zk,
+    }
+}
+
+/// Call `visit` on every node of `expr` which is not in `seen`, children first.
+///
+/// Constraint expressions share subexpressions through `Rc`s, so nodes are identified by their
+/// addresses to avoid walking shared subexpressions more than once.
+fn visit_nodes<F>(
+    expr: &SymbolicExpression<F>,
+    seen: &mut BTreeSet<*const SymbolicExpression<F>>,
+    visit: &mut impl FnMut(&SymbolicExpression<F>),
+) {
+    if !seen.insert(expr as *const SymbolicExpression<F>) {
+        return;
+    }
+    match expr {
+        SymbolicExpression::Add { x, y, .. }
+        | SymbolicExpression::Sub { x, y, .. }
+        | SymbolicExpression::Mul { x, y, .. } => {
+            visit_nodes(x, seen, visit);
+            visit_nodes(y, seen, visit);
+        }
+        SymbolicExpression::Neg { x, .. } => visit_nodes(x, seen, visit),
+        SymbolicExpression::Variable(_)
+        | SymbolicExpression::IsFirstRow
+        | SymbolicExpression::IsLastRow
+        | SymbolicExpression::IsTransition
+        | SymbolicExpression::IsTransitionWindow(_)
+        | SymbolicExpression::Constant(_) => {}
+    }
+    visit(expr);
+}
+
+/// Count the field operation performed by `node`, if any.
+const fn count_operation<F>(
+    node: &SymbolicExpression<F>,
+    num_multiplications: &mut usize,
+    num_additions: &mut usize,
+) {
+    match node {
+        SymbolicExpression::Mul { .. } => *num_multiplications += 1,
+        SymbolicExpression::Add { .. }
+        | SymbolicExpression::Sub { .. }
+        | SymbolicExpression::Neg { .. } => *num_additions += 1,
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{AirBuilder, BaseAir};
+    use p3_baby_bear::BabyBear;
+
+    use super::*;
+
+    /// Columns `[x, y, z, unused]`, with constraints `x^3 = y`, `y + z = 0` and
+    /// `y^3 = x` on transitions.
+    struct CubesAir;
+
+    impl<F> BaseAir<F> for CubesAir {
+        fn width(&self) -> usize {
+            4
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for CubesAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0).expect("Matrix is empty?");
+            let (x, y, z): (AB::Expr, AB::Expr, AB::Expr) =
+                (local[0].into(), local[1].into(), local[2].into());
+            builder.assert_eq(x.clone() * x.clone() * x.clone(), y.clone());
+            builder.assert_zero(y.clone() + z);
+            builder
+                .when_transition()
+                .assert_eq(y.clone() * y.clone() * y, x);
+        }
+    }
+
+    #[test]
+    fn test_analyze_air() {
+        let report = analyze_air::<BabyBear, _>(&CubesAir, 0, 0);
+
+        let degrees = report
+            .constraints
+            .iter()
+            .map(|c| c.degree)
+            .collect::<Vec<_>>();
+        assert_eq!(degrees, [3, 1, 3]);
+        assert_eq!(report.max_constraint_degree, 3);
+        assert_eq!(report.max_degree_constraints, [0, 2]);
+        assert_eq!(report.unused_main_columns, [3]);
+        assert!(report.unused_preprocessed_columns.is_empty());
+        assert_eq!(report.log_quotient_degree, 1);
+
+        // `x^3 - y` takes two multiplications and a subtraction. The transition selector is
+        // applied with one more multiplication.
+        assert_eq!(report.constraints[0].num_multiplications, 2);
+        assert_eq!(report.constraints[0].num_additions, 1);
+        assert_eq!(report.constraints[2].num_multiplications, 3);
+        assert_eq!(report.num_multiplications, 5);
+        assert_eq!(report.num_additions, 3);
+    }
+
+    #[test]
+    fn test_quotient_cost() {
+        let report = analyze_air::<BabyBear, _>(&CubesAir, 0, 0);
+        assert_eq!(report.quotient_degree(), 2);
+        assert_eq!(report.lde_size(10, 1), 1 << 11);
+        assert_eq!(report.quotient_domain_size(10), 1 << 11);
+        assert_eq!(report.quotient_cost(10), (1 << 11) * (5 + 3 + 2 * 3));
+
+        // Zero knowledge doubles the trace and raises the quotient degree.
+        let report = analyze_air::<BabyBear, _>(&CubesAir, 0, 1);
+        assert_eq!(report.log_quotient_degree, 2);
+        assert_eq!(report.lde_size(10, 1), 1 << 12);
+        assert_eq!(report.quotient_domain_size(10), 1 << 13);
+    }
+}
@@ -0,0 +1,346 @@
+//! Proving several AIRs at once, with a single opening proof.
+//!
+//! The main traces of all instances are committed to in one PCS round and their quotient chunks in
+//! another. A single `alpha` folds the constraints of every AIR and a single `zeta` is used for
+//! every opening, so the whole batch is covered by one PCS opening proof. The traces may have
+//! different heights. See `prover.rs` for an overview of the protocol for a single AIR.
+//!
+//! If any AIR has lookups, the lookup challenges are shared by every instance and the permutation
+//! traces of all instances are committed to in a third round, after the main traces. The AIRs may
+//! then interact with each other over buses: the verifier checks that the cumulative sums of all
+//! instances add up to zero, rather than that each of them is zero.
+//!
+//! Batch proving does not support zero-knowledge PCSs yet, nor AIRs with a preprocessed trace,
+//! auxiliary phases, main row offsets other than `[0, 1]` or periodic columns. [`prove_batch`]
+//! returns a [`BatchProvingError`] for such a batch.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::{Itertools, izip};
+use p3_air::Air;
+use p3_challenger::{CanObserve, FieldChallenger};
+use p3_commit::{Pcs, PolynomialSpace};
+use p3_field::PrimeCharacteristicRing;
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_util::log2_strict_usize;
+use tracing::{info_span, instrument};
+
+use crate::lookup::{evaluate_interactions, generate_permutation_trace};
+use crate::prover::quotient_values;
+use crate::{
+    BatchCommitments, BatchProof, NUM_LOOKUP_CHALLENGES, OpenedValues, ProverConstraintFolder,
+    StarkGenericConfig, SymbolicAirBuilder, Val, setup,
+};
+
+/// An AIR together with a trace and public values claimed to satisfy it.
+///
+/// To prove heterogeneous AIRs in one batch, `A` is typically an enum with a variant per AIR which
+/// dispatches `eval` to the contained AIR.
+#[derive(Debug)]
+pub struct StarkInstance<'a, SC: StarkGenericConfig, A> {
+    pub air: &'a A,
+    pub trace: RowMajorMatrix<Val<SC>>,
+    pub public_values: Vec<Val<SC>>,
+}
+
+/// The reasons a batch may not be supported by [`prove_batch`]. Instances are numbered in the order
+/// they were given.
+#[derive(Debug, PartialEq, Eq)]
+pub enum BatchProvingError {
+    /// The PCS is zero-knowledge.
+    ZeroKnowledge,
+    /// The batch has no instances.
+    EmptyBatch,
+    /// The AIR of an instance has a preprocessed trace.
+    PreprocessedTrace { instance: usize },
+    /// The AIR of an instance has auxiliary phases.
+    AuxPhases { instance: usize },
+    /// The AIR of an instance reads main rows at offsets other than `[0, 1]`.
+    MainRowOffsets { instance: usize },
+    /// The AIR of an instance has periodic columns.
+    PeriodicColumns { instance: usize },
+}
+
+/// Prove that every instance's trace satisfies its AIR, with a single opening proof.
+///
+/// Returns an error if the batch uses a feature which batch proving does not support yet, see the
+/// [module documentation](self).
+#[instrument(skip_all)]
+#[allow(clippy::multiple_bound_locations)] // cfg not supported in where clauses?
+pub fn prove_batch<
+    SC,
+    #[cfg(debug_assertions)] A: for<'a> Air<crate::check_constraints::DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>,
+    #[cfg(not(debug_assertions))] A,
+>(
+    config: &SC,
+    instances: Vec<StarkInstance<'_, SC, A>>,
+) -> Result<BatchProof<SC>, BatchProvingError>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<ProverConstraintFolder<'a, SC>>,
+{
+    if SC::Pcs::ZK {
+        return Err(BatchProvingError::ZeroKnowledge);
+    }
+    if instances.is_empty() {
+        return Err(BatchProvingError::EmptyBatch);
+    }
+
+    let pcs = config.pcs();
+    let mut challenger = config.initialise_challenger();
+
+    let (airs, traces, public_values): (Vec<_>, Vec<_>, Vec<_>) = instances
+        .into_iter()
+        .map(|instance| (instance.air, instance.trace, instance.public_values))
+        .multiunzip();
+
+    let proving_keys = airs
+        .iter()
+        .zip(&public_values)
+        .enumerate()
+        .map(|(instance, (&air, public_values))| {
+            let (pk, _) = setup(config, air, public_values.len());
+            if pk.vk.preprocessed.is_some() {
+                return Err(BatchProvingError::PreprocessedTrace { instance });
+            }
+            if !pk.vk.aux_phases.is_empty() {
+                return Err(BatchProvingError::AuxPhases { instance });
+            }
+            if pk.vk.main_row_offsets != [0, 1] {
+                return Err(BatchProvingError::MainRowOffsets { instance });
+            }
+            if !pk.vk.periodic_columns.is_empty() {
+                return Err(BatchProvingError::PeriodicColumns { instance });
+            }
+            Ok(pk)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    #[cfg(debug_assertions)]
+    for (&air, trace, public_values) in izip!(&airs, &traces, &public_values) {
+        crate::check_constraints::check_constraints(air, trace, None, &[], &[], public_values);
+    }
+
+    let degree_bits = traces
+        .iter()
+        .map(|trace| log2_strict_usize(trace.height()))
+        .collect_vec();
+    let trace_domains = degree_bits
+        .iter()
+        .map(|&bits| pcs.natural_domain_for_degree(1 << bits))
+        .collect_vec();
+
+    // Observe the shape of the batch and, for each instance, its degree and verifying key.
+    challenger.observe(Val::<SC>::from_usize(airs.len()));
+    for (pk, &bits) in proving_keys.iter().zip(&degree_bits) {
+        challenger.observe(Val::<SC>::from_usize(bits));
+        challenger.observe_algebra_element(pk.vk.digest(config));
+    }
+
+    // The permutation traces can only be generated once the main traces have been committed to,
+    // so we evaluate the interactions of every instance with lookups while we still have its trace.
+    let interactions = airs
+        .iter()
+        .map(|air| (air.sends(), air.receives()))
+        .collect_vec();
+    let interaction_values = izip!(&proving_keys, &interactions, &traces)
+        .map(|(pk, (sends, receives), trace)| {
+            (pk.vk.permutation_width != 0)
+                .then(|| evaluate_interactions(None, trace, sends, receives))
+        })
+        .collect_vec();
+    let has_lookups = interaction_values.iter().any(Option::is_some);
+
+    // Commit to every main trace in a single round. The underlying MMCS handles matrices of
+    // different heights.
+    let (main_commit, main_data) = info_span!("commit to main traces")
+        .in_scope(|| pcs.commit(trace_domains.iter().copied().zip(traces)));
+
+    challenger.observe(main_commit.clone());
+    for public_values in &public_values {
+        challenger.observe_slice(public_values);
+    }
+
+    // Generate and commit to the permutation traces of all instances with lookups, using the same
+    // lookup challenges for all of them so that interactions between AIRs cancel out.
+    let (permutation_randomness, permutation_commit, permutation_data, cumulative_sums) =
+        if has_lookups {
+            let randomness: Vec<SC::Challenge> = (0..NUM_LOOKUP_CHALLENGES)
+                .map(|_| challenger.sample_algebra_element())
+                .collect();
+            let (permutation_traces, cumulative_sums): (Vec<_>, Vec<_>) =
+                info_span!("generate permutation traces").in_scope(|| {
+                    izip!(&interaction_values, &interactions, &trace_domains)
+                        .map(|(interaction_values, (sends, receives), &trace_domain)| {
+                            let Some(interaction_values) = interaction_values else {
+                                return (None, None);
+                            };
+                            let (permutation_trace, cumulative_sum) = generate_permutation_trace(
+                                interaction_values,
+                                sends,
+                                receives,
+                                &randomness,
+                            );
+                            (
+                                Some((trace_domain, permutation_trace.flatten_to_base())),
+                                Some(cumulative_sum),
+                            )
+                        })
+                        .unzip()
+                });
+            let (permutation_commit, permutation_data) = info_span!("commit to permutation traces")
+                .in_scope(|| pcs.commit(permutation_traces.into_iter().flatten()));
+            challenger.observe(permutation_commit.clone());
+            for cumulative_sum in cumulative_sums.iter().flatten() {
+                challenger.observe_algebra_element(*cumulative_sum);
+            }
+            (
+                randomness,
+                Some(permutation_commit),
+                Some(permutation_data),
+                cumulative_sums,
+            )
+        } else {
+            (vec![], None, None, vec![None; airs.len()])
+        };
+
+    // The index of each instance's permutation trace within the permutation round, if it has one.
+    let permutation_indices = cumulative_sums
+        .iter()
+        .scan(0, |next_index, cumulative_sum| {
+            Some(cumulative_sum.map(|_| {
+                *next_index += 1;
+                *next_index - 1
+            }))
+        })
+        .collect_vec();
+
+    // A single alpha is used to fold the constraints of every AIR.
+    let alpha: SC::Challenge = challenger.sample_algebra_element();
+
+    // Compute the quotient of each instance and split it into chunks. All chunks of all instances
+    // are committed to in a single round.
+    let quotient_degrees = proving_keys
+        .iter()
+        .map(|pk| 1 << pk.vk.log_quotient_degree)
+        .collect_vec();
+    let quotient_chunks = info_span!("compute quotient polynomials").in_scope(|| {
+        izip!(
+            0..,
+            &public_values,
+            &proving_keys,
+            &trace_domains,
+            &degree_bits,
+            &permutation_indices,
+            &cumulative_sums
+        )
+        .flat_map(
+            |(i, public_values, pk, &trace_domain, &bits, &permutation_index, &cumulative_sum)| {
+                let log_quotient_degree = pk.vk.log_quotient_degree;
+                let quotient_degree = 1 << log_quotient_degree;
+                let quotient_domain =
+                    trace_domain.create_disjoint_domain(1 << (bits + log_quotient_degree));
+                let trace_on_quotient_domain =
+                    pcs.get_evaluations_on_domain(&main_data, i, quotient_domain);
+                let permutation_on_quotient_domain = permutation_index.map(|index| {
+                    let permutation_data = permutation_data.as_ref().unwrap();
+                    pcs.get_evaluations_on_domain(permutation_data, index, quotient_domain)
+                });
+                let quotient_values = quotient_values::<SC, _>(
+                    &pk.constraint_dag,
+                    &pk.vk.main_row_offsets,
+                    &[],
+                    public_values,
+                    trace_domain,
+                    quotient_domain,
+                    trace_on_quotient_domain,
+                    None,
+                    permutation_on_quotient_domain,
+                    &permutation_randomness,
+                    cumulative_sum.unwrap_or_default(),
+                    vec![],
+                    &[],
+                    alpha,
+                );
+                let quotient_flat = RowMajorMatrix::new_col(quotient_values).flatten_to_base();
+                quotient_domain
+                    .split_domains(quotient_degree)
+                    .into_iter()
+                    .zip(quotient_domain.split_evals(quotient_degree, quotient_flat))
+            },
+        )
+        .collect_vec()
+    });
+    let num_quotient_chunks = quotient_chunks.len();
+    let (quotient_commit, quotient_data) =
+        info_span!("commit to quotient poly chunks").in_scope(|| pcs.commit(quotient_chunks));
+    challenger.observe(quotient_commit.clone());
+
+    // A single out-of-domain point is used for every instance.
+    let zeta: SC::Challenge = challenger.sample_algebra_element();
+
+    let (opened_values, opening_proof) = info_span!("open").in_scope(|| {
+        let main_points = trace_domains
+            .iter()
+            .map(|trace_domain| vec![zeta, trace_domain.next_point(zeta).unwrap()])
+            .collect_vec();
+        let quotient_points = vec![vec![zeta]; num_quotient_chunks];
+        let permutation_round = permutation_data.as_ref().map(|permutation_data| {
+            let permutation_points = izip!(&trace_domains, &permutation_indices)
+                .filter(|(_, index)| index.is_some())
+                .map(|(trace_domain, _)| vec![zeta, trace_domain.next_point(zeta).unwrap()])
+                .collect_vec();
+            (permutation_data, permutation_points)
+        });
+        let rounds = [(&main_data, main_points), (&quotient_data, quotient_points)]
+            .into_iter()
+            .chain(permutation_round)
+            .collect();
+        pcs.open(rounds, &mut challenger)
+    });
+
+    let trace_idx = <SC as StarkGenericConfig>::Pcs::TRACE_IDX;
+    let quotient_idx = <SC as StarkGenericConfig>::Pcs::QUOTIENT_IDX;
+    let mut opened_quotient_chunks = opened_values[quotient_idx].iter();
+    // The permutation round, if any, comes right after the quotient round.
+    let mut opened_permutations = opened_values.get(quotient_idx + 1).into_iter().flatten();
+    let opened_values = opened_values[trace_idx]
+        .iter()
+        .zip(&quotient_degrees)
+        .zip(&permutation_indices)
+        .map(|((trace, &quotient_degree), permutation_index)| {
+            let permutation = permutation_index.and_then(|_| opened_permutations.next());
+            OpenedValues {
+                trace_local: trace[0].clone(),
+                trace_next: trace[1].clone(),
+                trace_extra_rows: vec![],
+                preprocessed_local: None,
+                preprocessed_next: None,
+                quotient_chunks: opened_quotient_chunks
+                    .by_ref()
+                    .take(quotient_degree)
+                    .map(|chunk| chunk[0].clone())
+                    .collect(),
+                random: None,
+                permutation_local: permutation.map(|permutation| permutation[0].clone()),
+                permutation_next: permutation.map(|permutation| permutation[1].clone()),
+                aux_local: vec![],
+                aux_next: vec![],
+            }
+        })
+        .collect();
+
+    Ok(BatchProof {
+        commitments: BatchCommitments {
+            main: main_commit,
+            quotient_chunks: quotient_commit,
+            permutation: permutation_commit,
+        },
+        opened_values,
+        opening_proof,
+        degree_bits,
+        cumulative_sums,
+    })
+}
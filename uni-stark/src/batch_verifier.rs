@@ -0,0 +1,276 @@
+//! See `batch_prover.rs` for an overview of the batch protocol.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::{Itertools, izip};
+use p3_air::{Air, BaseAir};
+use p3_challenger::{CanObserve, FieldChallenger};
+use p3_commit::{Pcs, PolynomialSpace};
+use p3_field::{BasedVectorSpace, PrimeCharacteristicRing};
+use tracing::instrument;
+
+use crate::verifier::verify_constraints;
+use crate::{
+    BatchProof, NUM_LOOKUP_CHALLENGES, PcsError, StarkGenericConfig, SymbolicAirBuilder, Val,
+    VerificationError, VerifierConstraintFolder, setup,
+};
+
+/// Verify a proof, produced by [`prove_batch`](crate::prove_batch), that traces satisfy each of
+/// `airs` with the corresponding `public_values`.
+///
+/// `airs` and `public_values` must be given in the same order as the instances were proven.
+#[instrument(skip_all)]
+pub fn verify_batch<SC, A>(
+    config: &SC,
+    airs: &[A],
+    proof: &BatchProof<SC>,
+    public_values: &[Vec<Val<SC>>],
+) -> Result<(), VerificationError<PcsError<SC>>>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+{
+    assert!(!SC::Pcs::ZK, "batch proving does not support ZK yet");
+
+    let BatchProof {
+        commitments,
+        opened_values,
+        opening_proof,
+        degree_bits,
+        cumulative_sums,
+    } = proof;
+
+    let pcs = config.pcs();
+
+    if airs.is_empty()
+        || airs.len() != public_values.len()
+        || airs.len() != opened_values.len()
+        || airs.len() != degree_bits.len()
+        || airs.len() != cumulative_sums.len()
+    {
+        return Err(VerificationError::InvalidProofShape);
+    }
+
+    let verifying_keys = airs
+        .iter()
+        .zip(public_values)
+        .map(|(air, public_values)| {
+            let (_, vk) = setup(config, air, public_values.len());
+            assert!(
+                vk.preprocessed.is_none(),
+                "batch proving does not support preprocessed traces yet"
+            );
+            assert!(
+                vk.aux_phases.is_empty(),
+                "batch proving does not support auxiliary phases yet"
+            );
+            assert_eq!(
+                vk.main_row_offsets,
+                [0, 1],
+                "batch proving does not support extra main row offsets yet"
+            );
+            assert!(
+                vk.periodic_columns.is_empty(),
+                "batch proving does not support periodic columns yet"
+            );
+            vk
+        })
+        .collect_vec();
+
+    let trace_domains = degree_bits
+        .iter()
+        .map(|&bits| pcs.natural_domain_for_degree(1 << bits))
+        .collect_vec();
+    let quotient_chunks_domains = izip!(&verifying_keys, &trace_domains, degree_bits)
+        .map(|(vk, trace_domain, &bits)| {
+            let quotient_degree = 1 << vk.log_quotient_degree;
+            trace_domain
+                .create_disjoint_domain(1 << (bits + vk.log_quotient_degree))
+                .split_domains(quotient_degree)
+        })
+        .collect_vec();
+
+    let valid_shape = izip!(
+        airs,
+        &verifying_keys,
+        opened_values,
+        cumulative_sums,
+        &quotient_chunks_domains
+    )
+    .all(
+        |(air, vk, opened_values, cumulative_sum, quotient_chunks_domains)| {
+            let air_width = <A as BaseAir<Val<SC>>>::width(air);
+            let has_lookups = vk.permutation_width != 0;
+            let valid_permutation_shape = |opened: &Option<Vec<SC::Challenge>>| match opened {
+                Some(values) => {
+                    has_lookups
+                        && values.len()
+                            == vk.permutation_width
+                                * <SC::Challenge as BasedVectorSpace<Val<SC>>>::DIMENSION
+                }
+                None => !has_lookups,
+            };
+            opened_values.trace_local.len() == air_width
+                && opened_values.trace_next.len() == air_width
+                && opened_values.trace_extra_rows.is_empty()
+                && opened_values.preprocessed_local.is_none()
+                && opened_values.preprocessed_next.is_none()
+                && opened_values.random.is_none()
+                && opened_values.aux_local.is_empty()
+                && opened_values.aux_next.is_empty()
+                && valid_permutation_shape(&opened_values.permutation_local)
+                && valid_permutation_shape(&opened_values.permutation_next)
+                && cumulative_sum.is_some() == has_lookups
+                && opened_values.quotient_chunks.len() == quotient_chunks_domains.len()
+                && opened_values
+                    .quotient_chunks
+                    .iter()
+                    .all(|qc| qc.len() == <SC::Challenge as BasedVectorSpace<Val<SC>>>::DIMENSION)
+        },
+    );
+    let has_lookups = cumulative_sums.iter().any(Option::is_some);
+    if !valid_shape || has_lookups != commitments.permutation.is_some() {
+        return Err(VerificationError::InvalidProofShape);
+    }
+
+    // The interactions between all AIRs are balanced if and only if their cumulative sums add up
+    // to zero.
+    if cumulative_sums
+        .iter()
+        .flatten()
+        .copied()
+        .sum::<SC::Challenge>()
+        != SC::Challenge::ZERO
+    {
+        return Err(VerificationError::UnbalancedLookups);
+    }
+
+    // Observe the shape of the batch and, for each instance, its degree and verifying key.
+    let mut challenger = config.initialise_challenger();
+    challenger.observe(Val::<SC>::from_usize(airs.len()));
+    for (vk, &bits) in verifying_keys.iter().zip(degree_bits) {
+        challenger.observe(Val::<SC>::from_usize(bits));
+        challenger.observe_algebra_element(vk.digest(config));
+    }
+
+    challenger.observe(commitments.main.clone());
+    for public_values in public_values {
+        challenger.observe_slice(public_values);
+    }
+
+    let permutation_randomness: Vec<SC::Challenge> = match &commitments.permutation {
+        Some(permutation_commit) => {
+            let randomness = (0..NUM_LOOKUP_CHALLENGES)
+                .map(|_| challenger.sample_algebra_element())
+                .collect();
+            challenger.observe(permutation_commit.clone());
+            for cumulative_sum in cumulative_sums.iter().flatten() {
+                challenger.observe_algebra_element(*cumulative_sum);
+            }
+            randomness
+        }
+        None => vec![],
+    };
+
+    // Soundness Error: n/|EF| where n is the largest number of constraints of any AIR.
+    let alpha: SC::Challenge = challenger.sample_algebra_element();
+    challenger.observe(commitments.quotient_chunks.clone());
+
+    // Soundness Error: dN/|EF| where `N` is the largest trace length and `d` the largest constraint
+    // degree.
+    let zeta: SC::Challenge = challenger.sample_algebra_element();
+
+    let main_openings = trace_domains
+        .iter()
+        .zip(opened_values)
+        .map(|(trace_domain, opened_values)| {
+            (
+                *trace_domain,
+                vec![
+                    (zeta, opened_values.trace_local.clone()),
+                    (
+                        trace_domain.next_point(zeta).unwrap(),
+                        opened_values.trace_next.clone(),
+                    ),
+                ],
+            )
+        })
+        .collect_vec();
+    let quotient_openings = quotient_chunks_domains
+        .iter()
+        .zip(opened_values)
+        .flat_map(|(domains, opened_values)| {
+            domains
+                .iter()
+                .zip(&opened_values.quotient_chunks)
+                .map(|(domain, values)| (*domain, vec![(zeta, values.clone())]))
+        })
+        .collect_vec();
+
+    let permutation_round = commitments.permutation.as_ref().map(|permutation_commit| {
+        let permutation_openings = trace_domains
+            .iter()
+            .zip(opened_values)
+            .filter_map(|(trace_domain, opened_values)| {
+                let permutation_local = opened_values.permutation_local.as_ref()?;
+                let permutation_next = opened_values.permutation_next.as_ref()?;
+                Some((
+                    *trace_domain,
+                    vec![
+                        (zeta, permutation_local.clone()),
+                        (
+                            trace_domain.next_point(zeta).unwrap(),
+                            permutation_next.clone(),
+                        ),
+                    ],
+                ))
+            })
+            .collect_vec();
+        (permutation_commit.clone(), permutation_openings)
+    });
+
+    let rounds = [
+        (commitments.main.clone(), main_openings),
+        (commitments.quotient_chunks.clone(), quotient_openings),
+    ]
+    .into_iter()
+    .chain(permutation_round)
+    .collect();
+    pcs.verify(rounds, opening_proof, &mut challenger)
+        .map_err(VerificationError::InvalidOpeningArgument)?;
+
+    for (
+        air,
+        opened_values,
+        cumulative_sum,
+        public_values,
+        trace_domain,
+        quotient_chunks_domains,
+    ) in izip!(
+        airs,
+        opened_values,
+        cumulative_sums,
+        public_values,
+        trace_domains,
+        &quotient_chunks_domains
+    ) {
+        verify_constraints::<SC, A, PcsError<SC>>(
+            air,
+            opened_values,
+            &[0, 1],
+            &[],
+            0,
+            &permutation_randomness,
+            cumulative_sum.unwrap_or_default(),
+            &[],
+            public_values,
+            trace_domain,
+            quotient_chunks_domains,
+            zeta,
+            alpha,
+        )?;
+    }
+
+    Ok(())
+}
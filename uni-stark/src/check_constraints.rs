@@ -1,81 +1,300 @@
 use alloc::vec::Vec;
 
-use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues};
-use p3_field::Field;
+use p3_air::{
+    Air, AirBuilder, AirBuilderWithPublicValues, AuxBuilder, ExtensionBuilder, FilteredAirBuilder,
+    PairBuilder, PeriodicAirBuilder,
+};
+use p3_field::{ExtensionField, Field};
 use p3_matrix::Matrix;
 use p3_matrix::dense::{RowMajorMatrix, RowMajorMatrixView};
-use p3_matrix::stack::VerticalPair;
 use tracing::instrument;
 
+/// A constraint which did not evaluate to zero, as reported by [`find_constraint_violations`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintViolation<EF> {
+    /// The row on which the constraint was evaluated, i.e. the first row of the window.
+    pub row: usize,
+    /// The index of the constraint among all assertions made by `eval` on a row, in order.
+    pub constraint_index: usize,
+    /// The value of the constraint, multiplied by the condition it was asserted under, if any.
+    pub value: EF,
+    /// Whether `row` is the first row, where `when_first_row` constraints apply.
+    pub is_first_row: bool,
+    /// Whether `row` is the last row, where `when_last_row` constraints apply.
+    pub is_last_row: bool,
+    /// Whether `row` is not the last row, where `when_transition` constraints apply.
+    pub is_transition: bool,
+    /// The filter the constraint was asserted under.
+    pub filter: ConstraintFilter,
+}
+
+/// The filter a constraint was asserted under, as reported in a [`ConstraintViolation`].
+///
+/// For a constraint asserted under nested filters, this is the outermost one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConstraintFilter {
+    /// The constraint was asserted on the builder itself, and applies on every row.
+    Unconditional,
+    /// The constraint was asserted under `when_first_row`.
+    FirstRow,
+    /// The constraint was asserted under `when_last_row`.
+    LastRow,
+    /// The constraint was asserted under `when_transition`.
+    Transition,
+    /// The constraint was asserted under `when_transition_window` with the given size.
+    TransitionWindow(usize),
+    /// The constraint was asserted under `when`, or `when_ne`, with some other condition.
+    Custom,
+}
+
 /// Runs constraint checks using a given AIR definition and trace matrix.
 ///
-/// Iterates over every row in `main`, providing both the current and next row
-/// (with wraparound) to the AIR logic. Also injects public values into the builder
+/// Iterates over every row in `main`, providing the rows at the AIR's main row offsets, and the
+/// current and next rows of the other traces (with wraparound) to the AIR logic. Also injects public values into the builder
 /// for first/last row assertions.
 ///
 /// # Arguments
 /// - `air`: The AIR logic to run
 /// - `main`: The trace matrix (rows of witness values)
+/// - `preprocessed`: The preprocessed trace matrix, if the AIR has one
+/// - `aux`: The trace of every auxiliary phase of the AIR
+/// - `aux_challenges`: The challenges of every auxiliary phase of the AIR
 /// - `public_values`: Public values provided to the builder
+///
+/// # Panics
+/// Panics on the first row on which some constraint is violated. Use
+/// [`find_constraint_violations`] to list every violation instead.
 #[instrument(name = "check constraints", skip_all)]
-pub(crate) fn check_constraints<F, A>(air: &A, main: &RowMajorMatrix<F>, public_values: &Vec<F>)
+pub(crate) fn check_constraints<F, EF, A>(
+    air: &A,
+    main: &RowMajorMatrix<F>,
+    preprocessed: Option<&RowMajorMatrix<F>>,
+    aux: &[RowMajorMatrix<EF>],
+    aux_challenges: &[Vec<EF>],
+    public_values: &[F],
+) where
+    F: Field,
+    EF: ExtensionField<F>,
+    A: for<'a> Air<DebugConstraintBuilder<'a, F, EF>>,
+{
+    let main_row_offsets = air.main_row_offsets();
+    let periodic_columns = air.periodic_columns();
+    for row in 0..main.height() {
+        let violations = eval_row(
+            air,
+            main,
+            preprocessed,
+            aux,
+            aux_challenges,
+            public_values,
+            &periodic_columns,
+            &main_row_offsets,
+            &[0, 1],
+            row,
+        );
+        if let Some(violation) = violations.first() {
+            assert_eq!(
+                violation.value,
+                EF::ZERO,
+                "constraints had nonzero value on row {} (constraint {})",
+                violation.row,
+                violation.constraint_index
+            );
+        }
+    }
+}
+
+/// Evaluate the constraints of `air` on every row of `main`, collecting every violation rather
+/// than panicking at the first.
+///
+/// As with [`check_constraints`], the builder exposes the rows at the AIR's main row offsets of the
+/// main trace, and the current and next rows of the other traces, with wraparound.
+///
+/// # Arguments
+/// - `air`: The AIR logic to run
+/// - `main`: The trace matrix (rows of witness values)
+/// - `preprocessed`: The preprocessed trace matrix, if the AIR has one
+/// - `aux`: The trace of every auxiliary phase of the AIR
+/// - `aux_challenges`: The challenges of every auxiliary phase of the AIR
+/// - `public_values`: Public values provided to the builder
+#[instrument(name = "find constraint violations", skip_all)]
+pub fn find_constraint_violations<F, EF, A>(
+    air: &A,
+    main: &RowMajorMatrix<F>,
+    preprocessed: Option<&RowMajorMatrix<F>>,
+    aux: &[RowMajorMatrix<EF>],
+    aux_challenges: &[Vec<EF>],
+    public_values: &[F],
+) -> Vec<ConstraintViolation<EF>>
 where
     F: Field,
-    A: for<'a> Air<DebugConstraintBuilder<'a, F>>,
+    EF: ExtensionField<F>,
+    A: for<'a> Air<DebugConstraintBuilder<'a, F, EF>>,
 {
-    let height = main.height();
+    let main_row_offsets = air.main_row_offsets();
+    let periodic_columns = air.periodic_columns();
+    (0..main.height())
+        .flat_map(|row| {
+            eval_row(
+                air,
+                main,
+                preprocessed,
+                aux,
+                aux_challenges,
+                public_values,
+                &periodic_columns,
+                &main_row_offsets,
+                &[0, 1],
+                row,
+            )
+        })
+        .collect()
+}
 
-    (0..height).for_each(|i| {
-        let i_next = (i + 1) % height;
+/// Evaluate the constraints of `air` on the window starting at `row`, returning the violations.
+///
+/// The window holds the rows at `main_offsets` from `row` of the main trace, and the rows at
+/// `offsets` from `row` of the preprocessed and auxiliary traces.
+#[allow(clippy::too_many_arguments)]
+fn eval_row<F, EF, A>(
+    air: &A,
+    main: &RowMajorMatrix<F>,
+    preprocessed: Option<&RowMajorMatrix<F>>,
+    aux: &[RowMajorMatrix<EF>],
+    aux_challenges: &[Vec<EF>],
+    public_values: &[F],
+    periodic_columns: &[Vec<F>],
+    main_offsets: &[usize],
+    offsets: &[usize],
+    row: usize,
+) -> Vec<ConstraintViolation<EF>>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    A: for<'a> Air<DebugConstraintBuilder<'a, F, EF>>,
+{
+    let height = main.height();
 
-        let local = main.row_slice(i).unwrap(); // i < height so unwrap should never fail.
-        let next = main.row_slice(i_next).unwrap(); // i_next < height so unwrap should never fail.
-        let main = VerticalPair::new(
-            RowMajorMatrixView::new_row(&*local),
-            RowMajorMatrixView::new_row(&*next),
-        );
+    let main_window = window(main, row, main_offsets);
+    let preprocessed_width = preprocessed.map_or(0, Matrix::width);
+    let preprocessed_window = preprocessed.map_or_else(Vec::new, |p| window(p, row, offsets));
+    let aux_windows = aux
+        .iter()
+        .map(|aux| (window(aux, row, offsets), aux.width()))
+        .collect::<Vec<_>>();
 
-        let mut builder = DebugConstraintBuilder {
-            row_index: i,
-            main,
-            public_values,
-            is_first_row: F::from_bool(i == 0),
-            is_last_row: F::from_bool(i == height - 1),
-            is_transition: F::from_bool(i != height - 1),
-        };
+    let mut builder = DebugConstraintBuilder {
+        row_index: row,
+        height,
+        main: RowMajorMatrixView::new(&main_window, main.width()),
+        preprocessed: RowMajorMatrixView::new(&preprocessed_window, preprocessed_width),
+        aux: aux_windows
+            .iter()
+            .map(|(values, width)| RowMajorMatrixView::new(values, *width))
+            .collect(),
+        aux_challenges,
+        public_values,
+        periodic_values: periodic_columns
+            .iter()
+            .map(|column| column[row % column.len()])
+            .collect(),
+        is_first_row: F::from_bool(row == 0),
+        is_last_row: F::from_bool(row == height - 1),
+        is_transition: F::from_bool(row != height - 1),
+        filter: ConstraintFilter::Unconditional,
+        constraint_index: 0,
+        violations: Vec::new(),
+    };
+    air.eval(&mut builder);
+    builder.violations
+}
 
-        air.eval(&mut builder);
-    });
+/// The values of the rows of `matrix` at `offsets` from `row`, with wraparound.
+fn window<T: Clone + Send + Sync>(
+    matrix: &RowMajorMatrix<T>,
+    row: usize,
+    offsets: &[usize],
+) -> Vec<T> {
+    let height = matrix.height();
+    offsets
+        .iter()
+        .flat_map(|offset| matrix.row((row + offset) % height).unwrap())
+        .collect()
 }
 
 /// A builder that runs constraint assertions during testing.
 ///
-/// Used in conjunction with [`check_constraints`] to simulate
-/// an execution trace and verify that the AIR logic enforces all constraints.
+/// Used in conjunction with [`check_constraints`] and [`find_constraint_violations`] to simulate
+/// an execution trace and verify that the AIR logic enforces all constraints. Rather than
+/// panicking, every assertion which fails is recorded as a [`ConstraintViolation`].
 #[derive(Debug)]
-pub struct DebugConstraintBuilder<'a, F: Field> {
+pub struct DebugConstraintBuilder<'a, F: Field, EF: ExtensionField<F> = F> {
     /// The index of the row currently being evaluated.
     row_index: usize,
-    /// A view of the current and next row as a vertical pair.
-    main: VerticalPair<RowMajorMatrixView<'a, F>, RowMajorMatrixView<'a, F>>,
+    /// The height of the trace.
+    height: usize,
+    /// A view of the window of rows starting at the current row.
+    main: RowMajorMatrixView<'a, F>,
+    /// A view of the window of rows of the preprocessed trace starting at the current row.
+    preprocessed: RowMajorMatrixView<'a, F>,
+    /// A view of the window of rows of every auxiliary phase starting at the current row.
+    aux: Vec<RowMajorMatrixView<'a, EF>>,
+    /// The challenges of every auxiliary phase.
+    aux_challenges: &'a [Vec<EF>],
     /// The public values provided for constraint validation (e.g. inputs or outputs).
     public_values: &'a [F],
+    /// The values of the periodic columns on the current row.
+    periodic_values: Vec<F>,
     /// A flag indicating whether this is the first row.
     is_first_row: F,
     /// A flag indicating whether this is the last row.
     is_last_row: F,
     /// A flag indicating whether this is a transition row (not the last row).
     is_transition: F,
+    /// The filter of the most recently created filtered builder.
+    filter: ConstraintFilter,
+    /// The index of the next assertion on the current row.
+    constraint_index: usize,
+    /// The assertions which failed on the current row.
+    violations: Vec<ConstraintViolation<EF>>,
 }
 
-impl<'a, F> AirBuilder for DebugConstraintBuilder<'a, F>
+impl<F: Field, EF: ExtensionField<F>> DebugConstraintBuilder<'_, F, EF> {
+    /// Returns a filtered builder whose assertions are recorded under `filter`.
+    ///
+    /// The filtered builder borrows `self` for as long as it lives, so every assertion made through
+    /// it is made before the filter can change again.
+    fn filtered(&mut self, filter: ConstraintFilter, condition: F) -> FilteredAirBuilder<'_, Self> {
+        self.filter = filter;
+        FilteredAirBuilder::new(self, condition)
+    }
+
+    /// Record the value of the next assertion, asserted under `filter`, if it is nonzero.
+    fn record(&mut self, value: EF, filter: ConstraintFilter) {
+        if !value.is_zero() {
+            self.violations.push(ConstraintViolation {
+                row: self.row_index,
+                constraint_index: self.constraint_index,
+                value,
+                is_first_row: self.is_first_row.is_one(),
+                is_last_row: self.is_last_row.is_one(),
+                is_transition: self.is_transition.is_one(),
+                filter,
+            });
+        }
+        self.constraint_index += 1;
+    }
+}
+
+impl<'a, F, EF> AirBuilder for DebugConstraintBuilder<'a, F, EF>
 where
     F: Field,
+    EF: ExtensionField<F>,
 {
     type F = F;
     type Expr = F;
     type Var = F;
-    type M = VerticalPair<RowMajorMatrixView<'a, F>, RowMajorMatrixView<'a, F>>;
+    type M = RowMajorMatrixView<'a, F>;
 
     fn main(&self) -> Self::M {
         self.main
@@ -89,37 +308,44 @@ where
         self.is_last_row
     }
 
-    /// # Panics
-    /// This function panics if `size` is not `2`.
+    /// Nonzero on all rows except the last `size - 1`.
     fn is_transition_window(&self, size: usize) -> Self::Expr {
-        if size == 2 {
-            self.is_transition
-        } else {
-            panic!("only supports a window size of 2")
-        }
+        F::from_bool(self.row_index + size <= self.height)
+    }
+
+    fn when<I: Into<Self::Expr>>(&mut self, condition: I) -> FilteredAirBuilder<'_, Self> {
+        self.filtered(ConstraintFilter::Custom, condition.into())
+    }
+
+    fn when_first_row(&mut self) -> FilteredAirBuilder<'_, Self> {
+        self.filtered(ConstraintFilter::FirstRow, self.is_first_row)
+    }
+
+    fn when_last_row(&mut self) -> FilteredAirBuilder<'_, Self> {
+        self.filtered(ConstraintFilter::LastRow, self.is_last_row)
+    }
+
+    fn when_transition(&mut self) -> FilteredAirBuilder<'_, Self> {
+        self.filtered(ConstraintFilter::Transition, self.is_transition)
+    }
+
+    fn when_transition_window(&mut self, size: usize) -> FilteredAirBuilder<'_, Self> {
+        let condition = self.is_transition_window(size);
+        self.filtered(ConstraintFilter::TransitionWindow(size), condition)
     }
 
     fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
-        assert_eq!(
-            x.into(),
-            F::ZERO,
-            "constraints had nonzero value on row {}",
-            self.row_index
-        );
+        self.record(x.into().into(), ConstraintFilter::Unconditional);
     }
 
-    fn assert_eq<I1: Into<Self::Expr>, I2: Into<Self::Expr>>(&mut self, x: I1, y: I2) {
-        let x = x.into();
-        let y = y.into();
-        assert_eq!(
-            x, y,
-            "values didn't match on row {}: {} != {}",
-            self.row_index, x, y
-        );
+    fn assert_zero_when<I: Into<Self::Expr>>(&mut self, condition: Self::Expr, x: I) {
+        self.record((condition * x.into()).into(), self.filter);
     }
 }
 
-impl<F: Field> AirBuilderWithPublicValues for DebugConstraintBuilder<'_, F> {
+impl<F: Field, EF: ExtensionField<F>> AirBuilderWithPublicValues
+    for DebugConstraintBuilder<'_, F, EF>
+{
     type PublicVar = Self::F;
 
     fn public_values(&self) -> &[Self::F] {
@@ -127,6 +353,53 @@ impl<F: Field> AirBuilderWithPublicValues for DebugConstraintBuilder<'_, F> {
     }
 }
 
+impl<F: Field, EF: ExtensionField<F>> PeriodicAirBuilder for DebugConstraintBuilder<'_, F, EF> {
+    type PeriodicVar = Self::F;
+
+    fn periodic_values(&self) -> &[Self::F] {
+        &self.periodic_values
+    }
+}
+
+impl<F: Field, EF: ExtensionField<F>> PairBuilder for DebugConstraintBuilder<'_, F, EF> {
+    fn preprocessed(&self) -> Self::M {
+        self.preprocessed
+    }
+}
+
+impl<F: Field, EF: ExtensionField<F>> ExtensionBuilder for DebugConstraintBuilder<'_, F, EF> {
+    type EF = EF;
+    type ExprEF = EF;
+    type VarEF = EF;
+
+    fn assert_zero_ext<I>(&mut self, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        self.record(x.into(), ConstraintFilter::Unconditional);
+    }
+
+    fn assert_zero_ext_when<I>(&mut self, condition: Self::Expr, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        self.record(x.into() * condition, self.filter);
+    }
+}
+
+impl<'a, F: Field, EF: ExtensionField<F>> AuxBuilder for DebugConstraintBuilder<'a, F, EF> {
+    type MAux = RowMajorMatrixView<'a, EF>;
+    type AuxChallenge = EF;
+
+    fn aux(&self, phase: usize) -> Self::MAux {
+        self.aux[phase]
+    }
+
+    fn aux_challenges(&self, phase: usize) -> &[Self::AuxChallenge] {
+        &self.aux_challenges[phase]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use alloc::vec;
@@ -159,8 +432,8 @@ mod tests {
             let main = builder.main();
 
             for col in 0..W {
-                let a = main.top.get(0, col).unwrap();
-                let b = main.bottom.get(0, col).unwrap();
+                let a = main.get(0, col).unwrap();
+                let b = main.get(1, col).unwrap();
 
                 // New logic: enforce row[i+1] = row[i] + 1, only on transitions
                 builder.when_transition().assert_eq(b, a + F::ONE);
@@ -170,11 +443,68 @@ mod tests {
             let public_values = builder.public_values;
             let mut when_last = builder.when(builder.is_last_row);
             for (i, &pv) in public_values.iter().enumerate().take(W) {
-                when_last.assert_eq(main.top.get(0, i).unwrap(), pv);
+                when_last.assert_eq(main.get(0, i).unwrap(), pv);
             }
         }
     }
 
+    /// A single column Fibonacci sequence, with constraints spanning a window of three rows.
+    #[derive(Debug)]
+    struct FibonacciWindowAir;
+
+    impl<F: Field> BaseAir<F> for FibonacciWindowAir {
+        fn width(&self) -> usize {
+            1
+        }
+
+        fn main_row_offsets(&self) -> Vec<usize> {
+            vec![0, 1, 2]
+        }
+    }
+
+    impl<F: Field> Air<DebugConstraintBuilder<'_, F>> for FibonacciWindowAir {
+        fn eval(&self, builder: &mut DebugConstraintBuilder<'_, F>) {
+            let main = builder.main();
+            let (a, b, c) = (
+                main.get(0, 0).unwrap(),
+                main.get(1, 0).unwrap(),
+                main.get(2, 0).unwrap(),
+            );
+            builder.when_first_row().assert_one(a);
+            builder.when_transition_window(3).assert_eq(c, a + b);
+        }
+    }
+
+    /// A single column which increases by one every three rows, read at row offsets zero and three.
+    #[derive(Debug)]
+    struct StrideAir;
+
+    impl<F: Field> BaseAir<F> for StrideAir {
+        fn width(&self) -> usize {
+            1
+        }
+
+        fn main_row_offsets(&self) -> Vec<usize> {
+            vec![0, 3]
+        }
+    }
+
+    impl<F: Field> Air<DebugConstraintBuilder<'_, F>> for StrideAir {
+        fn eval(&self, builder: &mut DebugConstraintBuilder<'_, F>) {
+            let main = builder.main();
+            let (a, b) = (main.get(0, 0).unwrap(), main.get(1, 0).unwrap());
+            builder.when_transition_window(4).assert_eq(b, a + F::ONE);
+        }
+    }
+
+    fn fibonacci_column(n: usize) -> RowMajorMatrix<BabyBear> {
+        let mut values = vec![BabyBear::ONE, BabyBear::ONE];
+        while values.len() < n {
+            values.push(values[values.len() - 2] + values[values.len() - 1]);
+        }
+        RowMajorMatrix::new_col(values)
+    }
+
     #[test]
     fn test_incremental_rows_with_last_row_check() {
         // Each row = previous + 1, with 4 rows total, 2 columns.
@@ -191,7 +521,7 @@ mod tests {
             BabyBear::new(4), // Row 3 (last)
         ];
         let main = RowMajorMatrix::new(values, 2);
-        check_constraints(&air, &main, &vec![BabyBear::new(4); 2]);
+        check_constraints(&air, &main, None, &[], &[], &[BabyBear::new(4); 2]);
     }
 
     #[test]
@@ -210,7 +540,7 @@ mod tests {
             BabyBear::new(6), // Row 3
         ];
         let main = RowMajorMatrix::new(values, 2);
-        check_constraints(&air, &main, &vec![BabyBear::new(6); 2]);
+        check_constraints(&air, &main, None, &[], &[], &[BabyBear::new(6); 2]);
     }
 
     #[test]
@@ -230,7 +560,14 @@ mod tests {
         ];
         let main = RowMajorMatrix::new(values, 2);
         // Wrong public value on column 1
-        check_constraints(&air, &main, &vec![BabyBear::new(4), BabyBear::new(5)]);
+        check_constraints(
+            &air,
+            &main,
+            None,
+            &[],
+            &[],
+            &[BabyBear::new(4), BabyBear::new(5)],
+        );
     }
 
     #[test]
@@ -244,6 +581,97 @@ mod tests {
             BabyBear::new(77), // Row 0
         ];
         let main = RowMajorMatrix::new(values, 2);
-        check_constraints(&air, &main, &vec![BabyBear::new(99), BabyBear::new(77)]);
+        check_constraints(
+            &air,
+            &main,
+            None,
+            &[],
+            &[],
+            &[BabyBear::new(99), BabyBear::new(77)],
+        );
+    }
+
+    #[test]
+    fn test_find_all_violations() {
+        // Row 2 does not equal row 1 + 1, in both columns.
+        let air = RowLogicAir::<2>;
+        let values = vec![
+            BabyBear::ONE,
+            BabyBear::ONE, // Row 0
+            BabyBear::new(2),
+            BabyBear::new(2), // Row 1
+            BabyBear::new(5),
+            BabyBear::new(5), // Row 2 (wrong)
+            BabyBear::new(6),
+            BabyBear::new(6), // Row 3
+        ];
+        let main = RowMajorMatrix::new(values, 2);
+        let violations =
+            find_constraint_violations(&air, &main, None, &[], &[], &[BabyBear::new(6); 2]);
+        let expected = (0..2)
+            .map(|constraint_index| ConstraintViolation {
+                row: 1,
+                constraint_index,
+                value: BabyBear::new(2),
+                is_first_row: false,
+                is_last_row: false,
+                is_transition: true,
+                filter: ConstraintFilter::Transition,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(violations, expected);
+    }
+
+    #[test]
+    fn test_find_violations_last_row_public_values() {
+        // Both public values are wrong, so both last row constraints fail.
+        let air = RowLogicAir::<2>;
+        let values = (1..=4)
+            .flat_map(|i| [BabyBear::new(i), BabyBear::new(i)])
+            .collect();
+        let main = RowMajorMatrix::new(values, 2);
+        let violations =
+            find_constraint_violations(&air, &main, None, &[], &[], &[BabyBear::new(5); 2]);
+        assert_eq!(violations.len(), 2);
+        for (violation, constraint_index) in violations.iter().zip(2..) {
+            assert_eq!(violation.row, 3);
+            assert_eq!(violation.constraint_index, constraint_index);
+            assert_eq!(violation.value, -BabyBear::ONE);
+            assert!(violation.is_last_row && !violation.is_transition);
+            assert_eq!(violation.filter, ConstraintFilter::Custom);
+        }
+    }
+
+    #[test]
+    fn test_window_of_three_rows() {
+        let air = FibonacciWindowAir;
+        let main = fibonacci_column(8);
+        assert!(find_constraint_violations(&air, &main, None, &[], &[], &[]).is_empty());
+
+        // Only the window starting at row 5 reads the last row.
+        let mut main = fibonacci_column(8);
+        main.values[7] += BabyBear::ONE;
+        let violations = find_constraint_violations(&air, &main, None, &[], &[], &[]);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].row, 5);
+        assert_eq!(violations[0].constraint_index, 1);
+        assert_eq!(violations[0].value, BabyBear::ONE);
+        assert_eq!(violations[0].filter, ConstraintFilter::TransitionWindow(3));
+    }
+
+    #[test]
+    fn test_non_contiguous_row_offsets() {
+        let air = StrideAir;
+        let column = |n: usize| {
+            RowMajorMatrix::new_col((0..n).map(|i| BabyBear::from_usize(i / 3)).collect())
+        };
+        assert!(find_constraint_violations(&air, &column(8), None, &[], &[], &[]).is_empty());
+
+        // Row 6 is read by the window starting at row 3 only.
+        let mut main = column(8);
+        main.values[6] += BabyBear::ONE;
+        let violations = find_constraint_violations(&air, &main, None, &[], &[], &[]);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].row, 3);
     }
 }
@@ -0,0 +1,455 @@
+//! A deduplicated form of an AIR's symbolic constraints, evaluated as a straight-line program.
+//!
+//! [`SymbolicExpression`]s are trees, so a subexpression built twice, or shared between several
+//! constraints, is evaluated once per use when the AIR itself is run on the quotient domain. A
+//! [`ConstraintDag`] hash-conses identical subexpressions into a single node and folds constant
+//! subexpressions, so every distinct operation is performed once per evaluation point.
+
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use hashbrown::HashMap;
+use p3_air::{AirBuilderWithPublicValues, AuxBuilder, PeriodicAirBuilder};
+use p3_field::Field;
+use p3_matrix::Matrix;
+use serde::{Deserialize, Serialize};
+
+use crate::{Entry, LookupBuilder, NUM_LOOKUP_CHALLENGES, SymbolicExpression, SymbolicVariable};
+
+/// A node of a [`ConstraintDag`]. Operands are the indices of earlier nodes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DagNode<F> {
+    Variable(SymbolicVariable<F>),
+    IsFirstRow,
+    IsLastRow,
+    IsTransition,
+    IsTransitionWindow(usize),
+    Constant(F),
+    Add(usize, usize),
+    Sub(usize, usize),
+    Neg(usize),
+    Mul(usize, usize),
+}
+
+/// Where the value of a node is stored while evaluating a [`ConstraintDag`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Slot {
+    /// The node takes base field values.
+    Base(usize),
+    /// The node takes extension field values, as it depends on an extension field variable.
+    Extension(usize),
+}
+
+/// A node with its operands resolved to the slots holding their values, so that evaluation does
+/// not dispatch on the field of each operand. Operations on a base and an extension field value
+/// take the extension field operand first, except for [`Self::BaseSubExt`].
+#[derive(Copy, Clone, Debug)]
+enum Instruction<F> {
+    Leaf(DagNode<F>),
+    BaseAdd(usize, usize),
+    BaseSub(usize, usize),
+    BaseMul(usize, usize),
+    BaseNeg(usize),
+    ExtAdd(usize, usize),
+    ExtSub(usize, usize),
+    ExtMul(usize, usize),
+    ExtNeg(usize),
+    ExtAddBase(usize, usize),
+    ExtSubBase(usize, usize),
+    BaseSubExt(usize, usize),
+    ExtMulBase(usize, usize),
+}
+
+/// The constraints of an AIR as a deduplicated DAG, whose nodes are in topological order.
+///
+/// Extension field constants are not representable, as [`SymbolicAirBuilder`] records them as base
+/// field constants.
+///
+/// [`SymbolicAirBuilder`]: crate::SymbolicAirBuilder
+#[derive(Clone, Debug)]
+pub struct ConstraintDag<F> {
+    nodes: Vec<DagNode<F>>,
+    slots: Vec<Slot>,
+    /// The instruction computing each node.
+    instructions: Vec<Instruction<F>>,
+    num_base_slots: usize,
+    num_extension_slots: usize,
+    /// The number of auxiliary phases referred to by the constraints.
+    num_aux_phases: usize,
+    /// The node of every constraint, in the order they were asserted.
+    constraints: Vec<usize>,
+}
+
+impl<F: Field> ConstraintDag<F> {
+    /// Lower symbolic constraints into a DAG, merging identical subexpressions and folding
+    /// constants.
+    pub fn new(constraints: &[SymbolicExpression<F>]) -> Self {
+        let mut lowering = Lowering {
+            nodes: Vec::new(),
+            node_indices: HashMap::new(),
+            lowered: BTreeMap::new(),
+        };
+        let constraints = constraints
+            .iter()
+            .map(|constraint| lowering.lower(constraint))
+            .collect();
+        Self::from_live_nodes(&lowering.nodes, constraints)
+    }
+
+    /// Build a DAG from the nodes which some constraint depends on, dropping those left unused by
+    /// constant folding.
+    fn from_live_nodes(all_nodes: &[DagNode<F>], mut constraints: Vec<usize>) -> Self {
+        let mut live = vec![false; all_nodes.len()];
+        for &constraint in &constraints {
+            live[constraint] = true;
+        }
+        for (index, node) in all_nodes.iter().enumerate().rev() {
+            if live[index] {
+                match *node {
+                    DagNode::Add(x, y) | DagNode::Sub(x, y) | DagNode::Mul(x, y) => {
+                        live[x] = true;
+                        live[y] = true;
+                    }
+                    DagNode::Neg(x) => live[x] = true,
+                    _ => {}
+                }
+            }
+        }
+
+        let mut dag = Self {
+            nodes: Vec::new(),
+            slots: Vec::new(),
+            instructions: Vec::new(),
+            num_base_slots: 0,
+            num_extension_slots: 0,
+            num_aux_phases: 0,
+            constraints: Vec::new(),
+        };
+        let mut new_indices = vec![usize::MAX; all_nodes.len()];
+        for (index, node) in all_nodes.iter().enumerate() {
+            if !live[index] {
+                continue;
+            }
+            let node = match *node {
+                DagNode::Add(x, y) => DagNode::Add(new_indices[x], new_indices[y]),
+                DagNode::Sub(x, y) => DagNode::Sub(new_indices[x], new_indices[y]),
+                DagNode::Mul(x, y) => DagNode::Mul(new_indices[x], new_indices[y]),
+                DagNode::Neg(x) => DagNode::Neg(new_indices[x]),
+                node => node,
+            };
+            new_indices[index] = dag.push(node);
+        }
+        for constraint in &mut constraints {
+            *constraint = new_indices[*constraint];
+        }
+        dag.constraints = constraints;
+        dag
+    }
+
+    /// Push `node`, assigning it a slot of the field its values lie in.
+    fn push(&mut self, node: DagNode<F>) -> usize {
+        let is_extension = |operand: usize| matches!(self.slots[operand], Slot::Extension(_));
+        let extension = match node {
+            DagNode::Variable(v) => match v.entry {
+                Entry::Preprocessed { .. }
+                | Entry::Main { .. }
+                | Entry::Public
+                | Entry::Periodic => false,
+                Entry::Aux { phase, .. } | Entry::AuxChallenge { phase } => {
+                    self.num_aux_phases = self.num_aux_phases.max(phase + 1);
+                    true
+                }
+                Entry::Permutation { .. } | Entry::Challenge => true,
+            },
+            DagNode::IsFirstRow
+            | DagNode::IsLastRow
+            | DagNode::IsTransition
+            | DagNode::IsTransitionWindow(_)
+            | DagNode::Constant(_) => false,
+            DagNode::Add(x, y) | DagNode::Sub(x, y) | DagNode::Mul(x, y) => {
+                is_extension(x) || is_extension(y)
+            }
+            DagNode::Neg(x) => is_extension(x),
+        };
+        let slot = if extension {
+            self.num_extension_slots += 1;
+            Slot::Extension(self.num_extension_slots - 1)
+        } else {
+            self.num_base_slots += 1;
+            Slot::Base(self.num_base_slots - 1)
+        };
+        let instruction = self.instruction(node);
+        self.nodes.push(node);
+        self.slots.push(slot);
+        self.instructions.push(instruction);
+        self.nodes.len() - 1
+    }
+
+    /// The instruction computing `node`, whose operands have been pushed.
+    fn instruction(&self, node: DagNode<F>) -> Instruction<F> {
+        use Slot::{Base, Extension};
+        match node {
+            DagNode::Add(x, y) => match (self.slots[x], self.slots[y]) {
+                (Base(x), Base(y)) => Instruction::BaseAdd(x, y),
+                (Extension(x), Extension(y)) => Instruction::ExtAdd(x, y),
+                (Extension(x), Base(y)) | (Base(y), Extension(x)) => Instruction::ExtAddBase(x, y),
+            },
+            DagNode::Sub(x, y) => match (self.slots[x], self.slots[y]) {
+                (Base(x), Base(y)) => Instruction::BaseSub(x, y),
+                (Extension(x), Extension(y)) => Instruction::ExtSub(x, y),
+                (Extension(x), Base(y)) => Instruction::ExtSubBase(x, y),
+                (Base(x), Extension(y)) => Instruction::BaseSubExt(x, y),
+            },
+            DagNode::Mul(x, y) => match (self.slots[x], self.slots[y]) {
+                (Base(x), Base(y)) => Instruction::BaseMul(x, y),
+                (Extension(x), Extension(y)) => Instruction::ExtMul(x, y),
+                (Extension(x), Base(y)) | (Base(y), Extension(x)) => Instruction::ExtMulBase(x, y),
+            },
+            DagNode::Neg(x) => match self.slots[x] {
+                Base(x) => Instruction::BaseNeg(x),
+                Extension(x) => Instruction::ExtNeg(x),
+            },
+            leaf => Instruction::Leaf(leaf),
+        }
+    }
+
+    /// The nodes of the DAG, in topological order.
+    pub fn nodes(&self) -> &[DagNode<F>] {
+        &self.nodes
+    }
+
+    /// The node of every constraint, in the order they were asserted.
+    pub fn constraints(&self) -> &[usize] {
+        &self.constraints
+    }
+
+    /// Evaluate the constraints with the values of `builder`, and assert each of them in order.
+    ///
+    /// This has the same effect as evaluating the AIR the DAG was built from, followed by its
+    /// lookup constraints, but performs every distinct operation only once.
+    pub fn eval<AB>(&self, builder: &mut AB)
+    where
+        AB: AirBuilderWithPublicValues<F = F> + LookupBuilder + AuxBuilder + PeriodicAirBuilder,
+    {
+        // Entries are read straight from the builder's views, as each variable is a single node.
+        let main = builder.main();
+        let preprocessed = builder.preprocessed();
+        let permutation = builder.permutation();
+        let aux = (0..self.num_aux_phases)
+            .map(|phase| builder.aux(phase))
+            .collect::<Vec<_>>();
+
+        let mut base = Vec::<AB::Expr>::with_capacity(self.num_base_slots);
+        let mut ext = Vec::<AB::ExprEF>::with_capacity(self.num_extension_slots);
+        for instruction in &self.instructions {
+            match *instruction {
+                Instruction::Leaf(DagNode::Variable(v)) => match v.entry {
+                    Entry::Preprocessed { offset } => {
+                        base.push(entry(&preprocessed, offset, v.index).into());
+                    }
+                    Entry::Main { offset } => base.push(entry(&main, offset, v.index).into()),
+                    Entry::Permutation { offset } => {
+                        ext.push(entry(&permutation, offset, v.index).into());
+                    }
+                    Entry::Aux { phase, offset } => {
+                        ext.push(entry(&aux[phase], offset, v.index).into());
+                    }
+                    Entry::Public => base.push(builder.public_values()[v.index].into()),
+                    Entry::Periodic => base.push(builder.periodic_values()[v.index].into()),
+                    Entry::Challenge => ext.push(if v.index < NUM_LOOKUP_CHALLENGES {
+                        builder.permutation_randomness()[v.index].into()
+                    } else {
+                        builder.cumulative_sum().into()
+                    }),
+                    Entry::AuxChallenge { phase } => {
+                        ext.push(builder.aux_challenges(phase)[v.index].into());
+                    }
+                },
+                Instruction::Leaf(DagNode::IsFirstRow) => base.push(builder.is_first_row()),
+                Instruction::Leaf(DagNode::IsLastRow) => base.push(builder.is_last_row()),
+                Instruction::Leaf(DagNode::IsTransition) => base.push(builder.is_transition()),
+                Instruction::Leaf(DagNode::IsTransitionWindow(size)) => {
+                    base.push(builder.is_transition_window(size));
+                }
+                Instruction::Leaf(DagNode::Constant(c)) => base.push(c.into()),
+                Instruction::Leaf(_) => unreachable!("operations are not leaves"),
+                Instruction::BaseAdd(x, y) => base.push(base[x].clone() + base[y].clone()),
+                Instruction::BaseSub(x, y) => base.push(base[x].clone() - base[y].clone()),
+                Instruction::BaseMul(x, y) => base.push(base[x].clone() * base[y].clone()),
+                Instruction::BaseNeg(x) => base.push(-base[x].clone()),
+                Instruction::ExtAdd(x, y) => ext.push(ext[x].clone() + ext[y].clone()),
+                Instruction::ExtSub(x, y) => ext.push(ext[x].clone() - ext[y].clone()),
+                Instruction::ExtMul(x, y) => ext.push(ext[x].clone() * ext[y].clone()),
+                Instruction::ExtNeg(x) => ext.push(-ext[x].clone()),
+                Instruction::ExtAddBase(x, y) => ext.push(ext[x].clone() + base[y].clone()),
+                Instruction::ExtSubBase(x, y) => ext.push(ext[x].clone() - base[y].clone()),
+                Instruction::BaseSubExt(x, y) => {
+                    ext.push(AB::ExprEF::from(base[x].clone()) - ext[y].clone());
+                }
+                Instruction::ExtMulBase(x, y) => ext.push(ext[x].clone() * base[y].clone()),
+            }
+        }
+
+        for &constraint in &self.constraints {
+            match self.slots[constraint] {
+                Slot::Base(i) => builder.assert_zero(base[i].clone()),
+                Slot::Extension(i) => builder.assert_zero_ext(ext[i].clone()),
+            }
+        }
+    }
+}
+
+/// The entry of `matrix` at `row` and `col`, which a constraint refers to.
+fn entry<T: Clone + Send + Sync, M: Matrix<T>>(matrix: &M, row: usize, col: usize) -> T {
+    matrix
+        .get(row, col)
+        .expect("A constraint refers to an entry outside its matrix")
+}
+
+/// The state of [`ConstraintDag::new`].
+struct Lowering<F> {
+    nodes: Vec<DagNode<F>>,
+    /// The index of every node pushed so far, to merge identical nodes.
+    node_indices: HashMap<DagNode<F>, usize>,
+    /// The node of every expression lowered so far, keyed by address, so that subexpressions
+    /// shared through `Rc`s are only walked once.
+    lowered: BTreeMap<*const SymbolicExpression<F>, usize>,
+}
+
+impl<F: Field> Lowering<F> {
+    fn lower(&mut self, expr: &SymbolicExpression<F>) -> usize {
+        let key = expr as *const SymbolicExpression<F>;
+        if let Some(&index) = self.lowered.get(&key) {
+            return index;
+        }
+        let index = match expr {
+            SymbolicExpression::Variable(v) => self.push(DagNode::Variable(*v)),
+            SymbolicExpression::IsFirstRow => self.push(DagNode::IsFirstRow),
+            SymbolicExpression::IsLastRow => self.push(DagNode::IsLastRow),
+            SymbolicExpression::IsTransition => self.push(DagNode::IsTransition),
+            SymbolicExpression::IsTransitionWindow(size) => {
+                self.push(DagNode::IsTransitionWindow(*size))
+            }
+            SymbolicExpression::Constant(c) => self.push(DagNode::Constant(*c)),
+            SymbolicExpression::Add { x, y, .. } => {
+                let (x, y) = (self.lower(x), self.lower(y));
+                self.add(x, y)
+            }
+            SymbolicExpression::Sub { x, y, .. } => {
+                let (x, y) = (self.lower(x), self.lower(y));
+                self.sub(x, y)
+            }
+            SymbolicExpression::Neg { x, .. } => {
+                let x = self.lower(x);
+                self.neg(x)
+            }
+            SymbolicExpression::Mul { x, y, .. } => {
+                let (x, y) = (self.lower(x), self.lower(y));
+                self.mul(x, y)
+            }
+        };
+        self.lowered.insert(key, index);
+        index
+    }
+
+    fn constant(&self, node: usize) -> Option<F> {
+        match self.nodes[node] {
+            DagNode::Constant(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    fn add(&mut self, x: usize, y: usize) -> usize {
+        match (self.constant(x), self.constant(y)) {
+            (Some(a), Some(b)) => self.push(DagNode::Constant(a + b)),
+            (Some(a), _) if a == F::ZERO => y,
+            (_, Some(b)) if b == F::ZERO => x,
+            // Order the operands of commutative operations, so that `x + y` and `y + x` merge.
+            _ => self.push(DagNode::Add(x.min(y), x.max(y))),
+        }
+    }
+
+    fn sub(&mut self, x: usize, y: usize) -> usize {
+        match (self.constant(x), self.constant(y)) {
+            (Some(a), Some(b)) => self.push(DagNode::Constant(a - b)),
+            (_, Some(b)) if b == F::ZERO => x,
+            (Some(a), _) if a == F::ZERO => self.neg(y),
+            _ if x == y => self.push(DagNode::Constant(F::ZERO)),
+            _ => self.push(DagNode::Sub(x, y)),
+        }
+    }
+
+    fn neg(&mut self, x: usize) -> usize {
+        match self.nodes[x] {
+            DagNode::Constant(c) => self.push(DagNode::Constant(-c)),
+            DagNode::Neg(inner) => inner,
+            _ => self.push(DagNode::Neg(x)),
+        }
+    }
+
+    fn mul(&mut self, x: usize, y: usize) -> usize {
+        match (self.constant(x), self.constant(y)) {
+            (Some(a), Some(b)) => self.push(DagNode::Constant(a * b)),
+            (Some(a), _) | (_, Some(a)) if a == F::ZERO => self.push(DagNode::Constant(F::ZERO)),
+            (Some(a), _) if a == F::ONE => y,
+            (_, Some(b)) if b == F::ONE => x,
+            (Some(a), _) if a == F::NEG_ONE => self.neg(y),
+            (_, Some(b)) if b == F::NEG_ONE => self.neg(x),
+            _ => self.push(DagNode::Mul(x.min(y), x.max(y))),
+        }
+    }
+
+    /// Push `node` unless an identical node exists, returning its index.
+    fn push(&mut self, node: DagNode<F>) -> usize {
+        *self.node_indices.entry(node).or_insert_with(|| {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{Air, AirBuilder, BaseAir};
+    use p3_baby_bear::BabyBear;
+    use p3_field::PrimeCharacteristicRing;
+
+    use super::*;
+    use crate::get_symbolic_constraints;
+
+    /// Columns `[x, y, z]`, with constraints that repeat `x * y` and contain trivial constants.
+    struct RepeatingAir;
+
+    impl<F> BaseAir<F> for RepeatingAir {
+        fn width(&self) -> usize {
+            3
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for RepeatingAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0).expect("Matrix is empty?");
+            let (x, y, z): (AB::Expr, AB::Expr, AB::Expr) =
+                (local[0].into(), local[1].into(), local[2].into());
+            builder.assert_eq(x.clone() * y.clone(), z.clone());
+            builder.assert_eq((y.clone() * x.clone()) * (x.clone() * y.clone()), z.clone());
+            builder.assert_zero((x.clone() + AB::Expr::ZERO) * AB::Expr::ONE - x);
+        }
+    }
+
+    #[test]
+    fn test_merge_and_fold() {
+        let constraints = get_symbolic_constraints::<BabyBear, _>(&RepeatingAir, 0, 0);
+        let dag = ConstraintDag::new(&constraints);
+
+        // The nodes are x, y, x * y, z, x * y - z, (x * y)^2, (x * y)^2 - z and the folded zero,
+        // while the unused constant one is dropped.
+        assert_eq!(dag.nodes().len(), 8);
+        assert_eq!(dag.nodes()[2], DagNode::Mul(0, 1));
+        assert_eq!(dag.nodes()[5], DagNode::Mul(2, 2));
+        assert_eq!(dag.nodes()[7], DagNode::Constant(BabyBear::ZERO));
+        assert_eq!(dag.constraints(), [4, 6, 7]);
+    }
+}
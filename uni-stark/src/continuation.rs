@@ -0,0 +1,332 @@
+//! Proving executions too long for a single trace, by splitting them into segments.
+//!
+//! One logical execution is split into segments of a fixed height, each of which is proven
+//! separately against a [`SegmentAir`]. Consecutive segments share a row: the last row of each
+//! segment is the first row of the next, so every transition of the execution lies within some
+//! segment. The boundary rows of a segment are exposed as public values, which the segment proof
+//! binds to its trace through `when_first_row` and `when_last_row` constraints, and the verifier
+//! checks that the boundary rows of consecutive segments agree.
+//!
+//! Each segment is proven in isolation, so constraints which the wrapped AIR asserts on the first
+//! or last row apply to every segment. Constraints on the initial and final rows of the whole
+//! execution should instead be checked against [`ContinuationProof::initial_row`] and
+//! [`ContinuationProof::final_row`].
+
+use alloc::vec::Vec;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir, BaseAirWithPublicValues};
+use p3_field::Field;
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use tracing::{info_span, instrument};
+
+use crate::{
+    ContinuationProof, PcsError, ProverConstraintFolder, SegmentProof, StarkGenericConfig,
+    SymbolicAirBuilder, Val, VerificationError, VerifierConstraintFolder, prove_with_key, setup,
+    verify_with_key,
+};
+
+/// An AIR whose traces are segments of a longer execution.
+///
+/// The public values of a segment are the public values of the wrapped AIR, which are shared by
+/// every segment, followed by the first row and then the last row of the segment's trace.
+#[derive(Clone, Debug)]
+pub struct SegmentAir<A> {
+    air: A,
+    num_public_values: usize,
+}
+
+impl<A> SegmentAir<A> {
+    /// Wrap `air`, which expects `num_public_values` public values of its own.
+    pub const fn new(air: A, num_public_values: usize) -> Self {
+        Self {
+            air,
+            num_public_values,
+        }
+    }
+
+    /// The wrapped AIR.
+    pub const fn inner(&self) -> &A {
+        &self.air
+    }
+
+    /// The public values of a segment with the given boundary rows.
+    fn segment_public_values<F: Clone>(
+        public_values: &[F],
+        first_row: &[F],
+        last_row: &[F],
+    ) -> Vec<F> {
+        [public_values, first_row, last_row].concat()
+    }
+}
+
+impl<F, A: BaseAir<F>> BaseAir<F> for SegmentAir<A> {
+    fn width(&self) -> usize {
+        self.air.width()
+    }
+}
+
+impl<F, A: BaseAir<F>> BaseAirWithPublicValues<F> for SegmentAir<A> {
+    fn num_public_values(&self) -> usize {
+        self.num_public_values + 2 * self.air.width()
+    }
+}
+
+impl<AB, A> Air<AB> for SegmentAir<A>
+where
+    AB: AirBuilderWithPublicValues,
+    A: Air<AB>,
+{
+    fn eval(&self, builder: &mut AB) {
+        self.air.eval(builder);
+
+        let main = builder.main();
+        let local = main.row_slice(0).expect("The matrix is empty?");
+        let width = self.air.width();
+        let boundaries = &builder.public_values()[self.num_public_values..];
+        let (first_row, last_row): (Vec<AB::Expr>, Vec<AB::Expr>) = (
+            boundaries[..width].iter().map(|&x| x.into()).collect(),
+            boundaries[width..2 * width]
+                .iter()
+                .map(|&x| x.into())
+                .collect(),
+        );
+
+        let mut when_first_row = builder.when_first_row();
+        for (&x, y) in local.iter().zip(first_row) {
+            when_first_row.assert_eq(x, y);
+        }
+        let mut when_last_row = builder.when_last_row();
+        for (&x, y) in local.iter().zip(last_row) {
+            when_last_row.assert_eq(x, y);
+        }
+    }
+}
+
+/// The ways in which a [`ContinuationProof`] may fail to verify.
+#[derive(Debug)]
+pub enum ContinuationError<PcsErr> {
+    /// The proof has no segments.
+    NoSegments,
+    /// The first or last row of a segment does not have the width of the AIR.
+    InvalidBoundaryRows { segment: usize },
+    /// The first row of a segment differs from the last row of the previous segment.
+    BrokenChain { segment: usize },
+    /// A segment has a different height than the first segment.
+    SegmentHeightMismatch { segment: usize },
+    /// The proof of a segment is invalid.
+    InvalidSegment {
+        segment: usize,
+        error: VerificationError<PcsErr>,
+    },
+}
+
+/// Split the trace of an execution into segments of `segment_height` rows, each starting with the
+/// last row of the previous one.
+///
+/// # Panics
+/// Panics if `segment_height` is not a power of two larger than `1`, or if the trace does not
+/// split into whole segments, i.e. its height is not one more than a multiple of
+/// `segment_height - 1`.
+pub fn split_trace<T: Clone + Send + Sync>(
+    trace: &RowMajorMatrix<T>,
+    segment_height: usize,
+) -> Vec<RowMajorMatrix<T>> {
+    assert!(
+        segment_height.is_power_of_two() && segment_height > 1,
+        "segment height must be a power of two larger than 1"
+    );
+    let height = trace.height();
+    let step = segment_height - 1;
+    assert!(
+        height > 1 && (height - 1) % step == 0,
+        "a trace of height {height} does not split into segments of height {segment_height}"
+    );
+    let width = trace.width();
+    (0..height - 1)
+        .step_by(step)
+        .map(|start| {
+            let values = trace.values[start * width..(start + segment_height) * width].to_vec();
+            RowMajorMatrix::new(values, width)
+        })
+        .collect()
+}
+
+/// Prove an execution split into `segments`, e.g. by [`split_trace`], one segment at a time.
+///
+/// The segments are consumed in order, so they may be generated lazily when the whole trace does
+/// not fit in memory. Every segment must have the same height, and must start with the last row
+/// of the previous segment.
+///
+/// # Panics
+/// Panics if there are no segments, if the segments have different heights or do not form a
+/// chain, or if the wrapped AIR has a preprocessed trace, periodic columns, extra main row offsets,
+/// lookups or auxiliary phases, none of which is supported by continuations yet.
+#[instrument(skip_all)]
+#[allow(clippy::multiple_bound_locations)] // cfg not supported in where clauses?
+pub fn prove_continuation<
+    SC,
+    #[cfg(debug_assertions)] A: for<'a> Air<crate::check_constraints::DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>,
+    #[cfg(not(debug_assertions))] A,
+    I,
+>(
+    config: &SC,
+    air: &SegmentAir<A>,
+    segments: I,
+    public_values: &[Val<SC>],
+) -> ContinuationProof<SC>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<ProverConstraintFolder<'a, SC>>,
+    I: IntoIterator<Item = RowMajorMatrix<Val<SC>>>,
+{
+    assert_supported::<Val<SC>, A>(&air.air);
+    assert_eq!(
+        public_values.len(),
+        air.num_public_values,
+        "number of public values does not match the AIR"
+    );
+    let (proving_key, _) = setup(config, air, BaseAirWithPublicValues::num_public_values(air));
+
+    let mut proofs: Vec<SegmentProof<SC>> = Vec::new();
+    let mut segment_height = None;
+    for (segment, trace) in segments.into_iter().enumerate() {
+        assert_eq!(
+            *segment_height.get_or_insert(trace.height()),
+            trace.height(),
+            "segment {segment} has a different height than the first segment"
+        );
+        let first_row: Vec<_> = trace
+            .first_row()
+            .expect("empty segment")
+            .into_iter()
+            .collect();
+        let last_row: Vec<_> = trace
+            .last_row()
+            .expect("empty segment")
+            .into_iter()
+            .collect();
+        if let Some(previous) = proofs.last() {
+            assert_eq!(
+                first_row, previous.last_row,
+                "segment {segment} does not start with the last row of the previous segment"
+            );
+        }
+        let segment_public_values =
+            SegmentAir::<A>::segment_public_values(public_values, &first_row, &last_row);
+        let proof = info_span!("prove segment", segment)
+            .in_scope(|| prove_with_key(config, &proving_key, air, trace, &segment_public_values));
+        proofs.push(SegmentProof {
+            first_row,
+            last_row,
+            proof,
+        });
+    }
+    assert!(!proofs.is_empty(), "cannot prove an empty execution");
+
+    ContinuationProof { segments: proofs }
+}
+
+/// Verify a proof of an execution split into segments, checking every segment proof and that the
+/// segments form a chain.
+///
+/// This does not check the initial and final rows of the execution, which callers should compare
+/// against [`ContinuationProof::initial_row`] and [`ContinuationProof::final_row`] as needed.
+#[instrument(skip_all)]
+pub fn verify_continuation<SC, A>(
+    config: &SC,
+    air: &SegmentAir<A>,
+    proof: &ContinuationProof<SC>,
+    public_values: &[Val<SC>],
+) -> Result<(), ContinuationError<PcsError<SC>>>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+{
+    assert_supported::<Val<SC>, A>(&air.air);
+    if proof.segments.is_empty() {
+        return Err(ContinuationError::NoSegments);
+    }
+    let (_, verifying_key) = setup(config, air, BaseAirWithPublicValues::num_public_values(air));
+
+    for (segment, segment_proof) in proof.segments.iter().enumerate() {
+        // The boundary rows are only checked as one concatenation of public values, so without
+        // this a prover could move the split between them and make the chain check vacuous.
+        if segment_proof.first_row.len() != air.width()
+            || segment_proof.last_row.len() != air.width()
+        {
+            return Err(ContinuationError::InvalidBoundaryRows { segment });
+        }
+        if segment > 0 && segment_proof.first_row != proof.segments[segment - 1].last_row {
+            return Err(ContinuationError::BrokenChain { segment });
+        }
+        if segment_proof.proof.degree_bits != proof.segments[0].proof.degree_bits {
+            return Err(ContinuationError::SegmentHeightMismatch { segment });
+        }
+        let segment_public_values = SegmentAir::<A>::segment_public_values(
+            public_values,
+            &segment_proof.first_row,
+            &segment_proof.last_row,
+        );
+        verify_with_key(
+            config,
+            &verifying_key,
+            air,
+            &segment_proof.proof,
+            &segment_public_values,
+        )
+        .map_err(|error| ContinuationError::InvalidSegment { segment, error })?;
+    }
+
+    Ok(())
+}
+
+/// Check that `air` only uses features whose meaning is preserved when its trace is segmented.
+fn assert_supported<F: Field, A: BaseAir<F>>(air: &A) {
+    assert!(
+        air.preprocessed_trace().is_none(),
+        "continuations do not support preprocessed traces yet"
+    );
+    assert!(
+        air.periodic_columns().is_empty(),
+        "continuations do not support periodic columns yet"
+    );
+    assert_eq!(
+        air.main_row_offsets(),
+        [0, 1],
+        "continuations do not support extra main row offsets yet"
+    );
+    assert!(
+        air.sends().is_empty() && air.receives().is_empty(),
+        "continuations do not support lookups yet"
+    );
+    assert!(
+        air.aux_phases().is_empty(),
+        "continuations do not support auxiliary phases yet"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_split_trace() {
+        let trace = RowMajorMatrix::new((0..26).collect(), 2);
+        let segments = split_trace(&trace, 4);
+        assert_eq!(segments.len(), 4);
+        for (i, segment) in segments.iter().enumerate() {
+            let start = 6 * i;
+            assert_eq!(segment.values, (start..start + 8).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "a trace of height 12 does not split into segments of height 4")]
+    fn test_split_trace_partial_segment() {
+        let trace = RowMajorMatrix::new_col(vec![0; 12]);
+        split_trace(&trace, 4);
+    }
+}
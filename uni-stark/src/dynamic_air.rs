@@ -0,0 +1,427 @@
+//! AIRs defined at runtime, by interpreting a serializable description of their constraints.
+
+use alloc::vec::Vec;
+
+use p3_air::{
+    Air, AirBuilderWithPublicValues, BaseAir, BaseAirWithPublicValues, Interaction, PairBuilder,
+    PairCol, PeriodicAirBuilder,
+};
+use p3_field::Field;
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use serde::{Deserialize, Serialize};
+
+use crate::symbolic_builder::valid_main_row_offsets;
+use crate::{ConstraintDag, DagNode, Entry, SymbolicAirBuilder};
+
+/// A serializable description of an AIR, from which a [`DynamicAir`] is built.
+///
+/// The constraints are given as a DAG in the same form as [`ConstraintDag`]: each node may only
+/// refer to earlier nodes, and each constraint is the index of the node which must vanish.
+/// Variables may refer to the preprocessed columns of the current and next rows, to the main
+/// columns of the rows at `main_row_offsets`, to periodic columns and to public values. Lookups
+/// are described by `sends` and `receives` rather than by constraints, and auxiliary trace phases
+/// are not supported, as they need code to generate.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct AirDescription<F: Field> {
+    /// The number of main columns.
+    pub width: usize,
+    /// The offsets of the main trace rows the constraints read, as in
+    /// [`BaseAir::main_row_offsets`].
+    pub main_row_offsets: Vec<usize>,
+    /// The number of public values.
+    pub num_public_values: usize,
+    /// The periodic columns, each given by its values over a single period, as in
+    /// [`BaseAir::periodic_columns`].
+    pub periodic_columns: Vec<Vec<F>>,
+    /// The preprocessed trace, if the AIR has one.
+    pub preprocessed_trace: Option<RowMajorMatrix<F>>,
+    /// The interactions sent on a bus by every row.
+    pub sends: Vec<Interaction<F>>,
+    /// The interactions received from a bus by every row.
+    pub receives: Vec<Interaction<F>>,
+    /// The nodes of the constraint DAG, in topological order.
+    pub nodes: Vec<DagNode<F>>,
+    /// The node of every constraint.
+    pub constraints: Vec<usize>,
+}
+
+impl<F: Field> AirDescription<F> {
+    /// Describe a compiled AIR, e.g. to serialize it for a [`DynamicAir`] elsewhere.
+    ///
+    /// # Panics
+    /// This function panics if the AIR has auxiliary trace phases.
+    pub fn from_air<A>(air: &A, num_public_values: usize) -> Self
+    where
+        A: Air<SymbolicAirBuilder<F>>,
+    {
+        assert!(
+            air.aux_phases().is_empty(),
+            "auxiliary phases cannot be described"
+        );
+        let preprocessed_trace = air.preprocessed_trace();
+        let preprocessed_width = preprocessed_trace.as_ref().map_or(0, Matrix::width);
+        // Evaluate the AIR alone, as the lookup constraints are implied by the interactions.
+        let main_row_offsets = air.main_row_offsets();
+        let periodic_columns = air.periodic_columns();
+        let mut builder = SymbolicAirBuilder::new(
+            preprocessed_width,
+            air.width(),
+            &main_row_offsets,
+            num_public_values,
+            periodic_columns.len(),
+            0,
+            &[],
+        );
+        air.eval(&mut builder);
+        let dag = ConstraintDag::new(&builder.constraints());
+        Self {
+            width: air.width(),
+            main_row_offsets,
+            num_public_values,
+            periodic_columns,
+            preprocessed_trace,
+            sends: air.sends(),
+            receives: air.receives(),
+            nodes: dag.nodes().to_vec(),
+            constraints: dag.constraints().to_vec(),
+        }
+    }
+}
+
+/// The ways in which an [`AirDescription`] may be malformed.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DynamicAirError {
+    /// A variable refers to an entry other than a preprocessed column, a main column, a periodic
+    /// column or a public value.
+    UnsupportedEntry { node: usize },
+    /// A variable refers to a row, column or public value which does not exist.
+    VariableOutOfBounds { node: usize },
+    /// The main row offsets are not strictly increasing from `0, 1`.
+    InvalidRowOffsets,
+    /// The period of a periodic column is not a power of two.
+    InvalidPeriodicColumn { column: usize },
+    /// A window selector is smaller than `3` rows or spans more rows than the main row offsets.
+    InvalidWindow { node: usize },
+    /// An operand does not refer to an earlier node.
+    InvalidOperand { node: usize },
+    /// A constraint does not refer to a node.
+    InvalidConstraint { constraint: usize },
+    /// An interaction refers to a column which does not exist. Interactions are numbered through
+    /// `sends` and then `receives`.
+    InvalidInteraction { interaction: usize },
+}
+
+/// An AIR whose constraints are interpreted from an [`AirDescription`] at runtime.
+#[derive(Clone, Debug)]
+pub struct DynamicAir<F: Field> {
+    description: AirDescription<F>,
+}
+
+impl<F: Field> DynamicAir<F> {
+    /// Check that `description` is well formed, and build an AIR from it.
+    pub fn new(description: AirDescription<F>) -> Result<Self, DynamicAirError> {
+        let preprocessed_width = description
+            .preprocessed_trace
+            .as_ref()
+            .map_or(0, Matrix::width);
+        if !valid_main_row_offsets(&description.main_row_offsets) {
+            return Err(DynamicAirError::InvalidRowOffsets);
+        }
+        if let Some(column) = description
+            .periodic_columns
+            .iter()
+            .position(|column| !column.len().is_power_of_two())
+        {
+            return Err(DynamicAirError::InvalidPeriodicColumn { column });
+        }
+        let window_size = description.main_row_offsets.last().unwrap() + 1;
+        for (node, dag_node) in description.nodes.iter().enumerate() {
+            match *dag_node {
+                DagNode::Variable(v) => {
+                    let in_bounds = match v.entry {
+                        Entry::Preprocessed { offset } => {
+                            offset <= 1 && v.index < preprocessed_width
+                        }
+                        Entry::Main { offset } => {
+                            offset < description.main_row_offsets.len()
+                                && v.index < description.width
+                        }
+                        Entry::Public => v.index < description.num_public_values,
+                        Entry::Periodic => v.index < description.periodic_columns.len(),
+                        _ => return Err(DynamicAirError::UnsupportedEntry { node }),
+                    };
+                    if !in_bounds {
+                        return Err(DynamicAirError::VariableOutOfBounds { node });
+                    }
+                }
+                DagNode::Add(x, y) | DagNode::Sub(x, y) | DagNode::Mul(x, y) => {
+                    if x >= node || y >= node {
+                        return Err(DynamicAirError::InvalidOperand { node });
+                    }
+                }
+                DagNode::Neg(x) => {
+                    if x >= node {
+                        return Err(DynamicAirError::InvalidOperand { node });
+                    }
+                }
+                DagNode::IsTransitionWindow(size) => {
+                    if size <= 2 || size > window_size {
+                        return Err(DynamicAirError::InvalidWindow { node });
+                    }
+                }
+                DagNode::IsFirstRow
+                | DagNode::IsLastRow
+                | DagNode::IsTransition
+                | DagNode::Constant(_) => {}
+            }
+        }
+        if let Some(constraint) = description
+            .constraints
+            .iter()
+            .position(|&node| node >= description.nodes.len())
+        {
+            return Err(DynamicAirError::InvalidConstraint { constraint });
+        }
+        let column_in_bounds = |&(column, _): &(PairCol, F)| match column {
+            PairCol::Preprocessed(index) => index < preprocessed_width,
+            PairCol::Main(index) => index < description.width,
+        };
+        if let Some(interaction) = description
+            .sends
+            .iter()
+            .chain(&description.receives)
+            .position(|interaction| {
+                !interaction
+                    .values
+                    .iter()
+                    .chain([&interaction.multiplicity])
+                    .all(|column| column.column_weights().iter().all(column_in_bounds))
+            })
+        {
+            return Err(DynamicAirError::InvalidInteraction { interaction });
+        }
+        Ok(Self { description })
+    }
+
+    /// The description this AIR was built from.
+    pub const fn description(&self) -> &AirDescription<F> {
+        &self.description
+    }
+}
+
+impl<F: Field> BaseAir<F> for DynamicAir<F> {
+    fn width(&self) -> usize {
+        self.description.width
+    }
+
+    fn main_row_offsets(&self) -> Vec<usize> {
+        self.description.main_row_offsets.clone()
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<F>> {
+        self.description.periodic_columns.clone()
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        self.description.preprocessed_trace.clone()
+    }
+
+    fn sends(&self) -> Vec<Interaction<F>> {
+        self.description.sends.clone()
+    }
+
+    fn receives(&self) -> Vec<Interaction<F>> {
+        self.description.receives.clone()
+    }
+}
+
+impl<F: Field> BaseAirWithPublicValues<F> for DynamicAir<F> {
+    fn num_public_values(&self) -> usize {
+        self.description.num_public_values
+    }
+}
+
+impl<F, AB> Air<AB> for DynamicAir<F>
+where
+    F: Field,
+    AB: AirBuilderWithPublicValues<F = F> + PairBuilder + PeriodicAirBuilder,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = window(&builder.main());
+        let preprocessed = window(&builder.preprocessed());
+
+        let mut values = Vec::<AB::Expr>::with_capacity(self.description.nodes.len());
+        for node in &self.description.nodes {
+            let value = match *node {
+                DagNode::Variable(v) => match v.entry {
+                    Entry::Preprocessed { offset } => preprocessed[offset][v.index].into(),
+                    Entry::Main { offset } => main[offset][v.index].into(),
+                    Entry::Public => builder.public_values()[v.index].into(),
+                    Entry::Periodic => builder.periodic_values()[v.index].into(),
+                    _ => unreachable!("entries are checked in DynamicAir::new"),
+                },
+                DagNode::IsFirstRow => builder.is_first_row(),
+                DagNode::IsLastRow => builder.is_last_row(),
+                DagNode::IsTransition => builder.is_transition(),
+                DagNode::IsTransitionWindow(size) => builder.is_transition_window(size),
+                DagNode::Constant(c) => c.into(),
+                DagNode::Add(x, y) => values[x].clone() + values[y].clone(),
+                DagNode::Sub(x, y) => values[x].clone() - values[y].clone(),
+                DagNode::Neg(x) => -values[x].clone(),
+                DagNode::Mul(x, y) => values[x].clone() * values[y].clone(),
+            };
+            values.push(value);
+        }
+
+        for &constraint in &self.description.constraints {
+            builder.assert_zero(values[constraint].clone());
+        }
+    }
+}
+
+/// The rows of `matrix`, padded with empty rows to at least the local and next rows.
+fn window<T: Clone + Send + Sync, M: Matrix<T>>(matrix: &M) -> Vec<Vec<T>> {
+    (0..matrix.height().max(2))
+        .map(|r| {
+            matrix
+                .row_slice(r)
+                .map(|row| row.to_vec())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use p3_air::VirtualPairCol;
+    use p3_baby_bear::BabyBear;
+    use p3_field::PrimeCharacteristicRing;
+
+    use super::*;
+    use crate::SymbolicVariable;
+
+    fn description(
+        nodes: Vec<DagNode<BabyBear>>,
+        constraints: Vec<usize>,
+    ) -> AirDescription<BabyBear> {
+        AirDescription {
+            width: 2,
+            main_row_offsets: vec![0, 1, 3],
+            num_public_values: 1,
+            periodic_columns: vec![vec![BabyBear::ONE, BabyBear::ZERO]],
+            preprocessed_trace: None,
+            sends: vec![],
+            receives: vec![],
+            nodes,
+            constraints,
+        }
+    }
+
+    fn variable(entry: Entry, index: usize) -> DagNode<BabyBear> {
+        DagNode::Variable(SymbolicVariable::new(entry, index))
+    }
+
+    #[test]
+    fn test_valid_description() {
+        let nodes = vec![
+            variable(Entry::Main { offset: 1 }, 1),
+            variable(Entry::Public, 0),
+            DagNode::Sub(0, 1),
+            DagNode::IsLastRow,
+            DagNode::Mul(2, 3),
+            variable(Entry::Main { offset: 2 }, 0),
+            DagNode::IsTransitionWindow(4),
+            DagNode::Mul(5, 6),
+            variable(Entry::Periodic, 0),
+            DagNode::Mul(0, 8),
+        ];
+        assert!(DynamicAir::new(description(nodes, vec![4, 7, 9])).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_descriptions() {
+        let main = variable(Entry::Main { offset: 0 }, 0);
+        let cases = [
+            (
+                vec![variable(Entry::Permutation { offset: 0 }, 0)],
+                vec![0],
+                DynamicAirError::UnsupportedEntry { node: 0 },
+            ),
+            (
+                vec![main, variable(Entry::Main { offset: 0 }, 2)],
+                vec![1],
+                DynamicAirError::VariableOutOfBounds { node: 1 },
+            ),
+            (
+                vec![variable(Entry::Main { offset: 3 }, 0)],
+                vec![0],
+                DynamicAirError::VariableOutOfBounds { node: 0 },
+            ),
+            (
+                vec![variable(Entry::Periodic, 1)],
+                vec![0],
+                DynamicAirError::VariableOutOfBounds { node: 0 },
+            ),
+            (
+                vec![main, DagNode::IsTransitionWindow(5)],
+                vec![1],
+                DynamicAirError::InvalidWindow { node: 1 },
+            ),
+            (
+                vec![variable(Entry::Preprocessed { offset: 0 }, 0)],
+                vec![0],
+                DynamicAirError::VariableOutOfBounds { node: 0 },
+            ),
+            (
+                vec![main, DagNode::Add(0, 1)],
+                vec![1],
+                DynamicAirError::InvalidOperand { node: 1 },
+            ),
+            (
+                vec![main, DagNode::Constant(BabyBear::ONE), DagNode::Sub(0, 1)],
+                vec![2, 3],
+                DynamicAirError::InvalidConstraint { constraint: 1 },
+            ),
+        ];
+        for (nodes, constraints, error) in cases {
+            assert_eq!(
+                DynamicAir::new(description(nodes, constraints)).unwrap_err(),
+                error
+            );
+        }
+
+        let mut invalid_offsets = description(vec![], vec![]);
+        invalid_offsets.main_row_offsets = vec![0, 2];
+        assert_eq!(
+            DynamicAir::new(invalid_offsets).unwrap_err(),
+            DynamicAirError::InvalidRowOffsets
+        );
+
+        let mut invalid_period = description(vec![], vec![]);
+        invalid_period.periodic_columns.push(vec![BabyBear::ONE; 3]);
+        assert_eq!(
+            DynamicAir::new(invalid_period).unwrap_err(),
+            DynamicAirError::InvalidPeriodicColumn { column: 1 }
+        );
+
+        let mut invalid_send = description(vec![], vec![]);
+        invalid_send.sends.push(Interaction::new(
+            0,
+            vec![VirtualPairCol::single_main(0)],
+            VirtualPairCol::ONE,
+        ));
+        invalid_send.receives.push(Interaction::new(
+            0,
+            vec![VirtualPairCol::single_main(1)],
+            VirtualPairCol::single_preprocessed(0),
+        ));
+        assert_eq!(
+            DynamicAir::new(invalid_send).unwrap_err(),
+            DynamicAirError::InvalidInteraction { interaction: 1 }
+        );
+    }
+}
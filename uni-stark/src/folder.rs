@@ -1,19 +1,33 @@
 use alloc::vec::Vec;
 
-use p3_air::{AirBuilder, AirBuilderWithPublicValues};
+use p3_air::{
+    AirBuilder, AirBuilderWithPublicValues, AuxBuilder, ExtensionBuilder, PairBuilder,
+    PeriodicAirBuilder, PermutationAirBuilder,
+};
 use p3_field::{BasedVectorSpace, PackedField};
 use p3_matrix::dense::RowMajorMatrixView;
 use p3_matrix::stack::VerticalPair;
 
-use crate::{PackedChallenge, PackedVal, StarkGenericConfig, Val};
+use crate::{LookupBuilder, PackedChallenge, PackedVal, StarkGenericConfig, Val};
 
 #[derive(Debug)]
 pub struct ProverConstraintFolder<'a, SC: StarkGenericConfig> {
     pub main: RowMajorMatrixView<'a, PackedVal<SC>>,
+    pub preprocessed: RowMajorMatrixView<'a, PackedVal<SC>>,
+    pub permutation: RowMajorMatrixView<'a, PackedChallenge<SC>>,
+    pub permutation_randomness: &'a [SC::Challenge],
+    pub cumulative_sum: SC::Challenge,
+    pub aux: Vec<RowMajorMatrixView<'a, PackedChallenge<SC>>>,
+    pub aux_challenges: &'a [Vec<SC::Challenge>],
     pub public_values: &'a Vec<Val<SC>>,
+    /// The values of the AIR's periodic columns.
+    pub periodic_values: Vec<PackedVal<SC>>,
     pub is_first_row: PackedVal<SC>,
     pub is_last_row: PackedVal<SC>,
     pub is_transition: PackedVal<SC>,
+    /// The selectors of transition windows of sizes `3, 4, ...`, up to the window spanned by the
+    /// AIR's main row offsets.
+    pub is_transition_windows: Vec<PackedVal<SC>>,
     pub alpha_powers: &'a [SC::Challenge],
     pub decomposed_alpha_powers: &'a [Vec<Val<SC>>],
     pub accumulator: PackedChallenge<SC>,
@@ -24,11 +38,22 @@ type ViewPair<'a, T> = VerticalPair<RowMajorMatrixView<'a, T>, RowMajorMatrixVie
 
 #[derive(Debug)]
 pub struct VerifierConstraintFolder<'a, SC: StarkGenericConfig> {
-    pub main: ViewPair<'a, SC::Challenge>,
+    pub main: RowMajorMatrixView<'a, SC::Challenge>,
+    pub preprocessed: RowMajorMatrixView<'a, SC::Challenge>,
+    pub permutation: ViewPair<'a, SC::Challenge>,
+    pub permutation_randomness: &'a [SC::Challenge],
+    pub cumulative_sum: SC::Challenge,
+    pub aux: Vec<ViewPair<'a, SC::Challenge>>,
+    pub aux_challenges: &'a [Vec<SC::Challenge>],
     pub public_values: &'a Vec<Val<SC>>,
+    /// The values of the AIR's periodic columns.
+    pub periodic_values: Vec<SC::Challenge>,
     pub is_first_row: SC::Challenge,
     pub is_last_row: SC::Challenge,
     pub is_transition: SC::Challenge,
+    /// The selectors of transition windows of sizes `3, 4, ...`, up to the window spanned by the
+    /// AIR's main row offsets.
+    pub is_transition_windows: Vec<SC::Challenge>,
     pub alpha: SC::Challenge,
     pub accumulator: SC::Challenge,
 }
@@ -55,13 +80,17 @@ impl<'a, SC: StarkGenericConfig> AirBuilder for ProverConstraintFolder<'a, SC> {
     }
 
     /// # Panics
-    /// This function panics if `size` is not `2`.
+    /// This function panics if `size` is less than `2` or larger than the window spanned by the
+    /// AIR's main row offsets.
     #[inline]
     fn is_transition_window(&self, size: usize) -> Self::Expr {
         if size == 2 {
             self.is_transition
         } else {
-            panic!("uni-stark only supports a window size of 2")
+            *size
+                .checked_sub(3)
+                .and_then(|i| self.is_transition_windows.get(i))
+                .expect("window size is not supported by the AIR's main row offsets")
         }
     }
 
@@ -94,11 +123,81 @@ impl<SC: StarkGenericConfig> AirBuilderWithPublicValues for ProverConstraintFold
     }
 }
 
+impl<SC: StarkGenericConfig> PeriodicAirBuilder for ProverConstraintFolder<'_, SC> {
+    type PeriodicVar = PackedVal<SC>;
+
+    #[inline]
+    fn periodic_values(&self) -> &[Self::PeriodicVar] {
+        &self.periodic_values
+    }
+}
+
+impl<SC: StarkGenericConfig> PairBuilder for ProverConstraintFolder<'_, SC> {
+    #[inline]
+    fn preprocessed(&self) -> Self::M {
+        self.preprocessed
+    }
+}
+
+impl<SC: StarkGenericConfig> ExtensionBuilder for ProverConstraintFolder<'_, SC> {
+    type EF = SC::Challenge;
+    type ExprEF = PackedChallenge<SC>;
+    type VarEF = PackedChallenge<SC>;
+
+    #[inline]
+    fn assert_zero_ext<I>(&mut self, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        let x: PackedChallenge<SC> = x.into();
+        let alpha_power = self.alpha_powers[self.constraint_index];
+        self.accumulator += x * alpha_power;
+        self.constraint_index += 1;
+    }
+}
+
+impl<'a, SC: StarkGenericConfig> PermutationAirBuilder for ProverConstraintFolder<'a, SC> {
+    type MP = RowMajorMatrixView<'a, PackedChallenge<SC>>;
+    type RandomVar = SC::Challenge;
+
+    #[inline]
+    fn permutation(&self) -> Self::MP {
+        self.permutation
+    }
+
+    #[inline]
+    fn permutation_randomness(&self) -> &[Self::RandomVar] {
+        self.permutation_randomness
+    }
+}
+
+impl<SC: StarkGenericConfig> LookupBuilder for ProverConstraintFolder<'_, SC> {
+    #[inline]
+    fn cumulative_sum(&self) -> Self::RandomVar {
+        self.cumulative_sum
+    }
+}
+
+impl<'a, SC: StarkGenericConfig> AuxBuilder for ProverConstraintFolder<'a, SC> {
+    type MAux = RowMajorMatrixView<'a, PackedChallenge<SC>>;
+    type AuxChallenge = SC::Challenge;
+
+    #[inline]
+    fn aux(&self, phase: usize) -> Self::MAux {
+        self.aux[phase]
+    }
+
+    #[inline]
+    fn aux_challenges(&self, phase: usize) -> &[Self::AuxChallenge] {
+        &self.aux_challenges[phase]
+    }
+}
+
 impl<'a, SC: StarkGenericConfig> AirBuilder for VerifierConstraintFolder<'a, SC> {
     type F = Val<SC>;
     type Expr = SC::Challenge;
     type Var = SC::Challenge;
-    type M = ViewPair<'a, SC::Challenge>;
+    type M = RowMajorMatrixView<'a, SC::Challenge>;
 
     fn main(&self) -> Self::M {
         self.main
@@ -113,12 +212,16 @@ impl<'a, SC: StarkGenericConfig> AirBuilder for VerifierConstraintFolder<'a, SC>
     }
 
     /// # Panics
-    /// This function panics if `size` is not `2`.
+    /// This function panics if `size` is less than `2` or larger than the window spanned by the
+    /// AIR's main row offsets.
     fn is_transition_window(&self, size: usize) -> Self::Expr {
         if size == 2 {
             self.is_transition
         } else {
-            panic!("uni-stark only supports a window size of 2")
+            *size
+                .checked_sub(3)
+                .and_then(|i| self.is_transition_windows.get(i))
+                .expect("window size is not supported by the AIR's main row offsets")
         }
     }
 
@@ -136,3 +239,62 @@ impl<SC: StarkGenericConfig> AirBuilderWithPublicValues for VerifierConstraintFo
         self.public_values
     }
 }
+
+impl<SC: StarkGenericConfig> PeriodicAirBuilder for VerifierConstraintFolder<'_, SC> {
+    type PeriodicVar = SC::Challenge;
+
+    fn periodic_values(&self) -> &[Self::PeriodicVar] {
+        &self.periodic_values
+    }
+}
+
+impl<SC: StarkGenericConfig> PairBuilder for VerifierConstraintFolder<'_, SC> {
+    fn preprocessed(&self) -> Self::M {
+        self.preprocessed
+    }
+}
+
+impl<SC: StarkGenericConfig> ExtensionBuilder for VerifierConstraintFolder<'_, SC> {
+    type EF = SC::Challenge;
+    type ExprEF = SC::Challenge;
+    type VarEF = SC::Challenge;
+
+    fn assert_zero_ext<I>(&mut self, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        self.assert_zero(x);
+    }
+}
+
+impl<'a, SC: StarkGenericConfig> PermutationAirBuilder for VerifierConstraintFolder<'a, SC> {
+    type MP = ViewPair<'a, SC::Challenge>;
+    type RandomVar = SC::Challenge;
+
+    fn permutation(&self) -> Self::MP {
+        self.permutation
+    }
+
+    fn permutation_randomness(&self) -> &[Self::RandomVar] {
+        self.permutation_randomness
+    }
+}
+
+impl<SC: StarkGenericConfig> LookupBuilder for VerifierConstraintFolder<'_, SC> {
+    fn cumulative_sum(&self) -> Self::RandomVar {
+        self.cumulative_sum
+    }
+}
+
+impl<'a, SC: StarkGenericConfig> AuxBuilder for VerifierConstraintFolder<'a, SC> {
+    type MAux = ViewPair<'a, SC::Challenge>;
+    type AuxChallenge = SC::Challenge;
+
+    fn aux(&self, phase: usize) -> Self::MAux {
+        self.aux[phase]
+    }
+
+    fn aux_challenges(&self, phase: usize) -> &[Self::AuxChallenge] {
+        &self.aux_challenges[phase]
+    }
+}
@@ -0,0 +1,372 @@
+//! Selecting FRI parameters which reach a target security level.
+//!
+//! Rather than hand-tuning the FRI parameters of a configuration, [`select_fri_parameters`]
+//! searches over the blowup, the number of queries, the proof of work and the length of the final
+//! polynomial, keeping the candidates which reach the target security and are not beaten on both
+//! proof size and prover time by another candidate. The chosen candidate can then be turned into
+//! a [`FriConfig`] with [`FriCandidate::fri_config`].
+
+use alloc::vec::Vec;
+use core::ops::RangeInclusive;
+
+use p3_commit::FriParameters;
+use p3_fri::FriConfig;
+
+use crate::soundness::{MAX_JOHNSON_M, exp2, log2};
+use crate::{ProximityRegime, SoundnessParameters};
+
+/// How the security of FRI parameters is measured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundnessModel {
+    /// The [ethSTARK](https://eprint.iacr.org/2021/582) conjecture, as in
+    /// `FriConfig::conjectured_soundness_bits`, capped by the size of the challenge field and
+    /// the collision resistance of the Merkle tree hash.
+    Conjectured,
+    /// The proven bound of [`SoundnessParameters::best_report`].
+    Proven,
+}
+
+/// The FRI parameters [`select_fri_parameters`] searches over.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FriSearchSpace {
+    /// The candidate values of `log_blowup`. A `log_blowup` of `0` gives no security and is
+    /// skipped.
+    pub log_blowups: RangeInclusive<usize>,
+    /// The largest number of bits of proof of work.
+    pub max_proof_of_work_bits: usize,
+    /// The largest `log_final_poly_len`.
+    pub max_log_final_poly_len: usize,
+}
+
+impl Default for FriSearchSpace {
+    fn default() -> Self {
+        Self {
+            log_blowups: 1..=4,
+            max_proof_of_work_bits: 24,
+            max_log_final_poly_len: 5,
+        }
+    }
+}
+
+/// FRI parameters reaching a target security level, along with estimates of their costs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FriCandidate {
+    /// The selected parameters, with the fewest queries reaching the target.
+    pub parameters: FriParameters,
+    /// The bits of security of the parameters, in the model they were selected in.
+    pub security_bits: f64,
+    /// A rough estimate of the size of a proof, in bits.
+    ///
+    /// This counts every batched polynomial as a single base field element in the opening of a
+    /// query and assumes a single input commitment, which is enough to compare candidates.
+    pub proof_size_bits: f64,
+    /// A rough estimate of the work of the prover, in field elements encoded and hashed plus the
+    /// hashes spent grinding the proof of work.
+    pub prover_cost: f64,
+}
+
+impl FriCandidate {
+    /// A FRI configuration with the parameters of this candidate, committing with `mmcs`.
+    pub const fn fri_config<M>(&self, mmcs: M) -> FriConfig<M> {
+        FriConfig::from_parameters(self.parameters, mmcs)
+    }
+}
+
+/// Search for FRI parameters with at least `target_bits` bits of security under `model`, for the
+/// AIR, trace size and fields described by `params`, whose own FRI parameters are ignored.
+///
+/// Every candidate folds in half in every round. `extension_degree` is the degree of the
+/// challenge field over the base field, used to estimate proof sizes. Returns the Pareto frontier
+/// of proof size against prover time, sorted from the smallest proof to the fastest prover, which
+/// is empty if the target cannot be reached in `space`.
+pub fn select_fri_parameters(
+    params: &SoundnessParameters,
+    extension_degree: usize,
+    target_bits: f64,
+    model: SoundnessModel,
+    space: &FriSearchSpace,
+) -> Vec<FriCandidate> {
+    let mut candidates = Vec::new();
+    for log_blowup in space.log_blowups.clone() {
+        if log_blowup == 0 {
+            continue;
+        }
+        for log_final_poly_len in 0..=space.max_log_final_poly_len.min(params.log_degree) {
+            for proof_of_work_bits in 0..=space.max_proof_of_work_bits {
+                let fri = FriParameters {
+                    log_blowup,
+                    log_final_poly_len,
+                    log_folding_arity: 1,
+                    num_queries: 0,
+                    proof_of_work_bits,
+                };
+                let params = SoundnessParameters { fri, ..*params };
+                let Some(num_queries) = min_num_queries(&params, target_bits, model) else {
+                    continue;
+                };
+                let params = SoundnessParameters {
+                    fri: FriParameters { num_queries, ..fri },
+                    ..params
+                };
+                candidates.push(FriCandidate {
+                    parameters: params.fri,
+                    security_bits: security_bits(&params, model),
+                    proof_size_bits: proof_size_bits(&params, extension_degree),
+                    prover_cost: prover_cost(&params, extension_degree),
+                });
+            }
+        }
+    }
+
+    // Keep the candidates with a lower prover cost than any smaller proof.
+    candidates.sort_by(|a, b| {
+        a.proof_size_bits
+            .total_cmp(&b.proof_size_bits)
+            .then(a.prover_cost.total_cmp(&b.prover_cost))
+    });
+    let mut frontier: Vec<FriCandidate> = Vec::new();
+    for candidate in candidates {
+        if frontier
+            .last()
+            .is_none_or(|best| candidate.prover_cost < best.prover_cost)
+        {
+            frontier.push(candidate);
+        }
+    }
+    frontier
+}
+
+/// The bits of security of `params` under `model`.
+fn security_bits(params: &SoundnessParameters, model: SoundnessModel) -> f64 {
+    match model {
+        SoundnessModel::Conjectured => {
+            let fri = params.fri;
+            let fri_bits = (fri.log_blowup * fri.num_queries + fri.proof_of_work_bits) as f64;
+            fri_bits
+                .min(params.log_field_size)
+                .min(params.merkle_digest_bits as f64 / 2.0)
+        }
+        SoundnessModel::Proven => params.best_report().total_bits,
+    }
+}
+
+/// The fewest queries with which `params` reaches `target_bits` bits of security, if any.
+fn min_num_queries(
+    params: &SoundnessParameters,
+    target_bits: f64,
+    model: SoundnessModel,
+) -> Option<usize> {
+    let fri = params.fri;
+    let num_queries = match model {
+        SoundnessModel::Conjectured => {
+            if params.log_field_size < target_bits
+                || (params.merkle_digest_bits as f64) / 2.0 < target_bits
+            {
+                return None;
+            }
+            let query_bits = target_bits - fri.proof_of_work_bits as f64;
+            ceil(query_bits / fri.log_blowup as f64)
+        }
+        SoundnessModel::Proven => {
+            // Every term but the query phase is independent of the number of queries, so in each
+            // regime the queries only need to cover what is left of the error budget.
+            let single_query = SoundnessParameters {
+                fri: FriParameters {
+                    num_queries: 1,
+                    ..fri
+                },
+                ..*params
+            };
+            core::iter::once(ProximityRegime::UniqueDecoding)
+                .chain((3..=MAX_JOHNSON_M).map(|m| ProximityRegime::Johnson { m }))
+                .filter_map(|regime| {
+                    let report = single_query.report(regime);
+                    if report.merkle_bits < target_bits {
+                        return None;
+                    }
+                    let other_error = exp2(-report.constraint_batching_bits)
+                        + exp2(-report.ood_bits)
+                        + exp2(-report.fri_commit_bits);
+                    let query_error = exp2(-target_bits) - other_error;
+                    (query_error > 0.0).then(|| {
+                        let query_bits = -log2(query_error) - report.proof_of_work_bits;
+                        ceil(query_bits / report.fri_query_bits)
+                    })
+                })
+                .min()?
+        }
+    };
+
+    // Round-off may leave the bound just below the target.
+    let mut num_queries = num_queries.max(1);
+    while security_bits(
+        &SoundnessParameters {
+            fri: FriParameters { num_queries, ..fri },
+            ..*params
+        },
+        model,
+    ) < target_bits
+    {
+        num_queries += 1;
+    }
+    Some(num_queries)
+}
+
+/// Estimate the size of a proof with parameters `params`, in bits.
+fn proof_size_bits(params: &SoundnessParameters, extension_degree: usize) -> f64 {
+    let fri = params.fri;
+    let extension_bits = params.log_field_size;
+    let base_bits = extension_bits / extension_degree as f64;
+    let digest_bits = params.merkle_digest_bits as f64;
+    let log_lde_size = params.log_degree + fri.log_blowup;
+    let num_rounds = params.log_degree.saturating_sub(fri.log_final_poly_len);
+
+    // The commitment of every folding round, the final polynomial and the proof of work witness.
+    let commit_phase = num_rounds as f64 * digest_bits
+        + exp2(fri.log_final_poly_len as f64) * extension_bits
+        + base_bits;
+    // Each query opens the inputs with a Merkle path, and every round a sibling with a path one
+    // level shorter than the previous.
+    let input_opening =
+        params.num_batched_polynomials as f64 * base_bits + log_lde_size as f64 * digest_bits;
+    let fri_openings = (0..num_rounds)
+        .map(|round| extension_bits + (log_lde_size - round - 1) as f64 * digest_bits)
+        .sum::<f64>();
+    commit_phase + fri.num_queries as f64 * (input_opening + fri_openings)
+}
+
+/// Estimate the work of a prover with parameters `params`.
+fn prover_cost(params: &SoundnessParameters, extension_degree: usize) -> f64 {
+    let fri = params.fri;
+    let lde_size = exp2((params.log_degree + fri.log_blowup) as f64);
+    // Every batched polynomial is evaluated over the whole domain, and the folded codewords add
+    // up to about twice the domain in extension field elements.
+    let encoding = params.num_batched_polynomials as f64 * lde_size;
+    let folding = 2.0 * lde_size * extension_degree as f64;
+    encoding + folding + exp2(fri.proof_of_work_bits as f64)
+}
+
+/// The smallest integer at least `x`, or `0` if `x` is negative.
+fn ceil(x: f64) -> usize {
+    if x <= 0.0 {
+        return 0;
+    }
+    let floor = x as usize;
+    if (floor as f64) < x { floor + 1 } else { floor }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters() -> SoundnessParameters {
+        SoundnessParameters {
+            log_degree: 20,
+            num_constraints: 100,
+            max_constraint_degree: 3,
+            num_opening_points: 2,
+            num_batched_polynomials: 400,
+            // The degree 4 extension of BabyBear.
+            log_field_size: 4.0 * 30.906_890_595_608_52,
+            fri: FriParameters {
+                log_blowup: 1,
+                log_final_poly_len: 0,
+                log_folding_arity: 1,
+                num_queries: 100,
+                proof_of_work_bits: 16,
+            },
+            merkle_digest_bits: 248,
+        }
+    }
+
+    fn assert_frontier(frontier: &[FriCandidate]) {
+        assert!(!frontier.is_empty());
+        for pair in frontier.windows(2) {
+            assert!(pair[0].proof_size_bits <= pair[1].proof_size_bits);
+            assert!(pair[0].prover_cost > pair[1].prover_cost);
+        }
+    }
+
+    #[test]
+    fn test_conjectured() {
+        let frontier = select_fri_parameters(
+            &parameters(),
+            4,
+            100.0,
+            SoundnessModel::Conjectured,
+            &FriSearchSpace::default(),
+        );
+        assert_frontier(&frontier);
+        for candidate in &frontier {
+            let fri = candidate.parameters;
+            assert!(candidate.security_bits >= 100.0);
+            // The fewest queries reaching the target.
+            assert_eq!(
+                fri.num_queries,
+                (100 - fri.proof_of_work_bits).div_ceil(fri.log_blowup)
+            );
+        }
+        // The smallest proofs come from the largest blowup.
+        assert_eq!(frontier[0].parameters.log_blowup, 4);
+    }
+
+    #[test]
+    fn test_proven() {
+        let params = parameters();
+        // Batching hundreds of polynomials over a domain of 2^21 points already costs more than
+        // 2^-100 in the commit phase, so a lower target is needed with proven soundness.
+        let unreachable = select_fri_parameters(
+            &params,
+            4,
+            100.0,
+            SoundnessModel::Proven,
+            &FriSearchSpace::default(),
+        );
+        assert!(unreachable.is_empty());
+
+        let frontier = select_fri_parameters(
+            &params,
+            4,
+            80.0,
+            SoundnessModel::Proven,
+            &FriSearchSpace::default(),
+        );
+        assert_frontier(&frontier);
+        for candidate in &frontier {
+            let fri = candidate.parameters;
+            let report = SoundnessParameters { fri, ..params }.best_report();
+            assert_eq!(report.total_bits, candidate.security_bits);
+            assert!(report.total_bits >= 80.0);
+            let fewer_queries = FriParameters {
+                num_queries: fri.num_queries - 1,
+                ..fri
+            };
+            let report = SoundnessParameters {
+                fri: fewer_queries,
+                ..params
+            }
+            .best_report();
+            assert!(report.total_bits < 80.0);
+        }
+    }
+
+    #[test]
+    fn test_unreachable_target() {
+        // The collision resistance of the hash caps the security.
+        let frontier = select_fri_parameters(
+            &parameters(),
+            4,
+            128.0,
+            SoundnessModel::Conjectured,
+            &FriSearchSpace::default(),
+        );
+        assert!(frontier.is_empty());
+    }
+
+    #[test]
+    fn test_ceil() {
+        assert_eq!(ceil(-1.5), 0);
+        assert_eq!(ceil(3.0), 3);
+        assert_eq!(ceil(3.2), 4);
+    }
+}
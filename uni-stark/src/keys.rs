@@ -0,0 +1,377 @@
+//! Proving and verifying keys, computed once per AIR in a setup phase.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use p3_air::{Air, AuxPhase};
+use p3_challenger::{CanObserve, FieldChallenger};
+use p3_commit::Pcs;
+use p3_field::{Field, PrimeCharacteristicRing};
+use p3_matrix::Matrix;
+use p3_util::log2_strict_usize;
+use serde::{Deserialize, Serialize};
+use tracing::{info_span, instrument};
+
+use crate::symbolic_builder::{log_quotient_degree_for, valid_main_row_offsets};
+use crate::{
+    Com, ConstraintDag, Entry, StarkGenericConfig, SymbolicAirBuilder, SymbolicExpression, Val,
+    get_symbolic_constraints, permutation_width,
+};
+
+pub type PcsProverData<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<
+    <SC as StarkGenericConfig>::Challenge,
+    <SC as StarkGenericConfig>::Challenger,
+>>::ProverData;
+
+/// The data a prover needs about an AIR, independent of any particular trace.
+///
+/// Unlike [`StarkVerifyingKey`], this is not serializable as it holds the PCS prover data of the
+/// preprocessed trace. Re-run [`setup`] to obtain it.
+pub struct StarkProvingKey<SC: StarkGenericConfig> {
+    /// The PCS prover data of the preprocessed trace, if the AIR has one.
+    pub(crate) preprocessed_data: Option<PcsProverData<SC>>,
+    /// The constraints of the AIR, evaluated symbolically.
+    pub(crate) symbolic_constraints: Vec<SymbolicExpression<Val<SC>>>,
+    /// The same constraints, deduplicated for evaluation on the quotient domain.
+    pub(crate) constraint_dag: ConstraintDag<Val<SC>>,
+    /// The verifying key for the same AIR.
+    pub(crate) vk: StarkVerifyingKey<SC>,
+}
+
+impl<SC: StarkGenericConfig> StarkProvingKey<SC> {
+    /// The verifying key corresponding to this proving key.
+    pub const fn verifying_key(&self) -> &StarkVerifyingKey<SC> {
+        &self.vk
+    }
+
+    /// The constraints of the AIR, evaluated symbolically.
+    pub fn symbolic_constraints(&self) -> &[SymbolicExpression<Val<SC>>] {
+        &self.symbolic_constraints
+    }
+
+    /// The constraints of the AIR, deduplicated for evaluation on the quotient domain.
+    pub const fn constraint_dag(&self) -> &ConstraintDag<Val<SC>> {
+        &self.constraint_dag
+    }
+}
+
+/// The data a verifier needs about an AIR, independent of any particular proof.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct StarkVerifyingKey<SC: StarkGenericConfig> {
+    /// The commitment to the preprocessed trace, if the AIR has one.
+    pub(crate) preprocessed: Option<PreprocessedVerifierKey<Com<SC>>>,
+    /// The number of public values the AIR expects.
+    pub(crate) num_public_values: usize,
+    /// The offsets of the main trace rows the AIR reads.
+    pub(crate) main_row_offsets: Vec<usize>,
+    /// The periodic columns of the AIR, each given by its values over a single period.
+    pub(crate) periodic_columns: Vec<Vec<Val<SC>>>,
+    /// The log of the number of chunks the quotient polynomial is split into, ignoring ZK.
+    pub(crate) log_quotient_degree: usize,
+    /// The number of extension field columns in the permutation trace, or `0` if the AIR has no
+    /// lookups.
+    pub(crate) permutation_width: usize,
+    /// The shapes of the AIR's auxiliary trace phases.
+    pub(crate) aux_phases: Vec<AuxPhase>,
+    /// A hash of the AIR's shape and symbolic constraints.
+    pub(crate) air_fingerprint: SC::Challenge,
+}
+
+/// The part of a verifying key describing a committed preprocessed trace.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PreprocessedVerifierKey<Com> {
+    pub(crate) commitment: Com,
+    pub(crate) width: usize,
+    pub(crate) degree_bits: usize,
+}
+
+impl<SC: StarkGenericConfig> Clone for StarkVerifyingKey<SC> {
+    fn clone(&self) -> Self {
+        Self {
+            preprocessed: self.preprocessed.clone(),
+            num_public_values: self.num_public_values,
+            main_row_offsets: self.main_row_offsets.clone(),
+            periodic_columns: self.periodic_columns.clone(),
+            log_quotient_degree: self.log_quotient_degree,
+            permutation_width: self.permutation_width,
+            aux_phases: self.aux_phases.clone(),
+            air_fingerprint: self.air_fingerprint,
+        }
+    }
+}
+
+impl<SC: StarkGenericConfig> StarkVerifyingKey<SC> {
+    /// The width of the preprocessed trace, or `0` if the AIR has none.
+    pub fn preprocessed_width(&self) -> usize {
+        self.preprocessed.as_ref().map_or(0, |p| p.width)
+    }
+
+    /// The number of public values the AIR expects.
+    pub const fn num_public_values(&self) -> usize {
+        self.num_public_values
+    }
+
+    /// The offsets of the main trace rows the AIR reads.
+    pub fn main_row_offsets(&self) -> &[usize] {
+        &self.main_row_offsets
+    }
+
+    /// The periodic columns of the AIR, each given by its values over a single period.
+    pub fn periodic_columns(&self) -> &[Vec<Val<SC>>] {
+        &self.periodic_columns
+    }
+
+    /// The log of the number of chunks the quotient polynomial is split into, ignoring ZK.
+    pub const fn log_quotient_degree(&self) -> usize {
+        self.log_quotient_degree
+    }
+
+    /// The number of extension field columns in the permutation trace, or `0` if the AIR has no
+    /// lookups.
+    pub const fn permutation_width(&self) -> usize {
+        self.permutation_width
+    }
+
+    /// The shapes of the AIR's auxiliary trace phases.
+    pub fn aux_phases(&self) -> &[AuxPhase] {
+        &self.aux_phases
+    }
+
+    /// A hash of the AIR's shape and symbolic constraints.
+    pub const fn air_fingerprint(&self) -> SC::Challenge {
+        self.air_fingerprint
+    }
+
+    /// Hash the whole verifying key to a single challenge field element.
+    ///
+    /// Both the prover and the verifier observe this digest before any commitment, which binds
+    /// every challenge to the AIR being proven and protects against transcript collisions between
+    /// distinct instances.
+    pub fn digest(&self, config: &SC) -> SC::Challenge {
+        let mut challenger = config.initialise_challenger();
+        challenger.observe_algebra_element(self.air_fingerprint);
+        challenger.observe(Val::<SC>::from_usize(self.num_public_values));
+        challenger.observe(Val::<SC>::from_usize(self.log_quotient_degree));
+        challenger.observe(Val::<SC>::from_usize(self.permutation_width));
+        observe_aux_phases::<Val<SC>, _>(&mut challenger, &self.aux_phases);
+        match &self.preprocessed {
+            Some(preprocessed) => {
+                challenger.observe(Val::<SC>::ONE);
+                challenger.observe(Val::<SC>::from_usize(preprocessed.width));
+                challenger.observe(Val::<SC>::from_usize(preprocessed.degree_bits));
+                challenger.observe(preprocessed.commitment.clone());
+            }
+            None => challenger.observe(Val::<SC>::ZERO),
+        }
+        challenger.sample_algebra_element()
+    }
+}
+
+/// Compute the proving and verifying keys of an AIR.
+///
+/// This evaluates the constraints symbolically, derives the quotient degree and, if the AIR has a
+/// preprocessed trace, commits to it. None of this depends on the main trace, so the keys can be
+/// reused across proofs.
+///
+/// # Panics
+/// Panics if the AIR's main row offsets are not strictly increasing from `0, 1`, if the period of a
+/// periodic column is not a power of two, or if the AIR has a preprocessed trace, lookups or
+/// auxiliary phases and the PCS is zero-knowledge.
+#[instrument(skip_all)]
+pub fn setup<SC, A>(
+    config: &SC,
+    air: &A,
+    num_public_values: usize,
+) -> (StarkProvingKey<SC>, StarkVerifyingKey<SC>)
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>>,
+{
+    let pcs = config.pcs();
+
+    // The preprocessed trace is committed in the same way as the main trace. It is fixed by the
+    // AIR, so the commitment is part of the verifying key rather than of each proof.
+    let (preprocessed, preprocessed_data) = match air.preprocessed_trace() {
+        Some(preprocessed_trace) => {
+            assert!(
+                !SC::Pcs::ZK,
+                "preprocessed traces are not supported when ZK is enabled"
+            );
+            let width = preprocessed_trace.width();
+            let degree = preprocessed_trace.height();
+            let domain = pcs.natural_domain_for_degree(degree);
+            let (commitment, data) = info_span!("commit to preprocessed trace data")
+                .in_scope(|| pcs.commit([(domain, preprocessed_trace)]));
+            let preprocessed = PreprocessedVerifierKey {
+                commitment,
+                width,
+                degree_bits: log2_strict_usize(degree),
+            };
+            (Some(preprocessed), Some(data))
+        }
+        None => (None, None),
+    };
+    let preprocessed_width = preprocessed.as_ref().map_or(0, |p| p.width);
+
+    let permutation_width = permutation_width(&air.sends(), &air.receives());
+    assert!(
+        permutation_width == 0 || !SC::Pcs::ZK,
+        "lookups are not supported when ZK is enabled"
+    );
+    let aux_phases = air.aux_phases();
+    assert!(
+        aux_phases.is_empty() || !SC::Pcs::ZK,
+        "auxiliary phases are not supported when ZK is enabled"
+    );
+
+    let main_row_offsets = air.main_row_offsets();
+    assert!(
+        valid_main_row_offsets(&main_row_offsets),
+        "main row offsets must be strictly increasing and start with 0, 1"
+    );
+    let periodic_columns = air.periodic_columns();
+    assert!(
+        periodic_columns
+            .iter()
+            .all(|column| column.len().is_power_of_two()),
+        "the period of a periodic column must be a power of two"
+    );
+
+    let symbolic_constraints =
+        get_symbolic_constraints::<Val<SC>, A>(air, preprocessed_width, num_public_values);
+    let max_constraint_degree = symbolic_constraints
+        .iter()
+        .map(SymbolicExpression::degree_multiple)
+        .max()
+        .unwrap_or(0);
+    let log_quotient_degree = log_quotient_degree_for(max_constraint_degree, config.is_zk());
+
+    // Fingerprint the AIR by hashing its widths and an encoding of every constraint.
+    let mut challenger = config.initialise_challenger();
+    challenger.observe(Val::<SC>::from_usize(air.width()));
+    challenger.observe(Val::<SC>::from_usize(main_row_offsets.len()));
+    for &offset in &main_row_offsets {
+        challenger.observe(Val::<SC>::from_usize(offset));
+    }
+    challenger.observe(Val::<SC>::from_usize(periodic_columns.len()));
+    for column in &periodic_columns {
+        challenger.observe(Val::<SC>::from_usize(column.len()));
+        challenger.observe_slice(column);
+    }
+    challenger.observe(Val::<SC>::from_usize(preprocessed_width));
+    challenger.observe(Val::<SC>::from_usize(permutation_width));
+    observe_aux_phases::<Val<SC>, _>(&mut challenger, &aux_phases);
+    challenger.observe(Val::<SC>::from_usize(symbolic_constraints.len()));
+    let mut seen = BTreeMap::new();
+    for constraint in &symbolic_constraints {
+        let index = observe_symbolic_expression(&mut challenger, constraint, &mut seen);
+        challenger.observe(Val::<SC>::from_usize(index));
+    }
+    let air_fingerprint = challenger.sample_algebra_element();
+
+    let vk = StarkVerifyingKey {
+        preprocessed,
+        num_public_values,
+        main_row_offsets,
+        periodic_columns,
+        log_quotient_degree,
+        permutation_width,
+        aux_phases,
+        air_fingerprint,
+    };
+    let constraint_dag = ConstraintDag::new(&symbolic_constraints);
+    let pk = StarkProvingKey {
+        preprocessed_data,
+        symbolic_constraints,
+        constraint_dag,
+        vk: vk.clone(),
+    };
+    (pk, vk)
+}
+
+/// Observe the number of auxiliary phases, followed by the shape of each.
+fn observe_aux_phases<F, C>(challenger: &mut C, aux_phases: &[AuxPhase])
+where
+    F: Field,
+    C: CanObserve<F>,
+{
+    challenger.observe(F::from_usize(aux_phases.len()));
+    for aux_phase in aux_phases {
+        challenger.observe(F::from_usize(aux_phase.num_challenges));
+        challenger.observe(F::from_usize(aux_phase.width));
+    }
+}
+
+/// Observe an encoding of `expr` as a straight-line program, returning the index of its node.
+///
+/// Constraint expressions share subexpressions through `Rc`s, so walking them as trees can take
+/// exponential time. Instead, every distinct node is observed once, after its children, as its
+/// operation followed by the indices of its children. `seen` maps node addresses to indices.
+fn observe_symbolic_expression<F, C>(
+    challenger: &mut C,
+    expr: &SymbolicExpression<F>,
+    seen: &mut BTreeMap<*const SymbolicExpression<F>, usize>,
+) -> usize
+where
+    F: Field,
+    C: CanObserve<F>,
+{
+    let key = expr as *const SymbolicExpression<F>;
+    if let Some(&index) = seen.get(&key) {
+        return index;
+    }
+
+    let observe_node = |challenger: &mut C, op: u8, args: &[usize]| {
+        challenger.observe(F::from_u8(op));
+        for &arg in args {
+            challenger.observe(F::from_usize(arg));
+        }
+    };
+    match expr {
+        SymbolicExpression::Variable(v) => {
+            let (entry, phase, offset) = match v.entry {
+                Entry::Preprocessed { offset } => (0, 0, offset),
+                Entry::Main { offset } => (1, 0, offset),
+                Entry::Permutation { offset } => (2, 0, offset),
+                Entry::Public => (3, 0, 0),
+                Entry::Challenge => (4, 0, 0),
+                Entry::Aux { phase, offset } => (5, phase, offset),
+                Entry::AuxChallenge { phase } => (6, phase, 0),
+                Entry::Periodic => (7, 0, 0),
+            };
+            observe_node(challenger, 0, &[entry, phase, offset, v.index]);
+        }
+        SymbolicExpression::IsFirstRow => observe_node(challenger, 1, &[]),
+        SymbolicExpression::IsLastRow => observe_node(challenger, 2, &[]),
+        SymbolicExpression::IsTransition => observe_node(challenger, 3, &[]),
+        SymbolicExpression::Constant(c) => {
+            observe_node(challenger, 4, &[]);
+            challenger.observe(*c);
+        }
+        SymbolicExpression::IsTransitionWindow(size) => observe_node(challenger, 9, &[*size]),
+        SymbolicExpression::Add { x, y, .. } => {
+            let x = observe_symbolic_expression(challenger, x, seen);
+            let y = observe_symbolic_expression(challenger, y, seen);
+            observe_node(challenger, 5, &[x, y]);
+        }
+        SymbolicExpression::Sub { x, y, .. } => {
+            let x = observe_symbolic_expression(challenger, x, seen);
+            let y = observe_symbolic_expression(challenger, y, seen);
+            observe_node(challenger, 6, &[x, y]);
+        }
+        SymbolicExpression::Neg { x, .. } => {
+            let x = observe_symbolic_expression(challenger, x, seen);
+            observe_node(challenger, 7, &[x]);
+        }
+        SymbolicExpression::Mul { x, y, .. } => {
+            let x = observe_symbolic_expression(challenger, x, seen);
+            let y = observe_symbolic_expression(challenger, y, seen);
+            observe_node(challenger, 8, &[x, y]);
+        }
+    }
+
+    let index = seen.len();
+    seen.insert(key, index);
+    index
+}
@@ -4,10 +4,20 @@
 
 extern crate alloc;
 
+mod analysis;
+mod batch_prover;
+mod batch_verifier;
 mod config;
+mod constraint_dag;
+mod continuation;
+mod dynamic_air;
 mod folder;
+mod fri_selection;
+mod keys;
+mod lookup;
 mod proof;
 mod prover;
+mod soundness;
 mod symbolic_builder;
 mod symbolic_expression;
 mod symbolic_variable;
@@ -15,11 +25,21 @@ mod verifier;
 
 mod check_constraints;
 
+pub use analysis::*;
+pub use batch_prover::*;
+pub use batch_verifier::*;
 pub use check_constraints::*;
 pub use config::*;
+pub use constraint_dag::*;
+pub use continuation::*;
+pub use dynamic_air::*;
 pub use folder::*;
+pub use fri_selection::*;
+pub use keys::*;
+pub use lookup::*;
 pub use proof::*;
 pub use prover::*;
+pub use soundness::*;
 pub use symbolic_builder::*;
 pub use symbolic_expression::*;
 pub use symbolic_variable::*;
@@ -0,0 +1,281 @@
+//! A LogUp lookup argument over the interactions declared by [`BaseAir::sends`] and
+//! [`BaseAir::receives`].
+//!
+//! Given challenges `alpha` and `beta`, the fingerprint of a tuple `(v_0, ..., v_{k-1})` on bus
+//! `b` is `alpha - (b + beta v_0 + ... + beta^k v_{k-1})`. The permutation trace has one extension
+//! field column `h_i` per interaction, holding `m_i / fingerprint_i` on every row, where the
+//! multiplicity `m_i` is negated for received tuples. A final column `z` holds the running sum of
+//! all `h_i` over the rows so far, so its value on the last row is the cumulative sum of the AIR.
+//!
+//! If every tuple is sent as many times as it is received, the cumulative sums of all AIRs taking
+//! part in the argument add up to zero, except with negligible probability over `alpha` and
+//! `beta`. A single AIR may also send and receive on the same bus, in which case its own
+//! cumulative sum is zero.
+//!
+//! [`BaseAir::sends`]: p3_air::BaseAir::sends
+//! [`BaseAir::receives`]: p3_air::BaseAir::receives
+
+use alloc::vec::Vec;
+
+use p3_air::{ExtensionBuilder, Interaction, PairBuilder, PermutationAirBuilder};
+use p3_field::{ExtensionField, Field, PrimeCharacteristicRing, batch_multiplicative_inverse};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_maybe_rayon::prelude::*;
+
+/// The number of challenges sampled for the lookup argument, namely `alpha` and `beta`.
+pub const NUM_LOOKUP_CHALLENGES: usize = 2;
+
+/// An `AirBuilder` which can evaluate the constraints of a LogUp lookup argument.
+pub trait LookupBuilder: PermutationAirBuilder + PairBuilder {
+    /// The claimed sum of the running sum column on the last row.
+    fn cumulative_sum(&self) -> Self::RandomVar;
+}
+
+/// The number of extension field columns in the permutation trace of an AIR with the given
+/// interactions.
+pub const fn permutation_width<F: Field>(
+    sends: &[Interaction<F>],
+    receives: &[Interaction<F>],
+) -> usize {
+    let num_interactions = sends.len() + receives.len();
+    if num_interactions == 0 {
+        0
+    } else {
+        num_interactions + 1
+    }
+}
+
+/// Pair every interaction with the sign of its multiplicity in the running sum.
+fn signed_interactions<'a, F: Field>(
+    sends: &'a [Interaction<F>],
+    receives: &'a [Interaction<F>],
+) -> impl Iterator<Item = (&'a Interaction<F>, F)> {
+    sends
+        .iter()
+        .map(|interaction| (interaction, F::ONE))
+        .chain(receives.iter().map(|interaction| (interaction, F::NEG_ONE)))
+}
+
+/// Evaluate the tuple and signed multiplicity of every interaction on every row of a trace.
+///
+/// Each row holds, for every interaction in turn, the values of its tuple followed by its
+/// multiplicity, negated for received tuples. This only reads the trace, so it can be done before
+/// the trace is moved into its commitment, whereas the lookup challenges are sampled after.
+pub(crate) fn evaluate_interactions<F: Field>(
+    preprocessed: Option<&RowMajorMatrix<F>>,
+    main: &RowMajorMatrix<F>,
+    sends: &[Interaction<F>],
+    receives: &[Interaction<F>],
+) -> RowMajorMatrix<F> {
+    let interactions: Vec<_> = signed_interactions(sends, receives).collect();
+    let width = interactions
+        .iter()
+        .map(|(interaction, _)| interaction.values.len() + 1)
+        .sum();
+
+    let mut values = F::zero_vec(main.height() * width);
+    values
+        .par_chunks_exact_mut(width)
+        .enumerate()
+        .for_each(|(row, values)| {
+            let main_row = main.row_slice(row).expect("row index is in bounds");
+            let preprocessed_row = preprocessed
+                .and_then(|preprocessed| preprocessed.row_slice(row))
+                .map(|row| row.to_vec())
+                .unwrap_or_default();
+            let mut offset = 0;
+            for (interaction, sign) in &interactions {
+                let num_values = interaction.values.len();
+                for (value, column) in values[offset..offset + num_values]
+                    .iter_mut()
+                    .zip(&interaction.values)
+                {
+                    *value = column.apply::<F, F>(&preprocessed_row, &main_row);
+                }
+                values[offset + num_values] = interaction
+                    .multiplicity
+                    .apply::<F, F>(&preprocessed_row, &main_row)
+                    * *sign;
+                offset += num_values + 1;
+            }
+        });
+
+    RowMajorMatrix::new(values, width)
+}
+
+/// Generate the permutation trace of a LogUp argument from the output of
+/// [`evaluate_interactions`], returning it along with the total sum.
+pub(crate) fn generate_permutation_trace<F, EF>(
+    interaction_values: &RowMajorMatrix<F>,
+    sends: &[Interaction<F>],
+    receives: &[Interaction<F>],
+    randomness: &[EF],
+) -> (RowMajorMatrix<EF>, EF)
+where
+    F: Field,
+    EF: ExtensionField<F>,
+{
+    let [alpha, beta] = randomness else {
+        panic!("expected {NUM_LOOKUP_CHALLENGES} lookup challenges");
+    };
+    let interactions: Vec<_> = signed_interactions(sends, receives).collect();
+    let num_interactions = interactions.len();
+    let height = interaction_values.height();
+
+    // The offset of each interaction's tuple within a row of `interaction_values`. Its
+    // multiplicity follows the tuple.
+    let offsets: Vec<_> = interactions
+        .iter()
+        .scan(0, |next_offset, (interaction, _)| {
+            let offset = *next_offset;
+            *next_offset += interaction.values.len() + 1;
+            Some(offset)
+        })
+        .collect();
+
+    // Compute every fingerprint, then invert them all at once.
+    let mut fingerprints = EF::zero_vec(height * num_interactions);
+    fingerprints
+        .par_chunks_exact_mut(num_interactions)
+        .enumerate()
+        .for_each(|(row, fingerprints)| {
+            let row = interaction_values
+                .row_slice(row)
+                .expect("row index is in bounds");
+            for ((fingerprint, (interaction, _)), &offset) in
+                fingerprints.iter_mut().zip(&interactions).zip(&offsets)
+            {
+                let combined = row[offset..offset + interaction.values.len()]
+                    .iter()
+                    .rev()
+                    .fold(EF::ZERO, |acc, &value| (acc + value) * *beta)
+                    + F::from_usize(interaction.bus);
+                *fingerprint = *alpha - combined;
+            }
+        });
+    let inverses = batch_multiplicative_inverse(&fingerprints);
+
+    let width = num_interactions + 1;
+    let mut values = EF::zero_vec(height * width);
+    values
+        .par_chunks_exact_mut(width)
+        .zip(inverses.par_chunks_exact(num_interactions))
+        .enumerate()
+        .for_each(|(row, (values, inverses))| {
+            let row = interaction_values
+                .row_slice(row)
+                .expect("row index is in bounds");
+            for (((value, inverse), (interaction, _)), &offset) in values
+                .iter_mut()
+                .zip(inverses)
+                .zip(&interactions)
+                .zip(&offsets)
+            {
+                *value = *inverse * row[offset + interaction.values.len()];
+            }
+        });
+
+    // Fill in the running sum column.
+    let mut running_sum = EF::ZERO;
+    for row in values.chunks_exact_mut(width) {
+        let (terms, sum) = row.split_at_mut(num_interactions);
+        running_sum += terms.iter().copied().sum::<EF>();
+        sum[0] = running_sum;
+    }
+
+    (RowMajorMatrix::new(values, width), running_sum)
+}
+
+/// Evaluate the constraints of a LogUp argument over the given interactions.
+///
+/// This does nothing if there are no interactions.
+pub fn eval_lookup_constraints<AB: LookupBuilder>(
+    builder: &mut AB,
+    sends: &[Interaction<AB::F>],
+    receives: &[Interaction<AB::F>],
+) {
+    if sends.is_empty() && receives.is_empty() {
+        return;
+    }
+
+    let main = builder.main();
+    let preprocessed = builder.preprocessed();
+    let permutation = builder.permutation();
+    let main_local = main.row_slice(0).expect("Matrix is empty?").to_vec();
+    let preprocessed_local = preprocessed
+        .row_slice(0)
+        .map(|row| row.to_vec())
+        .unwrap_or_default();
+    let permutation_local = permutation.row_slice(0).expect("Matrix is empty?").to_vec();
+    let permutation_next = permutation
+        .row_slice(1)
+        .expect("Matrix only has 1 row?")
+        .to_vec();
+
+    let &[alpha, beta] = builder.permutation_randomness() else {
+        panic!("expected {NUM_LOOKUP_CHALLENGES} lookup challenges");
+    };
+    let alpha: AB::ExprEF = alpha.into();
+    let beta: AB::ExprEF = beta.into();
+
+    let (terms_local, sum_local) = permutation_local.split_at(permutation_local.len() - 1);
+    let (terms_next, sum_next) = permutation_next.split_at(permutation_next.len() - 1);
+    let sum_local: AB::ExprEF = sum_local[0].into();
+    let sum_next: AB::ExprEF = sum_next[0].into();
+
+    // Each term is the signed multiplicity divided by the fingerprint of the tuple.
+    for (&term, (interaction, sign)) in terms_local.iter().zip(signed_interactions(sends, receives))
+    {
+        let combined = interaction
+            .values
+            .iter()
+            .rev()
+            .fold(AB::ExprEF::ZERO, |acc, value| {
+                (acc + value.apply::<AB::Expr, AB::Var>(&preprocessed_local, &main_local))
+                    * beta.clone()
+            })
+            + AB::Expr::from_usize(interaction.bus);
+        let fingerprint = alpha.clone() - combined;
+        let multiplicity = interaction
+            .multiplicity
+            .apply::<AB::Expr, AB::Var>(&preprocessed_local, &main_local)
+            * sign;
+        let term: AB::ExprEF = term.into();
+        builder.assert_eq_ext(term * fingerprint, multiplicity);
+    }
+
+    let terms_sum = |terms: &[AB::VarEF]| {
+        terms
+            .iter()
+            .fold(AB::ExprEF::ZERO, |acc, &term| acc + term.into())
+    };
+    builder
+        .when_first_row()
+        .assert_eq_ext(sum_local.clone(), terms_sum(terms_local));
+    builder
+        .when_transition()
+        .assert_eq_ext(sum_next, sum_local.clone() + terms_sum(terms_next));
+    let cumulative_sum: AB::ExprEF = builder.cumulative_sum().into();
+    builder
+        .when_last_row()
+        .assert_eq_ext(sum_local, cumulative_sum);
+}
+
+/// Recombine the opened values of a flattened extension field trace into extension field values.
+pub(crate) fn unflatten_extension_values<F, EF>(values: &[EF]) -> Vec<EF>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+{
+    values
+        .chunks_exact(EF::DIMENSION)
+        .map(|coeffs| {
+            coeffs
+                .iter()
+                .enumerate()
+                .map(|(i, &coeff)| EF::ith_basis_element(i).unwrap() * coeff)
+                .sum()
+        })
+        .collect()
+}
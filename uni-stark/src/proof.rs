@@ -3,9 +3,9 @@ use alloc::vec::Vec;
 use p3_commit::Pcs;
 use serde::{Deserialize, Serialize};
 
-use crate::StarkGenericConfig;
+use crate::{StarkGenericConfig, Val};
 
-type Com<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<
+pub type Com<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<
     <SC as StarkGenericConfig>::Challenge,
     <SC as StarkGenericConfig>::Challenger,
 >>::Commitment;
@@ -21,6 +21,8 @@ pub struct Proof<SC: StarkGenericConfig> {
     pub(crate) opened_values: OpenedValues<SC::Challenge>,
     pub(crate) opening_proof: PcsProof<SC>,
     pub(crate) degree_bits: usize,
+    /// The claimed total of the lookup running sum, if the AIR has lookups.
+    pub(crate) cumulative_sum: Option<SC::Challenge>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -28,12 +30,88 @@ pub struct Commitments<Com> {
     pub(crate) trace: Com,
     pub(crate) quotient_chunks: Com,
     pub(crate) random: Option<Com>,
+    pub(crate) permutation: Option<Com>,
+    /// One commitment per auxiliary trace phase, in the order the phases were generated.
+    pub(crate) aux: Vec<Com>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OpenedValues<Challenge> {
     pub(crate) trace_local: Vec<Challenge>,
     pub(crate) trace_next: Vec<Challenge>,
+    /// The opened values of the main trace at the AIR's row offsets after the first two, if any.
+    pub(crate) trace_extra_rows: Vec<Vec<Challenge>>,
+    pub(crate) preprocessed_local: Option<Vec<Challenge>>,
+    pub(crate) preprocessed_next: Option<Vec<Challenge>>,
     pub(crate) quotient_chunks: Vec<Vec<Challenge>>,
     pub(crate) random: Option<Vec<Challenge>>,
+    /// The opened values of the permutation trace, flattened to base field columns.
+    pub(crate) permutation_local: Option<Vec<Challenge>>,
+    pub(crate) permutation_next: Option<Vec<Challenge>>,
+    /// The opened values of each auxiliary trace phase, flattened to base field columns.
+    pub(crate) aux_local: Vec<Vec<Challenge>>,
+    pub(crate) aux_next: Vec<Vec<Challenge>>,
+}
+
+/// A proof that several traces satisfy their respective AIRs, produced by
+/// [`prove_batch`](crate::prove_batch).
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BatchProof<SC: StarkGenericConfig> {
+    pub(crate) commitments: BatchCommitments<Com<SC>>,
+    /// The opened values of each instance, in the order the instances were given.
+    pub(crate) opened_values: Vec<OpenedValues<SC::Challenge>>,
+    pub(crate) opening_proof: PcsProof<SC>,
+    /// The log of the trace height of each instance.
+    pub(crate) degree_bits: Vec<usize>,
+    /// The claimed total of the lookup running sum of each instance which has lookups.
+    pub(crate) cumulative_sums: Vec<Option<SC::Challenge>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchCommitments<Com> {
+    /// A single commitment to the main traces of all instances.
+    pub(crate) main: Com,
+    /// A single commitment to the quotient chunks of all instances.
+    pub(crate) quotient_chunks: Com,
+    /// A single commitment to the permutation traces of all instances with lookups, if any.
+    pub(crate) permutation: Option<Com>,
+}
+
+/// A proof of one logical execution, split into segments which were proven separately, produced
+/// by [`prove_continuation`](crate::prove_continuation).
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ContinuationProof<SC: StarkGenericConfig> {
+    /// The proof of each segment, in execution order.
+    pub(crate) segments: Vec<SegmentProof<SC>>,
+}
+
+/// The proof of a single segment of a [`ContinuationProof`], with the boundary rows it was proven
+/// against.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct SegmentProof<SC: StarkGenericConfig> {
+    /// The first row of the segment's trace.
+    pub(crate) first_row: Vec<Val<SC>>,
+    /// The last row of the segment's trace.
+    pub(crate) last_row: Vec<Val<SC>>,
+    pub(crate) proof: Proof<SC>,
+}
+
+impl<SC: StarkGenericConfig> ContinuationProof<SC> {
+    /// The number of segments the execution was split into.
+    pub fn num_segments(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// The first row of the whole execution, i.e. of its first segment.
+    pub fn initial_row(&self) -> Option<&[Val<SC>]> {
+        self.segments.first().map(|segment| &*segment.first_row)
+    }
+
+    /// The last row of the whole execution, i.e. of its last segment.
+    pub fn final_row(&self) -> Option<&[Val<SC>]> {
+        self.segments.last().map(|segment| &*segment.last_row)
+    }
 }
@@ -9,19 +9,25 @@ use p3_field::{BasedVectorSpace, PackedValue, PrimeCharacteristicRing};
 use p3_matrix::Matrix;
 use p3_matrix::dense::RowMajorMatrix;
 use p3_maybe_rayon::prelude::*;
-use p3_util::{log2_ceil_usize, log2_strict_usize};
+use p3_util::log2_strict_usize;
 use tracing::{debug_span, info_span, instrument};
 
+use crate::lookup::{evaluate_interactions, generate_permutation_trace};
 use crate::{
-    Commitments, Domain, OpenedValues, PackedChallenge, PackedVal, Proof, ProverConstraintFolder,
-    StarkGenericConfig, SymbolicAirBuilder, SymbolicExpression, Val, get_symbolic_constraints,
+    Commitments, ConstraintDag, Domain, NUM_LOOKUP_CHALLENGES, OpenedValues, PackedChallenge,
+    PackedVal, Proof, ProverConstraintFolder, StarkGenericConfig, StarkProvingKey,
+    SymbolicAirBuilder, Val, setup,
 };
 
+/// Prove that `trace` satisfies `air`.
+///
+/// This runs [`setup`] before proving. When producing several proofs for the same AIR, prefer
+/// calling [`setup`] once and then [`prove_with_key`].
 #[instrument(skip_all)]
 #[allow(clippy::multiple_bound_locations)] // cfg not supported in where clauses?
 pub fn prove<
     SC,
-    #[cfg(debug_assertions)] A: for<'a> Air<crate::check_constraints::DebugConstraintBuilder<'a, Val<SC>>>,
+    #[cfg(debug_assertions)] A: for<'a> Air<crate::check_constraints::DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>,
     #[cfg(not(debug_assertions))] A,
 >(
     config: &SC,
@@ -33,19 +39,50 @@ where
     SC: StarkGenericConfig,
     A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<ProverConstraintFolder<'a, SC>>,
 {
-    #[cfg(debug_assertions)]
-    crate::check_constraints::check_constraints(air, &trace, public_values);
+    let (proving_key, _) = setup(config, air, public_values.len());
+    prove_with_key(config, &proving_key, air, trace, public_values)
+}
+
+/// Prove that `trace` satisfies `air`, reusing a proving key computed by [`setup`].
+#[instrument(skip_all)]
+#[allow(clippy::multiple_bound_locations)] // cfg not supported in where clauses?
+pub fn prove_with_key<
+    SC,
+    #[cfg(debug_assertions)] A: for<'a> Air<crate::check_constraints::DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>,
+    #[cfg(not(debug_assertions))] A,
+>(
+    config: &SC,
+    proving_key: &StarkProvingKey<SC>,
+    air: &A,
+    trace: RowMajorMatrix<Val<SC>>,
+    public_values: &Vec<Val<SC>>,
+) -> Proof<SC>
+where
+    SC: StarkGenericConfig,
+    A: for<'a> Air<ProverConstraintFolder<'a, SC>>,
+{
+    let vk = proving_key.verifying_key();
+    assert_eq!(
+        public_values.len(),
+        vk.num_public_values,
+        "number of public values does not match the proving key"
+    );
 
     // Compute the height `N = 2^n` and `log_2(height)`, `n`, of the trace.
     let degree = trace.height();
     let log_degree = log2_strict_usize(degree);
     let log_ext_degree = log_degree + config.is_zk();
-
-    // Compute the constraint polynomials as vectors of symbolic expressions.
-    let symbolic_constraints = get_symbolic_constraints::<Val<SC>, A>(air, 0, public_values.len());
-
-    // Count the number of constraints that we have.
-    let constraint_count = symbolic_constraints.len();
+    // The window selectors only have a low enough degree if the window fits in the trace.
+    assert!(
+        vk.main_row_offsets.len() == 2 || degree > *vk.main_row_offsets.last().unwrap(),
+        "trace must be taller than the largest main row offset"
+    );
+    assert!(
+        vk.periodic_columns
+            .iter()
+            .all(|column| column.len() <= degree),
+        "the period of a periodic column must not exceed the trace height"
+    );
 
     // Each constraint polynomial looks like `C_j(X_1, ..., X_w, Y_1, ..., Y_w, Z_1, ..., Z_j)`.
     // When evaluated on a given row, the X_i's will be the `i`'th element of the that row, the
@@ -76,22 +113,43 @@ where
     // that S_i^2 should never appear in a constraint as it should just be replaced by `S_i`.
     //
     // For now in comments we assume that `deg(C) = 3` meaning `deg(C(x)) <= 3N - 2`
-    let constraint_degree = symbolic_constraints
-        .iter()
-        .map(SymbolicExpression::degree_multiple)
-        .max()
-        .unwrap_or(0);
-
-    // From the degree of the constraint polynomial, compute the number
-    // of quotient polynomials we will split Q(x) into. This is chosen to
-    // always be a power of 2.
-    let log_quotient_degree = log2_ceil_usize(constraint_degree - 1 + config.is_zk());
+    //
+    // During setup, the number of quotient polynomials we will split Q(x) into was computed from
+    // the degree of the constraint polynomial. This is chosen to always be a power of 2.
+    let log_quotient_degree = vk.log_quotient_degree;
     let quotient_degree = 1 << (log_quotient_degree + config.is_zk());
 
     // Initialize the PCS and the Challenger.
     let pcs = config.pcs();
     let mut challenger = config.initialise_challenger();
 
+    // The permutation trace of the lookup argument is generated after the main trace has been
+    // committed to, so we evaluate the interactions while we still have the trace. The auxiliary
+    // traces are generated by the AIR from the main trace itself, so for those we keep a copy.
+    let sends = air.sends();
+    let receives = air.receives();
+    let has_lookups = vk.permutation_width != 0;
+    let has_aux_phases = !vk.aux_phases.is_empty();
+    let preprocessed_trace = (has_lookups || has_aux_phases || cfg!(debug_assertions))
+        .then(|| air.preprocessed_trace())
+        .flatten();
+    let interaction_values = has_lookups
+        .then(|| evaluate_interactions(preprocessed_trace.as_ref(), &trace, &sends, &receives));
+    let main_trace = has_aux_phases.then(|| trace.clone());
+
+    // Without auxiliary phases, the constraints only involve the main and preprocessed traces.
+    #[cfg(debug_assertions)]
+    if !has_aux_phases {
+        crate::check_constraints::check_constraints(
+            air,
+            &trace,
+            preprocessed_trace.as_ref(),
+            &[],
+            &[],
+            public_values,
+        );
+    }
+
     // Get the subgroup `H` of size `N`. We treat each column `T_i` of
     // the trace as an evaluation vector of polynomials `T_i(x)` over `H`.
     // (In the Circle STARK case `H` is instead a standard position twin coset of size `N`)
@@ -113,11 +171,23 @@ where
     let (trace_commit, trace_data) =
         info_span!("commit to trace data").in_scope(|| pcs.commit([(ext_trace_domain, trace)]));
 
+    // The preprocessed trace was committed to during setup.
+    if let Some(preprocessed) = &vk.preprocessed {
+        assert_eq!(
+            preprocessed.degree_bits, log_degree,
+            "preprocessed trace height must match the main trace height"
+        );
+    }
+    let preprocessed_data = proving_key.preprocessed_data.as_ref();
+
     // Observe the instance.
     // degree < 2^255 so we can safely cast log_degree to a u8.
     challenger.observe(Val::<SC>::from_u8(log_ext_degree as u8));
     challenger.observe(Val::<SC>::from_u8(log_degree as u8));
-    // TODO: Might be best practice to include other instance data here; see verifier comment.
+
+    // Observe the digest of the verifying key, which includes the commitment to the preprocessed
+    // trace, if any.
+    challenger.observe_algebra_element(vk.digest(config));
 
     // Observe the Merkle root of the trace commitment.
     challenger.observe(trace_commit.clone());
@@ -125,6 +195,97 @@ where
     // Observe the public input values.
     challenger.observe_slice(public_values);
 
+    // If the AIR has lookups, sample the lookup challenges and commit to the permutation trace of
+    // the LogUp argument. We also observe the claimed total of the running sum, which the verifier
+    // checks to be zero.
+    //
+    // Soundness Error: Roughly (n + k) N/|EF|, where `n` is the number of interactions and `k` the
+    // largest number of values in a tuple, from either challenge hitting a root of the difference
+    // of the two sides of the LogUp identity.
+    let (permutation_randomness, permutation_commit, permutation_data, cumulative_sum) =
+        match &interaction_values {
+            Some(interaction_values) => {
+                let randomness: Vec<SC::Challenge> = (0..NUM_LOOKUP_CHALLENGES)
+                    .map(|_| challenger.sample_algebra_element())
+                    .collect();
+                let (permutation_trace, cumulative_sum) = info_span!("generate permutation trace")
+                    .in_scope(|| {
+                        generate_permutation_trace(
+                            interaction_values,
+                            &sends,
+                            &receives,
+                            &randomness,
+                        )
+                    });
+                let (permutation_commit, permutation_data) =
+                    info_span!("commit to permutation trace").in_scope(|| {
+                        pcs.commit([(trace_domain, permutation_trace.flatten_to_base())])
+                    });
+                challenger.observe(permutation_commit.clone());
+                challenger.observe_algebra_element(cumulative_sum);
+                (
+                    randomness,
+                    Some(permutation_commit),
+                    Some(permutation_data),
+                    Some(cumulative_sum),
+                )
+            }
+            None => (vec![], None, None, None),
+        };
+
+    // Generate and commit to each auxiliary phase in turn. The challenges of a phase are sampled
+    // after the main trace and all earlier phases have been observed, so a phase may depend on
+    // everything committed before it.
+    let mut aux_challenges: Vec<Vec<SC::Challenge>> = Vec::with_capacity(vk.aux_phases.len());
+    let mut aux_traces: Vec<RowMajorMatrix<SC::Challenge>> =
+        Vec::with_capacity(vk.aux_phases.len());
+    let mut aux_commits = Vec::with_capacity(vk.aux_phases.len());
+    let mut aux_data = Vec::with_capacity(vk.aux_phases.len());
+    for (phase, aux_phase) in vk.aux_phases.iter().enumerate() {
+        let main_trace = main_trace.as_ref().unwrap();
+        let challenges: Vec<SC::Challenge> = (0..aux_phase.num_challenges)
+            .map(|_| challenger.sample_algebra_element())
+            .collect();
+        let aux_trace = info_span!("generate auxiliary trace", phase).in_scope(|| {
+            air.generate_aux_trace(
+                phase,
+                preprocessed_trace.as_ref(),
+                main_trace,
+                &aux_traces,
+                &challenges,
+            )
+        });
+        assert_eq!(
+            aux_trace.width(),
+            aux_phase.width,
+            "auxiliary trace width does not match the declared phase"
+        );
+        assert_eq!(
+            aux_trace.height(),
+            degree,
+            "auxiliary trace height must match the main trace height"
+        );
+        let (commit, data) = info_span!("commit to auxiliary trace", phase)
+            .in_scope(|| pcs.commit([(trace_domain, aux_trace.clone().flatten_to_base())]));
+        challenger.observe(commit.clone());
+        aux_challenges.push(challenges);
+        aux_traces.push(aux_trace);
+        aux_commits.push(commit);
+        aux_data.push(data);
+    }
+
+    #[cfg(debug_assertions)]
+    if let Some(main_trace) = &main_trace {
+        crate::check_constraints::check_constraints(
+            air,
+            main_trace,
+            preprocessed_trace.as_ref(),
+            &aux_traces,
+            &aux_challenges,
+            public_values,
+        );
+    }
+
     // Get the first Fiat Shamir challenge which will be used to combine all constraint polynomials
     // into a single polynomial.
     //
@@ -159,19 +320,36 @@ where
     // This only works if the trace domain is `gH'` and the quotient domain is `gK` for some subgroup `K` contained in `H'`.
     // TODO: Make this explicit in `get_evaluations_on_domain` or otherwise fix this.
     let trace_on_quotient_domain = pcs.get_evaluations_on_domain(&trace_data, 0, quotient_domain);
+    let preprocessed_on_quotient_domain = preprocessed_data.map(|preprocessed_data| {
+        pcs.get_evaluations_on_domain(preprocessed_data, 0, quotient_domain)
+    });
+    let permutation_on_quotient_domain = permutation_data.as_ref().map(|permutation_data| {
+        pcs.get_evaluations_on_domain(permutation_data, 0, quotient_domain)
+    });
+    let aux_on_quotient_domain = aux_data
+        .iter()
+        .map(|aux_data| pcs.get_evaluations_on_domain(aux_data, 0, quotient_domain))
+        .collect();
 
     // Compute the quotient polynomial `Q(x)` by evaluating
     //          `C(T_1(x), ..., T_w(x), T_1(hx), ..., T_w(hx), selectors(x)) / Z_H(x)`
     // at every point in the quotient domain. The degree of `Q(x)` is `<= deg(C(x)) - N = 2N - 2` in the case
     // where `deg(C) = 3`. (See the discussion above constraint_degree for more details.)
-    let quotient_values = quotient_values(
-        air,
+    let quotient_values = quotient_values::<SC, _>(
+        &proving_key.constraint_dag,
+        &vk.main_row_offsets,
+        &vk.periodic_columns,
         public_values,
         trace_domain,
         quotient_domain,
         trace_on_quotient_domain,
+        preprocessed_on_quotient_domain,
+        permutation_on_quotient_domain,
+        &permutation_randomness,
+        cumulative_sum.unwrap_or_default(),
+        aux_on_quotient_domain,
+        &aux_challenges,
         alpha,
-        constraint_count,
     );
 
     // Due to `alpha`, evaluations of `Q` all lie in the extension field `E`.
@@ -228,6 +406,8 @@ where
         trace: trace_commit,
         quotient_chunks: quotient_commit,
         random: opt_r_commit.clone(),
+        permutation: permutation_commit,
+        aux: aux_commits,
     };
 
     if let Some(r_commit) = opt_r_commit {
@@ -247,16 +427,31 @@ where
     // cases but it is a completeness issue and contributes a completeness error of |gK| = 2N/|EF|.
     let zeta: SC::Challenge = challenger.sample_algebra_element();
     let zeta_next = trace_domain.next_point(zeta).unwrap();
+    // The main trace is opened at the point of every row the AIR reads, starting with `zeta` and
+    // `zeta_next`.
+    let main_points = row_points::<SC>(trace_domain, zeta, &vk.main_row_offsets);
 
     let is_random = opt_r_data.is_some();
     let (opened_values, opening_proof) = info_span!("open").in_scope(|| {
         let round0 = opt_r_data.as_ref().map(|r_data| (r_data, vec![vec![zeta]]));
-        let round1 = (&trace_data, vec![vec![zeta, zeta_next]]);
+        let round1 = (&trace_data, vec![main_points]);
         let round2 = (&quotient_data, vec![vec![zeta]; quotient_degree]); // open every chunk at zeta
+        let round3 = preprocessed_data
+            .map(|preprocessed_data| (preprocessed_data, vec![vec![zeta, zeta_next]]));
+        let round4 = permutation_data
+            .as_ref()
+            .map(|permutation_data| (permutation_data, vec![vec![zeta, zeta_next]]));
+
+        let aux_rounds = aux_data
+            .iter()
+            .map(|aux_data| (aux_data, vec![vec![zeta, zeta_next]]));
 
         let rounds = round0
             .into_iter()
             .chain([round1, round2])
+            .chain(round3)
+            .chain(round4)
+            .chain(aux_rounds)
             .collect::<Vec<_>>();
 
         pcs.open(rounds, &mut challenger)
@@ -265,10 +460,38 @@ where
     let quotient_idx = <SC as StarkGenericConfig>::Pcs::QUOTIENT_IDX;
     let trace_local = opened_values[trace_idx][0][0].clone();
     let trace_next = opened_values[trace_idx][0][1].clone();
+    let trace_extra_rows = opened_values[trace_idx][0][2..].to_vec();
     let quotient_chunks = opened_values[quotient_idx]
         .iter()
         .map(|v| v[0].clone())
         .collect_vec();
+    let (preprocessed_local, preprocessed_next) = if preprocessed_data.is_some() {
+        let preprocessed_idx = <SC as StarkGenericConfig>::Pcs::PREPROCESSED_IDX;
+        (
+            Some(opened_values[preprocessed_idx][0][0].clone()),
+            Some(opened_values[preprocessed_idx][0][1].clone()),
+        )
+    } else {
+        (None, None)
+    };
+    let (permutation_local, permutation_next) = if permutation_data.is_some() {
+        let permutation_idx = quotient_idx + 1 + preprocessed_data.is_some() as usize;
+        (
+            Some(opened_values[permutation_idx][0][0].clone()),
+            Some(opened_values[permutation_idx][0][1].clone()),
+        )
+    } else {
+        (None, None)
+    };
+    // The auxiliary rounds come last, one per phase.
+    let aux_idx = quotient_idx
+        + 1
+        + preprocessed_data.is_some() as usize
+        + permutation_data.is_some() as usize;
+    let (aux_local, aux_next) = opened_values[aux_idx..]
+        .iter()
+        .map(|opened| (opened[0][0].clone(), opened[0][1].clone()))
+        .unzip();
     let random = if is_random {
         Some(opened_values[0][0][0].clone())
     } else {
@@ -277,42 +500,85 @@ where
     let opened_values = OpenedValues {
         trace_local,
         trace_next,
+        trace_extra_rows,
+        preprocessed_local,
+        preprocessed_next,
         quotient_chunks,
         random,
+        permutation_local,
+        permutation_next,
+        aux_local,
+        aux_next,
     };
     Proof {
         commitments,
         opened_values,
         opening_proof,
         degree_bits: log_ext_degree,
+        cumulative_sum,
     }
 }
 
 #[instrument(name = "compute quotient polynomial", skip_all)]
 // TODO: Group some arguments to remove the `allow`?
 #[allow(clippy::too_many_arguments)]
-fn quotient_values<SC, A, Mat>(
-    air: &A,
+pub(crate) fn quotient_values<SC, Mat>(
+    constraints: &ConstraintDag<Val<SC>>,
+    main_row_offsets: &[usize],
+    periodic_columns: &[Vec<Val<SC>>],
     public_values: &Vec<Val<SC>>,
     trace_domain: Domain<SC>,
     quotient_domain: Domain<SC>,
     trace_on_quotient_domain: Mat,
+    preprocessed_on_quotient_domain: Option<Mat>,
+    permutation_on_quotient_domain: Option<Mat>,
+    permutation_randomness: &[SC::Challenge],
+    cumulative_sum: SC::Challenge,
+    aux_on_quotient_domain: Vec<Mat>,
+    aux_challenges: &[Vec<SC::Challenge>],
     alpha: SC::Challenge,
-    constraint_count: usize,
 ) -> Vec<SC::Challenge>
 where
     SC: StarkGenericConfig,
-    A: for<'a> Air<ProverConstraintFolder<'a, SC>>,
     Mat: Matrix<Val<SC>> + Sync,
 {
     let quotient_size = quotient_domain.size();
     let width = trace_on_quotient_domain.width();
+    let preprocessed_width = preprocessed_on_quotient_domain
+        .as_ref()
+        .map_or(0, Matrix::width);
+    // The permutation trace was flattened to base field columns when it was committed to.
+    let permutation_width = permutation_on_quotient_domain
+        .as_ref()
+        .map_or(0, |permutation| {
+            permutation.width() / <SC::Challenge as BasedVectorSpace<Val<SC>>>::DIMENSION
+        });
     let mut sels = debug_span!("Compute Selectors")
         .in_scope(|| trace_domain.selectors_on_coset(quotient_domain));
 
     let qdb = log2_strict_usize(quotient_domain.size()) - log2_strict_usize(trace_domain.size());
     let next_step = 1 << qdb;
 
+    // A window of `size` rows fits below row `x` if and only if each of the `size - 1` rows from
+    // `x` onwards has a next row, so its selector is the product of the transition selectors of
+    // those rows. The row after `x` is `next_step` points further along the quotient domain.
+    let window_size = main_row_offsets.last().map_or(2, |&offset| offset + 1);
+    let mut is_transition_windows: Vec<Vec<Val<SC>>> = Vec::with_capacity(window_size - 2);
+    for size in 3..=window_size {
+        let previous = is_transition_windows.last().unwrap_or(&sels.is_transition);
+        let shift = (size - 2) * next_step;
+        let selector = (0..quotient_size)
+            .map(|i| previous[i] * sels.is_transition[(i + shift) % quotient_size])
+            .collect();
+        is_transition_windows.push(selector);
+    }
+
+    // Periodic columns are evaluated from their period alone, as they are never committed to.
+    let mut periodic_on_quotient_domain = periodic_columns
+        .iter()
+        .map(|column| trace_domain.periodic_column_on_coset(column, quotient_domain))
+        .collect_vec();
+
     // We take PackedVal::<SC>::WIDTH worth of values at a time from a quotient_size slice, so we need to
     // pad with default values in the case where quotient_size is smaller than PackedVal::<SC>::WIDTH.
     for _ in quotient_size..PackedVal::<SC>::WIDTH {
@@ -320,9 +586,18 @@ where
         sels.is_last_row.push(Val::<SC>::default());
         sels.is_transition.push(Val::<SC>::default());
         sels.inv_vanishing.push(Val::<SC>::default());
+        for selector in &mut is_transition_windows {
+            selector.push(Val::<SC>::default());
+        }
+        for column in &mut periodic_on_quotient_domain {
+            column.push(Val::<SC>::default());
+        }
     }
 
-    let mut alpha_powers = alpha.powers().take(constraint_count).collect_vec();
+    let mut alpha_powers = alpha
+        .powers()
+        .take(constraints.constraints().len())
+        .collect_vec();
     alpha_powers.reverse();
     // alpha powers looks like Vec<EF> ~ Vec<[F; D]>
     // It's useful to also have access to the transpose of this of form [Vec<F>; D].
@@ -343,26 +618,77 @@ where
             let is_first_row = *PackedVal::<SC>::from_slice(&sels.is_first_row[i_range.clone()]);
             let is_last_row = *PackedVal::<SC>::from_slice(&sels.is_last_row[i_range.clone()]);
             let is_transition = *PackedVal::<SC>::from_slice(&sels.is_transition[i_range.clone()]);
+            let is_transition_windows = is_transition_windows
+                .iter()
+                .map(|selector| *PackedVal::<SC>::from_slice(&selector[i_range.clone()]))
+                .collect();
+            let periodic_values = periodic_on_quotient_domain
+                .iter()
+                .map(|column| *PackedVal::<SC>::from_slice(&column[i_range.clone()]))
+                .collect();
             let inv_vanishing = *PackedVal::<SC>::from_slice(&sels.inv_vanishing[i_range]);
 
-            let main = RowMajorMatrix::new(
-                trace_on_quotient_domain.vertically_packed_row_pair(i_start, next_step),
-                width,
+            let mut main_values =
+                trace_on_quotient_domain.vertically_packed_row_pair(i_start, next_step);
+            for &offset in &main_row_offsets[2..] {
+                main_values.extend(
+                    trace_on_quotient_domain
+                        .vertically_packed_row::<PackedVal<SC>>(i_start + offset * next_step),
+                );
+            }
+            let main = RowMajorMatrix::new(main_values, width);
+            let preprocessed = RowMajorMatrix::new(
+                preprocessed_on_quotient_domain
+                    .as_ref()
+                    .map_or_else(Vec::new, |preprocessed| {
+                        preprocessed.vertically_packed_row_pair(i_start, next_step)
+                    }),
+                preprocessed_width,
+            );
+            let permutation = RowMajorMatrix::new(
+                permutation_on_quotient_domain
+                    .as_ref()
+                    .map_or_else(Vec::new, |permutation| {
+                        pack_extension_rows::<SC>(
+                            &permutation.vertically_packed_row_pair(i_start, next_step),
+                        )
+                    }),
+                permutation_width,
             );
 
+            let aux = aux_on_quotient_domain
+                .iter()
+                .map(|aux| {
+                    RowMajorMatrix::new(
+                        pack_extension_rows::<SC>(
+                            &aux.vertically_packed_row_pair(i_start, next_step),
+                        ),
+                        aux.width() / SC::Challenge::DIMENSION,
+                    )
+                })
+                .collect_vec();
+
             let accumulator = PackedChallenge::<SC>::ZERO;
-            let mut folder = ProverConstraintFolder {
+            let mut folder = ProverConstraintFolder::<SC> {
                 main: main.as_view(),
+                preprocessed: preprocessed.as_view(),
+                permutation: permutation.as_view(),
+                permutation_randomness,
+                cumulative_sum,
+                aux: aux.iter().map(RowMajorMatrix::as_view).collect(),
+                aux_challenges,
                 public_values,
+                periodic_values,
                 is_first_row,
                 is_last_row,
                 is_transition,
+                is_transition_windows,
                 alpha_powers: &alpha_powers,
                 decomposed_alpha_powers: &decomposed_alpha_powers,
                 accumulator,
                 constraint_index: 0,
             };
-            air.eval(&mut folder);
+            constraints.eval(&mut folder);
 
             // quotient(x) = constraints(x) / Z_H(x)
             let quotient = folder.accumulator * inv_vanishing;
@@ -376,3 +702,34 @@ where
         })
         .collect()
 }
+
+/// The points of the rows at `offsets` from the row of `zeta`, in the same order.
+pub(crate) fn row_points<SC: StarkGenericConfig>(
+    trace_domain: Domain<SC>,
+    zeta: SC::Challenge,
+    offsets: &[usize],
+) -> Vec<SC::Challenge> {
+    let mut point = zeta;
+    let mut row = 0;
+    offsets
+        .iter()
+        .map(|&offset| {
+            for _ in row..offset {
+                point = trace_domain.next_point(point).unwrap();
+            }
+            row = offset;
+            point
+        })
+        .collect()
+}
+
+/// Recombine packed rows of a trace which was flattened to base field columns into packed
+/// extension field values.
+fn pack_extension_rows<SC: StarkGenericConfig>(
+    values: &[PackedVal<SC>],
+) -> Vec<PackedChallenge<SC>> {
+    values
+        .chunks_exact(SC::Challenge::DIMENSION)
+        .map(|coeffs| PackedChallenge::<SC>::from_basis_coefficients_fn(|i| coeffs[i]))
+        .collect()
+}
@@ -0,0 +1,472 @@
+//! Proven bounds on the soundness error of a STARK, as opposed to the conjectured bound of
+//! [`FriConfig::conjectured_soundness_bits`](p3_fri::FriConfig::conjectured_soundness_bits).
+//!
+//! A cheating prover can succeed in one of the following ways, each of which is bounded by a
+//! separate term of the [`SoundnessReport`]:
+//!
+//! - The random `alpha` combining the constraints cancels out a nonzero constraint, with
+//!   probability at most `L C / |EF|` for `C` constraints.
+//! - The out-of-domain point `zeta` is a root of the difference between the claimed and the actual
+//!   composition of some codeword close to the committed trace, with probability at most
+//!   `L (d (n + k - 1) + n - 1) / |EF|` for constraints of degree `d`, a trace of height `n` and
+//!   `k` opened rows.
+//! - The commit phase of FRI, batching `N` polynomials with powers of a challenge and then folding
+//!   the result by up to `2^a` in every round, again with powers of a challenge, maps a far
+//!   function to a close one.
+//! - Every query of the query phase of FRI misses the disagreements, after the prover ground
+//!   through the proof of work.
+//! - The prover finds a collision of the hash function of the Merkle trees.
+//!
+//! Here `|EF|` is the size of the challenge field and `L` is the size of the list of codewords the
+//! committed functions may be close to. The FRI terms are computed either in the unique decoding
+//! regime, where `L = 1`, or up to the Johnson bound, following Theorem 1.5 of
+//! [BCIKS20](https://eprint.iacr.org/2020/654), where the query phase is stronger but the other
+//! terms are weaker. The proximity gaps of circle codes match those of Reed-Solomon codes of the
+//! same rate, so the same bounds apply to `CirclePcs`.
+
+use p3_air::Air;
+use p3_commit::{FriBasedPcs, FriParameters, Pcs};
+use p3_field::{BasedVectorSpace, Field};
+use p3_matrix::Matrix;
+
+use crate::symbolic_builder::log_quotient_degree_for;
+use crate::{
+    StarkGenericConfig, SymbolicAirBuilder, SymbolicExpression, Val, get_symbolic_constraints,
+    permutation_width,
+};
+
+/// The largest Johnson proximity parameter `m` tried by [`SoundnessParameters::best_report`].
+pub(crate) const MAX_JOHNSON_M: usize = 64;
+
+/// The decoding regime in which the FRI terms of a [`SoundnessReport`] are computed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProximityRegime {
+    /// Proximity up to half the minimum distance, `(1 - rho) / 2`.
+    UniqueDecoding,
+    /// Proximity up to `1 - sqrt(rho) (1 + 1 / 2m)`, for a parameter `m >= 3`.
+    Johnson { m: usize },
+}
+
+/// The quantities which the soundness error of a proof depends on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SoundnessParameters {
+    /// The log of the height of the committed trace, including the doubling of a ZK PCS.
+    pub log_degree: usize,
+    /// The number of constraints, including those of the lookup argument.
+    pub num_constraints: usize,
+    /// The largest degree of any constraint.
+    pub max_constraint_degree: usize,
+    /// The number of rows of the main trace opened at each query point.
+    pub num_opening_points: usize,
+    /// The number of base field polynomial openings batched into a single FRI instance.
+    pub num_batched_polynomials: usize,
+    /// The log of the size of the challenge field.
+    pub log_field_size: f64,
+    /// The parameters of the FRI low-degree test.
+    pub fri: FriParameters,
+    /// The number of bits of a Merkle tree digest.
+    pub merkle_digest_bits: usize,
+}
+
+/// The number of bits of security of each source of soundness error, as bounded by
+/// [`SoundnessParameters::report`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SoundnessReport {
+    /// The regime the FRI terms were computed in.
+    pub regime: ProximityRegime,
+    /// The security against `alpha` cancelling out a nonzero constraint.
+    pub constraint_batching_bits: f64,
+    /// The security of the out-of-domain (DEEP) check at `zeta`.
+    pub ood_bits: f64,
+    /// The security of the commit phase of FRI, including the batching of its inputs.
+    pub fri_commit_bits: f64,
+    /// The security of the query phase of FRI, excluding the proof of work.
+    pub fri_query_bits: f64,
+    /// The bits of grinding added to the query phase.
+    pub proof_of_work_bits: f64,
+    /// The collision resistance of the Merkle tree hash, i.e. half its digest size.
+    pub merkle_bits: f64,
+    /// The overall security: the statistical errors are added up, and the result is capped by
+    /// the collision resistance of the hash.
+    pub total_bits: f64,
+}
+
+impl SoundnessParameters {
+    /// Collect the soundness parameters of proving `air` with `config`, for a trace of height
+    /// `2^log_degree` and Merkle trees with digests of `merkle_digest_bits` bits.
+    ///
+    /// The random codewords a hiding PCS adds to each commitment are not counted among the
+    /// batched polynomials.
+    pub fn new<SC, A>(
+        config: &SC,
+        air: &A,
+        num_public_values: usize,
+        log_degree: usize,
+        merkle_digest_bits: usize,
+    ) -> Self
+    where
+        SC: StarkGenericConfig,
+        SC::Pcs: FriBasedPcs,
+        A: Air<SymbolicAirBuilder<Val<SC>>>,
+    {
+        let preprocessed_width = air.preprocessed_trace().map_or(0, |trace| trace.width());
+        let constraints =
+            get_symbolic_constraints::<Val<SC>, A>(air, preprocessed_width, num_public_values);
+        let max_constraint_degree = constraints
+            .iter()
+            .map(SymbolicExpression::degree_multiple)
+            .max()
+            .unwrap_or(0);
+        let is_zk = config.is_zk();
+        let num_quotient_chunks =
+            1 << (log_quotient_degree_for(max_constraint_degree, is_zk) + is_zk);
+
+        // Every column is opened at every point of its row window, and the extension field
+        // columns are committed to as their base field coordinates.
+        let dimension = SC::Challenge::DIMENSION;
+        let num_opening_points = air.main_row_offsets().len();
+        let extension_width = permutation_width(&air.sends(), &air.receives())
+            + air
+                .aux_phases()
+                .iter()
+                .map(|phase| phase.width)
+                .sum::<usize>();
+        let num_batched_polynomials = air.width() * num_opening_points
+            + 2 * preprocessed_width
+            + 2 * extension_width * dimension
+            + num_quotient_chunks * dimension
+            + if <SC::Pcs as Pcs<SC::Challenge, SC::Challenger>>::ZK {
+                dimension
+            } else {
+                0
+            };
+
+        Self {
+            log_degree: log_degree + is_zk,
+            num_constraints: constraints.len(),
+            max_constraint_degree,
+            num_opening_points,
+            num_batched_polynomials,
+            log_field_size: log_extension_field_size::<Val<SC>>(dimension),
+            fri: config.pcs().fri_parameters(),
+            merkle_digest_bits,
+        }
+    }
+
+    /// Bound the soundness error with the FRI terms computed in `regime`.
+    pub fn report(&self, regime: ProximityRegime) -> SoundnessReport {
+        let log_blowup = self.fri.log_blowup as f64;
+        let log_lde_size = (self.log_degree + self.fri.log_blowup) as f64;
+        // `log_list_size` is the log of `L`, `log_agreement` the log of the probability that a
+        // single query misses, and the FRI commit phase error is
+        // `commit_factor sum_i |D_i|^commit_exponent / |EF|` over its batching and folding steps.
+        let (log_list_size, log_agreement, log_commit_factor, commit_exponent) = match regime {
+            ProximityRegime::UniqueDecoding => {
+                let rate = exp2(-log_blowup);
+                (0.0, log2((1.0 + rate) / 2.0), 0.0, 1.0)
+            }
+            ProximityRegime::Johnson { m } => {
+                assert!(m >= 3, "the Johnson proximity parameter must be at least 3");
+                let m_plus_half = m as f64 + 0.5;
+                (
+                    log2(m_plus_half) + log_blowup / 2.0,
+                    log2(1.0 + 0.5 / m as f64) - log_blowup / 2.0,
+                    7.0 * log2(m_plus_half) - log2(3.0) + 1.5 * log_blowup,
+                    2.0,
+                )
+            }
+        };
+
+        let num_constraints = self.num_constraints.max(1) as f64;
+        let constraint_batching_bits = self.log_field_size - log_list_size - log2(num_constraints);
+
+        let height = exp2(self.log_degree as f64);
+        let degree = self.max_constraint_degree.max(1) as f64;
+        let ood_numerator = degree * (height + self.num_opening_points as f64 - 1.0) + height - 1.0;
+        let ood_bits = self.log_field_size - log_list_size - log2(ood_numerator.max(1.0));
+
+        // Batching `N` polynomials with powers of a challenge costs `N - 1` times a single affine
+        // combination over the whole domain. A folding round of arity `2^a` combines `2^a`
+        // functions over a domain of size `|D| / 2^i` for some `i`, and every round starts at a
+        // different `i`, so summing `2^a - 1` combinations over every `i` bounds them all.
+        let num_rounds = self.log_degree.saturating_sub(self.fri.log_final_poly_len);
+        let max_arity = exp2(self.fri.log_folding_arity.max(1) as f64);
+        let folding_sum = (max_arity - 1.0)
+            * (0..num_rounds)
+                .map(|i| exp2(-commit_exponent * i as f64))
+                .sum::<f64>();
+        let batching_sum = self.num_batched_polynomials.saturating_sub(1) as f64;
+        let fri_commit_bits = self.log_field_size
+            - log_commit_factor
+            - commit_exponent * log_lde_size
+            - log2((batching_sum + folding_sum).max(1.0));
+
+        let fri_query_bits = -(self.fri.num_queries as f64) * log_agreement;
+        let proof_of_work_bits = self.fri.proof_of_work_bits as f64;
+        let merkle_bits = self.merkle_digest_bits as f64 / 2.0;
+
+        let protocol_error = [
+            constraint_batching_bits,
+            ood_bits,
+            fri_commit_bits,
+            fri_query_bits + proof_of_work_bits,
+        ]
+        .into_iter()
+        .map(|bits| exp2(-bits))
+        .sum::<f64>();
+        let total_bits = (-log2(protocol_error)).min(merkle_bits);
+
+        SoundnessReport {
+            regime,
+            constraint_batching_bits,
+            ood_bits,
+            fri_commit_bits,
+            fri_query_bits,
+            proof_of_work_bits,
+            merkle_bits,
+            total_bits,
+        }
+    }
+
+    /// Bound the soundness error in the regime which gives the most bits of security, trying
+    /// unique decoding and the Johnson bound with every `m` from `3` to `64`.
+    pub fn best_report(&self) -> SoundnessReport {
+        core::iter::once(ProximityRegime::UniqueDecoding)
+            .chain((3..=MAX_JOHNSON_M).map(|m| ProximityRegime::Johnson { m }))
+            .map(|regime| self.report(regime))
+            .fold(None, |best: Option<SoundnessReport>, report| match best {
+                Some(best) if best.total_bits >= report.total_bits => Some(best),
+                _ => Some(report),
+            })
+            .unwrap()
+    }
+}
+
+/// Compute the proven bits of security of proving `air` with `config`, for a trace of height
+/// `2^log_degree` and Merkle trees with digests of `merkle_digest_bits` bits.
+///
+/// This is [`SoundnessParameters::best_report`] of the parameters of the AIR and configuration.
+pub fn proven_soundness<SC, A>(
+    config: &SC,
+    air: &A,
+    num_public_values: usize,
+    log_degree: usize,
+    merkle_digest_bits: usize,
+) -> SoundnessReport
+where
+    SC: StarkGenericConfig,
+    SC::Pcs: FriBasedPcs,
+    A: Air<SymbolicAirBuilder<Val<SC>>>,
+{
+    SoundnessParameters::new(
+        config,
+        air,
+        num_public_values,
+        log_degree,
+        merkle_digest_bits,
+    )
+    .best_report()
+}
+
+/// The log of the size of the degree `extension_degree` extension of `F`, e.g. to fill in
+/// [`SoundnessParameters::log_field_size`] by hand.
+pub fn log_extension_field_size<F: Field>(extension_degree: usize) -> f64 {
+    extension_degree as f64 * log2_order::<F>()
+}
+
+/// The log of the order of the field `F`.
+fn log2_order<F: Field>() -> f64 {
+    // Keep the top 53 bits of the order, which is all an `f64` can hold.
+    let order = F::order();
+    let shift = order.bits().saturating_sub(53);
+    let top = (order >> shift)
+        .to_u64_digits()
+        .first()
+        .copied()
+        .unwrap_or(0);
+    log2(top as f64) + shift as f64
+}
+
+/// The base 2 logarithm of a positive `x`, as `f64::log2` is not available without `std`.
+pub(crate) fn log2(x: f64) -> f64 {
+    assert!(
+        x > 0.0,
+        "the logarithm is only defined for positive numbers"
+    );
+    // Split `x` into `2^exponent * mantissa` with `mantissa` in `[1, 2)`, and sum the series
+    // `ln(mantissa) = 2 atanh(z) = 2 (z + z^3 / 3 + z^5 / 5 + ...)` for `z <= 1 / 3`.
+    let bits = x.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i64 - 1023;
+    let mantissa = f64::from_bits((bits & ((1 << 52) - 1)) | (1023 << 52));
+    let z = (mantissa - 1.0) / (mantissa + 1.0);
+    let z_squared = z * z;
+    let mut power = z;
+    let mut ln = 0.0;
+    for k in 0..32 {
+        ln += power / (2 * k + 1) as f64;
+        power *= z_squared;
+    }
+    exponent as f64 + 2.0 * ln / core::f64::consts::LN_2
+}
+
+/// `2^x`, as `f64::exp2` is not available without `std`.
+pub(crate) fn exp2(x: f64) -> f64 {
+    if x < -1022.0 {
+        return 0.0;
+    }
+    assert!(x < 1024.0, "2^{x} overflows");
+    // Split `x` into an integer and a fractional part in `[0, 1)`, and sum the Taylor series of
+    // `e^(fraction ln 2)`.
+    let mut integer = x as i64;
+    if (integer as f64) > x {
+        integer -= 1;
+    }
+    let y = (x - integer as f64) * core::f64::consts::LN_2;
+    let mut term = 1.0;
+    let mut fraction = 0.0;
+    for k in 1..32 {
+        fraction += term;
+        term *= y / k as f64;
+    }
+    fraction * f64::from_bits(((integer + 1023) as u64) << 52)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn assert_close(x: f64, y: f64) {
+        assert!((x - y).abs() < 1e-6, "{x} is not close to {y}");
+    }
+
+    fn parameters() -> SoundnessParameters {
+        SoundnessParameters {
+            log_degree: 20,
+            num_constraints: 100,
+            max_constraint_degree: 3,
+            num_opening_points: 2,
+            num_batched_polynomials: 400,
+            // The degree 4 extension of BabyBear.
+            log_field_size: 4.0 * 30.906_890_595_608_52,
+            fri: FriParameters {
+                log_blowup: 1,
+                log_final_poly_len: 0,
+                log_folding_arity: 1,
+                num_queries: 100,
+                proof_of_work_bits: 16,
+            },
+            merkle_digest_bits: 248,
+        }
+    }
+
+    #[test]
+    fn test_log2_exp2() {
+        assert_close(log2(1.0), 0.0);
+        assert_close(log2(3.0), 1.584_962_500_721_156);
+        assert_close(log2(1e-30), -99.657_842_846_620_87);
+        assert_close(exp2(-0.5), core::f64::consts::FRAC_1_SQRT_2);
+        assert_close(exp2(10.25) / 1024.0, 1.189_207_115_002_721);
+        assert_eq!(exp2(-2000.0), 0.0);
+    }
+
+    #[test]
+    fn test_unique_decoding() {
+        let report = parameters().report(ProximityRegime::UniqueDecoding);
+        // Each query misses with probability `(1 + 1/2) / 2`.
+        assert_close(report.fri_query_bits, 100.0 * log2(4.0 / 3.0));
+        assert_close(
+            report.constraint_batching_bits,
+            4.0 * 30.906_890_595_608_52 - log2(100.0),
+        );
+        assert_close(
+            report.ood_bits,
+            4.0 * 30.906_890_595_608_52 - log2(3.0 * (1048576.0 + 1.0) + 1048575.0),
+        );
+        // Batching 400 polynomials and 20 folding rounds over a domain of size 2^21.
+        assert_close(
+            report.fri_commit_bits,
+            4.0 * 30.906_890_595_608_52 - 21.0 - log2(399.0 + 2.0 - exp2(-19.0)),
+        );
+        assert_eq!(report.merkle_bits, 124.0);
+        // The query phase dominates the error.
+        assert!(report.total_bits < report.fri_query_bits + report.proof_of_work_bits);
+        assert!(report.total_bits > report.fri_query_bits + report.proof_of_work_bits - 0.01);
+    }
+
+    #[test]
+    fn test_johnson_bound() {
+        // A small trace keeps the quadratic commit phase error of the Johnson regime in check.
+        let params = SoundnessParameters {
+            log_degree: 10,
+            fri: FriParameters {
+                log_blowup: 3,
+                num_queries: 40,
+                ..parameters().fri
+            },
+            ..parameters()
+        };
+        let unique = params.report(ProximityRegime::UniqueDecoding);
+        let johnson = params.report(ProximityRegime::Johnson { m: 3 });
+        // The query phase is stronger up to the Johnson bound, every other term is weaker.
+        assert_close(
+            johnson.fri_query_bits,
+            -40.0 * log2(exp2(-1.5) * (1.0 + 1.0 / 6.0)),
+        );
+        assert!(johnson.fri_query_bits > unique.fri_query_bits);
+        assert!(johnson.fri_commit_bits < unique.fri_commit_bits);
+        assert!(johnson.ood_bits < unique.ood_bits);
+        assert!(johnson.constraint_batching_bits < unique.constraint_batching_bits);
+
+        let best = params.best_report();
+        assert!(best.total_bits >= unique.total_bits);
+        assert!(best.total_bits >= johnson.total_bits);
+        assert!(matches!(best.regime, ProximityRegime::Johnson { .. }));
+    }
+
+    #[test]
+    fn test_merkle_cap() {
+        let params = SoundnessParameters {
+            merkle_digest_bits: 160,
+            fri: FriParameters {
+                num_queries: 400,
+                ..parameters().fri
+            },
+            ..parameters()
+        };
+        assert_eq!(params.best_report().total_bits, 80.0);
+    }
+
+    #[test]
+    fn test_folding_arity() {
+        let params = SoundnessParameters {
+            num_batched_polynomials: 1,
+            ..parameters()
+        };
+        let quaternary = SoundnessParameters {
+            fri: FriParameters {
+                log_folding_arity: 2,
+                ..params.fri
+            },
+            ..params
+        };
+        // Each round combines four functions rather than two.
+        let regime = ProximityRegime::UniqueDecoding;
+        assert_close(
+            params.report(regime).fri_commit_bits - quaternary.report(regime).fri_commit_bits,
+            log2(3.0),
+        );
+    }
+
+    #[test]
+    fn test_johnson_parameter() {
+        let reports: Vec<_> = [3, 4, 5]
+            .into_iter()
+            .map(|m| parameters().report(ProximityRegime::Johnson { m }))
+            .collect();
+        // A larger `m` allows a larger proximity parameter, at the cost of a larger list.
+        assert!(reports[0].fri_query_bits < reports[2].fri_query_bits);
+        assert!(reports[0].ood_bits > reports[2].ood_bits);
+    }
+}
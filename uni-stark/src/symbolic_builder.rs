@@ -1,15 +1,20 @@
 use alloc::vec;
 use alloc::vec::Vec;
 
-use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, PairBuilder};
+use p3_air::{
+    Air, AirBuilder, AirBuilderWithPublicValues, AuxBuilder, AuxPhase, ExtensionBuilder,
+    PairBuilder, PeriodicAirBuilder, PermutationAirBuilder,
+};
 use p3_field::Field;
 use p3_matrix::dense::RowMajorMatrix;
 use p3_util::log2_ceil_usize;
 use tracing::instrument;
 
-use crate::Entry;
 use crate::symbolic_expression::SymbolicExpression;
 use crate::symbolic_variable::SymbolicVariable;
+use crate::{
+    Entry, LookupBuilder, NUM_LOOKUP_CHALLENGES, eval_lookup_constraints, permutation_width,
+};
 
 #[instrument(name = "infer log of constraint degree", skip_all)]
 pub fn get_log_quotient_degree<F, A>(
@@ -22,10 +27,17 @@ where
     F: Field,
     A: Air<SymbolicAirBuilder<F>>,
 {
+    log_quotient_degree_for(
+        get_max_constraint_degree(air, preprocessed_width, num_public_values),
+        is_zk,
+    )
+}
+
+/// Compute the log of the quotient degree from the maximal degree of the constraints.
+pub(crate) fn log_quotient_degree_for(max_constraint_degree: usize, is_zk: usize) -> usize {
     assert!(is_zk <= 1, "is_zk must be either 0 or 1");
     // We pad to at least degree 2, since a quotient argument doesn't make sense with smaller degrees.
-    let constraint_degree =
-        (get_max_constraint_degree(air, preprocessed_width, num_public_values) + is_zk).max(2);
+    let constraint_degree = (max_constraint_degree + is_zk).max(2);
 
     // The quotient's actual degree is approximately (max_constraint_degree - 1) n,
     // where subtracting 1 comes from division by the vanishing polynomial.
@@ -33,6 +45,12 @@ where
     log2_ceil_usize(constraint_degree - 1)
 }
 
+/// Whether `offsets` are strictly increasing and start with `0, 1`, as required of
+/// [`BaseAir::main_row_offsets`](p3_air::BaseAir::main_row_offsets).
+pub(crate) fn valid_main_row_offsets(offsets: &[usize]) -> bool {
+    offsets.starts_with(&[0, 1]) && offsets.windows(2).all(|pair| pair[0] < pair[1])
+}
+
 #[instrument(name = "infer constraint degree", skip_all, level = "debug")]
 pub fn get_max_constraint_degree<F, A>(
     air: &A,
@@ -60,8 +78,19 @@ where
     F: Field,
     A: Air<SymbolicAirBuilder<F>>,
 {
-    let mut builder = SymbolicAirBuilder::new(preprocessed_width, air.width(), num_public_values);
+    let sends = air.sends();
+    let receives = air.receives();
+    let mut builder = SymbolicAirBuilder::new(
+        preprocessed_width,
+        air.width(),
+        &air.main_row_offsets(),
+        num_public_values,
+        air.periodic_columns().len(),
+        permutation_width(&sends, &receives),
+        &air.aux_phases(),
+    );
     air.eval(&mut builder);
+    eval_lookup_constraints(&mut builder, &sends, &receives);
     builder.constraints()
 }
 
@@ -70,12 +99,27 @@ where
 pub struct SymbolicAirBuilder<F: Field> {
     preprocessed: RowMajorMatrix<SymbolicVariable<F>>,
     main: RowMajorMatrix<SymbolicVariable<F>>,
+    /// The number of rows spanned by the main window, i.e. one more than the largest row offset.
+    window_size: usize,
     public_values: Vec<SymbolicVariable<F>>,
+    periodic_values: Vec<SymbolicVariable<F>>,
+    permutation: RowMajorMatrix<SymbolicVariable<F>>,
+    permutation_randomness: Vec<SymbolicVariable<F>>,
+    aux: Vec<RowMajorMatrix<SymbolicVariable<F>>>,
+    aux_challenges: Vec<Vec<SymbolicVariable<F>>>,
     constraints: Vec<SymbolicExpression<F>>,
 }
 
 impl<F: Field> SymbolicAirBuilder<F> {
-    pub(crate) fn new(preprocessed_width: usize, width: usize, num_public_values: usize) -> Self {
+    pub(crate) fn new(
+        preprocessed_width: usize,
+        width: usize,
+        main_row_offsets: &[usize],
+        num_public_values: usize,
+        num_periodic_columns: usize,
+        permutation_width: usize,
+        aux_phases: &[AuxPhase],
+    ) -> Self {
         let prep_values = [0, 1]
             .into_iter()
             .flat_map(|offset| {
@@ -83,8 +127,7 @@ impl<F: Field> SymbolicAirBuilder<F> {
                     .map(move |index| SymbolicVariable::new(Entry::Preprocessed { offset }, index))
             })
             .collect();
-        let main_values = [0, 1]
-            .into_iter()
+        let main_values = (0..main_row_offsets.len())
             .flat_map(|offset| {
                 (0..width).map(move |index| SymbolicVariable::new(Entry::Main { offset }, index))
             })
@@ -92,10 +135,55 @@ impl<F: Field> SymbolicAirBuilder<F> {
         let public_values = (0..num_public_values)
             .map(move |index| SymbolicVariable::new(Entry::Public, index))
             .collect();
+        let periodic_values = (0..num_periodic_columns)
+            .map(move |index| SymbolicVariable::new(Entry::Periodic, index))
+            .collect();
+        let perm_values = [0, 1]
+            .into_iter()
+            .flat_map(|offset| {
+                (0..permutation_width)
+                    .map(move |index| SymbolicVariable::new(Entry::Permutation { offset }, index))
+            })
+            .collect();
+        // The cumulative sum follows the lookup challenges, as it is likewise fixed by the
+        // transcript before the constraints are folded.
+        let permutation_randomness = (0..=NUM_LOOKUP_CHALLENGES)
+            .map(|index| SymbolicVariable::new(Entry::Challenge, index))
+            .collect();
+        let aux = aux_phases
+            .iter()
+            .enumerate()
+            .map(|(phase, aux_phase)| {
+                let values = [0, 1]
+                    .into_iter()
+                    .flat_map(|offset| {
+                        (0..aux_phase.width).map(move |index| {
+                            SymbolicVariable::new(Entry::Aux { phase, offset }, index)
+                        })
+                    })
+                    .collect();
+                RowMajorMatrix::new(values, aux_phase.width)
+            })
+            .collect();
+        let aux_challenges = aux_phases
+            .iter()
+            .enumerate()
+            .map(|(phase, aux_phase)| {
+                (0..aux_phase.num_challenges)
+                    .map(|index| SymbolicVariable::new(Entry::AuxChallenge { phase }, index))
+                    .collect()
+            })
+            .collect();
         Self {
             preprocessed: RowMajorMatrix::new(prep_values, preprocessed_width),
             main: RowMajorMatrix::new(main_values, width),
+            window_size: main_row_offsets.last().map_or(2, |&offset| offset + 1),
             public_values,
+            periodic_values,
+            permutation: RowMajorMatrix::new(perm_values, permutation_width),
+            permutation_randomness,
+            aux,
+            aux_challenges,
             constraints: vec![],
         }
     }
@@ -124,12 +212,18 @@ impl<F: Field> AirBuilder for SymbolicAirBuilder<F> {
     }
 
     /// # Panics
-    /// This function panics if `size` is not `2`.
+    /// This function panics if `size` is less than `2` or larger than the window spanned by the
+    /// AIR's main row offsets.
     fn is_transition_window(&self, size: usize) -> Self::Expr {
-        if size == 2 {
-            SymbolicExpression::IsTransition
-        } else {
-            panic!("uni-stark only supports a window size of 2")
+        match size {
+            2 => SymbolicExpression::IsTransition,
+            _ if size > 2 && size <= self.window_size => {
+                SymbolicExpression::IsTransitionWindow(size)
+            }
+            _ => panic!(
+                "window size {size} is not supported by main row offsets spanning {} rows",
+                self.window_size
+            ),
         }
     }
 
@@ -145,12 +239,66 @@ impl<F: Field> AirBuilderWithPublicValues for SymbolicAirBuilder<F> {
     }
 }
 
+impl<F: Field> PeriodicAirBuilder for SymbolicAirBuilder<F> {
+    type PeriodicVar = SymbolicVariable<F>;
+    fn periodic_values(&self) -> &[Self::PeriodicVar] {
+        &self.periodic_values
+    }
+}
+
 impl<F: Field> PairBuilder for SymbolicAirBuilder<F> {
     fn preprocessed(&self) -> Self::M {
         self.preprocessed.clone()
     }
 }
 
+/// Extension field values are represented by the same symbolic expressions as base field values.
+/// This suffices to compute degrees and to record the shape of the constraints.
+impl<F: Field> ExtensionBuilder for SymbolicAirBuilder<F> {
+    type EF = F;
+    type ExprEF = SymbolicExpression<F>;
+    type VarEF = SymbolicVariable<F>;
+
+    fn assert_zero_ext<I>(&mut self, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        self.constraints.push(x.into());
+    }
+}
+
+impl<F: Field> PermutationAirBuilder for SymbolicAirBuilder<F> {
+    type MP = RowMajorMatrix<Self::VarEF>;
+    type RandomVar = SymbolicVariable<F>;
+
+    fn permutation(&self) -> Self::MP {
+        self.permutation.clone()
+    }
+
+    fn permutation_randomness(&self) -> &[Self::RandomVar] {
+        &self.permutation_randomness[..NUM_LOOKUP_CHALLENGES]
+    }
+}
+
+impl<F: Field> LookupBuilder for SymbolicAirBuilder<F> {
+    fn cumulative_sum(&self) -> Self::RandomVar {
+        self.permutation_randomness[NUM_LOOKUP_CHALLENGES]
+    }
+}
+
+impl<F: Field> AuxBuilder for SymbolicAirBuilder<F> {
+    type MAux = RowMajorMatrix<Self::VarEF>;
+    type AuxChallenge = SymbolicVariable<F>;
+
+    fn aux(&self, phase: usize) -> Self::MAux {
+        self.aux[phase].clone()
+    }
+
+    fn aux_challenges(&self, phase: usize) -> &[Self::AuxChallenge] {
+        &self.aux_challenges[phase]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use alloc::vec;
@@ -271,7 +419,7 @@ mod tests {
 
     #[test]
     fn test_symbolic_air_builder_initialization() {
-        let builder = SymbolicAirBuilder::<BabyBear>::new(2, 4, 3);
+        let builder = SymbolicAirBuilder::<BabyBear>::new(2, 4, &[0, 1], 3, 0, 0, &[]);
 
         let expected_main = [
             SymbolicVariable::<BabyBear>::new(Entry::Main { offset: 0 }, 0),
@@ -300,7 +448,7 @@ mod tests {
 
     #[test]
     fn test_symbolic_air_builder_is_first_last_row() {
-        let builder = SymbolicAirBuilder::<BabyBear>::new(2, 4, 3);
+        let builder = SymbolicAirBuilder::<BabyBear>::new(2, 4, &[0, 1], 3, 0, 0, &[]);
 
         assert!(
             matches!(builder.is_first_row(), SymbolicExpression::IsFirstRow),
@@ -315,7 +463,7 @@ mod tests {
 
     #[test]
     fn test_symbolic_air_builder_assert_zero() {
-        let mut builder = SymbolicAirBuilder::<BabyBear>::new(2, 4, 3);
+        let mut builder = SymbolicAirBuilder::<BabyBear>::new(2, 4, &[0, 1], 3, 0, 0, &[]);
         let expr = SymbolicExpression::Constant(BabyBear::new(5));
         builder.assert_zero(expr.clone());
 
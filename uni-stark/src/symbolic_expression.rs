@@ -14,6 +14,9 @@ pub enum SymbolicExpression<F> {
     IsFirstRow,
     IsLastRow,
     IsTransition,
+    /// The selector of rows `r` for which rows `r` through `r + size - 1` are all in the trace,
+    /// for a window `size` larger than `2`.
+    IsTransitionWindow(usize),
     Constant(F),
     Add {
         x: Rc<Self>,
@@ -41,7 +44,9 @@ impl<F> SymbolicExpression<F> {
     pub const fn degree_multiple(&self) -> usize {
         match self {
             Self::Variable(v) => v.degree_multiple(),
-            Self::IsFirstRow | Self::IsLastRow => 1,
+            // A window selector vanishes on the last `size - 1` rows, so it has degree
+            // `size - 1`, which is at most `n - 1` as the trace is at least as tall as the window.
+            Self::IsFirstRow | Self::IsLastRow | Self::IsTransitionWindow(_) => 1,
             Self::IsTransition | Self::Constant(_) => 0,
             Self::Add {
                 degree_multiple, ..
@@ -284,6 +289,13 @@ mod tests {
             "IsTransition should have degree 0"
         );
 
+        let is_transition_window = SymbolicExpression::<BabyBear>::IsTransitionWindow(8);
+        assert_eq!(
+            is_transition_window.degree_multiple(),
+            1,
+            "IsTransitionWindow should have degree 1"
+        );
+
         let add_expr = SymbolicExpression::<BabyBear>::Add {
             x: Rc::new(variable_expr.clone()),
             y: Rc::new(preprocessed_var.clone()),
@@ -2,23 +2,43 @@ use core::marker::PhantomData;
 use core::ops::{Add, Mul, Sub};
 
 use p3_field::Field;
+use serde::{Deserialize, Serialize};
 
 use crate::symbolic_expression::SymbolicExpression;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Entry {
-    Preprocessed { offset: usize },
-    Main { offset: usize },
-    Permutation { offset: usize },
+    Preprocessed {
+        offset: usize,
+    },
+    /// A main column, where `offset` is the index of the row within the AIR's
+    /// [`main_row_offsets`](p3_air::BaseAir::main_row_offsets).
+    Main {
+        offset: usize,
+    },
+    Permutation {
+        offset: usize,
+    },
+    Aux {
+        phase: usize,
+        offset: usize,
+    },
     Public,
     Challenge,
+    AuxChallenge {
+        phase: usize,
+    },
+    /// A periodic column on the current row, see
+    /// [`periodic_columns`](p3_air::BaseAir::periodic_columns).
+    Periodic,
 }
 
 /// A variable within the evaluation window, i.e. a column in either the local or next row.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct SymbolicVariable<F> {
     pub entry: Entry,
     pub index: usize,
+    #[serde(skip)]
     pub(crate) _phantom: PhantomData<F>,
 }
 
@@ -33,8 +53,12 @@ impl<F> SymbolicVariable<F> {
 
     pub const fn degree_multiple(&self) -> usize {
         match self.entry {
-            Entry::Preprocessed { .. } | Entry::Main { .. } | Entry::Permutation { .. } => 1,
-            Entry::Public | Entry::Challenge => 0,
+            Entry::Preprocessed { .. }
+            | Entry::Main { .. }
+            | Entry::Permutation { .. }
+            | Entry::Aux { .. }
+            | Entry::Periodic => 1,
+            Entry::Public | Entry::Challenge | Entry::AuxChallenge { .. } => 0,
         }
     }
 }
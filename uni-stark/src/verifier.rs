@@ -3,7 +3,7 @@
 use alloc::vec;
 use alloc::vec::Vec;
 
-use itertools::Itertools;
+use itertools::{Itertools, izip};
 use p3_air::{Air, BaseAir};
 use p3_challenger::{CanObserve, FieldChallenger};
 use p3_commit::{Pcs, PolynomialSpace};
@@ -13,9 +13,18 @@ use p3_matrix::stack::VerticalPair;
 use p3_util::zip_eq::zip_eq;
 use tracing::instrument;
 
-use crate::symbolic_builder::{SymbolicAirBuilder, get_log_quotient_degree};
-use crate::{PcsError, Proof, StarkGenericConfig, Val, VerifierConstraintFolder};
+use crate::lookup::unflatten_extension_values;
+use crate::prover::row_points;
+use crate::symbolic_builder::SymbolicAirBuilder;
+use crate::{
+    Domain, NUM_LOOKUP_CHALLENGES, OpenedValues, PcsError, Proof, StarkGenericConfig,
+    StarkVerifyingKey, Val, VerifierConstraintFolder, eval_lookup_constraints, setup,
+};
 
+/// Verify a proof that some trace satisfies `air`.
+///
+/// This runs [`setup`] before verifying. When verifying several proofs for the same AIR, prefer
+/// calling [`setup`] once (or loading a serialized verifying key) and then [`verify_with_key`].
 #[instrument(skip_all)]
 pub fn verify<SC, A>(
     config: &SC,
@@ -26,19 +35,53 @@ pub fn verify<SC, A>(
 where
     SC: StarkGenericConfig,
     A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+{
+    let (_, verifying_key) = setup(config, air, public_values.len());
+    verify_with_key(config, &verifying_key, air, proof, public_values)
+}
+
+/// Verify a proof that some trace satisfies `air`, using a verifying key computed by [`setup`].
+#[instrument(skip_all)]
+pub fn verify_with_key<SC, A>(
+    config: &SC,
+    verifying_key: &StarkVerifyingKey<SC>,
+    air: &A,
+    proof: &Proof<SC>,
+    public_values: &Vec<Val<SC>>,
+) -> Result<(), VerificationError<PcsError<SC>>>
+where
+    SC: StarkGenericConfig,
+    A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
 {
     let Proof {
         commitments,
         opened_values,
         opening_proof,
         degree_bits,
+        cumulative_sum,
     } = proof;
 
     let pcs = config.pcs();
 
     let degree = 1 << degree_bits;
-    let log_quotient_degree =
-        get_log_quotient_degree::<Val<SC>, A>(air, 0, public_values.len(), config.is_zk());
+
+    if public_values.len() != verifying_key.num_public_values {
+        return Err(VerificationError::InvalidProofShape);
+    }
+
+    // As checked by the prover, the window must fit in the trace, or the main row offsets would
+    // wrap around the trace domain.
+    let main_row_offsets = &verifying_key.main_row_offsets;
+    if main_row_offsets.len() > 2 && degree >> config.is_zk() <= *main_row_offsets.last().unwrap() {
+        return Err(VerificationError::InvalidProofShape);
+    }
+
+    // The preprocessed trace is part of the AIR, so its commitment comes from the verifying key
+    // rather than from the proof.
+    let preprocessed = verifying_key.preprocessed.as_ref();
+    let preprocessed_width = verifying_key.preprocessed_width();
+
+    let log_quotient_degree = verifying_key.log_quotient_degree;
     let quotient_degree = 1 << (log_quotient_degree + config.is_zk());
 
     let mut challenger = config.initialise_challenger();
@@ -65,9 +108,60 @@ where
         return Err(VerificationError::RandomizationError);
     }
 
+    if preprocessed.is_some_and(|p| p.degree_bits + config.is_zk() != *degree_bits) {
+        return Err(VerificationError::InvalidProofShape);
+    }
+
+    // The permutation trace is present if and only if the AIR has lookups.
+    let permutation_width = verifying_key.permutation_width;
+    let has_lookups = permutation_width != 0;
+    if has_lookups != commitments.permutation.is_some() || has_lookups != cumulative_sum.is_some() {
+        return Err(VerificationError::InvalidProofShape);
+    }
+    // The lookups are balanced if and only if the running sum ends at zero.
+    if cumulative_sum.is_some_and(|sum| !sum.is_zero()) {
+        return Err(VerificationError::UnbalancedLookups);
+    }
+
+    // There is one commitment per auxiliary phase.
+    let aux_phases = &verifying_key.aux_phases;
+    if commitments.aux.len() != aux_phases.len() {
+        return Err(VerificationError::InvalidProofShape);
+    }
+
     let air_width = <A as BaseAir<Val<SC>>>::width(air);
+    let valid_aux_shape = |opened: &[Vec<SC::Challenge>]| {
+        opened.len() == aux_phases.len()
+            && opened.iter().zip(aux_phases).all(|(values, aux_phase)| {
+                values.len() == aux_phase.width * SC::Challenge::DIMENSION
+            })
+    };
+    let valid_permutation_shape = |opened: &Option<Vec<SC::Challenge>>| match opened {
+        Some(values) => has_lookups && values.len() == permutation_width * SC::Challenge::DIMENSION,
+        None => !has_lookups,
+    };
+    let valid_preprocessed_shape = |opened: &Option<Vec<SC::Challenge>>| match opened {
+        Some(values) => preprocessed.is_some() && values.len() == preprocessed_width,
+        None => preprocessed.is_none(),
+    };
     let valid_shape = opened_values.trace_local.len() == air_width
         && opened_values.trace_next.len() == air_width
+        && opened_values.trace_extra_rows.len() == main_row_offsets.len() - 2
+        && opened_values
+            .trace_extra_rows
+            .iter()
+            .all(|row| row.len() == air_width)
+        && valid_preprocessed_shape(&opened_values.preprocessed_local)
+        && valid_preprocessed_shape(&opened_values.preprocessed_next)
+        && valid_permutation_shape(&opened_values.permutation_local)
+        && valid_permutation_shape(&opened_values.permutation_next)
+        && valid_aux_shape(&opened_values.aux_local)
+        && valid_aux_shape(&opened_values.aux_next)
+        // Every periodic column must repeat a whole number of times over the trace.
+        && verifying_key
+            .periodic_columns
+            .iter()
+            .all(|column| column.len() <= init_trace_domain.size())
         && opened_values.quotient_chunks.len() == quotient_degree
         && opened_values
             .quotient_chunks
@@ -86,15 +180,43 @@ where
     // Observe the instance.
     challenger.observe(Val::<SC>::from_usize(proof.degree_bits));
     challenger.observe(Val::<SC>::from_usize(proof.degree_bits - config.is_zk()));
-    // TODO: Might be best practice to include other instance data here in the transcript, like some
-    // encoding of the AIR. This protects against transcript collisions between distinct instances.
-    // Practically speaking though, the only related known attack is from failing to include public
-    // values. It's not clear if failing to include other instance data could enable a transcript
-    // collision, since most such changes would completely change the set of satisfying witnesses.
+
+    // Observe the digest of the verifying key. This encodes the AIR and the commitment to its
+    // preprocessed trace, which protects against transcript collisions between distinct instances.
+    challenger.observe_algebra_element(verifying_key.digest(config));
 
     challenger.observe(commitments.trace.clone());
     challenger.observe_slice(public_values);
 
+    // We've already checked that the permutation commitment and the cumulative sum are present if
+    // and only if the AIR has lookups.
+    let permutation_randomness: Vec<SC::Challenge> =
+        if let (Some(permutation_commit), Some(sum)) = (&commitments.permutation, cumulative_sum) {
+            let randomness = (0..NUM_LOOKUP_CHALLENGES)
+                .map(|_| challenger.sample_algebra_element())
+                .collect();
+            challenger.observe(permutation_commit.clone());
+            challenger.observe_algebra_element(*sum);
+            randomness
+        } else {
+            vec![]
+        };
+
+    // Sample the challenges of each auxiliary phase before observing its commitment.
+    let aux_challenges: Vec<Vec<SC::Challenge>> = zip_eq(
+        aux_phases,
+        &commitments.aux,
+        VerificationError::InvalidProofShape,
+    )?
+    .map(|(aux_phase, aux_commit)| {
+        let challenges = (0..aux_phase.num_challenges)
+            .map(|_| challenger.sample_algebra_element())
+            .collect();
+        challenger.observe(aux_commit.clone());
+        challenges
+    })
+    .collect();
+
     // Get the first Fiat Shamir challenge which will be used to combine all constraint polynomials
     // into a single polynomial.
     //
@@ -113,6 +235,7 @@ where
     // Soundness Error: dN/|EF| where `N` is the trace length and our constraint polynomial has degree `d`.
     let zeta: SC::Challenge = challenger.sample_algebra_element();
     let zeta_next = init_trace_domain.next_point(zeta).unwrap();
+    let main_points = row_points::<SC>(init_trace_domain, zeta, main_row_offsets);
 
     // We've already checked that commitments.random and opened_values.random are present if and only if ZK is enabled.
     let mut coms_to_verify = if let Some(random_commit) = &commitments.random {
@@ -132,10 +255,16 @@ where
             commitments.trace.clone(),
             vec![(
                 trace_domain,
-                vec![
-                    (zeta, opened_values.trace_local.clone()),
-                    (zeta_next, opened_values.trace_next.clone()),
-                ],
+                // We've already checked that there is one opened row per main row offset.
+                main_points
+                    .into_iter()
+                    .zip(
+                        [&opened_values.trace_local, &opened_values.trace_next]
+                            .into_iter()
+                            .chain(&opened_values.trace_extra_rows),
+                    )
+                    .map(|(point, values)| (point, values.clone()))
+                    .collect(),
             )],
         ),
         (
@@ -150,10 +279,101 @@ where
             .collect_vec(),
         ),
     ]);
+    if let (Some(preprocessed), Some(preprocessed_local), Some(preprocessed_next)) = (
+        preprocessed,
+        &opened_values.preprocessed_local,
+        &opened_values.preprocessed_next,
+    ) {
+        coms_to_verify.push((
+            preprocessed.commitment.clone(),
+            vec![(
+                trace_domain,
+                vec![
+                    (zeta, preprocessed_local.clone()),
+                    (zeta_next, preprocessed_next.clone()),
+                ],
+            )],
+        ));
+    }
+
+    if let (Some(permutation_commit), Some(permutation_local), Some(permutation_next)) = (
+        &commitments.permutation,
+        &opened_values.permutation_local,
+        &opened_values.permutation_next,
+    ) {
+        coms_to_verify.push((
+            permutation_commit.clone(),
+            vec![(
+                trace_domain,
+                vec![
+                    (zeta, permutation_local.clone()),
+                    (zeta_next, permutation_next.clone()),
+                ],
+            )],
+        ));
+    }
+
+    coms_to_verify.extend(
+        izip!(
+            &commitments.aux,
+            &opened_values.aux_local,
+            &opened_values.aux_next
+        )
+        .map(|(aux_commit, aux_local, aux_next)| {
+            (
+                aux_commit.clone(),
+                vec![(
+                    trace_domain,
+                    vec![(zeta, aux_local.clone()), (zeta_next, aux_next.clone())],
+                )],
+            )
+        }),
+    );
 
     pcs.verify(coms_to_verify, opening_proof, &mut challenger)
         .map_err(VerificationError::InvalidOpeningArgument)?;
 
+    verify_constraints::<SC, A, PcsError<SC>>(
+        air,
+        opened_values,
+        main_row_offsets,
+        &verifying_key.periodic_columns,
+        preprocessed_width,
+        &permutation_randomness,
+        cumulative_sum.unwrap_or_default(),
+        &aux_challenges,
+        public_values,
+        init_trace_domain,
+        &quotient_chunks_domains,
+        zeta,
+        alpha,
+    )
+}
+
+/// Check that the constraints of `air`, folded with `alpha` and evaluated at `zeta` from the opened
+/// trace values, agree with the opened quotient chunks.
+///
+/// The caller is responsible for checking the shape of `opened_values` and verifying the openings.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn verify_constraints<SC, A, PcsErr>(
+    air: &A,
+    opened_values: &OpenedValues<SC::Challenge>,
+    main_row_offsets: &[usize],
+    periodic_columns: &[Vec<Val<SC>>],
+    preprocessed_width: usize,
+    permutation_randomness: &[SC::Challenge],
+    cumulative_sum: SC::Challenge,
+    aux_challenges: &[Vec<SC::Challenge>],
+    public_values: &Vec<Val<SC>>,
+    init_trace_domain: Domain<SC>,
+    quotient_chunks_domains: &[Domain<SC>],
+    zeta: SC::Challenge,
+    alpha: SC::Challenge,
+) -> Result<(), VerificationError<PcsErr>>
+where
+    SC: StarkGenericConfig,
+    A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+{
     let zps = quotient_chunks_domains
         .iter()
         .enumerate()
@@ -190,21 +410,96 @@ where
 
     let sels = init_trace_domain.selectors_at_point(zeta);
 
-    let main = VerticalPair::new(
-        RowMajorMatrixView::new_row(&opened_values.trace_local),
-        RowMajorMatrixView::new_row(&opened_values.trace_next),
+    // The selector of a window of `size` rows is the product of the transition selectors of the
+    // first `size - 1` rows of the window.
+    let window_size = main_row_offsets.last().map_or(2, |&offset| offset + 1);
+    let mut is_transition_windows: Vec<SC::Challenge> = Vec::with_capacity(window_size - 2);
+    let mut point = zeta;
+    let mut selector = sels.is_transition;
+    for _ in 3..=window_size {
+        point = init_trace_domain.next_point(point).unwrap();
+        selector *= init_trace_domain.selectors_at_point(point).is_transition;
+        is_transition_windows.push(selector);
+    }
+
+    let periodic_values = periodic_columns
+        .iter()
+        .map(|column| init_trace_domain.periodic_column_at_point(column, zeta))
+        .collect();
+
+    let main_values = [&opened_values.trace_local, &opened_values.trace_next]
+        .into_iter()
+        .chain(&opened_values.trace_extra_rows)
+        .flatten()
+        .copied()
+        .collect_vec();
+    let main = RowMajorMatrixView::new(&main_values, opened_values.trace_local.len());
+
+    let preprocessed_values = [
+        &opened_values.preprocessed_local,
+        &opened_values.preprocessed_next,
+    ]
+    .into_iter()
+    .flatten()
+    .flatten()
+    .copied()
+    .collect_vec();
+    let preprocessed = RowMajorMatrixView::new(&preprocessed_values, preprocessed_width);
+
+    // The permutation trace was committed to as flattened base field columns.
+    let unflatten = |values: &Option<Vec<SC::Challenge>>| {
+        values
+            .as_deref()
+            .map(unflatten_extension_values::<Val<SC>, SC::Challenge>)
+            .unwrap_or_default()
+    };
+    let permutation_local = unflatten(&opened_values.permutation_local);
+    let permutation_next = unflatten(&opened_values.permutation_next);
+    let permutation = VerticalPair::new(
+        RowMajorMatrixView::new_row(&permutation_local),
+        RowMajorMatrixView::new_row(&permutation_next),
     );
 
+    let aux_values = opened_values
+        .aux_local
+        .iter()
+        .zip(&opened_values.aux_next)
+        .map(|(local, next)| {
+            (
+                unflatten_extension_values::<Val<SC>, SC::Challenge>(local),
+                unflatten_extension_values::<Val<SC>, SC::Challenge>(next),
+            )
+        })
+        .collect_vec();
+    let aux = aux_values
+        .iter()
+        .map(|(local, next)| {
+            VerticalPair::new(
+                RowMajorMatrixView::new_row(local),
+                RowMajorMatrixView::new_row(next),
+            )
+        })
+        .collect();
+
     let mut folder = VerifierConstraintFolder {
         main,
+        preprocessed,
+        permutation,
+        permutation_randomness,
+        cumulative_sum,
+        aux,
+        aux_challenges,
         public_values,
+        periodic_values,
         is_first_row: sels.is_first_row,
         is_last_row: sels.is_last_row,
         is_transition: sels.is_transition,
+        is_transition_windows,
         alpha,
         accumulator: SC::Challenge::ZERO,
     };
     air.eval(&mut folder);
+    eval_lookup_constraints(&mut folder, &air.sends(), &air.receives());
     let folded_constraints = folder.accumulator;
 
     // Finally, check that
@@ -226,4 +521,7 @@ pub enum VerificationError<PcsErr> {
     OodEvaluationMismatch,
     /// The FRI batch randomization does not correspond to the ZK setting.
     RandomizationError,
+    /// The running sum of the lookup argument did not end at zero, i.e. some tuple was sent a
+    /// different number of times than it was received.
+    UnbalancedLookups,
 }
@@ -0,0 +1,216 @@
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{Field, PrimeCharacteristicRing};
+use p3_fri::{TwoAdicFriPcs, create_test_fri_config};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{
+    BatchProvingError, StarkConfig, StarkInstance, VerificationError, prove_batch, verify_batch,
+};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+/// Columns `[left, right]`, starting at `pis[0], pis[1]` and ending with `right = pis[2]`.
+pub struct FibonacciAir;
+
+impl FibonacciAir {
+    fn generate_trace<F: Field>(n: usize) -> (RowMajorMatrix<F>, Vec<F>) {
+        let (mut left, mut right) = (F::ZERO, F::ONE);
+        let mut values = Vec::with_capacity(2 * n);
+        for _ in 0..n {
+            values.extend([left, right]);
+            (left, right) = (right, left + right);
+        }
+        let last = values[2 * n - 1];
+        (RowMajorMatrix::new(values, 2), vec![F::ZERO, F::ONE, last])
+    }
+}
+
+/// Columns `[x, x^4]`. This has a higher constraint degree than [`FibonacciAir`].
+pub struct PowAir;
+
+impl PowAir {
+    fn generate_trace<F: Field>(n: usize) -> RowMajorMatrix<F> {
+        let values = (0..n)
+            .flat_map(|i| {
+                let x = F::from_usize(i + 3);
+                [x, x.exp_u64(4)]
+            })
+            .collect();
+        RowMajorMatrix::new(values, 2)
+    }
+}
+
+/// Dispatches to one of the AIRs in the batch.
+pub enum BatchAir {
+    Fibonacci(FibonacciAir),
+    Pow(PowAir),
+}
+
+impl<F> BaseAir<F> for BatchAir {
+    fn width(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for BatchAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0).expect("Matrix is empty?");
+        let next = main.row_slice(1).expect("Matrix only has 1 row?");
+
+        match self {
+            Self::Fibonacci(_) => {
+                let pis = builder.public_values();
+                let (a, b, x) = (pis[0], pis[1], pis[2]);
+
+                builder.when_first_row().assert_eq(local[0], a);
+                builder.when_first_row().assert_eq(local[1], b);
+                builder.when_transition().assert_eq(local[1], next[0]);
+                builder
+                    .when_transition()
+                    .assert_eq(local[0] + local[1], next[1]);
+                builder.when_last_row().assert_eq(local[1], x);
+            }
+            Self::Pow(_) => {
+                let x = local[0].into();
+                builder.assert_eq(x.clone() * x.clone() * x.clone() * x, local[1]);
+            }
+        }
+    }
+}
+
+/// A constant column which also reads the row after next, which batch proving does not support.
+pub struct ConstantWindowAir;
+
+impl<F> BaseAir<F> for ConstantWindowAir {
+    fn width(&self) -> usize {
+        1
+    }
+
+    fn main_row_offsets(&self) -> Vec<usize> {
+        vec![0, 1, 2]
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for ConstantWindowAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0).expect("Matrix is empty?");
+        let next = main.row_slice(2).expect("Matrix only has 2 rows?");
+        builder
+            .when_transition_window(3)
+            .assert_eq(local[0], next[0]);
+    }
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2BabyBear<16>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs =
+    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+type Dft = Radix2DitParallel<Val>;
+type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+
+fn make_config() -> MyConfig {
+    let mut rng = SmallRng::seed_from_u64(1);
+    let perm = Perm::new_from_rng_128(&mut rng);
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = create_test_fri_config(challenge_mmcs, 1);
+    let pcs = Pcs::new(Dft::default(), val_mmcs, fri_config);
+    MyConfig::new(pcs, Challenger::new(perm))
+}
+
+fn make_instances(
+    airs: &[BatchAir],
+) -> (Vec<StarkInstance<'_, MyConfig, BatchAir>>, Vec<Vec<Val>>) {
+    let (fib_trace, fib_pis) = FibonacciAir::generate_trace::<Val>(1 << 5);
+    let pow_trace = PowAir::generate_trace::<Val>(1 << 3);
+    let instances = vec![
+        StarkInstance {
+            air: &airs[0],
+            trace: fib_trace,
+            public_values: fib_pis.clone(),
+        },
+        StarkInstance {
+            air: &airs[1],
+            trace: pow_trace,
+            public_values: vec![],
+        },
+    ];
+    (instances, vec![fib_pis, vec![]])
+}
+
+#[test]
+fn test_batch() {
+    let config = make_config();
+    let airs = [BatchAir::Fibonacci(FibonacciAir), BatchAir::Pow(PowAir)];
+    let (instances, public_values) = make_instances(&airs);
+
+    let proof = prove_batch(&config, instances).expect("batch is supported");
+    verify_batch(&config, &airs, &proof, &public_values).expect("verification failed");
+}
+
+#[test]
+fn test_batch_wrong_public_values() {
+    let config = make_config();
+    let airs = [BatchAir::Fibonacci(FibonacciAir), BatchAir::Pow(PowAir)];
+    let (instances, mut public_values) = make_instances(&airs);
+    let proof = prove_batch(&config, instances).expect("batch is supported");
+
+    public_values[0][2] += Val::ONE;
+    assert!(verify_batch(&config, &airs, &proof, &public_values).is_err());
+}
+
+#[test]
+fn test_batch_wrong_airs() {
+    let config = make_config();
+    let airs = [BatchAir::Fibonacci(FibonacciAir), BatchAir::Pow(PowAir)];
+    let (instances, public_values) = make_instances(&airs);
+    let proof = prove_batch(&config, instances).expect("batch is supported");
+
+    // The AIRs must be given in the order they were proven.
+    let swapped_airs = [BatchAir::Pow(PowAir), BatchAir::Fibonacci(FibonacciAir)];
+    let swapped_public_values = vec![public_values[1].clone(), public_values[0].clone()];
+    assert!(verify_batch(&config, &swapped_airs, &proof, &swapped_public_values).is_err());
+
+    // Dropping an instance changes the shape of the batch.
+    assert!(matches!(
+        verify_batch(&config, &airs[..1], &proof, &public_values[..1]),
+        Err(VerificationError::InvalidProofShape)
+    ));
+}
+
+#[test]
+fn test_batch_unsupported() {
+    let config = make_config();
+    assert!(matches!(
+        prove_batch::<MyConfig, BatchAir>(&config, vec![]),
+        Err(BatchProvingError::EmptyBatch)
+    ));
+
+    let window_air = ConstantWindowAir;
+    let window_instance = StarkInstance {
+        air: &window_air,
+        trace: RowMajorMatrix::new_col(vec![Val::ONE; 1 << 3]),
+        public_values: vec![],
+    };
+    assert!(matches!(
+        prove_batch(&config, vec![window_instance]),
+        Err(BatchProvingError::MainRowOffsets { instance: 0 })
+    ));
+}
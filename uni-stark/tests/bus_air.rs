@@ -0,0 +1,210 @@
+use p3_air::{Air, AirBuilder, BaseAir, Interaction, VirtualPairCol};
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{Field, PrimeCharacteristicRing};
+use p3_fri::{TwoAdicFriPcs, create_test_fri_config};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{
+    PcsError, StarkConfig, StarkInstance, VerificationError, prove_batch, verify_batch,
+};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+const CUBE_BUS: usize = 0;
+
+/// A toy CPU which accumulates cubes, delegating the cubing to [`CubeAir`].
+///
+/// Columns: `[acc, x, x^3]`, with `acc' = acc + x^3`. Every row sends `(x, x^3)` on the cube bus.
+pub struct CpuAir;
+
+/// A table of cubes. Columns: `[x, x^3, multiplicity]`, with `x^3 = x * x * x`. Every row receives
+/// `(x, x^3)` on the cube bus, `multiplicity` times.
+pub struct CubeAir {
+    bus: usize,
+}
+
+pub enum BusAir {
+    Cpu(CpuAir),
+    Cube(CubeAir),
+}
+
+impl<F: Field> BaseAir<F> for BusAir {
+    fn width(&self) -> usize {
+        3
+    }
+
+    fn sends(&self) -> Vec<Interaction<F>> {
+        match self {
+            Self::Cpu(_) => vec![Interaction::new(
+                CUBE_BUS,
+                vec![
+                    VirtualPairCol::single_main(1),
+                    VirtualPairCol::single_main(2),
+                ],
+                VirtualPairCol::ONE,
+            )],
+            Self::Cube(_) => vec![],
+        }
+    }
+
+    fn receives(&self) -> Vec<Interaction<F>> {
+        match self {
+            Self::Cpu(_) => vec![],
+            Self::Cube(cube) => vec![Interaction::new(
+                cube.bus,
+                vec![
+                    VirtualPairCol::single_main(0),
+                    VirtualPairCol::single_main(1),
+                ],
+                VirtualPairCol::single_main(2),
+            )],
+        }
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for BusAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0).expect("Matrix is empty?");
+        let next = main.row_slice(1).expect("Matrix only has 1 row?");
+
+        match self {
+            Self::Cpu(_) => {
+                builder.when_first_row().assert_zero(local[0]);
+                builder
+                    .when_transition()
+                    .assert_eq(next[0], local[0] + local[2]);
+            }
+            Self::Cube(_) => {
+                let x = local[0].into();
+                builder.assert_eq(x.clone() * x.clone() * x, local[1]);
+            }
+        }
+    }
+}
+
+fn generate_cpu_trace<F: Field>(inputs: &[u64]) -> RowMajorMatrix<F> {
+    let mut acc = F::ZERO;
+    let mut values = Vec::with_capacity(3 * inputs.len());
+    for &x in inputs {
+        let cube = F::from_u64(x).cube();
+        values.extend([acc, F::from_u64(x), cube]);
+        acc += cube;
+    }
+    RowMajorMatrix::new(values, 3)
+}
+
+fn generate_cube_trace<F: Field>(inputs: &[u64], log_height: usize) -> RowMajorMatrix<F> {
+    let mut multiplicities = vec![0; 1 << log_height];
+    for &x in inputs {
+        multiplicities[x as usize] += 1;
+    }
+    let values = multiplicities
+        .into_iter()
+        .enumerate()
+        .flat_map(|(x, multiplicity)| {
+            let x = F::from_usize(x);
+            [x, x.cube(), F::from_usize(multiplicity)]
+        })
+        .collect();
+    RowMajorMatrix::new(values, 3)
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2BabyBear<16>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs =
+    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+type Dft = Radix2DitParallel<Val>;
+type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+
+fn make_config() -> MyConfig {
+    let mut rng = SmallRng::seed_from_u64(1);
+    let perm = Perm::new_from_rng_128(&mut rng);
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = create_test_fri_config(challenge_mmcs, 1);
+    let pcs = Pcs::new(Dft::default(), val_mmcs, fri_config);
+    MyConfig::new(pcs, Challenger::new(perm))
+}
+
+/// The inputs of the CPU, which are all less than `2^3`, the height of the cube table.
+fn cpu_inputs() -> Vec<u64> {
+    (0..1 << 5).map(|i| (i * 5 + 1) % 7).collect()
+}
+
+fn prove_and_verify(
+    airs: &[BusAir],
+    cpu_trace: RowMajorMatrix<Val>,
+    cube_trace: RowMajorMatrix<Val>,
+) -> Result<(), VerificationError<PcsError<MyConfig>>> {
+    let config = make_config();
+    let instances = vec![
+        StarkInstance {
+            air: &airs[0],
+            trace: cpu_trace,
+            public_values: vec![],
+        },
+        StarkInstance {
+            air: &airs[1],
+            trace: cube_trace,
+            public_values: vec![],
+        },
+    ];
+    let proof = prove_batch(&config, instances).expect("batch is supported");
+    verify_batch(&config, airs, &proof, &[vec![], vec![]])
+}
+
+#[test]
+fn test_bus() {
+    let airs = [BusAir::Cpu(CpuAir), BusAir::Cube(CubeAir { bus: CUBE_BUS })];
+    let inputs = cpu_inputs();
+    let cpu_trace = generate_cpu_trace(&inputs);
+    let cube_trace = generate_cube_trace(&inputs, 3);
+
+    prove_and_verify(&airs, cpu_trace, cube_trace).expect("verification failed");
+}
+
+#[test]
+fn test_bus_wrong_cube() {
+    let airs = [BusAir::Cpu(CpuAir), BusAir::Cube(CubeAir { bus: CUBE_BUS })];
+    let inputs = cpu_inputs();
+    // The CPU claims the cube of its last input is off by one, which is consistent with its own
+    // constraints but not with the cube table.
+    let mut cpu_trace = generate_cpu_trace::<Val>(&inputs);
+    let last = cpu_trace.values.len() - 1;
+    cpu_trace.values[last] += Val::ONE;
+    let cube_trace = generate_cube_trace(&inputs, 3);
+
+    assert!(matches!(
+        prove_and_verify(&airs, cpu_trace, cube_trace),
+        Err(VerificationError::UnbalancedLookups)
+    ));
+}
+
+#[test]
+fn test_bus_mismatched_buses() {
+    // The cube table listens on a different bus than the CPU sends on.
+    let airs = [BusAir::Cpu(CpuAir), BusAir::Cube(CubeAir { bus: 1 })];
+    let inputs = cpu_inputs();
+    let cpu_trace = generate_cpu_trace(&inputs);
+    let cube_trace = generate_cube_trace(&inputs, 3);
+
+    assert!(matches!(
+        prove_and_verify(&airs, cpu_trace, cube_trace),
+        Err(VerificationError::UnbalancedLookups)
+    ));
+}
@@ -0,0 +1,120 @@
+use p3_air::{Air, BaseAir};
+use p3_baby_bear::{BabyBear, GenericPoseidon2LinearLayersBabyBear, Poseidon2BabyBear};
+use p3_blake3_air::Blake3Air;
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{
+    BasedVectorSpace, Field, PackedFieldExtension, PackedValue, PrimeCharacteristicRing,
+};
+use p3_fri::TwoAdicFriPcs;
+use p3_matrix::dense::RowMajorMatrixView;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_poseidon2_air::{Poseidon2Air, RoundConstants};
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{
+    ConstraintDag, PackedChallenge, PackedVal, ProverConstraintFolder, StarkConfig,
+    SymbolicAirBuilder, get_symbolic_constraints,
+};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+type Val = BabyBear;
+type Perm = Poseidon2BabyBear<16>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs =
+    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+type Pcs = TwoAdicFriPcs<Val, Radix2DitParallel<Val>, ValMmcs, ChallengeMmcs>;
+type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+
+type MyPoseidon2Air = Poseidon2Air<Val, GenericPoseidon2LinearLayersBabyBear, 16, 7, 1, 4, 13>;
+
+/// Fold the constraints of `air` on random local and next rows and selectors, either with the AIR
+/// itself or with its constraint DAG, and return the folded values for each lane.
+fn fold_constraints<A>(air: &A, use_dag: bool, seed: u64) -> Vec<Challenge>
+where
+    A: BaseAir<Val>
+        + Air<SymbolicAirBuilder<Val>>
+        + for<'a> Air<ProverConstraintFolder<'a, MyConfig>>,
+{
+    let constraints = get_symbolic_constraints(air, 0, 0);
+    let dag = ConstraintDag::new(&constraints);
+
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let mut random_packed = || PackedVal::<MyConfig>::from_fn(|_| rng.random());
+    let width = air.width();
+    let main = (0..2 * width).map(|_| random_packed()).collect::<Vec<_>>();
+    let (is_first_row, is_last_row, is_transition) =
+        (random_packed(), random_packed(), random_packed());
+
+    let alpha: Challenge = rng.random();
+    let alpha_powers = alpha.powers().take(constraints.len()).collect::<Vec<_>>();
+    let decomposed_alpha_powers = (0..<Challenge as BasedVectorSpace<Val>>::DIMENSION)
+        .map(|i| {
+            alpha_powers
+                .iter()
+                .map(|x| <Challenge as BasedVectorSpace<Val>>::as_basis_coefficients_slice(x)[i])
+                .collect()
+        })
+        .collect::<Vec<_>>();
+
+    let mut folder = ProverConstraintFolder::<MyConfig> {
+        main: RowMajorMatrixView::new(&main, width),
+        preprocessed: RowMajorMatrixView::new(&[], 0),
+        permutation: RowMajorMatrixView::new(&[], 0),
+        permutation_randomness: &[],
+        cumulative_sum: Challenge::ZERO,
+        aux: vec![],
+        aux_challenges: &[],
+        public_values: &vec![],
+        periodic_values: vec![],
+        is_first_row,
+        is_last_row,
+        is_transition,
+        is_transition_windows: vec![],
+        alpha_powers: &alpha_powers,
+        decomposed_alpha_powers: &decomposed_alpha_powers,
+        accumulator: PackedChallenge::<MyConfig>::ZERO,
+        constraint_index: 0,
+    };
+    if use_dag {
+        dag.eval(&mut folder);
+    } else {
+        air.eval(&mut folder);
+    }
+    assert_eq!(folder.constraint_index, constraints.len());
+    <PackedChallenge<MyConfig> as PackedFieldExtension<Val, Challenge>>::to_ext_iter([
+        folder.accumulator
+    ])
+    .collect()
+}
+
+fn check_dag_matches_air<A>(air: &A)
+where
+    A: BaseAir<Val>
+        + Air<SymbolicAirBuilder<Val>>
+        + for<'a> Air<ProverConstraintFolder<'a, MyConfig>>,
+{
+    for seed in 0..4 {
+        assert_eq!(
+            fold_constraints(air, true, seed),
+            fold_constraints(air, false, seed)
+        );
+    }
+}
+
+#[test]
+fn test_dag_matches_poseidon2_air() {
+    let constants = RoundConstants::from_rng(&mut SmallRng::seed_from_u64(1));
+    check_dag_matches_air(&MyPoseidon2Air::new(constants));
+}
+
+#[test]
+fn test_dag_matches_blake3_air() {
+    check_dag_matches_air(&Blake3Air {});
+}
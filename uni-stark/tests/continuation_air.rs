@@ -0,0 +1,207 @@
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{Field, PrimeCharacteristicRing};
+use p3_fri::{TwoAdicFriPcs, create_test_fri_config};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{
+    ContinuationError, ContinuationProof, Proof, SegmentAir, StarkConfig, prove_continuation,
+    split_trace, verify_continuation,
+};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+/// Pairs of consecutive Fibonacci numbers, with no constraints on the first or last row so that
+/// any window of the sequence is a valid segment.
+pub struct FibonacciStepAir;
+
+impl<F> BaseAir<F> for FibonacciStepAir {
+    fn width(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for FibonacciStepAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (
+            main.row_slice(0).expect("empty trace"),
+            main.row_slice(1).expect("single row trace"),
+        );
+
+        let mut when_transition = builder.when_transition();
+        when_transition.assert_eq(next[0], local[1]);
+        when_transition.assert_eq(next[1], local[0] + local[1]);
+    }
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2BabyBear<16>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs =
+    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+type Dft = Radix2DitParallel<Val>;
+type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+
+fn make_config() -> MyConfig {
+    let mut rng = SmallRng::seed_from_u64(1);
+    let perm = Perm::new_from_rng_128(&mut rng);
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = create_test_fri_config(challenge_mmcs, 1);
+    let pcs = Pcs::new(Dft::default(), val_mmcs, fri_config);
+    MyConfig::new(pcs, Challenger::new(perm))
+}
+
+fn generate_trace(height: usize) -> RowMajorMatrix<Val> {
+    let (mut a, mut b) = (Val::ZERO, Val::ONE);
+    let mut values = vec![];
+    for _ in 0..height {
+        values.extend([a, b]);
+        (a, b) = (b, a + b);
+    }
+    RowMajorMatrix::new(values, 2)
+}
+
+#[test]
+fn test_continuation() {
+    let config = make_config();
+    let air = SegmentAir::new(FibonacciStepAir, 0);
+    let trace = generate_trace(1 + 4 * 15);
+    let expected_final_row = trace.last_row().unwrap().into_iter().collect::<Vec<_>>();
+
+    let proof = prove_continuation(&config, &air, split_trace(&trace, 16), &[]);
+    assert_eq!(proof.num_segments(), 4);
+    assert_eq!(proof.initial_row(), Some(&[Val::ZERO, Val::ONE][..]));
+    assert_eq!(proof.final_row(), Some(&expected_final_row[..]));
+
+    verify_continuation(&config, &air, &proof, &[]).expect("verification failed");
+}
+
+#[test]
+fn test_continuation_single_segment() {
+    let config = make_config();
+    let air = SegmentAir::new(FibonacciStepAir, 0);
+    let trace = generate_trace(8);
+
+    let proof = prove_continuation(&config, &air, [trace], &[]);
+    verify_continuation(&config, &air, &proof, &[]).expect("verification failed");
+}
+
+/// Concatenate the segments of two serialized proofs, as a malicious prover could.
+fn concat_proofs(
+    first: &ContinuationProof<MyConfig>,
+    second: &ContinuationProof<MyConfig>,
+) -> ContinuationProof<MyConfig> {
+    let first = postcard::to_allocvec(first).expect("unable to serialize proof");
+    let second = postcard::to_allocvec(second).expect("unable to serialize proof");
+    // Both proofs have a single segment, so their encodings start with the length `1`.
+    assert_eq!((first[0], second[0]), (1, 1));
+    let bytes = [&[2], &first[1..], &second[1..]].concat();
+    postcard::from_bytes(&bytes).expect("unable to deserialize proof")
+}
+
+#[test]
+fn test_continuation_broken_chain() {
+    let config = make_config();
+    let air = SegmentAir::new(FibonacciStepAir, 0);
+    let trace = generate_trace(1 + 3 * 7);
+    let segments = split_trace(&trace, 8);
+
+    // Each segment is valid on its own, but skipping the middle one breaks the chain.
+    let first = prove_continuation(&config, &air, [segments[0].clone()], &[]);
+    let last = prove_continuation(&config, &air, [segments[2].clone()], &[]);
+    let proof = concat_proofs(&first, &last);
+    assert!(matches!(
+        verify_continuation(&config, &air, &proof, &[]),
+        Err(ContinuationError::BrokenChain { segment: 1 })
+    ));
+
+    let middle = prove_continuation(&config, &air, [segments[1].clone()], &[]);
+    let proof = concat_proofs(&first, &middle);
+    verify_continuation(&config, &air, &proof, &[]).expect("verification failed");
+}
+
+/// Move the split between the first and last rows of each segment to the given index, keeping the
+/// public values of every segment the same, as a malicious prover could.
+fn resplit_boundary_rows(
+    proof: &ContinuationProof<MyConfig>,
+    splits: &[usize],
+) -> ContinuationProof<MyConfig> {
+    type RawSegment = (Vec<Val>, Vec<Val>, Proof<MyConfig>);
+    let bytes = postcard::to_allocvec(proof).expect("unable to serialize proof");
+    let segments: Vec<RawSegment> =
+        postcard::from_bytes(&bytes).expect("unable to deserialize segments");
+    let segments = segments
+        .into_iter()
+        .zip(splits)
+        .map(|((first_row, last_row, proof), &split)| {
+            let mut first_row = [first_row, last_row].concat();
+            let last_row = first_row.split_off(split);
+            (first_row, last_row, proof)
+        })
+        .collect::<Vec<_>>();
+    let bytes = postcard::to_allocvec(&segments).expect("unable to serialize segments");
+    postcard::from_bytes(&bytes).expect("unable to deserialize proof")
+}
+
+#[test]
+fn test_continuation_resplit_boundary_rows() {
+    let config = make_config();
+    let air = SegmentAir::new(FibonacciStepAir, 0);
+    let trace = generate_trace(1 + 3 * 7);
+    let segments = split_trace(&trace, 8);
+
+    // The chain is broken, but with both rows of the first segment declared as its first row and
+    // both rows of the second as its last row, the rows compared by the chain check are empty.
+    let first = prove_continuation(&config, &air, [segments[0].clone()], &[]);
+    let last = prove_continuation(&config, &air, [segments[2].clone()], &[]);
+    let proof = resplit_boundary_rows(&concat_proofs(&first, &last), &[4, 0]);
+    assert!(matches!(
+        verify_continuation(&config, &air, &proof, &[]),
+        Err(ContinuationError::InvalidBoundaryRows { segment: 0 })
+    ));
+}
+
+#[test]
+fn test_continuation_segment_height_mismatch() {
+    let config = make_config();
+    let air = SegmentAir::new(FibonacciStepAir, 0);
+    let trace = generate_trace(1 + 7 + 15);
+
+    // The segments form a chain, but the second one is twice as tall as the first.
+    let first = RowMajorMatrix::new(trace.values[..2 * 8].to_vec(), 2);
+    let second = RowMajorMatrix::new(trace.values[2 * 7..].to_vec(), 2);
+    let first = prove_continuation(&config, &air, [first], &[]);
+    let second = prove_continuation(&config, &air, [second], &[]);
+    let proof = concat_proofs(&first, &second);
+    assert!(matches!(
+        verify_continuation(&config, &air, &proof, &[]),
+        Err(ContinuationError::SegmentHeightMismatch { segment: 1 })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "segment 1 does not start with the last row of the previous segment")]
+fn test_continuation_unchained_segments() {
+    let config = make_config();
+    let air = SegmentAir::new(FibonacciStepAir, 0);
+    let trace = generate_trace(1 + 2 * 7);
+    let mut segments = split_trace(&trace, 8);
+    segments.reverse();
+
+    prove_continuation(&config, &air, segments, &[]);
+}
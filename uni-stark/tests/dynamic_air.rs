@@ -0,0 +1,149 @@
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir, Interaction, VirtualPairCol};
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{Field, PrimeCharacteristicRing};
+use p3_fri::{TwoAdicFriPcs, create_test_fri_config};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{AirDescription, DynamicAir, StarkConfig, SymbolicAirBuilder, prove, verify};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+/// Columns `[a, b]`, with `(a, b)` starting at `(0, 1)` and stepping to `(b, a + b)`. The last
+/// value of `b` is the only public value.
+pub struct FibonacciAir;
+
+impl<F> BaseAir<F> for FibonacciAir {
+    fn width(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for FibonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0).expect("Matrix is empty?");
+        let next = main.row_slice(1).expect("Matrix only has 1 row?");
+        let result = builder.public_values()[0];
+
+        builder.when_first_row().assert_zero(local[0]);
+        builder.when_first_row().assert_one(local[1]);
+        builder.when_transition().assert_eq(next[0], local[1]);
+        builder
+            .when_transition()
+            .assert_eq(next[1], local[0] + local[1]);
+        builder.when_last_row().assert_eq(local[1], result);
+    }
+}
+
+/// Columns `[a, b]`, which must be permutations of each other, checked by a lookup.
+pub struct PermutationAir;
+
+impl<F: Field> BaseAir<F> for PermutationAir {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn sends(&self) -> Vec<Interaction<F>> {
+        vec![Interaction::new(
+            0,
+            vec![VirtualPairCol::single_main(0)],
+            VirtualPairCol::ONE,
+        )]
+    }
+
+    fn receives(&self) -> Vec<Interaction<F>> {
+        vec![Interaction::new(
+            0,
+            vec![VirtualPairCol::single_main(1)],
+            VirtualPairCol::ONE,
+        )]
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for PermutationAir {
+    fn eval(&self, _builder: &mut AB) {}
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2BabyBear<16>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs =
+    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+type Dft = Radix2DitParallel<Val>;
+type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+
+fn make_config() -> MyConfig {
+    let mut rng = SmallRng::seed_from_u64(1);
+    let perm = Perm::new_from_rng_128(&mut rng);
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = create_test_fri_config(challenge_mmcs, 1);
+    let pcs = Pcs::new(Dft::default(), val_mmcs, fri_config);
+    MyConfig::new(pcs, Challenger::new(perm))
+}
+
+/// Describe `air`, and load the description back as a [`DynamicAir`] as if from a file.
+fn load<A: Air<SymbolicAirBuilder<Val>>>(air: &A, num_public_values: usize) -> DynamicAir<Val> {
+    let description = AirDescription::from_air(air, num_public_values);
+    let bytes = postcard::to_allocvec(&description).expect("unable to serialize description");
+    let description: AirDescription<Val> =
+        postcard::from_bytes(&bytes).expect("unable to deserialize description");
+    DynamicAir::new(description).expect("invalid description")
+}
+
+fn fibonacci_trace(log_height: usize) -> (RowMajorMatrix<Val>, Val) {
+    let (mut a, mut b) = (Val::ZERO, Val::ONE);
+    let mut values = vec![];
+    for _ in 0..1 << log_height {
+        values.extend([a, b]);
+        (a, b) = (b, a + b);
+    }
+    (RowMajorMatrix::new(values, 2), a)
+}
+
+#[test]
+fn test_dynamic_fibonacci() {
+    let config = make_config();
+    let air = load(&FibonacciAir, 1);
+    let (trace, result) = fibonacci_trace(5);
+
+    let proof = prove(&config, &air, trace, &vec![result]);
+    verify(&config, &air, &proof, &vec![result]).expect("verification failed");
+}
+
+#[test]
+fn test_dynamic_fibonacci_wrong_result() {
+    let config = make_config();
+    let air = load(&FibonacciAir, 1);
+    let (trace, result) = fibonacci_trace(5);
+
+    let proof = prove(&config, &air, trace, &vec![result]);
+    assert!(verify(&config, &air, &proof, &vec![result + Val::ONE]).is_err());
+}
+
+#[test]
+fn test_dynamic_lookup() {
+    let config = make_config();
+    let air = load(&PermutationAir, 0);
+    let height = 1 << 5;
+    let values = (0..height)
+        .flat_map(|i| [Val::from_usize(i), Val::from_usize((7 * i + 5) % height)])
+        .collect();
+    let trace = RowMajorMatrix::new(values, 2);
+
+    let proof = prove(&config, &air, trace, &vec![]);
+    verify(&config, &air, &proof, &vec![]).expect("verification failed");
+}
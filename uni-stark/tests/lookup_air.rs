@@ -0,0 +1,219 @@
+use p3_air::{Air, AirBuilder, BaseAir, Interaction, VirtualPairCol};
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{Field, PrimeCharacteristicRing};
+use p3_fri::{TwoAdicFriPcs, create_test_fri_config};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{StarkConfig, VerificationError, prove, verify};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+/// An AIR which looks up pairs `(a, b)` in a fixed table of pairs `(t, 2t)`.
+///
+/// Preprocessed columns: `[t, 2t]`, where `t` is the row index.
+/// Main columns: `[a, b, multiplicity]`, where `multiplicity` counts the rows with `a = t`.
+pub struct DoublingLookupAir {
+    log_height: usize,
+}
+
+impl DoublingLookupAir {
+    pub fn generate_trace<F: Field>(&self, inputs: &[usize]) -> RowMajorMatrix<F> {
+        let height = 1 << self.log_height;
+        assert_eq!(inputs.len(), height);
+        let mut multiplicities = vec![0; height];
+        for &a in inputs {
+            multiplicities[a] += 1;
+        }
+        let values = inputs
+            .iter()
+            .zip(multiplicities)
+            .flat_map(|(&a, multiplicity)| {
+                [
+                    F::from_usize(a),
+                    F::from_usize(2 * a),
+                    F::from_usize(multiplicity),
+                ]
+            })
+            .collect();
+        RowMajorMatrix::new(values, 3)
+    }
+}
+
+impl<F: Field> BaseAir<F> for DoublingLookupAir {
+    fn width(&self) -> usize {
+        3
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        let values = (0..1 << self.log_height)
+            .flat_map(|t| [F::from_usize(t), F::from_usize(2 * t)])
+            .collect();
+        Some(RowMajorMatrix::new(values, 2))
+    }
+
+    fn sends(&self) -> Vec<Interaction<F>> {
+        vec![Interaction::new(
+            0,
+            vec![
+                VirtualPairCol::single_main(0),
+                VirtualPairCol::single_main(1),
+            ],
+            VirtualPairCol::ONE,
+        )]
+    }
+
+    fn receives(&self) -> Vec<Interaction<F>> {
+        vec![Interaction::new(
+            0,
+            vec![
+                VirtualPairCol::single_preprocessed(0),
+                VirtualPairCol::single_preprocessed(1),
+            ],
+            VirtualPairCol::single_main(2),
+        )]
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for DoublingLookupAir {
+    fn eval(&self, _builder: &mut AB) {
+        // All constraints come from the lookup argument.
+    }
+}
+
+/// An AIR whose two main columns are permutations of each other, and whose first column starts
+/// at zero.
+pub struct PermutationAir;
+
+impl<F: Field> BaseAir<F> for PermutationAir {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn sends(&self) -> Vec<Interaction<F>> {
+        vec![Interaction::new(
+            0,
+            vec![VirtualPairCol::single_main(0)],
+            VirtualPairCol::ONE,
+        )]
+    }
+
+    fn receives(&self) -> Vec<Interaction<F>> {
+        vec![Interaction::new(
+            0,
+            vec![VirtualPairCol::single_main(1)],
+            VirtualPairCol::ONE,
+        )]
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for PermutationAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0).expect("Matrix is empty?");
+        builder.when_first_row().assert_zero(local[0]);
+    }
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2BabyBear<16>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs =
+    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+type Dft = Radix2DitParallel<Val>;
+type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+
+fn make_config() -> MyConfig {
+    let mut rng = SmallRng::seed_from_u64(1);
+    let perm = Perm::new_from_rng_128(&mut rng);
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = create_test_fri_config(challenge_mmcs, 1);
+    let pcs = Pcs::new(Dft::default(), val_mmcs, fri_config);
+    MyConfig::new(pcs, Challenger::new(perm))
+}
+
+fn doubling_inputs(log_height: usize) -> Vec<usize> {
+    (0..1 << log_height)
+        .map(|i| (i * i + 3) % (1 << log_height))
+        .collect()
+}
+
+#[test]
+fn test_lookup_preprocessed_table() {
+    let config = make_config();
+    let air = DoublingLookupAir { log_height: 4 };
+    let trace = air.generate_trace::<Val>(&doubling_inputs(4));
+
+    let proof = prove(&config, &air, trace, &vec![]);
+    verify(&config, &air, &proof, &vec![]).expect("verification failed");
+}
+
+#[test]
+fn test_lookup_wrong_multiplicity() {
+    let config = make_config();
+    let air = DoublingLookupAir { log_height: 4 };
+    let mut trace = air.generate_trace::<Val>(&doubling_inputs(4));
+    trace.values[2] += Val::ONE;
+
+    let proof = prove(&config, &air, trace, &vec![]);
+    assert!(matches!(
+        verify(&config, &air, &proof, &vec![]),
+        Err(VerificationError::UnbalancedLookups)
+    ));
+}
+
+#[test]
+fn test_lookup_missing_from_table() {
+    let config = make_config();
+    let air = DoublingLookupAir { log_height: 4 };
+    let mut trace = air.generate_trace::<Val>(&doubling_inputs(4));
+    // `(a, b)` is no longer of the form `(t, 2t)`.
+    trace.values[1] += Val::ONE;
+
+    let proof = prove(&config, &air, trace, &vec![]);
+    assert!(matches!(
+        verify(&config, &air, &proof, &vec![]),
+        Err(VerificationError::UnbalancedLookups)
+    ));
+}
+
+#[test]
+fn test_lookup_main_columns() {
+    let config = make_config();
+    let air = PermutationAir;
+    let height = 1 << 5;
+    let values = (0..height)
+        .flat_map(|i| [Val::from_usize(i), Val::from_usize((7 * i + 5) % height)])
+        .collect();
+    let trace = RowMajorMatrix::new(values, 2);
+
+    let proof = prove(&config, &air, trace, &vec![]);
+    verify(&config, &air, &proof, &vec![]).expect("verification failed");
+}
+
+#[test]
+fn test_lookup_not_a_permutation() {
+    let config = make_config();
+    let air = PermutationAir;
+    let height = 1 << 5;
+    let values = (0..height)
+        .flat_map(|i| [Val::from_usize(i), Val::from_usize(i / 2)])
+        .collect();
+    let trace = RowMajorMatrix::new(values, 2);
+
+    let proof = prove(&config, &air, trace, &vec![]);
+    assert!(verify(&config, &air, &proof, &vec![]).is_err());
+}
@@ -5,7 +5,7 @@ use itertools::Itertools;
 use p3_air::{Air, AirBuilder, BaseAir};
 use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
 use p3_challenger::{DuplexChallenger, HashChallenger, SerializingChallenger32};
-use p3_circle::CirclePcs;
+use p3_circle::{CirclePcs, HidingCirclePcs};
 use p3_commit::ExtensionMmcs;
 use p3_commit::testing::TrivialPcs;
 use p3_dft::Radix2DitParallel;
@@ -17,6 +17,7 @@ use p3_matrix::Matrix;
 use p3_matrix::dense::RowMajorMatrix;
 use p3_merkle_tree::{MerkleTreeHidingMmcs, MerkleTreeMmcs};
 use p3_mersenne_31::Mersenne31;
+use p3_stir::{StirConfig, StirPcs};
 use p3_symmetric::{
     CompressionFunctionFromHasher, PaddingFreeSponge, SerializingHasher, TruncatedPermutation,
 };
@@ -211,6 +212,8 @@ fn do_test_bb_twoadic(log_blowup: usize, degree: u64, log_n: usize) -> Result<()
     let fri_config = FriConfig {
         log_blowup,
         log_final_poly_len: 5,
+        log_folding_arity: 1,
+        log_folding_arities: vec![],
         num_queries: 40,
         proof_of_work_bits: 8,
         mmcs: challenge_mmcs,
@@ -299,7 +302,71 @@ fn prove_bb_twoadic_deg5() -> Result<(), impl Debug> {
     do_test_bb_twoadic(2, 5, 6)
 }
 
-fn do_test_m31_circle(log_blowup: usize, degree: u64, log_n: usize) -> Result<(), impl Debug> {
+fn do_test_bb_stir(log_blowup: usize, degree: u64, log_n: usize) -> Result<(), impl Debug> {
+    type Val = BabyBear;
+    type Challenge = BinomialExtensionField<Val, 4>;
+
+    type Perm = Poseidon2BabyBear<16>;
+    let mut rng = SmallRng::seed_from_u64(1);
+    let perm = Perm::new_from_rng_128(&mut rng);
+
+    type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+    let hash = MyHash::new(perm.clone());
+
+    type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+    let compress = MyCompress::new(perm.clone());
+
+    type ValMmcs =
+        MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+    let val_mmcs = ValMmcs::new(hash, compress);
+
+    type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+
+    type Dft = Radix2DitParallel<Val>;
+    let dft = Dft::default();
+
+    type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+
+    let stir_config = StirConfig {
+        log_blowup,
+        log_folding_factor: 2,
+        log_final_poly_len: 2,
+        security_bits: 40,
+        proof_of_work_bits: 8,
+        mmcs: challenge_mmcs,
+    };
+    type Pcs = StirPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+    let pcs = Pcs::new(dft, val_mmcs, stir_config);
+    let challenger = Challenger::new(perm);
+
+    type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+    let config = MyConfig::new(pcs, challenger);
+
+    let air = MulAir {
+        degree,
+        ..Default::default()
+    };
+
+    do_test(config, air, 1 << log_n)
+}
+
+#[test]
+fn prove_bb_stir_deg2() -> Result<(), impl Debug> {
+    do_test_bb_stir(1, 2, 7)
+}
+
+#[test]
+fn prove_bb_stir_deg4() -> Result<(), impl Debug> {
+    do_test_bb_stir(2, 4, 6)
+}
+
+fn do_test_m31_circle(
+    log_blowup: usize,
+    log_final_poly_len: usize,
+    degree: u64,
+    log_n: usize,
+) -> Result<(), impl Debug> {
     type Val = Mersenne31;
     type Challenge = BinomialExtensionField<Val, 3>;
 
@@ -321,7 +388,9 @@ fn do_test_m31_circle(log_blowup: usize, degree: u64, log_n: usize) -> Result<()
 
     let fri_config = FriConfig {
         log_blowup,
-        log_final_poly_len: 0,
+        log_final_poly_len,
+        log_folding_arity: 1,
+        log_folding_arities: vec![],
         num_queries: 40,
         proof_of_work_bits: 8,
         mmcs: challenge_mmcs,
@@ -349,10 +418,52 @@ fn do_test_m31_circle(log_blowup: usize, degree: u64, log_n: usize) -> Result<()
 
 #[test]
 fn prove_m31_circle_deg2() -> Result<(), impl Debug> {
-    do_test_m31_circle(1, 2, 8)
+    do_test_m31_circle(1, 0, 2, 8)
 }
 
 #[test]
 fn prove_m31_circle_deg3() -> Result<(), impl Debug> {
-    do_test_m31_circle(1, 3, 9)
+    do_test_m31_circle(1, 0, 3, 9)
+}
+
+#[test]
+fn prove_m31_circle_final_poly() -> Result<(), impl Debug> {
+    do_test_m31_circle(1, 5, 3, 9)
+}
+
+#[test]
+fn prove_m31_circle_deg3_zk() -> Result<(), impl Debug> {
+    type Val = Mersenne31;
+    type Challenge = BinomialExtensionField<Val, 3>;
+
+    type ByteHash = Keccak256Hash;
+    type FieldHash = SerializingHasher<ByteHash>;
+    let byte_hash = ByteHash {};
+    let field_hash = FieldHash::new(byte_hash);
+
+    type MyCompress = CompressionFunctionFromHasher<ByteHash, 2, 32>;
+    let compress = MyCompress::new(byte_hash);
+
+    type ValMmcs = MerkleTreeHidingMmcs<Val, u8, FieldHash, MyCompress, SmallRng, 32, 4>;
+    let val_mmcs = ValMmcs::new(field_hash, compress, SmallRng::seed_from_u64(1));
+
+    type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+
+    type Challenger = SerializingChallenger32<Val, HashChallenger<u8, ByteHash, 32>>;
+
+    let fri_config = create_test_fri_config_zk(challenge_mmcs);
+    type HidingPcs = HidingCirclePcs<Val, ValMmcs, ChallengeMmcs, SmallRng>;
+    let pcs = HidingPcs::new(val_mmcs, fri_config, 4, SmallRng::seed_from_u64(1));
+    let challenger = Challenger::from_hasher(vec![], byte_hash);
+
+    type MyConfig = StarkConfig<HidingPcs, Challenge, Challenger>;
+    let config = MyConfig::new(pcs, challenger);
+
+    let air = MulAir {
+        degree: 3,
+        uses_boundary_constraints: true,
+        uses_transition_constraints: true,
+    };
+    do_test(config, air, 1 << 8)
 }
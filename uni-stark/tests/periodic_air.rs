@@ -0,0 +1,215 @@
+use core::marker::PhantomData;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir, PeriodicAirBuilder};
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::{DuplexChallenger, HashChallenger, SerializingChallenger32};
+use p3_circle::CirclePcs;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{Field, PrimeCharacteristicRing};
+use p3_fri::{FriConfig, TwoAdicFriPcs, create_test_fri_config};
+use p3_keccak::Keccak256Hash;
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_mersenne_31::Mersenne31;
+use p3_symmetric::{
+    CompressionFunctionFromHasher, PaddingFreeSponge, SerializingHasher, TruncatedPermutation,
+};
+use p3_uni_stark::{AirDescription, DynamicAir, StarkConfig, prove, setup, verify};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+/// An accumulator `acc` which adds the periodic increments `1, 2, 3, 4, 1, 2, ...` on every row,
+/// starting from the public value, and a column `sample` which must equal `acc` on every eighth
+/// row and vanish elsewhere.
+pub struct PeriodicAir;
+
+impl<F: Field> BaseAir<F> for PeriodicAir {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<F>> {
+        vec![
+            (1..=4).map(F::from_u8).collect(),
+            (0..8).map(|i| F::from_bool(i == 7)).collect(),
+        ]
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues + PeriodicAirBuilder> Air<AB> for PeriodicAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (
+            main.row_slice(0).expect("empty trace"),
+            main.row_slice(1).expect("single row trace"),
+        );
+        let (increment, is_sampled): (AB::Expr, AB::Expr) = (
+            builder.periodic_values()[0].into(),
+            builder.periodic_values()[1].into(),
+        );
+        let start = builder.public_values()[0];
+
+        builder.when_first_row().assert_eq(local[0], start);
+        builder
+            .when_transition()
+            .assert_eq(next[0], local[0] + increment);
+        builder.assert_eq(local[1], is_sampled * local[0]);
+    }
+}
+
+fn generate_trace<F: Field>(log_height: usize, start: F) -> RowMajorMatrix<F> {
+    let mut acc = start;
+    let mut values = vec![];
+    for i in 0..1 << log_height {
+        values.push(acc);
+        values.push(if i % 8 == 7 { acc } else { F::ZERO });
+        acc += F::from_usize(i % 4 + 1);
+    }
+    RowMajorMatrix::new(values, 2)
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2BabyBear<16>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs =
+    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+type Dft = Radix2DitParallel<Val>;
+type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+
+fn make_config() -> MyConfig {
+    let mut rng = SmallRng::seed_from_u64(1);
+    let perm = Perm::new_from_rng_128(&mut rng);
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = create_test_fri_config(challenge_mmcs, 1);
+    let pcs = Pcs::new(Dft::default(), val_mmcs, fri_config);
+    MyConfig::new(pcs, Challenger::new(perm))
+}
+
+#[test]
+fn test_periodic_air() {
+    let config = make_config();
+    let public_values = vec![Val::from_u8(3)];
+    let trace = generate_trace(6, public_values[0]);
+
+    let (_, vk) = setup(&config, &PeriodicAir, public_values.len());
+    assert_eq!(vk.periodic_columns().len(), 2);
+
+    let proof = prove(&config, &PeriodicAir, trace, &public_values);
+    verify(&config, &PeriodicAir, &proof, &public_values).expect("verification failed");
+}
+
+#[test]
+fn test_periodic_air_period_of_trace_height() {
+    let config = make_config();
+    let public_values = vec![Val::ZERO];
+    let trace = generate_trace(3, public_values[0]);
+
+    let proof = prove(&config, &PeriodicAir, trace, &public_values);
+    verify(&config, &PeriodicAir, &proof, &public_values).expect("verification failed");
+}
+
+#[test]
+fn test_periodic_air_wrong_public_values() {
+    let config = make_config();
+    let public_values = vec![Val::from_u8(3)];
+    let trace = generate_trace(6, public_values[0]);
+
+    let proof = prove(&config, &PeriodicAir, trace, &public_values);
+    assert!(verify(&config, &PeriodicAir, &proof, &vec![Val::from_u8(4)]).is_err());
+}
+
+#[test]
+fn test_dynamic_periodic_air() {
+    let config = make_config();
+    let public_values = vec![Val::from_u8(7)];
+    let trace = generate_trace(5, public_values[0]);
+
+    let description = AirDescription::from_air(&PeriodicAir, public_values.len());
+    let air = DynamicAir::new(description).expect("invalid description");
+
+    let proof = prove(&config, &air, trace, &public_values);
+    verify(&config, &air, &proof, &public_values).expect("verification failed");
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "assertion `left == right` failed: constraints had nonzero value")]
+fn test_periodic_air_bad_sample() {
+    let config = make_config();
+    let public_values = vec![Val::from_u8(3)];
+    let mut trace = generate_trace(6, public_values[0]);
+    // Sample the accumulator on a row where the periodic selector is zero.
+    trace.values[2 * 3 + 1] = trace.values[2 * 3];
+
+    prove(&config, &PeriodicAir, trace, &public_values);
+}
+
+#[test]
+#[should_panic(expected = "the period of a periodic column must not exceed the trace height")]
+fn test_periodic_air_trace_shorter_than_period() {
+    let config = make_config();
+    let public_values = vec![Val::ZERO];
+    let trace = generate_trace(2, public_values[0]);
+
+    prove(&config, &PeriodicAir, trace, &public_values);
+}
+
+#[test]
+fn test_periodic_air_circle() {
+    type Val = Mersenne31;
+    type Challenge = BinomialExtensionField<Val, 3>;
+
+    type ByteHash = Keccak256Hash;
+    type FieldHash = SerializingHasher<ByteHash>;
+    let byte_hash = ByteHash {};
+    let field_hash = FieldHash::new(byte_hash);
+
+    type MyCompress = CompressionFunctionFromHasher<ByteHash, 2, 32>;
+    let compress = MyCompress::new(byte_hash);
+
+    type ValMmcs = MerkleTreeMmcs<Val, u8, FieldHash, MyCompress, 32>;
+    let val_mmcs = ValMmcs::new(field_hash, compress);
+
+    type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+
+    type Challenger = SerializingChallenger32<Val, HashChallenger<u8, ByteHash, 32>>;
+
+    let fri_config = FriConfig {
+        log_blowup: 1,
+        log_final_poly_len: 0,
+        log_folding_arity: 1,
+        log_folding_arities: vec![],
+        num_queries: 40,
+        proof_of_work_bits: 8,
+        mmcs: challenge_mmcs,
+    };
+
+    type Pcs = CirclePcs<Val, ValMmcs, ChallengeMmcs>;
+    let pcs = Pcs {
+        mmcs: val_mmcs,
+        fri_config,
+        _phantom: PhantomData,
+    };
+    let config = StarkConfig::<Pcs, Challenge, Challenger>::new(
+        pcs,
+        Challenger::from_hasher(vec![], byte_hash),
+    );
+
+    let public_values = vec![Val::from_u8(5)];
+    let trace = generate_trace(6, public_values[0]);
+
+    let proof = prove(&config, &PeriodicAir, trace, &public_values);
+    verify(&config, &PeriodicAir, &proof, &public_values).expect("verification failed");
+}
@@ -0,0 +1,212 @@
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir, PairBuilder};
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::Field;
+use p3_field::extension::BinomialExtensionField;
+use p3_fri::{TwoAdicFriPcs, create_test_fri_config};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{
+    StarkConfig, StarkVerifyingKey, VerificationError, prove, prove_with_key, setup, verify,
+    verify_with_key,
+};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+/// An AIR which accumulates a fixed column of constants, but only on rows where a fixed selector
+/// column is set.
+///
+/// Preprocessed columns: `[selector, constant]`.
+/// Main columns: `[acc]`.
+///
+/// Constraints:
+/// - `acc = 0` on the first row,
+/// - `acc' = acc + selector * constant` on transition rows,
+/// - `acc + selector * constant = pis[0]` on the last row.
+pub struct SelectiveSumAir {
+    /// The value of `constant` on row `i` is `i * multiplier`.
+    multiplier: u64,
+    log_height: usize,
+}
+
+impl SelectiveSumAir {
+    fn preprocessed_row<F: Field>(&self, i: usize) -> [F; 2] {
+        // Select every third row.
+        [
+            F::from_bool(i.is_multiple_of(3)),
+            F::from_u64(i as u64 * self.multiplier),
+        ]
+    }
+
+    pub fn generate_trace<F: Field>(&self) -> (RowMajorMatrix<F>, F) {
+        let mut acc = F::ZERO;
+        let mut values = Vec::with_capacity(1 << self.log_height);
+        for i in 0..1 << self.log_height {
+            values.push(acc);
+            let [selector, constant] = self.preprocessed_row::<F>(i);
+            acc += selector * constant;
+        }
+        (RowMajorMatrix::new_col(values), acc)
+    }
+}
+
+impl<F: Field> BaseAir<F> for SelectiveSumAir {
+    fn width(&self) -> usize {
+        1
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        let values = (0..1 << self.log_height)
+            .flat_map(|i| self.preprocessed_row(i))
+            .collect();
+        Some(RowMajorMatrix::new(values, 2))
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues + PairBuilder> Air<AB> for SelectiveSumAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let preprocessed = builder.preprocessed();
+        let total = builder.public_values()[0];
+
+        let local = main.row_slice(0).expect("Matrix is empty?");
+        let next = main.row_slice(1).expect("Matrix only has 1 row?");
+        let prep_local = preprocessed.row_slice(0).expect("Matrix is empty?");
+
+        let acc = local[0];
+        let acc_next = next[0];
+        let selector = prep_local[0];
+        let constant = prep_local[1];
+        let new_acc = acc.into() + selector.into() * constant.into();
+
+        builder.when_first_row().assert_zero(acc);
+        builder
+            .when_transition()
+            .assert_eq(acc_next, new_acc.clone());
+        builder.when_last_row().assert_eq(new_acc, total);
+    }
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2BabyBear<16>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs =
+    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+type Dft = Radix2DitParallel<Val>;
+type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+
+fn make_config() -> MyConfig {
+    let mut rng = SmallRng::seed_from_u64(1);
+    let perm = Perm::new_from_rng_128(&mut rng);
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = create_test_fri_config(challenge_mmcs, 1);
+    let pcs = Pcs::new(Dft::default(), val_mmcs, fri_config);
+    MyConfig::new(pcs, Challenger::new(perm))
+}
+
+#[test]
+fn test_preprocessed() {
+    let config = make_config();
+    let air = SelectiveSumAir {
+        multiplier: 5,
+        log_height: 4,
+    };
+    let (trace, total) = air.generate_trace::<Val>();
+    let pis = vec![total];
+
+    let proof = prove(&config, &air, trace, &pis);
+    verify(&config, &air, &proof, &pis).expect("verification failed");
+}
+
+#[test]
+fn test_preprocessed_wrong_air() {
+    let config = make_config();
+    let air = SelectiveSumAir {
+        multiplier: 5,
+        log_height: 4,
+    };
+    let (trace, total) = air.generate_trace::<Val>();
+    let pis = vec![total];
+    let proof = prove(&config, &air, trace, &pis);
+
+    // The verifier commits to the preprocessed trace itself, so a proof for one set of fixed
+    // columns must not verify against another.
+    let other_air = SelectiveSumAir {
+        multiplier: 7,
+        log_height: 4,
+    };
+    assert!(verify(&config, &other_air, &proof, &pis).is_err());
+
+    // A preprocessed trace of the wrong height is rejected before any opening is checked.
+    let short_air = SelectiveSumAir {
+        multiplier: 5,
+        log_height: 3,
+    };
+    assert!(matches!(
+        verify(&config, &short_air, &proof, &pis),
+        Err(VerificationError::InvalidProofShape)
+    ));
+}
+
+#[test]
+fn test_preprocessed_with_keys() {
+    let config = make_config();
+    let air = SelectiveSumAir {
+        multiplier: 5,
+        log_height: 4,
+    };
+    let (pk, vk) = setup(&config, &air, 1);
+
+    // A verifier service would load the verifying key from disk.
+    let vk_bytes = postcard::to_allocvec(&vk).expect("unable to serialize verifying key");
+    let vk: StarkVerifyingKey<MyConfig> =
+        postcard::from_bytes(&vk_bytes).expect("unable to deserialize verifying key");
+
+    // The keys are reused across proofs.
+    for _ in 0..2 {
+        let (trace, total) = air.generate_trace::<Val>();
+        let pis = vec![total];
+        let proof = prove_with_key(&config, &pk, &air, trace, &pis);
+        verify_with_key(&config, &vk, &air, &proof, &pis).expect("verification failed");
+    }
+}
+
+#[test]
+fn test_preprocessed_wrong_key() {
+    let config = make_config();
+    let air = SelectiveSumAir {
+        multiplier: 5,
+        log_height: 4,
+    };
+    let (pk, _) = setup(&config, &air, 1);
+    let (trace, total) = air.generate_trace::<Val>();
+    let pis = vec![total];
+    let proof = prove_with_key(&config, &pk, &air, trace, &pis);
+
+    let other_air = SelectiveSumAir {
+        multiplier: 7,
+        log_height: 4,
+    };
+    let (_, other_vk) = setup(&config, &other_air, 1);
+    assert_ne!(pk.verifying_key().digest(&config), other_vk.digest(&config));
+    assert!(verify_with_key(&config, &other_vk, &air, &proof, &pis).is_err());
+
+    // The verifying key fixes the number of public values.
+    let (_, vk_more_pis) = setup(&config, &air, 2);
+    assert!(matches!(
+        verify_with_key(&config, &vk_more_pis, &air, &proof, &pis),
+        Err(VerificationError::InvalidProofShape)
+    ));
+}
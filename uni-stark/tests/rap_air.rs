@@ -0,0 +1,163 @@
+use p3_air::{Air, AuxBuilder, AuxPhase, BaseAir, ExtensionBuilder};
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{ExtensionField, Field, PrimeCharacteristicRing};
+use p3_fri::{TwoAdicFriPcs, create_test_fri_config};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{StarkConfig, prove, verify};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+/// An AIR whose two main columns `[a, b]` are permutations of each other, checked with a grand
+/// product.
+///
+/// The first auxiliary phase, with a single challenge `gamma`, holds the running product `z` of
+/// `(gamma - a) / (gamma - b)`, which must end at one. The second phase, which has no challenges,
+/// holds the inverse of `z` and so depends on the first.
+pub struct GrandProductAir;
+
+impl<F: Field> BaseAir<F> for GrandProductAir {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn aux_phases(&self) -> Vec<AuxPhase> {
+        vec![AuxPhase::new(1, 1), AuxPhase::new(0, 1)]
+    }
+
+    fn generate_aux_trace<EF>(
+        &self,
+        phase: usize,
+        _preprocessed: Option<&RowMajorMatrix<F>>,
+        main: &RowMajorMatrix<F>,
+        previous_phases: &[RowMajorMatrix<EF>],
+        challenges: &[EF],
+    ) -> RowMajorMatrix<EF>
+    where
+        EF: ExtensionField<F>,
+    {
+        match phase {
+            0 => {
+                let gamma = challenges[0];
+                let values = main
+                    .rows()
+                    .scan(EF::ONE, |z, mut row| {
+                        let (a, b) = (row.next().unwrap(), row.next().unwrap());
+                        *z *= (gamma - a) * (gamma - b).inverse();
+                        Some(*z)
+                    })
+                    .collect();
+                RowMajorMatrix::new_col(values)
+            }
+            1 => {
+                let values = previous_phases[0]
+                    .values
+                    .iter()
+                    .map(|z| z.inverse())
+                    .collect();
+                RowMajorMatrix::new_col(values)
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<AB: AuxBuilder> Air<AB> for GrandProductAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0).expect("Matrix is empty?");
+        let next = main.row_slice(1).expect("Matrix only has 1 row?");
+        let product = builder.aux(0);
+        let z_local: AB::ExprEF = product.get(0, 0).unwrap().into();
+        let z_next: AB::ExprEF = product.get(1, 0).unwrap().into();
+        let inverse = builder.aux(1);
+        let z_inv: AB::ExprEF = inverse.get(0, 0).unwrap().into();
+        let gamma: AB::ExprEF = builder.aux_challenges(0)[0].into();
+
+        let numerator = |a: AB::Var| gamma.clone() - a.into();
+        let denominator = |b: AB::Var| gamma.clone() - b.into();
+
+        builder
+            .when_first_row()
+            .assert_eq_ext(z_local.clone() * denominator(local[1]), numerator(local[0]));
+        builder.when_transition().assert_eq_ext(
+            z_next * denominator(next[1]),
+            z_local.clone() * numerator(next[0]),
+        );
+        builder.when_last_row().assert_one_ext(z_local.clone());
+        builder.assert_one_ext(z_local * z_inv);
+    }
+}
+
+fn generate_trace<F: Field>(log_height: usize, shift: usize) -> RowMajorMatrix<F> {
+    let height = 1 << log_height;
+    let values = (0..height)
+        .flat_map(|i| [F::from_usize(i), F::from_usize((i + shift) % height)])
+        .collect();
+    RowMajorMatrix::new(values, 2)
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2BabyBear<16>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs =
+    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+type Dft = Radix2DitParallel<Val>;
+type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+
+fn make_config() -> MyConfig {
+    let mut rng = SmallRng::seed_from_u64(1);
+    let perm = Perm::new_from_rng_128(&mut rng);
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = create_test_fri_config(challenge_mmcs, 1);
+    let pcs = Pcs::new(Dft::default(), val_mmcs, fri_config);
+    MyConfig::new(pcs, Challenger::new(perm))
+}
+
+#[test]
+fn test_grand_product() {
+    let config = make_config();
+    let trace = generate_trace::<Val>(5, 3);
+
+    let proof = prove(&config, &GrandProductAir, trace, &vec![]);
+    verify(&config, &GrandProductAir, &proof, &vec![]).expect("verification failed");
+}
+
+/// A trace whose second column is not a permutation of the first.
+fn invalid_trace() -> RowMajorMatrix<Val> {
+    let mut trace = generate_trace::<Val>(5, 3);
+    trace.values[1] += Val::ONE;
+    trace
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "assertion `left == right` failed: constraints had nonzero value")]
+fn test_grand_product_not_a_permutation() {
+    let config = make_config();
+    prove(&config, &GrandProductAir, invalid_trace(), &vec![]);
+}
+
+// Without debug assertions, the prover does not check the constraints, so this checks that the
+// verifier rejects the proof it produces.
+#[cfg(not(debug_assertions))]
+#[test]
+fn test_grand_product_not_a_permutation() {
+    let config = make_config();
+    let proof = prove(&config, &GrandProductAir, invalid_trace(), &vec![]);
+    assert!(verify(&config, &GrandProductAir, &proof, &vec![]).is_err());
+}
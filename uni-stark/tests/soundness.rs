@@ -0,0 +1,193 @@
+use core::marker::PhantomData;
+
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::{DuplexChallenger, HashChallenger, SerializingChallenger32};
+use p3_circle::CirclePcs;
+use p3_commit::{ExtensionMmcs, FriParameters};
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{Field, PrimeCharacteristicRing};
+use p3_fri::{FriConfig, TwoAdicFriPcs};
+use p3_keccak::Keccak256Hash;
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_mersenne_31::Mersenne31;
+use p3_symmetric::{
+    CompressionFunctionFromHasher, PaddingFreeSponge, SerializingHasher, TruncatedPermutation,
+};
+use p3_uni_stark::{
+    FriSearchSpace, ProximityRegime, SoundnessModel, SoundnessParameters, StarkConfig, prove,
+    proven_soundness, select_fri_parameters, verify,
+};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+/// Consecutive Fibonacci numbers and the cube of the first, which raises the quotient degree.
+pub struct CubicFibonacciAir;
+
+impl<F> BaseAir<F> for CubicFibonacciAir {
+    fn width(&self) -> usize {
+        3
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for CubicFibonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (
+            main.row_slice(0).expect("empty trace"),
+            main.row_slice(1).expect("single row trace"),
+        );
+
+        let mut when_transition = builder.when_transition();
+        when_transition.assert_eq(next[0], local[1]);
+        when_transition.assert_eq(next[1], local[0] + local[1]);
+        builder.assert_eq(local[2], local[0] * local[0] * local[0]);
+    }
+}
+
+fn fri_config<M>(mmcs: M) -> FriConfig<M> {
+    FriConfig {
+        log_blowup: 2,
+        log_final_poly_len: 0,
+        log_folding_arity: 1,
+        log_folding_arities: vec![],
+        num_queries: 60,
+        proof_of_work_bits: 16,
+        mmcs,
+    }
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2BabyBear<16>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs =
+    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+type Dft = Radix2DitParallel<Val>;
+type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+
+fn make_config(fri_parameters: FriParameters) -> MyConfig {
+    let mut rng = SmallRng::seed_from_u64(1);
+    let perm = Perm::new_from_rng_128(&mut rng);
+    let val_mmcs = ValMmcs::new(MyHash::new(perm.clone()), MyCompress::new(perm.clone()));
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = FriConfig::from_parameters(fri_parameters, challenge_mmcs);
+    let pcs = Pcs::new(Dft::default(), val_mmcs, fri_config);
+    MyConfig::new(pcs, Challenger::new(perm))
+}
+
+fn generate_trace(height: usize) -> RowMajorMatrix<Val> {
+    let (mut a, mut b) = (Val::ZERO, Val::ONE);
+    let mut values = vec![];
+    for _ in 0..height {
+        values.extend([a, b, a.cube()]);
+        (a, b) = (b, a + b);
+    }
+    RowMajorMatrix::new(values, 3)
+}
+
+#[test]
+fn test_two_adic_soundness() {
+    let config = make_config(fri_config(()).parameters());
+
+    let params = SoundnessParameters::new(&config, &CubicFibonacciAir, 0, 20, 248);
+    assert_eq!(params.log_degree, 20);
+    assert_eq!(params.num_constraints, 3);
+    assert_eq!(params.max_constraint_degree, 3);
+    assert_eq!(params.num_opening_points, 2);
+    // Three columns opened at two points, and two quotient chunks of four coordinates each.
+    assert_eq!(params.num_batched_polynomials, 3 * 2 + 2 * 4);
+    assert_eq!(params.fri.num_queries, 60);
+
+    let unique = params.report(ProximityRegime::UniqueDecoding);
+    // Each query misses with probability `(1 + 1/4) / 2`.
+    assert!((unique.fri_query_bits - 60.0 * 0.678_071_905_112_638).abs() < 1e-6);
+    assert_eq!(unique.proof_of_work_bits, 16.0);
+    assert_eq!(unique.merkle_bits, 124.0);
+    assert!(unique.ood_bits > 100.0 && unique.constraint_batching_bits > 100.0);
+
+    let report = proven_soundness(&config, &CubicFibonacciAir, 0, 20, 248);
+    assert_eq!(report, params.best_report());
+    assert!(report.total_bits >= unique.total_bits);
+    assert!(report.total_bits <= report.fri_query_bits + report.proof_of_work_bits);
+    // The proven bound is weaker than the conjectured one.
+    assert!(report.total_bits < 2.0 * 60.0 + 16.0);
+}
+
+#[test]
+fn test_circle_soundness() {
+    type Val = Mersenne31;
+    type Challenge = BinomialExtensionField<Val, 3>;
+
+    type ByteHash = Keccak256Hash;
+    type FieldHash = SerializingHasher<ByteHash>;
+    let byte_hash = ByteHash {};
+    let field_hash = FieldHash::new(byte_hash);
+
+    type MyCompress = CompressionFunctionFromHasher<ByteHash, 2, 32>;
+    let compress = MyCompress::new(byte_hash);
+
+    type ValMmcs = MerkleTreeMmcs<Val, u8, FieldHash, MyCompress, 32>;
+    let val_mmcs = ValMmcs::new(field_hash, compress);
+
+    type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+
+    type Challenger = SerializingChallenger32<Val, HashChallenger<u8, ByteHash, 32>>;
+
+    type Pcs = CirclePcs<Val, ValMmcs, ChallengeMmcs>;
+    let pcs = Pcs {
+        mmcs: val_mmcs,
+        fri_config: fri_config(challenge_mmcs),
+        _phantom: PhantomData,
+    };
+    let config = StarkConfig::<Pcs, Challenge, Challenger>::new(
+        pcs,
+        Challenger::from_hasher(vec![], byte_hash),
+    );
+
+    let params = SoundnessParameters::new(&config, &CubicFibonacciAir, 0, 16, 256);
+    // The challenge field is a degree 3 extension of M31.
+    assert!((params.log_field_size - 93.0).abs() < 1e-6);
+    assert_eq!(params.num_batched_polynomials, 3 * 2 + 2 * 3);
+
+    let report = params.best_report();
+    assert_eq!(report.merkle_bits, 128.0);
+    // The small challenge field makes the Johnson regime's commit phase error too large, so the
+    // unique decoding regime gives the best bound.
+    assert_eq!(report.regime, ProximityRegime::UniqueDecoding);
+    assert!(report.ood_bits < 93.0 - 16.0);
+    assert!(report.total_bits < report.fri_query_bits + report.proof_of_work_bits);
+    assert!(report.total_bits > 56.0);
+}
+
+#[test]
+fn test_selected_fri_parameters() {
+    // Any FRI parameters will do to collect the parameters of the AIR.
+    let config = make_config(fri_config(()).parameters());
+    let params = SoundnessParameters::new(&config, &CubicFibonacciAir, 0, 6, 248);
+    let frontier = select_fri_parameters(
+        &params,
+        4,
+        64.0,
+        SoundnessModel::Conjectured,
+        &FriSearchSpace::default(),
+    );
+    assert!(!frontier.is_empty());
+
+    // Prove with the fastest candidate, which uses the least proof of work.
+    let fastest = frontier.last().unwrap();
+    assert!(fastest.security_bits >= 64.0);
+    assert!(fastest.fri_config(()).conjectured_soundness_bits() >= 64);
+    let config = make_config(fastest.parameters);
+    let trace = generate_trace(1 << 6);
+    let proof = prove(&config, &CubicFibonacciAir, trace, &vec![]);
+    verify(&config, &CubicFibonacciAir, &proof, &vec![]).expect("verification failed");
+}
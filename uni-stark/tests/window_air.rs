@@ -0,0 +1,160 @@
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{Field, PrimeCharacteristicRing};
+use p3_fri::{TwoAdicFriPcs, create_test_fri_config};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{
+    AirDescription, DynamicAir, StarkConfig, VerificationError, prove, setup, verify,
+};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+/// A single column holding the Fibonacci sequence, starting from the two public values.
+///
+/// Besides the usual recurrence over three rows, the AIR reads seven rows ahead to check the
+/// identity `F(n + 7) = 13 F(n + 1) + 8 F(n)`.
+pub struct WindowFibonacciAir;
+
+impl<F> BaseAir<F> for WindowFibonacciAir {
+    fn width(&self) -> usize {
+        1
+    }
+
+    fn main_row_offsets(&self) -> Vec<usize> {
+        vec![0, 1, 2, 7]
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for WindowFibonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let rows: [AB::Expr; 4] =
+            core::array::from_fn(|i| main.row_slice(i).expect("missing row")[0].into());
+        let [row_0, row_1, row_2, row_7] = rows;
+        let (a, b): (AB::Expr, AB::Expr) = (
+            builder.public_values()[0].into(),
+            builder.public_values()[1].into(),
+        );
+
+        builder.when_first_row().assert_eq(row_0.clone(), a);
+        builder.when_first_row().assert_eq(row_1.clone(), b);
+        builder
+            .when_transition_window(3)
+            .assert_eq(row_2, row_0.clone() + row_1.clone());
+        builder.when_transition_window(8).assert_eq(
+            row_7,
+            row_1 * AB::Expr::from_u8(13) + row_0 * AB::Expr::from_u8(8),
+        );
+    }
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2BabyBear<16>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs =
+    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+type Dft = Radix2DitParallel<Val>;
+type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+
+fn make_config() -> MyConfig {
+    let mut rng = SmallRng::seed_from_u64(1);
+    let perm = Perm::new_from_rng_128(&mut rng);
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = create_test_fri_config(challenge_mmcs, 1);
+    let pcs = Pcs::new(Dft::default(), val_mmcs, fri_config);
+    MyConfig::new(pcs, Challenger::new(perm))
+}
+
+fn generate_trace(log_height: usize, a: Val, b: Val) -> RowMajorMatrix<Val> {
+    let (mut a, mut b) = (a, b);
+    let mut values = vec![];
+    for _ in 0..1 << log_height {
+        values.push(a);
+        (a, b) = (b, a + b);
+    }
+    RowMajorMatrix::new_col(values)
+}
+
+#[test]
+fn test_window_fibonacci() {
+    let config = make_config();
+    let public_values = vec![Val::from_u8(2), Val::from_u8(5)];
+    let trace = generate_trace(5, public_values[0], public_values[1]);
+
+    let (_, vk) = setup(&config, &WindowFibonacciAir, public_values.len());
+    assert_eq!(vk.main_row_offsets(), [0, 1, 2, 7]);
+
+    let proof = prove(&config, &WindowFibonacciAir, trace, &public_values);
+    verify(&config, &WindowFibonacciAir, &proof, &public_values).expect("verification failed");
+}
+
+#[test]
+fn test_window_fibonacci_wrong_public_values() {
+    let config = make_config();
+    let public_values = vec![Val::from_u8(2), Val::from_u8(5)];
+    let trace = generate_trace(5, public_values[0], public_values[1]);
+
+    let proof = prove(&config, &WindowFibonacciAir, trace, &public_values);
+    let wrong_public_values = vec![Val::from_u8(2), Val::from_u8(6)];
+    assert!(verify(&config, &WindowFibonacciAir, &proof, &wrong_public_values).is_err());
+}
+
+#[test]
+fn test_window_fibonacci_short_trace() {
+    let config = make_config();
+    let public_values = vec![Val::from_u8(2), Val::from_u8(5)];
+    let trace = generate_trace(5, public_values[0], public_values[1]);
+    let proof = prove(&config, &WindowFibonacciAir, trace, &public_values);
+
+    // Claim a trace of height 4, which the offset 7 would wrap around. The proof ends with the
+    // degree bits and the absent cumulative sum of the lookups.
+    let mut bytes = postcard::to_allocvec(&proof).expect("unable to serialize proof");
+    let len = bytes.len();
+    assert_eq!(bytes[len - 2..], [5, 0]);
+    bytes[len - 2] = 2;
+    let proof = postcard::from_bytes(&bytes).expect("unable to deserialize proof");
+    assert!(matches!(
+        verify(&config, &WindowFibonacciAir, &proof, &public_values),
+        Err(VerificationError::InvalidProofShape)
+    ));
+}
+
+#[test]
+fn test_dynamic_window_fibonacci() {
+    let config = make_config();
+    let public_values = vec![Val::ZERO, Val::ONE];
+    let trace = generate_trace(4, public_values[0], public_values[1]);
+
+    let description = AirDescription::from_air(&WindowFibonacciAir, public_values.len());
+    let air = DynamicAir::new(description).expect("invalid description");
+
+    let proof = prove(&config, &air, trace, &public_values);
+    verify(&config, &air, &proof, &public_values).expect("verification failed");
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "assertion `left == right` failed: constraints had nonzero value")]
+fn test_window_fibonacci_bad_row() {
+    let config = make_config();
+    let public_values = vec![Val::from_u8(2), Val::from_u8(5)];
+    let mut trace = generate_trace(5, public_values[0], public_values[1]);
+    trace.values[20] += Val::ONE;
+
+    prove(&config, &WindowFibonacciAir, trace, &public_values);
+}